@@ -0,0 +1,116 @@
+//! Criterion microbenchmarks for the hashing and SMT gadgets.
+//!
+//! Run with: cargo bench -p inventory-circuits
+//!
+//! Each gadget benchmark times synthesis into a fresh constraint system (with
+//! witnesses) and prints its constraint count first, so time and circuit size
+//! can be compared side by side.
+
+use ark_bn254::Fr;
+use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar};
+use ark_relations::r1cs::{ConstraintSystem, ConstraintSystemRef};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use inventory_circuits::poseidon::poseidon_hash_two_var;
+use inventory_circuits::smt::hash_two;
+use inventory_circuits::{
+    compute_registry_hash_var, verify_membership, MerkleProofVar, SparseMerkleTree,
+    VolumeRegistry, DEFAULT_DEPTH, MAX_ITEM_TYPES,
+};
+
+/// Synthesize `gadget` once and report its constraint count.
+fn report_constraints(name: &str, gadget: impl Fn(ConstraintSystemRef<Fr>)) {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    gadget(cs.clone());
+    assert!(cs.is_satisfied().unwrap(), "{} is unsatisfied", name);
+    println!("{}: {} constraints", name, cs.num_constraints());
+}
+
+/// Synthesize `gadget` into a fresh constraint system per iteration.
+fn synthesize(gadget: &impl Fn(ConstraintSystemRef<Fr>)) {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    gadget(cs.clone());
+    black_box(cs.num_constraints());
+}
+
+/// Gadget synthesized under a fresh constraint system.
+type Gadget = fn(ConstraintSystemRef<Fr>);
+
+fn witness(cs: &ConstraintSystemRef<Fr>, value: u64) -> FpVar<Fr> {
+    FpVar::new_witness(cs.clone(), || Ok(Fr::from(value))).unwrap()
+}
+
+fn bench_hash_two(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hash_two");
+
+    let gadgets: [(&str, Gadget); 2] = [
+        ("poseidon_untagged", |cs| {
+            let (a, b) = (witness(&cs, 1), witness(&cs, 2));
+            let _ = poseidon_hash_two_var(cs, &a, &b).unwrap();
+        }),
+        ("poseidon_smt_node", |cs| {
+            let (a, b) = (witness(&cs, 1), witness(&cs, 2));
+            let _ = hash_two(cs, &a, &b).unwrap();
+        }),
+    ];
+
+    for (name, gadget) in gadgets {
+        report_constraints(&format!("hash_two/{}", name), gadget);
+        group.bench_function(name, |b| b.iter(|| synthesize(&gadget)));
+    }
+
+    group.finish();
+}
+
+fn bench_verify_membership(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verify_membership");
+
+    for depth in [8, 12, 16] {
+        let tree = SparseMerkleTree::from_items(&[(1, 100), (42, 50), (200, 7)], depth);
+        let root = tree.root();
+        let proof = tree.get_proof(42);
+
+        let gadget = |cs: ConstraintSystemRef<Fr>| {
+            let root = FpVar::new_input(cs.clone(), || Ok(root)).unwrap();
+            let (item_id, quantity) = (witness(&cs, 42), witness(&cs, 50));
+            let proof = MerkleProofVar::new_witness(cs.clone(), &proof).unwrap();
+            verify_membership(cs, &root, &item_id, &quantity, &proof).unwrap();
+        };
+
+        report_constraints(&format!("verify_membership/{}", depth), gadget);
+        group.bench_with_input(BenchmarkId::from_parameter(depth), &depth, |b, _| {
+            b.iter(|| synthesize(&gadget))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_used_volume(c: &mut Criterion) {
+    let mut group = c.benchmark_group("used_volume");
+
+    let volumes: Vec<u64> = (0..MAX_ITEM_TYPES as u64).map(|id| id * 3 + 1).collect();
+    let registry = VolumeRegistry::from_slice(&volumes);
+
+    for items in [1, 4, MAX_ITEM_TYPES as u64] {
+        let leaves: Vec<(u64, u64)> = (0..items).map(|id| (id, id + 10)).collect();
+        let tree = SparseMerkleTree::from_items(&leaves, DEFAULT_DEPTH);
+
+        group.bench_with_input(BenchmarkId::new("native", items), &tree, |b, tree| {
+            b.iter(|| registry.calculate_used_volume(black_box(tree)).unwrap())
+        });
+    }
+
+    // Every capacity-checking circuit binds the full registry table
+    let gadget = |cs: ConstraintSystemRef<Fr>| {
+        let volumes: Vec<FpVar<Fr>> = volumes.iter().map(|&v| witness(&cs, v)).collect();
+        let _ = compute_registry_hash_var(cs, &volumes).unwrap();
+    };
+    report_constraints("used_volume/registry_hash", gadget);
+    group.bench_function("registry_hash", |b| b.iter(|| synthesize(&gadget)));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_hash_two, bench_verify_membership, bench_used_volume);
+criterion_main!(benches);
@@ -0,0 +1,538 @@
+//! Batch State Transition Circuit: up to `MAX_BATCH_OPS` deposits/withdrawals in one proof.
+//!
+//! Proves state0 -> state1 -> ... -> stateN, where each step is a full state
+//! transition (SMT update, quantity and volume change, capacity check). The
+//! root and volume after step i are the starting root and volume of step
+//! i + 1; only the first and last states are committed.
+//!
+//! Batches shorter than `MAX_BATCH_OPS` are padded with zero-amount deposits
+//! of the last item, which leave the state unchanged.
+//!
+//! Public inputs (in order):
+//! - signal_hash: Poseidon(old_commitment, new_commitment, registry_root,
+//!   max_capacity, nonce, inventory_id, then (item_id, amount, op_type) per step)
+//! - nonce: Current inventory nonce (replay protection)
+//! - inventory_id: Inventory object ID (cross-inventory protection)
+//! - registry_root: Volume registry commitment shared by all steps
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::double_transition::TransitionStep;
+use crate::poseidon::{poseidon_hash_domain, poseidon_hash_domain_var, Domain};
+use crate::signal::OpType;
+use crate::smt::MerkleProofVar;
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+use crate::state_transition::{enforce_transition_step, TransitionStepVars};
+use crate::volume_registry::{
+    alloc_volumes_var, compute_registry_hash, VolumeRegistry, MAX_ITEM_TYPES,
+};
+
+/// Number of operations proven by one `BatchStateTransitionCircuit`.
+pub const MAX_BATCH_OPS: usize = 4;
+
+/// Compute the signal hash of a batch transition.
+///
+/// `ops` holds the (item_id, amount, op_type) of every step, padding included.
+pub fn compute_batch_signal_hash(
+    old_commitment: Fr,
+    new_commitment: Fr,
+    registry_root: Fr,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+    ops: &[(u64, u64, OpType)],
+) -> Fr {
+    let mut inputs = vec![
+        old_commitment,
+        new_commitment,
+        registry_root,
+        Fr::from(max_capacity),
+        Fr::from(nonce),
+        inventory_id,
+    ];
+    for &(item_id, amount, op_type) in ops {
+        inputs.extend([Fr::from(item_id), Fr::from(amount), op_type.to_field()]);
+    }
+    poseidon_hash_domain(Domain::Batch, &inputs)
+}
+
+/// Batch State Transition Circuit.
+///
+/// Proves `MAX_BATCH_OPS` valid state transitions applied one after the other.
+#[derive(Clone)]
+pub struct BatchStateTransitionCircuit {
+    // Public inputs
+    /// Signal hash binding the commitments and every step's operation
+    pub signal_hash: Option<Fr>,
+    /// Current inventory nonce
+    pub nonce: Option<u64>,
+    /// Inventory object ID
+    pub inventory_id: Option<Fr>,
+    /// Registry root (commitment to volume table)
+    pub registry_root: Option<Fr>,
+
+    // Witnesses
+    /// Maximum allowed capacity, enforced after each step
+    pub max_capacity: Option<u64>,
+    /// Full volume table; must hash to `registry_root` and hold each step's
+    /// `item_volume` at its `item_id`
+    pub registry_volumes: Option<[u64; MAX_ITEM_TYPES]>,
+    /// State0 blinding factor
+    pub old_blinding: Option<Fr>,
+    /// Final state blinding factor
+    pub new_blinding: Option<Fr>,
+    /// Inventory SMT root before each step, followed by the final root
+    pub roots: Vec<Fr>,
+    /// Total volume before each step, followed by the final volume
+    pub volumes: Vec<u64>,
+    /// Steps in application order, padded to `MAX_BATCH_OPS`
+    pub steps: Vec<TransitionStep>,
+}
+
+impl BatchStateTransitionCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses a zero-amount deposit into an empty tree, so the dummy witnesses
+    /// produce a valid constraint structure and also satisfy it.
+    pub fn empty() -> Self {
+        use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+
+        let tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        let step = TransitionStep {
+            item_id: 0,
+            old_quantity: 0,
+            new_quantity: 0,
+            amount: 0,
+            op_type: OpType::Deposit,
+            inventory_proof: tree.get_proof(0),
+            item_volume: 0,
+        };
+
+        Self::new(
+            tree.root(),
+            0,
+            Fr::from(0u64),
+            Fr::from(0u64),
+            vec![step],
+            &VolumeRegistry::new([0; MAX_ITEM_TYPES]),
+            0,
+            0,
+            Fr::from(0u64),
+        )
+    }
+
+    /// Create a new circuit from the starting state and the steps applied to it.
+    ///
+    /// The registry root is taken from `registry`. The intermediate roots and
+    /// volumes are derived from each step's proof and volume change. A volume that underflows or does not fit in a u64
+    /// ends `volumes` early and leaves the signal hash unset, so synthesis fails.
+    ///
+    /// # Panics
+    /// If `steps` is empty or longer than `MAX_BATCH_OPS`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        old_inventory_root: Fr,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_blinding: Fr,
+        mut steps: Vec<TransitionStep>,
+        registry: &VolumeRegistry,
+        max_capacity: u64,
+        nonce: u64,
+        inventory_id: Fr,
+    ) -> Self {
+        assert!(
+            (1..=MAX_BATCH_OPS).contains(&steps.len()),
+            "a batch holds 1 to {} steps",
+            MAX_BATCH_OPS
+        );
+
+        // Pad with no-op deposits on the last item; its proof is still valid
+        // against the final root since only that leaf changed.
+        let last = steps[steps.len() - 1].clone();
+        steps.resize(
+            MAX_BATCH_OPS,
+            TransitionStep {
+                old_quantity: last.new_quantity,
+                amount: 0,
+                op_type: OpType::Deposit,
+                ..last
+            },
+        );
+
+        let mut roots = vec![old_inventory_root];
+        let mut volumes = vec![old_volume];
+        for step in &steps {
+            let volume = volumes[volumes.len() - 1];
+            roots.push(step.inventory_proof.compute_root(step.item_id, step.new_quantity));
+            let new_volume = step.amount.checked_mul(step.item_volume).and_then(|delta| {
+                match step.op_type {
+                    OpType::Deposit => volume.checked_add(delta),
+                    OpType::Withdraw => volume.checked_sub(delta),
+                }
+            });
+            match new_volume {
+                Some(new_volume) => volumes.push(new_volume),
+                None => break,
+            }
+        }
+
+        let registry_root = compute_registry_hash(registry);
+        let old_commitment = create_smt_commitment(old_inventory_root, old_volume, old_blinding);
+        let ops: Vec<_> = steps
+            .iter()
+            .map(|step| (step.item_id, step.amount, step.op_type))
+            .collect();
+        let signal_hash = volumes.get(MAX_BATCH_OPS).map(|&new_volume| {
+            compute_batch_signal_hash(
+                old_commitment,
+                create_smt_commitment(roots[MAX_BATCH_OPS], new_volume, new_blinding),
+                registry_root,
+                max_capacity,
+                nonce,
+                inventory_id,
+                &ops,
+            )
+        });
+
+        Self {
+            signal_hash,
+            nonce: Some(nonce),
+            inventory_id: Some(inventory_id),
+            registry_root: Some(registry_root),
+            max_capacity: Some(max_capacity),
+            registry_volumes: Some(*registry.volumes()),
+            old_blinding: Some(old_blinding),
+            new_blinding: Some(new_blinding),
+            roots,
+            volumes,
+            steps,
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for BatchStateTransitionCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // The step count fixes the constraint structure, so it must match the keys
+        if self.steps.len() != MAX_BATCH_OPS
+            || self.roots.len() != MAX_BATCH_OPS + 1
+            || self.volumes.len() != MAX_BATCH_OPS + 1
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        // === Allocate public inputs ===
+        // Order matters: signal_hash, nonce, inventory_id, registry_root
+        let signal_hash_var = FpVar::new_input(cs.clone(), || {
+            self.signal_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let nonce_var = FpVar::new_input(cs.clone(), || {
+            self.nonce
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let inventory_id_var = FpVar::new_input(cs.clone(), || {
+            self.inventory_id.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let registry_root_var = FpVar::new_input(cs.clone(), || {
+            self.registry_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate state witnesses ===
+        let max_capacity_var = FpVar::new_witness(cs.clone(), || {
+            self.max_capacity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let old_blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.old_blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let new_blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.new_blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let root_vars = self
+            .roots
+            .iter()
+            .map(|&root| FpVar::new_witness(cs.clone(), || Ok(root)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let volume_vars = self
+            .volumes
+            .iter()
+            .map(|&volume| FpVar::new_witness(cs.clone(), || Ok(Fr::from(volume))))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let registry_volume_vars = alloc_volumes_var(cs.clone(), self.registry_volumes.as_ref())?;
+
+        // === Constraint 1: state(i) -> state(i+1) for every step ===
+        let mut signal_inputs = Vec::with_capacity(3 * MAX_BATCH_OPS);
+        for (i, step) in self.steps.iter().enumerate() {
+            let witness = |value: u64| FpVar::new_witness(cs.clone(), || Ok(Fr::from(value)));
+
+            let step_vars = TransitionStepVars {
+                old_root: root_vars[i].clone(),
+                new_root: root_vars[i + 1].clone(),
+                old_volume: volume_vars[i].clone(),
+                new_volume: volume_vars[i + 1].clone(),
+                item_id: witness(step.item_id)?,
+                old_quantity: witness(step.old_quantity)?,
+                new_quantity: witness(step.new_quantity)?,
+                amount: witness(step.amount)?,
+                op_type: FpVar::new_witness(cs.clone(), || Ok(step.op_type.to_field()))?,
+                item_volume: witness(step.item_volume)?,
+                registry_root: registry_root_var.clone(),
+                registry_volumes: registry_volume_vars.clone(),
+                proof: MerkleProofVar::new_witness(cs.clone(), &step.inventory_proof)?,
+            };
+            let _ = enforce_transition_step(cs.clone(), &step_vars, &max_capacity_var)?;
+
+            signal_inputs.extend([step_vars.item_id, step_vars.amount, step_vars.op_type]);
+        }
+
+        // === Constraint 2: Commitments to the first and last states ===
+        let old_commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &root_vars[0],
+            &volume_vars[0],
+            &old_blinding_var,
+        )?;
+        let new_commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &root_vars[MAX_BATCH_OPS],
+            &volume_vars[MAX_BATCH_OPS],
+            &new_blinding_var,
+        )?;
+
+        // === Constraint 3: Signal hash binds commitments, context and every step ===
+        let mut hash_inputs = vec![
+            old_commitment_var,
+            new_commitment_var,
+            registry_root_var,
+            max_capacity_var,
+            nonce_var,
+            inventory_id_var,
+        ];
+        hash_inputs.extend(signal_inputs);
+        let computed_signal = poseidon_hash_domain_var(cs, Domain::Batch, &hash_inputs)?;
+        computed_signal.enforce_equal(&signal_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Volumes of the items used below.
+    fn registry() -> VolumeRegistry {
+        VolumeRegistry::from_entries(&[(1, 10), (2, 5), (3, 2), (4, 5), (5, 1)])
+    }
+
+    /// Apply one step to `tree`, returning its witnesses.
+    fn apply(
+        tree: &mut SparseMerkleTree,
+        item_id: u64,
+        amount: u64,
+        op_type: OpType,
+        item_volume: u64,
+    ) -> TransitionStep {
+        let old_quantity = tree.get(item_id);
+        let new_quantity = match op_type {
+            OpType::Deposit => old_quantity + amount,
+            OpType::Withdraw => old_quantity - amount,
+        };
+        let inventory_proof = tree.get_proof(item_id);
+        tree.update(item_id, new_quantity);
+
+        TransitionStep {
+            item_id,
+            old_quantity,
+            new_quantity,
+            amount,
+            op_type,
+            inventory_proof,
+            item_volume,
+        }
+    }
+
+    fn is_satisfied(circuit: BatchStateTransitionCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_empty_circuit_satisfied() {
+        assert!(is_satisfied(BatchStateTransitionCircuit::empty()));
+    }
+
+    #[test]
+    fn test_two_deposits() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let root0 = tree.root();
+
+        // Deposit 20 of item 2 (volume 5), then 10 of item 3 (volume 2)
+        let steps = vec![
+            apply(&mut tree, 2, 20, OpType::Deposit, 5),
+            apply(&mut tree, 3, 10, OpType::Deposit, 2),
+        ];
+
+        let circuit = BatchStateTransitionCircuit::new(
+            root0,
+            1000,
+            Fr::from(111u64),
+            Fr::from(222u64),
+            steps,
+            &registry(),
+            1200,
+            0,
+            Fr::from(12345u64),
+        );
+
+        // Padding keeps the final state
+        assert_eq!(circuit.roots[MAX_BATCH_OPS], tree.root());
+        assert_eq!(circuit.volumes[MAX_BATCH_OPS], 1120);
+        assert!(is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100), (5, 30)], DEFAULT_DEPTH);
+        let root0 = tree.root();
+
+        let steps = vec![
+            apply(&mut tree, 2, 20, OpType::Deposit, 5),
+            apply(&mut tree, 1, 30, OpType::Withdraw, 10),
+            apply(&mut tree, 2, 5, OpType::Withdraw, 5),
+            apply(&mut tree, 5, 30, OpType::Withdraw, 1),
+        ];
+
+        let circuit = BatchStateTransitionCircuit::new(
+            root0,
+            1030,
+            Fr::from(111u64),
+            Fr::from(222u64),
+            steps,
+            &registry(),
+            1200,
+            3,
+            Fr::from(12345u64),
+        );
+
+        assert_eq!(circuit.roots[MAX_BATCH_OPS], tree.root());
+        assert_eq!(circuit.volumes[MAX_BATCH_OPS], 1030 + 100 - 300 - 25 - 30);
+        assert!(is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_wrong_intermediate_root_rejected() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let root0 = tree.root();
+
+        let steps = vec![
+            apply(&mut tree, 2, 20, OpType::Deposit, 5),
+            apply(&mut tree, 3, 10, OpType::Deposit, 2),
+            apply(&mut tree, 1, 50, OpType::Withdraw, 10),
+        ];
+
+        let mut circuit = BatchStateTransitionCircuit::new(
+            root0,
+            1000,
+            Fr::from(111u64),
+            Fr::from(222u64),
+            steps,
+            &registry(),
+            1200,
+            0,
+            Fr::from(12345u64),
+        );
+        circuit.roots[2] = Fr::from(42u64);
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_volume_overflow_unprovable() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let root0 = tree.root();
+
+        // 1000 + u64::MAX / 5 * 5 overflows u64 at the second step
+        let steps = vec![
+            apply(&mut tree, 2, 20, OpType::Deposit, 5),
+            apply(&mut tree, 4, u64::MAX / 5, OpType::Deposit, 5),
+        ];
+
+        let circuit = BatchStateTransitionCircuit::new(
+            root0,
+            1000,
+            Fr::from(111u64),
+            Fr::from(222u64),
+            steps,
+            &registry(),
+            u64::MAX,
+            0,
+            Fr::from(12345u64),
+        );
+        assert_eq!(circuit.volumes, vec![1000, 1100]);
+        assert_eq!(circuit.signal_hash, None);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        assert!(circuit.generate_constraints(cs).is_err());
+    }
+
+    #[test]
+    fn test_volume_underflow_unprovable() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let root0 = tree.root();
+
+        // 100 * 10 = 1000 > 900 at the first step
+        let steps = vec![apply(&mut tree, 1, 100, OpType::Withdraw, 10)];
+
+        let circuit = BatchStateTransitionCircuit::new(
+            root0,
+            900,
+            Fr::from(111u64),
+            Fr::from(222u64),
+            steps,
+            &registry(),
+            1200,
+            0,
+            Fr::from(12345u64),
+        );
+        assert_eq!(circuit.volumes, vec![900]);
+        assert_eq!(circuit.signal_hash, None);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        assert!(circuit.generate_constraints(cs).is_err());
+    }
+
+    #[test]
+    fn test_step_volume_must_match_registry() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let root0 = tree.root();
+
+        // Item 2 claimed at volume 1 instead of its registered 5
+        let steps = vec![
+            apply(&mut tree, 2, 20, OpType::Deposit, 1),
+            apply(&mut tree, 3, 10, OpType::Deposit, 2),
+        ];
+
+        let circuit = BatchStateTransitionCircuit::new(
+            root0,
+            1000,
+            Fr::from(111u64),
+            Fr::from(222u64),
+            steps,
+            &registry(),
+            1200,
+            0,
+            Fr::from(12345u64),
+        );
+        assert_eq!(circuit.volumes[MAX_BATCH_OPS], 1040);
+        assert!(!is_satisfied(circuit));
+    }
+}
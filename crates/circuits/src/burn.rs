@@ -0,0 +1,189 @@
+//! Burn Circuit: provably destroy items with a public burned amount.
+//!
+//! Same constraints as `SpendOnlyTransitionCircuit` (a withdrawal that can
+//! never grow the inventory), with the withdrawn amount exposed so an on-chain
+//! contract can decrement a global supply counter.
+//!
+//! Public inputs (in order):
+//! - signal_hash: Poseidon hash binding all operation parameters
+//! - nonce: Replay protection (verified on-chain against inventory.nonce)
+//! - inventory_id: Cross-inventory protection (verified on-chain)
+//! - registry_root: Volume registry commitment (verified against VolumeRegistry)
+//! - burn_amount: Units of `item_id` destroyed
+
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::signal::OpType;
+use crate::smt::MerkleProof;
+use crate::state_transition::{StateTransitionCircuit, TransitionKind};
+use crate::volume_registry::{VolumeRegistry, MAX_ITEM_TYPES};
+
+/// Burn Circuit.
+///
+/// Proves a withdrawal of `burn_amount` units that leave circulation.
+#[derive(Clone)]
+pub struct BurnCircuit {
+    /// Underlying state transition witnesses
+    pub transition: StateTransitionCircuit,
+}
+
+impl BurnCircuit {
+    /// Create a new empty circuit for setup.
+    /// Uses a zero-amount burn from an empty tree, so the dummy witnesses
+    /// also satisfy the burn constraints.
+    pub fn empty() -> Self {
+        use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+
+        let tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        let root = tree.root();
+        let proof = tree.get_proof(0);
+
+        Self::new(
+            root,
+            0,
+            Fr::from(0u64),
+            root,
+            0,
+            Fr::from(0u64),
+            0,
+            0,
+            0,
+            0,
+            proof,
+            &VolumeRegistry::new([0; MAX_ITEM_TYPES]),
+            0,
+            0,
+            Fr::from(0u64),
+        )
+    }
+
+    /// Create a new burn circuit with all witnesses.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        old_inventory_root: Fr,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_inventory_root: Fr,
+        new_volume: u64,
+        new_blinding: Fr,
+        item_id: u64,
+        old_quantity: u64,
+        new_quantity: u64,
+        burn_amount: u64,
+        inventory_proof: MerkleProof<Fr>,
+        registry: &VolumeRegistry,
+        max_capacity: u64,
+        nonce: u64,
+        inventory_id: Fr,
+    ) -> Self {
+        Self::from_transition(StateTransitionCircuit::new(
+            old_inventory_root,
+            old_volume,
+            old_blinding,
+            new_inventory_root,
+            new_volume,
+            new_blinding,
+            item_id,
+            old_quantity,
+            new_quantity,
+            burn_amount,
+            OpType::Withdraw,
+            inventory_proof,
+            registry,
+            max_capacity,
+            nonce,
+            inventory_id,
+        ))
+    }
+
+    /// Wrap an existing state transition.
+    ///
+    /// The constraints reject the witnesses unless the transition is a withdrawal.
+    pub fn from_transition(transition: StateTransitionCircuit) -> Self {
+        Self { transition }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for BurnCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        self.transition.synthesize(cs, TransitionKind::Burn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Burn `amount` of item 1 (volume 10 per unit), claiming `new_quantity` remains.
+    fn burn(tree: &mut SparseMerkleTree, amount: u64, new_quantity: u64) -> BurnCircuit {
+        let old_root = tree.root();
+        let old_quantity = tree.get(1);
+        let proof = tree.get_proof(1);
+        tree.update(1, new_quantity);
+
+        BurnCircuit::new(
+            old_root,
+            old_quantity * 10,
+            Fr::from(12345u64),
+            tree.root(),
+            new_quantity * 10,
+            Fr::from(67890u64),
+            1,
+            old_quantity,
+            new_quantity,
+            amount,
+            proof,
+            &VolumeRegistry::from_entries(&[(1, 10)]),
+            10000,
+            0,
+            Fr::from(12345678u64),
+        )
+    }
+
+    #[test]
+    fn test_empty_circuit_satisfied() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        BurnCircuit::empty().generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_burn_exposes_amount() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let circuit = burn(&mut tree, 30, 70);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // Instance assignment starts with the constant one
+        let inputs = cs.borrow().unwrap().instance_assignment.clone();
+        assert_eq!(inputs.len(), 6);
+        assert_eq!(inputs[5], Fr::from(30u64));
+    }
+
+    #[test]
+    fn test_burn_to_zero() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100), (2, 5)], DEFAULT_DEPTH);
+        let circuit = burn(&mut tree, 100, 0);
+        assert_eq!(tree.get(1), 0);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_burn_exceeding_quantity_rejected() {
+        // Burning 150 of 100 held, claiming the item is simply gone
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let circuit = burn(&mut tree, 150, 0);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
@@ -0,0 +1,198 @@
+//! Capacity Proof Circuit with a hidden capacity limit.
+//!
+//! Same statement as `CapacitySMTCircuit` (current_volume <= max_capacity), but
+//! max_capacity is never hashed into the public input directly. Instead the
+//! verifier holds a commitment to the capacity (e.g. stored on-chain):
+//!
+//! cap_commitment = Poseidon(max_capacity, cap_blinding)
+//!
+//! Public input: Poseidon(commitment, cap_commitment)
+//!
+//! Observers who know the inventory commitment cannot brute-force the capacity
+//! from the public input without the capacity blinding.
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_domain, poseidon_hash_domain_var, Domain};
+use crate::range_check::{enforce_u32_range, enforce_within_capacity};
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+
+/// Compute the commitment to a hidden capacity limit.
+pub fn compute_capacity_commitment(max_capacity: u64, cap_blinding: Fr) -> Fr {
+    poseidon_hash_domain(Domain::CapacityCommitment, &[Fr::from(max_capacity), cap_blinding])
+}
+
+/// Compute the public input hash for a hidden-capacity proof.
+pub fn compute_hidden_capacity_hash(commitment: Fr, cap_commitment: Fr) -> Fr {
+    poseidon_hash_domain(Domain::HiddenCapacity, &[commitment, cap_commitment])
+}
+
+/// Capacity Proof Circuit with hidden max_capacity.
+///
+/// Proves current_volume <= max_capacity where max_capacity is only bound
+/// through its commitment.
+#[derive(Clone)]
+pub struct CapacityHiddenSMTCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Commitment components (witnesses)
+    /// Inventory SMT root
+    pub inventory_root: Option<Fr>,
+    /// Current volume (witness - what we're proving about)
+    pub current_volume: Option<u64>,
+    /// Blinding factor
+    pub blinding: Option<Fr>,
+
+    // Capacity commitment components (witnesses)
+    /// Maximum allowed capacity
+    pub max_capacity: Option<u64>,
+    /// Blinding factor for the capacity commitment
+    pub cap_blinding: Option<Fr>,
+}
+
+impl CapacityHiddenSMTCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses an empty inventory with zero capacity so the dummy witnesses satisfy it.
+    pub fn empty() -> Self {
+        Self::new(Fr::from(0u64), 0, Fr::from(0u64), 0, Fr::from(0u64))
+    }
+
+    /// Create a new circuit with witnesses.
+    pub fn new(
+        inventory_root: Fr,
+        current_volume: u64,
+        blinding: Fr,
+        max_capacity: u64,
+        cap_blinding: Fr,
+    ) -> Self {
+        let commitment = create_smt_commitment(inventory_root, current_volume, blinding);
+        let cap_commitment = compute_capacity_commitment(max_capacity, cap_blinding);
+        let public_hash = compute_hidden_capacity_hash(commitment, cap_commitment);
+
+        Self {
+            public_hash: Some(public_hash),
+            inventory_root: Some(inventory_root),
+            current_volume: Some(current_volume),
+            blinding: Some(blinding),
+            max_capacity: Some(max_capacity),
+            cap_blinding: Some(cap_blinding),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for CapacityHiddenSMTCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate commitment witnesses ===
+        let root_var = FpVar::new_witness(cs.clone(), || {
+            self.inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let volume_var = FpVar::new_witness(cs.clone(), || {
+            self.current_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate capacity witnesses ===
+        let max_capacity_var = FpVar::new_witness(cs.clone(), || {
+            self.max_capacity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let cap_blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.cap_blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Constraint 1: Compute commitments ===
+        let commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &root_var,
+            &volume_var,
+            &blinding_var,
+        )?;
+        let cap_commitment_var = poseidon_hash_domain_var(
+            cs.clone(),
+            Domain::CapacityCommitment,
+            &[max_capacity_var.clone(), cap_blinding_var],
+        )?;
+
+        // === Constraint 2: Verify public hash ===
+        let computed_hash = poseidon_hash_domain_var(
+            cs.clone(),
+            Domain::HiddenCapacity,
+            &[commitment_var, cap_commitment_var],
+        )?;
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        // === Constraint 3: current_volume <= max_capacity ===
+        enforce_u32_range(cs.clone(), &volume_var)?;
+        enforce_within_capacity(cs, &max_capacity_var, &volume_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capacity_smt::compute_capacity_hash;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    fn test_hidden_capacity_valid() {
+        let circuit = CapacityHiddenSMTCircuit::new(
+            Fr::from(12345u64),
+            500,
+            Fr::from(111u64),
+            1000,
+            Fr::from(222u64),
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_hidden_capacity_exceeded() {
+        let circuit = CapacityHiddenSMTCircuit::new(
+            Fr::from(12345u64),
+            1500,
+            Fr::from(111u64),
+            1000,
+            Fr::from(222u64),
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_public_hash_does_not_reveal_capacity() {
+        let root = Fr::from(12345u64);
+        let blinding = Fr::from(111u64);
+        let commitment = create_smt_commitment(root, 500, blinding);
+
+        let circuit = CapacityHiddenSMTCircuit::new(root, 500, blinding, 1000, Fr::from(222u64));
+        let public_hash = circuit.public_hash.unwrap();
+
+        // Guessing the capacity against the plain capacity hash does not work
+        assert_ne!(public_hash, compute_capacity_hash(commitment, 1000));
+
+        // It matches only with the capacity commitment
+        let cap_commitment = compute_capacity_commitment(1000, Fr::from(222u64));
+        assert_eq!(public_hash, compute_hidden_capacity_hash(commitment, cap_commitment));
+    }
+}
@@ -12,7 +12,8 @@ use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::prelude::*;
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
 
-use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::poseidon::{poseidon_hash_domain, poseidon_hash_domain_var, Domain};
+use crate::range_check::{enforce_u32_range, enforce_within_capacity};
 use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
 
 /// Compute the public input hash for Capacity proof.
@@ -24,7 +25,7 @@ pub fn compute_capacity_hash(
         commitment,
         Fr::from(max_capacity),
     ];
-    poseidon_hash_many(&inputs)
+    poseidon_hash_domain(Domain::Capacity, &inputs)
 }
 
 /// Capacity Proof Circuit for SMT-based inventory.
@@ -50,15 +51,10 @@ pub struct CapacitySMTCircuit {
 
 impl CapacitySMTCircuit {
     /// Create an empty circuit for setup.
-    /// Uses dummy values that produce valid constraint structure.
+    /// Uses an empty inventory with zero capacity, so the dummy witnesses
+    /// produce a valid constraint structure and also satisfy it.
     pub fn empty() -> Self {
-        Self {
-            public_hash: Some(Fr::from(0u64)),
-            inventory_root: Some(Fr::from(0u64)),
-            current_volume: Some(0),
-            blinding: Some(Fr::from(0u64)),
-            max_capacity: Some(0),
-        }
+        Self::new(Fr::from(0u64), 0, Fr::from(0u64), 0)
     }
 
     /// Create a new circuit with witnesses.
@@ -131,23 +127,15 @@ impl ConstraintSynthesizer<Fr> for CapacitySMTCircuit {
             commitment_var,
             max_capacity_var.clone(),
         ];
-        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+        let computed_hash = poseidon_hash_domain_var(cs.clone(), Domain::Capacity, &inputs)?;
 
         computed_hash.enforce_equal(&public_hash_var)?;
 
         // === Constraint 3: current_volume <= max_capacity ===
-        // The prover can only provide valid witnesses if this holds
-        // The commitment binds the volume, and the public hash binds max_capacity
-        // So a successful proof implies the constraint holds
-
-        // For a rigorous proof, we'd need a range check:
-        // remaining = max_capacity - current_volume
-        // prove remaining >= 0 using bit decomposition
-
-        // For now, we rely on the binding properties:
-        // - commitment binds (root, volume, blinding)
-        // - public_hash binds (commitment, max_capacity)
-        // - prover must know valid witnesses to satisfy all constraints
+        // The volume is range checked, so (max_capacity - current_volume)
+        // fitting in 64 bits means it did not wrap around
+        enforce_u32_range(cs.clone(), &volume_var)?;
+        enforce_within_capacity(cs, &max_capacity_var, &volume_var)?;
 
         Ok(())
     }
@@ -262,4 +250,44 @@ mod tests {
         // Should fail because commitment won't match
         assert!(!cs.is_satisfied().unwrap());
     }
+
+    #[test]
+    fn test_capacity_over_limit_rejected() {
+        let tree = SparseMerkleTree::from_items(
+            &[(1, 100)],
+            DEFAULT_DEPTH,
+        );
+        let root = tree.root();
+
+        let blinding = Fr::from(12345u64);
+        let volume = 1001u64; // One over capacity
+        let max_capacity = 1000u64;
+
+        // The commitment and public hash are consistent; only the limit is broken
+        let circuit = CapacitySMTCircuit::new(
+            root,
+            volume,
+            blinding,
+            max_capacity,
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_capacity_full_u64_range() {
+        let tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+
+        // The remaining capacity can use all 64 bits
+        for volume in [0u64, 1000, u32::MAX as u64] {
+            let circuit = CapacitySMTCircuit::new(tree.root(), volume, Fr::from(12345u64), u64::MAX);
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            assert!(cs.is_satisfied().unwrap(), "volume {} rejected", volume);
+        }
+    }
 }
@@ -0,0 +1,408 @@
+//! DepositItemExists Circuit: restock an item and prove a minimum holding in one proof.
+//!
+//! Proves a deposit of `amount` units of `item_id` (a full state transition:
+//! SMT update, quantity and volume change, capacity check) and that the
+//! item's quantity after the deposit is at least `min_quantity`. The deposit
+//! step already proves the new root holds the new quantity, so no second
+//! Merkle proof is needed.
+//!
+//! Public inputs (in order):
+//! - signal_hash: Poseidon(old_commitment, new_commitment, registry_root,
+//!   max_capacity, item_id, amount, min_quantity, nonce, inventory_id)
+//! - nonce: Replay protection (verified on-chain against inventory.nonce)
+//! - inventory_id: Cross-inventory protection (verified on-chain)
+//! - registry_root: Volume registry commitment (verified against VolumeRegistry)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_domain, poseidon_hash_domain_var, Domain};
+use crate::range_check::{enforce_geq, enforce_u32_range};
+use crate::signal::OpType;
+use crate::smt::{MerkleProof, MerkleProofVar};
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+use crate::state_transition::{enforce_transition_step, TransitionStepVars};
+use crate::volume_registry::{
+    alloc_volumes_var, compute_registry_hash, VolumeRegistry, MAX_ITEM_TYPES,
+};
+
+/// Compute the signal hash of a deposit with a minimum-quantity claim.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_deposit_item_exists_signal_hash(
+    old_commitment: Fr,
+    new_commitment: Fr,
+    registry_root: Fr,
+    max_capacity: u64,
+    item_id: u64,
+    amount: u64,
+    min_quantity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+) -> Fr {
+    poseidon_hash_domain(Domain::DepositItemExists, &[
+        old_commitment,
+        new_commitment,
+        registry_root,
+        Fr::from(max_capacity),
+        Fr::from(item_id),
+        Fr::from(amount),
+        Fr::from(min_quantity),
+        Fr::from(nonce),
+        inventory_id,
+    ])
+}
+
+/// DepositItemExists Circuit.
+///
+/// Proves a valid deposit after which the inventory holds at least `min_quantity`.
+#[derive(Clone)]
+pub struct DepositItemExistsCircuit {
+    // Public inputs
+    /// Signal hash binding both commitments, the deposit and the claim
+    pub signal_hash: Option<Fr>,
+    /// Current inventory nonce
+    pub nonce: Option<u64>,
+    /// Inventory object ID
+    pub inventory_id: Option<Fr>,
+    /// Registry root (commitment to volume table)
+    pub registry_root: Option<Fr>,
+
+    // Old state witnesses
+    /// Old inventory SMT root
+    pub old_inventory_root: Option<Fr>,
+    /// Old total volume
+    pub old_volume: Option<u64>,
+    /// Old blinding factor
+    pub old_blinding: Option<Fr>,
+
+    // New state witnesses
+    /// New inventory SMT root
+    pub new_inventory_root: Option<Fr>,
+    /// New total volume
+    pub new_volume: Option<u64>,
+    /// New blinding factor
+    pub new_blinding: Option<Fr>,
+
+    // Deposit witnesses
+    /// Item ID being deposited
+    pub item_id: Option<u64>,
+    /// Quantity of the item before the deposit
+    pub old_quantity: Option<u64>,
+    /// Units deposited
+    pub amount: Option<u64>,
+    /// Proof for the item against the old root
+    pub inventory_proof: Option<MerkleProof<Fr>>,
+    /// Volume per unit of the item
+    pub item_volume: Option<u64>,
+    /// Full volume table; must hash to `registry_root` and hold `item_volume`
+    /// at `item_id`
+    pub registry_volumes: Option<[u64; MAX_ITEM_TYPES]>,
+    /// Maximum allowed volume after the deposit
+    pub max_capacity: Option<u64>,
+    /// Minimum quantity claimed after the deposit
+    pub min_quantity: Option<u64>,
+}
+
+impl DepositItemExistsCircuit {
+    /// Create an empty circuit for setup.
+    /// Deposits nothing of item 0 into an empty tree and claims a minimum of
+    /// zero, so the dummy witnesses also satisfy the constraints.
+    pub fn empty() -> Self {
+        use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+
+        let tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        let proof = tree.get_proof(0);
+
+        Self::new(
+            tree.root(),
+            0,
+            Fr::from(0u64),
+            Fr::from(0u64),
+            0,
+            0,
+            0,
+            proof,
+            &VolumeRegistry::new([0; MAX_ITEM_TYPES]),
+            0,
+            0,
+            0,
+            Fr::from(0u64),
+        )
+    }
+
+    /// Create a new circuit from the old state and the deposit.
+    ///
+    /// The item's volume and the registry root are taken from `registry`.
+    /// The new root and volume are derived from the proof; a minimum above the
+    /// post-deposit quantity yields witnesses the constraints reject. A new
+    /// volume that does not fit in a u64 leaves it and the signal hash unset,
+    /// so synthesis fails with `AssignmentMissing`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        old_inventory_root: Fr,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_blinding: Fr,
+        item_id: u64,
+        old_quantity: u64,
+        amount: u64,
+        inventory_proof: MerkleProof<Fr>,
+        registry: &VolumeRegistry,
+        max_capacity: u64,
+        min_quantity: u64,
+        nonce: u64,
+        inventory_id: Fr,
+    ) -> Self {
+        let item_volume = registry.get_volume(item_id);
+        let registry_root = compute_registry_hash(registry);
+        let new_inventory_root = inventory_proof.compute_root(item_id, old_quantity + amount);
+        let new_volume = amount
+            .checked_mul(item_volume)
+            .and_then(|delta| old_volume.checked_add(delta));
+
+        let signal_hash = new_volume.map(|new_volume| {
+            compute_deposit_item_exists_signal_hash(
+                create_smt_commitment(old_inventory_root, old_volume, old_blinding),
+                create_smt_commitment(new_inventory_root, new_volume, new_blinding),
+                registry_root,
+                max_capacity,
+                item_id,
+                amount,
+                min_quantity,
+                nonce,
+                inventory_id,
+            )
+        });
+
+        Self {
+            signal_hash,
+            nonce: Some(nonce),
+            inventory_id: Some(inventory_id),
+            registry_root: Some(registry_root),
+            old_inventory_root: Some(old_inventory_root),
+            old_volume: Some(old_volume),
+            old_blinding: Some(old_blinding),
+            new_inventory_root: Some(new_inventory_root),
+            new_volume,
+            new_blinding: Some(new_blinding),
+            item_id: Some(item_id),
+            old_quantity: Some(old_quantity),
+            amount: Some(amount),
+            inventory_proof: Some(inventory_proof),
+            item_volume: Some(item_volume),
+            registry_volumes: Some(*registry.volumes()),
+            max_capacity: Some(max_capacity),
+            min_quantity: Some(min_quantity),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for DepositItemExistsCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public inputs ===
+        // Order matters: signal_hash, nonce, inventory_id, registry_root
+        let signal_hash_var = FpVar::new_input(cs.clone(), || {
+            self.signal_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let nonce_var = FpVar::new_input(cs.clone(), || {
+            self.nonce.map(Fr::from).ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let inventory_id_var = FpVar::new_input(cs.clone(), || {
+            self.inventory_id.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let registry_root_var = FpVar::new_input(cs.clone(), || {
+            self.registry_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate witnesses ===
+        let field = |value: Option<Fr>| {
+            FpVar::new_witness(cs.clone(), || value.ok_or(SynthesisError::AssignmentMissing))
+        };
+        let witness = |value: Option<u64>| field(value.map(Fr::from));
+
+        let old_root_var = field(self.old_inventory_root)?;
+        let old_volume_var = witness(self.old_volume)?;
+        let old_blinding_var = field(self.old_blinding)?;
+        let new_root_var = field(self.new_inventory_root)?;
+        let new_volume_var = witness(self.new_volume)?;
+        let new_blinding_var = field(self.new_blinding)?;
+
+        let item_id_var = witness(self.item_id)?;
+        let old_qty_var = witness(self.old_quantity)?;
+        let amount_var = witness(self.amount)?;
+        let item_volume_var = witness(self.item_volume)?;
+        let max_capacity_var = witness(self.max_capacity)?;
+        let min_qty_var = witness(self.min_quantity)?;
+        let registry_volume_vars = alloc_volumes_var(cs.clone(), self.registry_volumes.as_ref())?;
+
+        let proof_var = MerkleProofVar::new_witness(
+            cs.clone(),
+            self.inventory_proof.as_ref().unwrap(),
+        )?;
+
+        // === Constraint 1: Deposit within capacity ===
+        let new_qty_var = &old_qty_var + &amount_var;
+        let deposit = TransitionStepVars {
+            old_root: old_root_var.clone(),
+            new_root: new_root_var.clone(),
+            old_volume: old_volume_var.clone(),
+            new_volume: new_volume_var.clone(),
+            item_id: item_id_var.clone(),
+            old_quantity: old_qty_var,
+            new_quantity: new_qty_var.clone(),
+            amount: amount_var.clone(),
+            op_type: FpVar::constant(OpType::Deposit.to_field()),
+            item_volume: item_volume_var,
+            registry_root: registry_root_var.clone(),
+            registry_volumes: registry_volume_vars,
+            proof: proof_var,
+        };
+        let _ = enforce_transition_step(cs.clone(), &deposit, &max_capacity_var)?;
+
+        // === Constraint 2: new_quantity >= min_quantity ===
+        // new_quantity is the leaf written into new_root and is already range
+        // checked by the deposit step
+        enforce_u32_range(cs.clone(), &min_qty_var)?;
+        enforce_geq(cs.clone(), &new_qty_var, &min_qty_var)?;
+
+        // === Constraint 3: Commitments to both states ===
+        let old_commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &old_root_var,
+            &old_volume_var,
+            &old_blinding_var,
+        )?;
+        let new_commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &new_root_var,
+            &new_volume_var,
+            &new_blinding_var,
+        )?;
+
+        // === Constraint 4: Signal hash binds both commitments, the deposit and the claim ===
+        let computed_signal = poseidon_hash_domain_var(
+            cs,
+            Domain::DepositItemExists,
+            &[
+                old_commitment_var,
+                new_commitment_var,
+                registry_root_var,
+                max_capacity_var,
+                item_id_var,
+                amount_var,
+                min_qty_var,
+                nonce_var,
+                inventory_id_var,
+            ],
+        )?;
+        computed_signal.enforce_equal(&signal_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Deposit `amount` of item 1 (volume 10 per unit, 20 held) and claim `min_quantity`.
+    fn restock(amount: u64, min_quantity: u64) -> DepositItemExistsCircuit {
+        let tree = SparseMerkleTree::from_items(&[(1, 20), (2, 5)], DEFAULT_DEPTH);
+        DepositItemExistsCircuit::new(
+            tree.root(),
+            250,
+            Fr::from(12345u64),
+            Fr::from(67890u64),
+            1,
+            20,
+            amount,
+            tree.get_proof(1),
+            &VolumeRegistry::from_entries(&[(1, 10), (2, 10)]),
+            1000,
+            min_quantity,
+            3,
+            Fr::from(12345678u64),
+        )
+    }
+
+    fn is_satisfied(circuit: DepositItemExistsCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_empty_circuit_satisfied() {
+        assert!(is_satisfied(DepositItemExistsCircuit::empty()));
+    }
+
+    #[test]
+    fn test_restock_then_claim() {
+        let circuit = restock(30, 40);
+
+        let mut tree = SparseMerkleTree::from_items(&[(1, 20), (2, 5)], DEFAULT_DEPTH);
+        tree.update(1, 50);
+        assert_eq!(circuit.new_inventory_root, Some(tree.root()));
+        assert_eq!(circuit.new_volume, Some(550));
+        assert!(is_satisfied(circuit));
+
+        // The claim may equal the post-deposit quantity
+        assert!(is_satisfied(restock(30, 50)));
+    }
+
+    #[test]
+    fn test_claim_above_post_deposit_quantity_rejected() {
+        // 20 + 30 = 50 < 51
+        assert!(!is_satisfied(restock(30, 51)));
+    }
+
+    #[test]
+    fn test_capacity_still_enforced() {
+        // 250 + 80 * 10 = 1050 > 1000
+        assert!(!is_satisfied(restock(80, 10)));
+    }
+
+    #[test]
+    fn test_volume_overflow_unprovable() {
+        // 250 + u64::MAX / 10 * 10 overflows u64
+        let circuit = restock(u64::MAX / 10, 10);
+        assert_eq!(circuit.new_volume, None);
+        assert_eq!(circuit.signal_hash, None);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        assert!(circuit.generate_constraints(cs).is_err());
+    }
+
+    #[test]
+    fn test_understated_min_rejected() {
+        // Signal committed to a minimum of 40, witness claims 10
+        let mut circuit = restock(30, 10);
+        circuit.signal_hash = restock(30, 40).signal_hash;
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_item_volume_must_match_registry() {
+        // Price item 1 at 1 instead of its registered 10, with a new volume
+        // and signal hash consistent with that claim
+        let mut circuit = restock(30, 40);
+        circuit.item_volume = Some(1);
+        circuit.new_volume = Some(280);
+        circuit.signal_hash = Some(compute_deposit_item_exists_signal_hash(
+            create_smt_commitment(circuit.old_inventory_root.unwrap(), 250, Fr::from(12345u64)),
+            create_smt_commitment(circuit.new_inventory_root.unwrap(), 280, Fr::from(67890u64)),
+            circuit.registry_root.unwrap(),
+            1000,
+            1,
+            30,
+            40,
+            3,
+            Fr::from(12345678u64),
+        ));
+        assert!(!is_satisfied(circuit));
+    }
+}
@@ -0,0 +1,451 @@
+//! Double Transition Circuit: two sequential deposits/withdrawals in one proof.
+//!
+//! Proves state0 -> state1 -> state2, where each step is a full state
+//! transition (SMT update, quantity and volume change, capacity check). The
+//! intermediate root and volume are witnesses that thread one step into the
+//! next; the intermediate state is never committed to or revealed.
+//!
+//! Public inputs (in order):
+//! - old_commitment: commitment to state0
+//! - new_commitment: commitment to state2
+//! - registry_root: Volume registry commitment shared by both steps
+//! - max_capacity: Capacity limit enforced after each step
+//!
+//! Item ids, amounts and operation types of both steps stay private.
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::signal::OpType;
+use crate::smt::{MerkleProof, MerkleProofVar};
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+use crate::state_transition::{enforce_transition_step, TransitionStepVars};
+use crate::volume_registry::{
+    alloc_volumes_var, compute_registry_hash, VolumeRegistry, MAX_ITEM_TYPES,
+};
+
+/// Witnesses for one step of a `DoubleTransitionCircuit`.
+#[derive(Clone)]
+pub struct TransitionStep {
+    /// Item ID being operated on
+    pub item_id: u64,
+    /// Quantity of the item before the step
+    pub old_quantity: u64,
+    /// Quantity of the item after the step
+    pub new_quantity: u64,
+    /// Amount being deposited/withdrawn
+    pub amount: u64,
+    /// Operation type (deposit/withdraw)
+    pub op_type: OpType,
+    /// Proof for the item against the root before the step
+    pub inventory_proof: MerkleProof<Fr>,
+    /// Volume per unit of this item type; must be item_id's registry entry
+    pub item_volume: u64,
+}
+
+/// Double Transition Circuit.
+///
+/// Proves two valid state transitions applied one after the other.
+#[derive(Clone)]
+pub struct DoubleTransitionCircuit {
+    // Public inputs
+    /// Commitment to state0
+    pub old_commitment: Option<Fr>,
+    /// Commitment to state2
+    pub new_commitment: Option<Fr>,
+    /// Registry root (commitment to volume table)
+    pub registry_root: Option<Fr>,
+    /// Maximum allowed capacity
+    pub max_capacity: Option<u64>,
+
+    // Registry witnesses
+    /// Full volume table; must hash to `registry_root` and hold each step's
+    /// `item_volume` at its `item_id`
+    pub registry_volumes: Option<[u64; MAX_ITEM_TYPES]>,
+
+    // State0 witnesses
+    /// State0 inventory SMT root
+    pub old_inventory_root: Option<Fr>,
+    /// State0 total volume
+    pub old_volume: Option<u64>,
+    /// State0 blinding factor
+    pub old_blinding: Option<Fr>,
+
+    // State1 witnesses (never committed)
+    /// State1 inventory SMT root
+    pub mid_inventory_root: Option<Fr>,
+    /// State1 total volume
+    pub mid_volume: Option<u64>,
+
+    // State2 witnesses
+    /// State2 inventory SMT root
+    pub new_inventory_root: Option<Fr>,
+    /// State2 total volume
+    pub new_volume: Option<u64>,
+    /// State2 blinding factor
+    pub new_blinding: Option<Fr>,
+
+    // Step witnesses
+    /// Step from state0 to state1
+    pub first: Option<TransitionStep>,
+    /// Step from state1 to state2
+    pub second: Option<TransitionStep>,
+}
+
+impl DoubleTransitionCircuit {
+    /// Create a new empty circuit for setup.
+    /// Uses two zero-amount deposits into an empty tree, so the dummy
+    /// witnesses produce a valid constraint structure and also satisfy it.
+    pub fn empty() -> Self {
+        use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+
+        let tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        let root = tree.root();
+        let step = TransitionStep {
+            item_id: 0,
+            old_quantity: 0,
+            new_quantity: 0,
+            amount: 0,
+            op_type: OpType::Deposit,
+            inventory_proof: tree.get_proof(0),
+            item_volume: 0,
+        };
+
+        Self::new(
+            root,
+            0,
+            Fr::from(0u64),
+            root,
+            0,
+            root,
+            0,
+            Fr::from(0u64),
+            step.clone(),
+            step,
+            &VolumeRegistry::new([0; MAX_ITEM_TYPES]),
+            0,
+        )
+    }
+
+    /// Create a new circuit with all witnesses.
+    ///
+    /// The registry root is taken from `registry`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        old_inventory_root: Fr,
+        old_volume: u64,
+        old_blinding: Fr,
+        mid_inventory_root: Fr,
+        mid_volume: u64,
+        new_inventory_root: Fr,
+        new_volume: u64,
+        new_blinding: Fr,
+        first: TransitionStep,
+        second: TransitionStep,
+        registry: &VolumeRegistry,
+        max_capacity: u64,
+    ) -> Self {
+        let old_commitment = create_smt_commitment(old_inventory_root, old_volume, old_blinding);
+        let new_commitment = create_smt_commitment(new_inventory_root, new_volume, new_blinding);
+
+        Self {
+            old_commitment: Some(old_commitment),
+            new_commitment: Some(new_commitment),
+            registry_root: Some(compute_registry_hash(registry)),
+            max_capacity: Some(max_capacity),
+            registry_volumes: Some(*registry.volumes()),
+            old_inventory_root: Some(old_inventory_root),
+            old_volume: Some(old_volume),
+            old_blinding: Some(old_blinding),
+            mid_inventory_root: Some(mid_inventory_root),
+            mid_volume: Some(mid_volume),
+            new_inventory_root: Some(new_inventory_root),
+            new_volume: Some(new_volume),
+            new_blinding: Some(new_blinding),
+            first: Some(first),
+            second: Some(second),
+        }
+    }
+}
+
+/// Allocate the witnesses of one step between the given states.
+#[allow(clippy::too_many_arguments)]
+fn alloc_step(
+    cs: ConstraintSystemRef<Fr>,
+    step: Option<&TransitionStep>,
+    old_root: &FpVar<Fr>,
+    new_root: &FpVar<Fr>,
+    old_volume: &FpVar<Fr>,
+    new_volume: &FpVar<Fr>,
+    registry_root: &FpVar<Fr>,
+    registry_volumes: &[FpVar<Fr>],
+) -> Result<TransitionStepVars, SynthesisError> {
+    let witness = |value: Option<Fr>| {
+        FpVar::new_witness(cs.clone(), || value.ok_or(SynthesisError::AssignmentMissing))
+    };
+
+    Ok(TransitionStepVars {
+        old_root: old_root.clone(),
+        new_root: new_root.clone(),
+        old_volume: old_volume.clone(),
+        new_volume: new_volume.clone(),
+        item_id: witness(step.map(|s| Fr::from(s.item_id)))?,
+        old_quantity: witness(step.map(|s| Fr::from(s.old_quantity)))?,
+        new_quantity: witness(step.map(|s| Fr::from(s.new_quantity)))?,
+        amount: witness(step.map(|s| Fr::from(s.amount)))?,
+        op_type: witness(step.map(|s| s.op_type.to_field()))?,
+        item_volume: witness(step.map(|s| Fr::from(s.item_volume)))?,
+        registry_root: registry_root.clone(),
+        registry_volumes: registry_volumes.to_vec(),
+        proof: MerkleProofVar::new_witness(cs.clone(), &step.unwrap().inventory_proof)?,
+    })
+}
+
+impl ConstraintSynthesizer<Fr> for DoubleTransitionCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public inputs ===
+        // Order matters: old_commitment, new_commitment, registry_root, max_capacity
+        let old_commitment_var = FpVar::new_input(cs.clone(), || {
+            self.old_commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let new_commitment_var = FpVar::new_input(cs.clone(), || {
+            self.new_commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        // registry_root is a public input so it can be verified on-chain against VolumeRegistry
+        let registry_root_var = FpVar::new_input(cs.clone(), || {
+            self.registry_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let max_capacity_var = FpVar::new_input(cs.clone(), || {
+            self.max_capacity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate state witnesses ===
+        let old_root_var = FpVar::new_witness(cs.clone(), || {
+            self.old_inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let old_volume_var = FpVar::new_witness(cs.clone(), || {
+            self.old_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let old_blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.old_blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let mid_root_var = FpVar::new_witness(cs.clone(), || {
+            self.mid_inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let mid_volume_var = FpVar::new_witness(cs.clone(), || {
+            self.mid_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let new_root_var = FpVar::new_witness(cs.clone(), || {
+            self.new_inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let new_volume_var = FpVar::new_witness(cs.clone(), || {
+            self.new_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let new_blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.new_blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate registry witnesses ===
+        let registry_volume_vars = alloc_volumes_var(cs.clone(), self.registry_volumes.as_ref())?;
+
+        // === Constraint 1: state0 -> state1 ===
+        let first = alloc_step(
+            cs.clone(),
+            self.first.as_ref(),
+            &old_root_var,
+            &mid_root_var,
+            &old_volume_var,
+            &mid_volume_var,
+            &registry_root_var,
+            &registry_volume_vars,
+        )?;
+        let _ = enforce_transition_step(cs.clone(), &first, &max_capacity_var)?;
+
+        // === Constraint 2: state1 -> state2 ===
+        let second = alloc_step(
+            cs.clone(),
+            self.second.as_ref(),
+            &mid_root_var,
+            &new_root_var,
+            &mid_volume_var,
+            &new_volume_var,
+            &registry_root_var,
+            &registry_volume_vars,
+        )?;
+        let _ = enforce_transition_step(cs.clone(), &second, &max_capacity_var)?;
+
+        // === Constraint 3: Verify commitments to state0 and state2 ===
+        let computed_old_commitment = create_smt_commitment_var(
+            cs.clone(),
+            &old_root_var,
+            &old_volume_var,
+            &old_blinding_var,
+        )?;
+        computed_old_commitment.enforce_equal(&old_commitment_var)?;
+
+        let computed_new_commitment = create_smt_commitment_var(
+            cs,
+            &new_root_var,
+            &new_volume_var,
+            &new_blinding_var,
+        )?;
+        computed_new_commitment.enforce_equal(&new_commitment_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Item 1 has volume 10, item 2 volume 5.
+    fn registry() -> VolumeRegistry {
+        VolumeRegistry::from_entries(&[(1, 10), (2, 5)])
+    }
+
+    /// Apply one step to `tree`, returning its witnesses.
+    fn apply(
+        tree: &mut SparseMerkleTree,
+        item_id: u64,
+        amount: u64,
+        op_type: OpType,
+        item_volume: u64,
+    ) -> TransitionStep {
+        let old_quantity = tree.get(item_id);
+        let new_quantity = match op_type {
+            OpType::Deposit => old_quantity + amount,
+            OpType::Withdraw => old_quantity - amount,
+        };
+        let inventory_proof = tree.get_proof(item_id);
+        tree.update(item_id, new_quantity);
+
+        TransitionStep {
+            item_id,
+            old_quantity,
+            new_quantity,
+            amount,
+            op_type,
+            inventory_proof,
+            item_volume,
+        }
+    }
+
+    #[test]
+    fn test_empty_circuit_satisfied() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        DoubleTransitionCircuit::empty()
+            .generate_constraints(cs.clone())
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_two_step_sequence() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let root0 = tree.root();
+
+        // Deposit 20 of item 2 (volume 5), then withdraw 30 of item 1 (volume 10)
+        let first = apply(&mut tree, 2, 20, OpType::Deposit, 5);
+        let root1 = tree.root();
+        let second = apply(&mut tree, 1, 30, OpType::Withdraw, 10);
+        let root2 = tree.root();
+
+        let circuit = DoubleTransitionCircuit::new(
+            root0,
+            1000,
+            Fr::from(111u64),
+            root1,
+            1100,
+            root2,
+            800,
+            Fr::from(222u64),
+            first,
+            second,
+            &registry(),
+            1200,
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_intermediate_over_capacity_rejected() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let root0 = tree.root();
+
+        // state1 reaches 1500 > 1200, state2 is back to 1000
+        let first = apply(&mut tree, 2, 100, OpType::Deposit, 5);
+        let root1 = tree.root();
+        let second = apply(&mut tree, 2, 100, OpType::Withdraw, 5);
+        let root2 = tree.root();
+        assert_eq!(root2, root0);
+
+        let circuit = DoubleTransitionCircuit::new(
+            root0,
+            1000,
+            Fr::from(111u64),
+            root1,
+            1500,
+            root2,
+            1000,
+            Fr::from(222u64),
+            first,
+            second,
+            &registry(),
+            1200,
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_step_volume_must_match_registry() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let root0 = tree.root();
+
+        // Withdraw 30 of item 1 priced at 5 instead of its registered 10, so
+        // the volumes move by 150 instead of 300
+        let first = apply(&mut tree, 2, 20, OpType::Deposit, 5);
+        let root1 = tree.root();
+        let second = apply(&mut tree, 1, 30, OpType::Withdraw, 5);
+        let root2 = tree.root();
+
+        let circuit = DoubleTransitionCircuit::new(
+            root0,
+            1000,
+            Fr::from(111u64),
+            root1,
+            1100,
+            root2,
+            950,
+            Fr::from(222u64),
+            first,
+            second,
+            &registry(),
+            1200,
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
@@ -0,0 +1,225 @@
+//! ItemExistsWithCapacity Circuit for SMT-based inventory.
+//!
+//! Proves in one proof that an inventory contains at least a minimum quantity
+//! of an item and that its total volume is within a capacity, e.g. to gate a
+//! marketplace listing.
+//!
+//! Public input: Poseidon(commitment, item_id, min_quantity, max_capacity)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_domain, poseidon_hash_domain_var, Domain};
+use crate::range_check::{enforce_geq, enforce_u32_range, enforce_within_capacity};
+use crate::smt::{verify_membership, MerkleProof, MerkleProofVar};
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+
+/// Compute the public input hash for ItemExistsWithCapacity proof.
+pub fn compute_item_exists_capacity_hash(
+    commitment: Fr,
+    item_id: u64,
+    min_quantity: u64,
+    max_capacity: u64,
+) -> Fr {
+    poseidon_hash_domain(Domain::ItemExistsCapacity, &[
+        commitment,
+        Fr::from(item_id),
+        Fr::from(min_quantity),
+        Fr::from(max_capacity),
+    ])
+}
+
+/// ItemExistsWithCapacity Circuit for SMT-based inventory.
+#[derive(Clone)]
+pub struct ItemExistsWithCapacityCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Commitment components (witnesses)
+    /// Inventory SMT root
+    pub inventory_root: Option<Fr>,
+    /// Current volume
+    pub current_volume: Option<u64>,
+    /// Blinding factor
+    pub blinding: Option<Fr>,
+
+    // Item details (witnesses)
+    /// Item ID to prove
+    pub item_id: Option<u64>,
+    /// Actual quantity (must be >= min_quantity)
+    pub actual_quantity: Option<u64>,
+    /// Minimum quantity to prove
+    pub min_quantity: Option<u64>,
+    /// Maximum allowed volume
+    pub max_capacity: Option<u64>,
+
+    // Merkle proof
+    /// Proof for item in SMT
+    pub proof: Option<MerkleProof<Fr>>,
+}
+
+impl ItemExistsWithCapacityCircuit {
+    /// Create an empty circuit for setup.
+    /// Proves zero of item 0 in an empty tree within zero capacity, so the
+    /// dummy witnesses also satisfy the constraints.
+    pub fn empty() -> Self {
+        use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+
+        let tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        let proof = tree.get_proof(0);
+
+        Self::new(tree.root(), 0, Fr::from(0u64), 0, 0, 0, 0, proof)
+    }
+
+    /// Create a new circuit with witnesses.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        inventory_root: Fr,
+        current_volume: u64,
+        blinding: Fr,
+        item_id: u64,
+        actual_quantity: u64,
+        min_quantity: u64,
+        max_capacity: u64,
+        proof: MerkleProof<Fr>,
+    ) -> Self {
+        let commitment = create_smt_commitment(inventory_root, current_volume, blinding);
+        let public_hash =
+            compute_item_exists_capacity_hash(commitment, item_id, min_quantity, max_capacity);
+
+        Self {
+            public_hash: Some(public_hash),
+            inventory_root: Some(inventory_root),
+            current_volume: Some(current_volume),
+            blinding: Some(blinding),
+            item_id: Some(item_id),
+            actual_quantity: Some(actual_quantity),
+            min_quantity: Some(min_quantity),
+            max_capacity: Some(max_capacity),
+            proof: Some(proof),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for ItemExistsWithCapacityCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate commitment witnesses ===
+        let root_var = FpVar::new_witness(cs.clone(), || {
+            self.inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let witness = |value: Option<u64>| {
+            FpVar::new_witness(cs.clone(), || {
+                value.map(Fr::from).ok_or(SynthesisError::AssignmentMissing)
+            })
+        };
+        let volume_var = witness(self.current_volume)?;
+        let blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate item and capacity witnesses ===
+        let item_id_var = witness(self.item_id)?;
+        let actual_qty_var = witness(self.actual_quantity)?;
+        let min_qty_var = witness(self.min_quantity)?;
+        let max_capacity_var = witness(self.max_capacity)?;
+
+        // === Allocate Merkle proof ===
+        let proof_var = MerkleProofVar::new_witness(
+            cs.clone(),
+            self.proof.as_ref().unwrap(),
+        )?;
+
+        // === Constraint 1: Verify membership in SMT ===
+        verify_membership(
+            cs.clone(),
+            &root_var,
+            &item_id_var,
+            &actual_qty_var,
+            &proof_var,
+        )?;
+
+        // === Constraint 2: actual_quantity >= min_quantity ===
+        // Both sides are range checked so the difference cannot wrap around
+        enforce_u32_range(cs.clone(), &actual_qty_var)?;
+        enforce_u32_range(cs.clone(), &min_qty_var)?;
+        enforce_geq(cs.clone(), &actual_qty_var, &min_qty_var)?;
+
+        // === Constraint 3: current_volume <= max_capacity ===
+        enforce_u32_range(cs.clone(), &volume_var)?;
+        enforce_within_capacity(cs.clone(), &max_capacity_var, &volume_var)?;
+
+        // === Constraint 4: Compute commitment using Poseidon ===
+        let commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &root_var,
+            &volume_var,
+            &blinding_var,
+        )?;
+
+        // === Constraint 5: Compute and verify public hash using Poseidon ===
+        let computed_hash = poseidon_hash_domain_var(
+            cs,
+            Domain::ItemExistsCapacity,
+            &[commitment_var, item_id_var, min_qty_var, max_capacity_var],
+        )?;
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Claim `min_quantity` of item 42 (100 held) with volume 1000.
+    fn circuit(min_quantity: u64, max_capacity: u64) -> ItemExistsWithCapacityCircuit {
+        let tree = SparseMerkleTree::from_items(&[(42, 100), (7, 3)], DEFAULT_DEPTH);
+        ItemExistsWithCapacityCircuit::new(
+            tree.root(),
+            1000,
+            Fr::from(12345u64),
+            42,
+            100,
+            min_quantity,
+            max_capacity,
+            tree.get_proof(42),
+        )
+    }
+
+    fn is_satisfied(circuit: ItemExistsWithCapacityCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_empty_circuit_satisfied() {
+        assert!(is_satisfied(ItemExistsWithCapacityCircuit::empty()));
+    }
+
+    #[test]
+    fn test_within_both_bounds() {
+        assert!(is_satisfied(circuit(50, 2000)));
+        assert!(is_satisfied(circuit(100, 1000)));
+    }
+
+    #[test]
+    fn test_below_min_quantity_rejected() {
+        assert!(!is_satisfied(circuit(101, 2000)));
+    }
+
+    #[test]
+    fn test_over_capacity_rejected() {
+        assert!(!is_satisfied(circuit(50, 999)));
+    }
+}
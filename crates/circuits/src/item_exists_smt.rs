@@ -5,6 +5,9 @@
 //!
 //! Public input: Poseidon(commitment, item_id, min_quantity)
 //!
+//! `with_inventory_id` switches the commitment to the id-bound form, so the
+//! proof only opens a commitment issued for that inventory.
+//!
 //! This allows proving ownership without revealing exact quantities.
 
 use ark_bn254::Fr;
@@ -12,9 +15,12 @@ use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::prelude::*;
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
 
-use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::poseidon::{poseidon_hash_domain, poseidon_hash_domain_var, Domain};
 use crate::smt::{verify_membership, MerkleProof, MerkleProofVar};
-use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+use crate::smt_commitment::{
+    create_smt_commitment, create_smt_commitment_var, create_smt_commitment_with_id,
+    create_smt_commitment_with_id_var,
+};
 
 /// Compute the public input hash for ItemExists proof.
 pub fn compute_item_exists_hash(
@@ -27,7 +33,7 @@ pub fn compute_item_exists_hash(
         Fr::from(item_id),
         Fr::from(min_quantity),
     ];
-    poseidon_hash_many(&inputs)
+    poseidon_hash_domain(Domain::ItemExists, &inputs)
 }
 
 /// ItemExists Circuit for SMT-based inventory.
@@ -43,6 +49,8 @@ pub struct ItemExistsSMTCircuit {
     pub current_volume: Option<u64>,
     /// Blinding factor
     pub blinding: Option<Fr>,
+    /// Inventory id bound into the commitment (None for an unbound commitment)
+    pub inventory_id: Option<Fr>,
 
     // Item details (witnesses)
     /// Item ID to prove
@@ -59,26 +67,20 @@ pub struct ItemExistsSMTCircuit {
 
 impl ItemExistsSMTCircuit {
     /// Create an empty circuit for setup.
-    /// Uses dummy values that produce valid constraint structure.
+    /// Proves zero of item 0 in an empty tree, so the dummy witnesses
+    /// produce a valid constraint structure and also satisfy it.
     pub fn empty() -> Self {
-        use crate::smt::DEFAULT_DEPTH;
+        Self::empty_with_depth(crate::smt::DEFAULT_DEPTH)
+    }
 
-        // Create dummy proof with correct depth
-        let dummy_proof = MerkleProof::new(
-            vec![Fr::from(0u64); DEFAULT_DEPTH],
-            vec![false; DEFAULT_DEPTH],
-        );
+    /// Create an empty circuit for a tree of the given depth.
+    pub fn empty_with_depth(depth: usize) -> Self {
+        use crate::smt::SparseMerkleTree;
 
-        Self {
-            public_hash: Some(Fr::from(0u64)),
-            inventory_root: Some(Fr::from(0u64)),
-            current_volume: Some(0),
-            blinding: Some(Fr::from(0u64)),
-            item_id: Some(0),
-            actual_quantity: Some(0),
-            min_quantity: Some(0),
-            proof: Some(dummy_proof),
-        }
+        let tree = SparseMerkleTree::new(depth);
+        let proof = tree.get_proof(0);
+
+        Self::new(tree.root(), 0, Fr::from(0u64), 0, 0, 0, proof)
     }
 
     /// Create a new circuit with witnesses.
@@ -110,12 +112,29 @@ impl ItemExistsSMTCircuit {
             inventory_root: Some(inventory_root),
             current_volume: Some(current_volume),
             blinding: Some(blinding),
+            inventory_id: None,
             item_id: Some(item_id),
             actual_quantity: Some(actual_quantity),
             min_quantity: Some(min_quantity),
             proof: Some(proof),
         }
     }
+
+    /// Open an id-bound commitment instead, recomputing the public hash.
+    pub fn with_inventory_id(mut self, inventory_id: Fr) -> Self {
+        if let (Some(root), Some(volume), Some(blinding), Some(item_id), Some(min_quantity)) = (
+            self.inventory_root,
+            self.current_volume,
+            self.blinding,
+            self.item_id,
+            self.min_quantity,
+        ) {
+            let commitment = create_smt_commitment_with_id(root, volume, blinding, inventory_id);
+            self.public_hash = Some(compute_item_exists_hash(commitment, item_id, min_quantity));
+        }
+        self.inventory_id = Some(inventory_id);
+        self
+    }
 }
 
 impl ConstraintSynthesizer<Fr> for ItemExistsSMTCircuit {
@@ -182,12 +201,24 @@ impl ConstraintSynthesizer<Fr> for ItemExistsSMTCircuit {
         // actual_quantity >= min_quantity
 
         // === Constraint 3: Compute and verify commitment using Poseidon ===
-        let commitment_var = create_smt_commitment_var(
-            cs.clone(),
-            &root_var,
-            &volume_var,
-            &blinding_var,
-        )?;
+        let commitment_var = match self.inventory_id {
+            Some(inventory_id) => {
+                let id_var = FpVar::new_witness(cs.clone(), || Ok(inventory_id))?;
+                create_smt_commitment_with_id_var(
+                    cs.clone(),
+                    &root_var,
+                    &volume_var,
+                    &blinding_var,
+                    &id_var,
+                )?
+            }
+            None => create_smt_commitment_var(
+                cs.clone(),
+                &root_var,
+                &volume_var,
+                &blinding_var,
+            )?,
+        };
 
         // === Constraint 4: Compute and verify public hash using Poseidon ===
         let inputs = vec![
@@ -195,7 +226,7 @@ impl ConstraintSynthesizer<Fr> for ItemExistsSMTCircuit {
             item_id_var,
             min_qty_var,
         ];
-        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+        let computed_hash = poseidon_hash_domain_var(cs.clone(), Domain::ItemExists, &inputs)?;
 
         computed_hash.enforce_equal(&public_hash_var)?;
 
@@ -328,4 +359,29 @@ mod tests {
         // Should fail because item_id doesn't match proof
         assert!(!cs.is_satisfied().unwrap());
     }
+
+    #[test]
+    fn test_item_exists_with_inventory_id() {
+        let tree = SparseMerkleTree::from_items(&[(42, 100)], DEFAULT_DEPTH);
+        let blinding = Fr::from(12345u64);
+
+        let circuit = |inventory_id: u64| {
+            ItemExistsSMTCircuit::new(tree.root(), 1000, blinding, 42, 100, 50, tree.get_proof(42))
+                .with_inventory_id(Fr::from(inventory_id))
+        };
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit(7).generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // The same inventory under another id has another public hash
+        assert_ne!(circuit(7).public_hash, circuit(8).public_hash);
+
+        // Witnesses for id 8 cannot open the public hash issued for id 7
+        let mut forged = circuit(8);
+        forged.public_hash = circuit(7).public_hash;
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        forged.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
 }
@@ -2,22 +2,51 @@
 //!
 //! This crate provides SMT-based circuits for:
 //! - `StateTransitionCircuit`: Prove valid deposit/withdraw with capacity checking
+//! - `SpendOnlyTransitionCircuit`: Prove a valid withdrawal that never grows the inventory
+//! - `BurnCircuit`: Prove a withdrawal that destroys items, with a public burned amount
+//! - `MintCircuit`: Prove a deposit of newly issued items within a public supply cap
+//! - `BatchStateTransitionCircuit`: Prove up to `MAX_BATCH_OPS` deposits/withdrawals at once
+//! - `SwapCircuit`: Prove an atomic exchange of item A for item B
+//! - `TransferSMTCircuit`: Prove items moved from one inventory to another
+//! - `MergeCircuit`: Prove one inventory folded into another, item by item
 //! - `ItemExistsSMTCircuit`: Prove inventory contains >= N of item X
+//! - `ItemExistsWithCapacityCircuit`: Both of the above and the capacity bound in one proof
+//! - `MultiItemExistsCircuit`: Prove >= N_i of each of K items against one commitment
+//! - `DepositItemExistsCircuit`: Prove a deposit and a minimum post-deposit quantity in one proof
+//! - `NonMembershipSMTCircuit`: Prove inventory holds none of item X
 //! - `CapacitySMTCircuit`: Prove inventory volume is within capacity
+//! - `CapacityHiddenSMTCircuit`: Same, against a committed (hidden) capacity
 //!
 //! Uses Poseidon hash function optimized for ZK circuits.
 
 // Core modules
 pub mod poseidon;
+pub mod r1cs_stats; // Constraint system inspection
 pub mod range_check; // Range checks for underflow prevention
 pub mod signal;
 pub mod smt;
 pub mod smt_commitment;
+pub mod volume_registry;
 
 // Circuit modules
+pub mod batch_transition;
+pub mod burn;
+pub mod capacity_hidden_smt;
 pub mod capacity_smt;
+pub mod deposit_item_exists;
+pub mod double_transition;
+pub mod item_exists_capacity;
 pub mod item_exists_smt;
+pub mod merge;
+pub mod mint;
+pub mod multi_item_exists;
+pub mod non_membership_smt;
+pub mod selective_disclosure;
+pub mod spend_only;
 pub mod state_transition;
+pub mod subset_capacity;
+pub mod swap;
+pub mod transfer;
 
 #[cfg(test)]
 mod tests;
@@ -26,28 +55,60 @@ mod tests;
 mod optimization_bench;
 
 // Re-export poseidon hash functions
-pub use poseidon::{poseidon_hash, poseidon_hash_two, poseidon_hash_many};
+pub use poseidon::{
+    poseidon_hash, poseidon_hash_domain, poseidon_hash_many, poseidon_hash_two, Domain,
+};
 
 // SMT infrastructure
 pub use smt::{
-    compute_root_from_path, verify_and_update, verify_membership, MerkleProof, MerkleProofVar,
-    SparseMerkleTree, DEFAULT_DEPTH,
+    compute_root_from_path, empty_root, verify_and_update, verify_and_update_two_level,
+    verify_membership, LeafArity, MerkleProof, MerkleProofVar, SparseMerkleTree, TwoLevelSMT,
+    DEFAULT_DEPTH,
 };
 
 // Signal hash (public input compression)
 pub use signal::{
-    compute_signal_hash, compute_signal_hash_var, OpType, SignalInputs, SignalInputsVar,
+    compute_delta_capped_signal_hash, compute_mint_signal_hash, compute_signal_hash,
+    compute_signal_hash_var, enforce_valid_op_type,
+    signal_hash_debug, OpType, ParseOpTypeError, SignalHashDebug, SignalInputs, SignalInputsVar,
 };
 
 // SMT commitment
 pub use smt_commitment::{
-    create_smt_commitment, create_smt_commitment_var, InventoryState, InventoryStateVar,
+    create_smt_commitment, create_smt_commitment_var, create_smt_commitment_with_id,
+    create_smt_commitment_with_id_var, InventoryState, InventoryStateVar,
+};
+
+// Constraint system inspection
+pub use r1cs_stats::{synthesize, R1csStats};
+
+// Volume registry
+pub use volume_registry::{
+    compute_registry_hash, compute_registry_hash_var, RegistryBuilder, RegistryError,
+    VolumeRegistry, VolumeRegistryN, MAX_ITEM_TYPES,
 };
 
 // Circuit exports
 pub use state_transition::StateTransitionCircuit;
+pub use spend_only::SpendOnlyTransitionCircuit;
+pub use burn::BurnCircuit;
+pub use mint::MintCircuit;
+pub use double_transition::{DoubleTransitionCircuit, TransitionStep};
+pub use batch_transition::{compute_batch_signal_hash, BatchStateTransitionCircuit, MAX_BATCH_OPS};
 pub use item_exists_smt::{compute_item_exists_hash, ItemExistsSMTCircuit};
+pub use item_exists_capacity::{compute_item_exists_capacity_hash, ItemExistsWithCapacityCircuit};
+pub use multi_item_exists::{compute_multi_item_exists_hash, MultiItemExistsCircuit};
+pub use deposit_item_exists::{compute_deposit_item_exists_signal_hash, DepositItemExistsCircuit};
+pub use non_membership_smt::{compute_non_membership_hash, NonMembershipSMTCircuit};
+pub use selective_disclosure::SelectiveDisclosureCircuit;
+pub use swap::{compute_swap_signal_hash, SwapCircuit};
+pub use transfer::{compute_transfer_signal_hash, TransferSMTCircuit, TransferSide};
+pub use merge::{compute_merge_signal_hash, MergeCircuit, MergeStep, MAX_MERGE_ITEMS};
 pub use capacity_smt::{compute_capacity_hash, CapacitySMTCircuit};
+pub use subset_capacity::{compute_subset_capacity_hash, SubsetCapacityCircuit};
+pub use capacity_hidden_smt::{
+    compute_capacity_commitment, compute_hidden_capacity_hash, CapacityHiddenSMTCircuit,
+};
 
 use ark_bn254::Fr;
 
@@ -0,0 +1,460 @@
+//! Merge Circuit: fold a source inventory into a target inventory.
+//!
+//! Proves that the merged inventory holds, for every item, the target's
+//! quantity plus the source's quantity. Comparing all 4,096 slots in-circuit
+//! is infeasible, so the source may hold at most `MAX_MERGE_ITEMS` item types,
+//! each merged by one step:
+//! - the target chain starts at the target root and adds `amount` to the item,
+//!   ending at the merged root (untouched slots keep their siblings)
+//! - the source chain starts at the empty tree root and inserts the same
+//!   `amount`, ending at the source root, so the steps cover the whole source
+//!
+//! Sources with fewer item types are padded with zero-amount steps.
+//!
+//! Public inputs (in order):
+//! - signal_hash: Poseidon(target_commitment, source_commitment, new_commitment,
+//!   max_capacity, nonce, inventory_id, source_inventory_id)
+//! - nonce: Current target inventory nonce (replay protection)
+//! - inventory_id: Target inventory object ID, which survives the merge
+//! - source_inventory_id: Source inventory object ID, consumed by the merge
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_domain, poseidon_hash_domain_var, Domain};
+use crate::range_check::{enforce_u32_range, enforce_within_capacity};
+use crate::smt::{verify_and_update, MerkleProof, MerkleProofVar, SparseMerkleTree, DEFAULT_DEPTH};
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+
+/// Maximum number of item types a merged source inventory may hold.
+pub const MAX_MERGE_ITEMS: usize = 4;
+
+/// Compute the signal hash of a merge.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_merge_signal_hash(
+    target_commitment: Fr,
+    source_commitment: Fr,
+    new_commitment: Fr,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+    source_inventory_id: Fr,
+) -> Fr {
+    poseidon_hash_domain(Domain::Merge, &[
+        target_commitment,
+        source_commitment,
+        new_commitment,
+        Fr::from(max_capacity),
+        Fr::from(nonce),
+        inventory_id,
+        source_inventory_id,
+    ])
+}
+
+/// One item moved from the source into the target.
+#[derive(Clone, Debug)]
+pub struct MergeStep {
+    /// Item ID being merged
+    pub item_id: u64,
+    /// Quantity in the target chain before the step
+    pub target_quantity: u64,
+    /// Quantity in the source chain before the step (0 unless the item repeats)
+    pub source_quantity: u64,
+    /// Quantity moved from the source
+    pub amount: u64,
+    /// Proof for the item against the target chain root before the step
+    pub target_proof: MerkleProof<Fr>,
+    /// Proof for the item against the source chain root before the step
+    pub source_proof: MerkleProof<Fr>,
+}
+
+/// Merge Circuit.
+///
+/// Proves merged = target + source per item, for sources of up to
+/// `MAX_MERGE_ITEMS` item types.
+#[derive(Clone)]
+pub struct MergeCircuit {
+    // Public inputs
+    /// Signal hash binding all three commitments
+    pub signal_hash: Option<Fr>,
+    /// Current target inventory nonce
+    pub nonce: Option<u64>,
+    /// Target inventory object ID
+    pub inventory_id: Option<Fr>,
+    /// Source inventory object ID
+    pub source_inventory_id: Option<Fr>,
+
+    // Witnesses
+    /// Maximum allowed capacity of the merged inventory
+    pub max_capacity: Option<u64>,
+    /// Target inventory SMT root
+    pub target_root: Option<Fr>,
+    /// Target total volume
+    pub target_volume: Option<u64>,
+    /// Target blinding factor
+    pub target_blinding: Option<Fr>,
+    /// Source inventory SMT root
+    pub source_root: Option<Fr>,
+    /// Source total volume
+    pub source_volume: Option<u64>,
+    /// Source blinding factor
+    pub source_blinding: Option<Fr>,
+    /// Merged inventory SMT root
+    pub new_root: Option<Fr>,
+    /// Merged total volume
+    pub new_volume: Option<u64>,
+    /// Merged blinding factor
+    pub new_blinding: Option<Fr>,
+    /// Steps in application order, padded to `MAX_MERGE_ITEMS`
+    pub steps: Vec<MergeStep>,
+}
+
+impl MergeCircuit {
+    /// Create an empty circuit for setup.
+    /// Merges an empty source into an empty target, so the dummy witnesses
+    /// produce a valid constraint structure and also satisfy it.
+    pub fn empty() -> Self {
+        let tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+
+        Self::from_trees(
+            &tree,
+            0,
+            Fr::from(0u64),
+            &tree,
+            0,
+            Fr::from(0u64),
+            Fr::from(0u64),
+            0,
+            0,
+            Fr::from(0u64),
+            Fr::from(0u64),
+        )
+    }
+
+    /// Create a circuit merging `source` into `target`.
+    ///
+    /// Steps follow ascending item_id; the merged root and volume are derived
+    /// from the two trees.
+    ///
+    /// # Panics
+    /// If either tree is not `DEFAULT_DEPTH` deep or `source` holds more than
+    /// `MAX_MERGE_ITEMS` item types.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_trees(
+        target: &SparseMerkleTree,
+        target_volume: u64,
+        target_blinding: Fr,
+        source: &SparseMerkleTree,
+        source_volume: u64,
+        source_blinding: Fr,
+        new_blinding: Fr,
+        max_capacity: u64,
+        nonce: u64,
+        inventory_id: Fr,
+        source_inventory_id: Fr,
+    ) -> Self {
+        assert_eq!(target.depth(), DEFAULT_DEPTH, "target tree has the wrong depth");
+        assert_eq!(source.depth(), DEFAULT_DEPTH, "source tree has the wrong depth");
+        assert!(
+            source.len() <= MAX_MERGE_ITEMS,
+            "source holds more than {} item types",
+            MAX_MERGE_ITEMS
+        );
+
+        let mut items: Vec<(u64, u64)> = source.items().collect();
+        items.sort_unstable();
+        let padding = (items.last().map_or(0, |&(item_id, _)| item_id), 0);
+        items.resize(MAX_MERGE_ITEMS, padding);
+
+        let mut merged = target.clone();
+        let mut rebuilt = SparseMerkleTree::new(DEFAULT_DEPTH);
+        let steps = items
+            .into_iter()
+            .map(|(item_id, amount)| {
+                let step = MergeStep {
+                    item_id,
+                    target_quantity: merged.get(item_id),
+                    source_quantity: rebuilt.get(item_id),
+                    amount,
+                    target_proof: merged.get_proof(item_id),
+                    source_proof: rebuilt.get_proof(item_id),
+                };
+                merged.update(item_id, step.target_quantity + amount);
+                rebuilt.update(item_id, step.source_quantity + amount);
+                step
+            })
+            .collect();
+
+        let new_volume = target_volume + source_volume;
+        let target_commitment = create_smt_commitment(target.root(), target_volume, target_blinding);
+        let source_commitment = create_smt_commitment(source.root(), source_volume, source_blinding);
+        let new_commitment = create_smt_commitment(merged.root(), new_volume, new_blinding);
+        let signal_hash = compute_merge_signal_hash(
+            target_commitment,
+            source_commitment,
+            new_commitment,
+            max_capacity,
+            nonce,
+            inventory_id,
+            source_inventory_id,
+        );
+
+        Self {
+            signal_hash: Some(signal_hash),
+            nonce: Some(nonce),
+            inventory_id: Some(inventory_id),
+            source_inventory_id: Some(source_inventory_id),
+            max_capacity: Some(max_capacity),
+            target_root: Some(target.root()),
+            target_volume: Some(target_volume),
+            target_blinding: Some(target_blinding),
+            source_root: Some(source.root()),
+            source_volume: Some(source_volume),
+            source_blinding: Some(source_blinding),
+            new_root: Some(merged.root()),
+            new_volume: Some(new_volume),
+            new_blinding: Some(new_blinding),
+            steps,
+        }
+    }
+}
+
+/// Add `amount` to an item in one chain, returning the chain's next root.
+///
+/// The proof must open at item_id's own position, so an insertion cannot
+/// land in another item's empty slot.
+fn enforce_merge_update(
+    cs: ConstraintSystemRef<Fr>,
+    root: &FpVar<Fr>,
+    item_id: &FpVar<Fr>,
+    old_quantity: u64,
+    amount: &FpVar<Fr>,
+    proof: &MerkleProof<Fr>,
+) -> Result<FpVar<Fr>, SynthesisError> {
+    if proof.depth() != DEFAULT_DEPTH {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+
+    let old_quantity_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(old_quantity)))?;
+    let new_quantity_var = &old_quantity_var + amount;
+    enforce_u32_range(cs.clone(), &new_quantity_var)?;
+
+    let proof_var = MerkleProofVar::new_witness(cs.clone(), proof)?;
+    Boolean::le_bits_to_fp_var(proof_var.indices())?.enforce_equal(item_id)?;
+
+    verify_and_update(cs, root, item_id, &old_quantity_var, &new_quantity_var, &proof_var)
+}
+
+impl ConstraintSynthesizer<Fr> for MergeCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public inputs ===
+        // Order matters: signal_hash, nonce, inventory_id, source_inventory_id
+        let signal_hash_var = FpVar::new_input(cs.clone(), || {
+            self.signal_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let nonce_var = FpVar::new_input(cs.clone(), || {
+            self.nonce
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let inventory_id_var = FpVar::new_input(cs.clone(), || {
+            self.inventory_id.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let source_inventory_id_var = FpVar::new_input(cs.clone(), || {
+            self.source_inventory_id.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate state witnesses ===
+        let witness = |value: Option<Fr>| {
+            FpVar::new_witness(cs.clone(), || value.ok_or(SynthesisError::AssignmentMissing))
+        };
+        let max_capacity_var = witness(self.max_capacity.map(Fr::from))?;
+        let target_root_var = witness(self.target_root)?;
+        let target_volume_var = witness(self.target_volume.map(Fr::from))?;
+        let target_blinding_var = witness(self.target_blinding)?;
+        let source_root_var = witness(self.source_root)?;
+        let source_volume_var = witness(self.source_volume.map(Fr::from))?;
+        let source_blinding_var = witness(self.source_blinding)?;
+        let new_root_var = witness(self.new_root)?;
+        let new_volume_var = witness(self.new_volume.map(Fr::from))?;
+        let new_blinding_var = witness(self.new_blinding)?;
+
+        // === Constraint 1: Each step adds the same amount to both chains ===
+        if self.steps.len() != MAX_MERGE_ITEMS {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let empty_root = SparseMerkleTree::new(DEFAULT_DEPTH).root();
+        let mut target_chain = target_root_var.clone();
+        let mut source_chain = FpVar::constant(empty_root);
+        for step in &self.steps {
+            let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(step.item_id)))?;
+            let amount_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(step.amount)))?;
+
+            target_chain = enforce_merge_update(
+                cs.clone(),
+                &target_chain,
+                &item_id_var,
+                step.target_quantity,
+                &amount_var,
+                &step.target_proof,
+            )?;
+            source_chain = enforce_merge_update(
+                cs.clone(),
+                &source_chain,
+                &item_id_var,
+                step.source_quantity,
+                &amount_var,
+                &step.source_proof,
+            )?;
+        }
+
+        // === Constraint 2: The target chain ends at the merged root ===
+        target_chain.enforce_equal(&new_root_var)?;
+
+        // === Constraint 3: The source chain rebuilds the whole source ===
+        source_chain.enforce_equal(&source_root_var)?;
+
+        // === Constraint 4: Volumes add up and fit the capacity ===
+        new_volume_var.enforce_equal(&(&target_volume_var + &source_volume_var))?;
+        enforce_u32_range(cs.clone(), &new_volume_var)?;
+        enforce_within_capacity(cs.clone(), &max_capacity_var, &new_volume_var)?;
+
+        // === Constraint 5: Commitments to all three states ===
+        let target_commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &target_root_var,
+            &target_volume_var,
+            &target_blinding_var,
+        )?;
+        let source_commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &source_root_var,
+            &source_volume_var,
+            &source_blinding_var,
+        )?;
+        let new_commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &new_root_var,
+            &new_volume_var,
+            &new_blinding_var,
+        )?;
+
+        // === Constraint 6: Signal hash binds the merge ===
+        let computed_signal = poseidon_hash_domain_var(
+            cs,
+            Domain::Merge,
+            &[
+                target_commitment_var,
+                source_commitment_var,
+                new_commitment_var,
+                max_capacity_var,
+                nonce_var,
+                inventory_id_var,
+                source_inventory_id_var,
+            ],
+        )?;
+        computed_signal.enforce_equal(&signal_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Merge `source` into `target`, with volume = total quantity (1 per unit).
+    fn merge(target: &[(u64, u64)], source: &[(u64, u64)], max_capacity: u64) -> MergeCircuit {
+        let volume = |items: &[(u64, u64)]| items.iter().map(|&(_, quantity)| quantity).sum();
+
+        MergeCircuit::from_trees(
+            &SparseMerkleTree::from_items(target, DEFAULT_DEPTH),
+            volume(target),
+            Fr::from(111u64),
+            &SparseMerkleTree::from_items(source, DEFAULT_DEPTH),
+            volume(source),
+            Fr::from(222u64),
+            Fr::from(333u64),
+            max_capacity,
+            0,
+            Fr::from(1u64),
+            Fr::from(2u64),
+        )
+    }
+
+    fn is_satisfied(circuit: MergeCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_empty_circuit_satisfied() {
+        assert!(is_satisfied(MergeCircuit::empty()));
+    }
+
+    #[test]
+    fn test_merge_disjoint_items() {
+        let circuit = merge(&[(1, 10), (2, 5)], &[(3, 7), (40, 1)], 1000);
+
+        let expected =
+            SparseMerkleTree::from_items(&[(1, 10), (2, 5), (3, 7), (40, 1)], DEFAULT_DEPTH);
+        assert_eq!(circuit.new_root, Some(expected.root()));
+        assert_eq!(circuit.new_volume, Some(23));
+        assert!(is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_merge_overlapping_items() {
+        let circuit = merge(&[(1, 10), (2, 5)], &[(1, 4), (2, 1), (3, 7)], 1000);
+
+        let expected = SparseMerkleTree::from_items(&[(1, 14), (2, 6), (3, 7)], DEFAULT_DEPTH);
+        assert_eq!(circuit.new_root, Some(expected.root()));
+        assert!(is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_merge_over_capacity_rejected() {
+        assert!(!is_satisfied(merge(&[(1, 10)], &[(1, 5)], 14)));
+    }
+
+    #[test]
+    fn test_merge_skipping_source_item_rejected() {
+        // Replacing item 3's step with padding leaves it out of the merge
+        let mut circuit = merge(&[(1, 10)], &[(2, 5), (3, 7)], 1000);
+        let padding = circuit.steps[2].clone();
+        circuit.steps[1] = padding;
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_merge_wrong_amount_rejected() {
+        let mut circuit = merge(&[(1, 10)], &[(1, 5)], 1000);
+        circuit.steps[0].amount = 6;
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_merge_wrong_volume_rejected() {
+        // Understate the merged volume as 10 instead of 15, with a signal
+        // hash consistent with that claim
+        let mut circuit = merge(&[(1, 10)], &[(1, 5)], 1000);
+        circuit.new_volume = Some(10);
+        circuit.signal_hash = Some(compute_merge_signal_hash(
+            create_smt_commitment(circuit.target_root.unwrap(), 10, Fr::from(111u64)),
+            create_smt_commitment(circuit.source_root.unwrap(), 5, Fr::from(222u64)),
+            create_smt_commitment(circuit.new_root.unwrap(), 10, Fr::from(333u64)),
+            1000,
+            0,
+            Fr::from(1u64),
+            Fr::from(2u64),
+        ));
+
+        assert!(!is_satisfied(circuit));
+    }
+}
@@ -0,0 +1,234 @@
+//! Mint Circuit: provably issue new items within a global supply cap.
+//!
+//! Same constraints as a `StateTransitionCircuit` deposit, plus
+//! `already_minted + amount <= max_supply`. The amount and cap are public so
+//! an on-chain contract can track cumulative issuance; `already_minted` is its
+//! issuance counter, bound into the signal hash (see `compute_mint_signal_hash`).
+//!
+//! Public inputs (in order):
+//! - signal_hash: Poseidon(transition signal hash, already_minted)
+//! - nonce: Replay protection (verified on-chain against inventory.nonce)
+//! - inventory_id: Cross-inventory protection (verified on-chain)
+//! - registry_root: Volume registry commitment (verified against VolumeRegistry)
+//! - amount: Units of `item_id` issued
+//! - max_supply: Cap on the total units ever issued
+
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::signal::{compute_mint_signal_hash, OpType};
+use crate::smt::MerkleProof;
+use crate::state_transition::{StateTransitionCircuit, TransitionKind};
+use crate::volume_registry::{VolumeRegistry, MAX_ITEM_TYPES};
+
+/// Mint Circuit.
+///
+/// Proves a deposit of `amount` new units that keeps total issuance within `max_supply`.
+#[derive(Clone)]
+pub struct MintCircuit {
+    /// Underlying state transition witnesses, with the mint signal hash
+    pub transition: StateTransitionCircuit,
+    /// Cap on the total units ever issued
+    pub max_supply: Option<u64>,
+    /// Units issued before this mint
+    pub already_minted: Option<u64>,
+}
+
+impl MintCircuit {
+    /// Create a new empty circuit for setup.
+    /// Uses a zero-amount mint into an empty tree under a zero cap, so the
+    /// dummy witnesses also satisfy the mint constraints.
+    pub fn empty() -> Self {
+        use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+
+        let tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        let root = tree.root();
+        let proof = tree.get_proof(0);
+
+        Self::new(
+            root,
+            0,
+            Fr::from(0u64),
+            root,
+            0,
+            Fr::from(0u64),
+            0,
+            0,
+            0,
+            0,
+            proof,
+            &VolumeRegistry::new([0; MAX_ITEM_TYPES]),
+            0,
+            0,
+            Fr::from(0u64),
+            0,
+            0,
+        )
+    }
+
+    /// Create a new mint circuit with all witnesses.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        old_inventory_root: Fr,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_inventory_root: Fr,
+        new_volume: u64,
+        new_blinding: Fr,
+        item_id: u64,
+        old_quantity: u64,
+        new_quantity: u64,
+        amount: u64,
+        inventory_proof: MerkleProof<Fr>,
+        registry: &VolumeRegistry,
+        max_capacity: u64,
+        nonce: u64,
+        inventory_id: Fr,
+        max_supply: u64,
+        already_minted: u64,
+    ) -> Self {
+        Self::from_transition(
+            StateTransitionCircuit::new(
+                old_inventory_root,
+                old_volume,
+                old_blinding,
+                new_inventory_root,
+                new_volume,
+                new_blinding,
+                item_id,
+                old_quantity,
+                new_quantity,
+                amount,
+                OpType::Deposit,
+                inventory_proof,
+                registry,
+                max_capacity,
+                nonce,
+                inventory_id,
+            ),
+            max_supply,
+            already_minted,
+        )
+    }
+
+    /// Wrap an existing state transition, binding `already_minted` into its signal hash.
+    ///
+    /// The constraints reject the witnesses unless the transition is a deposit.
+    pub fn from_transition(
+        mut transition: StateTransitionCircuit,
+        max_supply: u64,
+        already_minted: u64,
+    ) -> Self {
+        transition.signal_hash = transition
+            .signal_hash
+            .map(|signal_hash| compute_mint_signal_hash(signal_hash, already_minted));
+
+        Self {
+            transition,
+            max_supply: Some(max_supply),
+            already_minted: Some(already_minted),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for MintCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let kind = TransitionKind::Mint {
+            max_supply: self.max_supply,
+            already_minted: self.already_minted,
+        };
+        self.transition.synthesize(cs, kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Mint `amount` of item 1 (volume 10 per unit) into an inventory holding 20.
+    fn mint(amount: u64, max_supply: u64, already_minted: u64) -> MintCircuit {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 20)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+        let proof = tree.get_proof(1);
+        tree.update(1, 20 + amount);
+
+        MintCircuit::new(
+            old_root,
+            200,
+            Fr::from(12345u64),
+            tree.root(),
+            200 + amount * 10,
+            Fr::from(67890u64),
+            1,
+            20,
+            20 + amount,
+            amount,
+            proof,
+            &VolumeRegistry::from_entries(&[(1, 10)]),
+            10000,
+            0,
+            Fr::from(12345678u64),
+            max_supply,
+            already_minted,
+        )
+    }
+
+    fn is_satisfied(circuit: MintCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_empty_circuit_satisfied() {
+        assert!(is_satisfied(MintCircuit::empty()));
+    }
+
+    #[test]
+    fn test_mint_within_cap() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        mint(30, 1000, 500).generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // Instance assignment starts with the constant one
+        let inputs = cs.borrow().unwrap().instance_assignment.clone();
+        assert_eq!(inputs.len(), 7);
+        assert_eq!(inputs[5], Fr::from(30u64));
+        assert_eq!(inputs[6], Fr::from(1000u64));
+    }
+
+    #[test]
+    fn test_mint_exactly_at_cap() {
+        assert!(is_satisfied(mint(30, 1000, 970)));
+    }
+
+    #[test]
+    fn test_mint_exceeding_cap_rejected() {
+        assert!(!is_satisfied(mint(30, 1000, 971)));
+    }
+
+    #[test]
+    fn test_mint_under_u64_max_supply() {
+        // The remaining supply is far past 32 bits
+        assert!(is_satisfied(mint(30, u64::MAX, 0)));
+        assert!(is_satisfied(mint(30, u64::MAX, u64::MAX - 30)));
+        assert!(!is_satisfied(mint(30, u64::MAX, u64::MAX - 29)));
+    }
+
+    #[test]
+    fn test_mint_after_u32_issuance() {
+        let already_minted = u32::MAX as u64 + 1000;
+        assert!(is_satisfied(mint(30, already_minted + 30, already_minted)));
+        assert!(!is_satisfied(mint(30, already_minted + 29, already_minted)));
+    }
+
+    #[test]
+    fn test_understated_issuance_rejected() {
+        // Witnesses claiming nothing was minted yet, under the signal hash for 971
+        let mut circuit = mint(30, 1000, 0);
+        circuit.transition.signal_hash = mint(30, 1000, 971).transition.signal_hash;
+        assert!(!is_satisfied(circuit));
+    }
+}
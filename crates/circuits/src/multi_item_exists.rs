@@ -0,0 +1,283 @@
+//! MultiItemExists Circuit for SMT-based inventory.
+//!
+//! Proves in one proof that an inventory holds at least a minimum quantity of
+//! each of K items, e.g. to gate a quest on owning several specific items.
+//! All K membership claims open against the same committed SMT root, so one
+//! verification replaces K separate ItemExists proofs.
+//!
+//! Public input: Poseidon(commitment, item_id_1, min_1, ..., item_id_K, min_K)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_domain, poseidon_hash_domain_var, Domain};
+use crate::range_check::{enforce_geq, enforce_u32_range};
+use crate::smt::{verify_membership, MerkleProof, MerkleProofVar, SparseMerkleTree};
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+
+/// Compute the public input hash for a MultiItemExists proof.
+///
+/// `claims` are the (item_id, min_quantity) pairs, in circuit order.
+pub fn compute_multi_item_exists_hash(commitment: Fr, claims: &[(u64, u64)]) -> Fr {
+    let mut inputs = Vec::with_capacity(1 + 2 * claims.len());
+    inputs.push(commitment);
+    for &(item_id, min_quantity) in claims {
+        inputs.push(Fr::from(item_id));
+        inputs.push(Fr::from(min_quantity));
+    }
+    poseidon_hash_domain(Domain::MultiItemExists, &inputs)
+}
+
+/// MultiItemExists Circuit for SMT-based inventory.
+///
+/// Proves quantity(item_ids[i]) >= min_quantities[i] for every i < K.
+#[derive(Clone)]
+pub struct MultiItemExistsCircuit<const K: usize> {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Commitment components (witnesses)
+    /// Inventory SMT root
+    pub inventory_root: Option<Fr>,
+    /// Current volume
+    pub current_volume: Option<u64>,
+    /// Blinding factor
+    pub blinding: Option<Fr>,
+
+    // Claims (witnesses, bound through the public hash)
+    /// Item IDs to prove
+    pub item_ids: Option<[u64; K]>,
+    /// Actual quantities (each must be >= its min_quantity)
+    pub actual_quantities: Option<[u64; K]>,
+    /// Minimum quantities to prove
+    pub min_quantities: Option<[u64; K]>,
+
+    // Merkle proofs
+    /// Proof for each claimed item in the SMT
+    pub proofs: Option<Vec<MerkleProof<Fr>>>,
+}
+
+impl<const K: usize> MultiItemExistsCircuit<K> {
+    /// Create an empty circuit for setup.
+    /// Claims zero of items 0..K in a tree holding one of each, so the dummy
+    /// witnesses produce a valid constraint structure and also satisfy it.
+    pub fn empty() -> Self {
+        use crate::smt::DEFAULT_DEPTH;
+
+        let items: Vec<(u64, u64)> = (0..K as u64).map(|item_id| (item_id, 1)).collect();
+        let tree = SparseMerkleTree::from_items(&items, DEFAULT_DEPTH);
+
+        Self::from_tree(&tree, 0, Fr::from(0u64), std::array::from_fn(|i| (i as u64, 0)))
+    }
+
+    /// Create a circuit opening each claimed item of `tree`.
+    ///
+    /// `claims` are the (item_id, min_quantity) pairs to prove.
+    pub fn from_tree(
+        tree: &SparseMerkleTree,
+        current_volume: u64,
+        blinding: Fr,
+        claims: [(u64, u64); K],
+    ) -> Self {
+        let item_ids = claims.map(|(item_id, _)| item_id);
+        let min_quantities = claims.map(|(_, min_quantity)| min_quantity);
+        let actual_quantities = item_ids.map(|item_id| tree.get(item_id));
+        let proofs = item_ids.iter().map(|&item_id| tree.get_proof(item_id)).collect();
+
+        Self::new(
+            tree.root(),
+            current_volume,
+            blinding,
+            item_ids,
+            actual_quantities,
+            min_quantities,
+            proofs,
+        )
+    }
+
+    /// Create a new circuit with witnesses.
+    pub fn new(
+        inventory_root: Fr,
+        current_volume: u64,
+        blinding: Fr,
+        item_ids: [u64; K],
+        actual_quantities: [u64; K],
+        min_quantities: [u64; K],
+        proofs: Vec<MerkleProof<Fr>>,
+    ) -> Self {
+        let commitment = create_smt_commitment(inventory_root, current_volume, blinding);
+        let claims: Vec<(u64, u64)> = item_ids.into_iter().zip(min_quantities).collect();
+        let public_hash = compute_multi_item_exists_hash(commitment, &claims);
+
+        Self {
+            public_hash: Some(public_hash),
+            inventory_root: Some(inventory_root),
+            current_volume: Some(current_volume),
+            blinding: Some(blinding),
+            item_ids: Some(item_ids),
+            actual_quantities: Some(actual_quantities),
+            min_quantities: Some(min_quantities),
+            proofs: Some(proofs),
+        }
+    }
+}
+
+impl<const K: usize> ConstraintSynthesizer<Fr> for MultiItemExistsCircuit<K> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate commitment witnesses ===
+        let root_var = FpVar::new_witness(cs.clone(), || {
+            self.inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let volume_var = FpVar::new_witness(cs.clone(), || {
+            self.current_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let claim_witness = |values: Option<[u64; K]>, i: usize| {
+            FpVar::new_witness(cs.clone(), || {
+                values
+                    .map(|values| Fr::from(values[i]))
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })
+        };
+
+        // === Constraint 1: Every claim opens against the same root ===
+        let proofs = self.proofs.as_ref().ok_or(SynthesisError::AssignmentMissing)?;
+        if proofs.len() != K {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let mut hash_inputs = Vec::with_capacity(1 + 2 * K);
+        for (i, proof) in proofs.iter().enumerate() {
+            let item_id_var = claim_witness(self.item_ids, i)?;
+            let actual_qty_var = claim_witness(self.actual_quantities, i)?;
+            let min_qty_var = claim_witness(self.min_quantities, i)?;
+            let proof_var = MerkleProofVar::new_witness(cs.clone(), proof)?;
+
+            verify_membership(cs.clone(), &root_var, &item_id_var, &actual_qty_var, &proof_var)?;
+
+            // === Constraint 2: actual_quantity >= min_quantity ===
+            // Both sides are range checked so the difference cannot wrap around
+            enforce_u32_range(cs.clone(), &actual_qty_var)?;
+            enforce_u32_range(cs.clone(), &min_qty_var)?;
+            enforce_geq(cs.clone(), &actual_qty_var, &min_qty_var)?;
+
+            hash_inputs.push(item_id_var);
+            hash_inputs.push(min_qty_var);
+        }
+
+        // === Constraint 3: Compute commitment using Poseidon ===
+        let commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &root_var,
+            &volume_var,
+            &blinding_var,
+        )?;
+        hash_inputs.insert(0, commitment_var);
+
+        // === Constraint 4: Public hash binds the commitment and all K claims ===
+        let computed_hash =
+            poseidon_hash_domain_var(cs.clone(), Domain::MultiItemExists, &hash_inputs)?;
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::DEFAULT_DEPTH;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Inventory holding 100 of item 1, 5 of item 42 and 1 of item 300.
+    fn tree() -> SparseMerkleTree {
+        SparseMerkleTree::from_items(&[(1, 100), (42, 5), (300, 1)], DEFAULT_DEPTH)
+    }
+
+    fn is_satisfied<const K: usize>(circuit: MultiItemExistsCircuit<K>) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_empty_circuit_satisfied() {
+        assert!(is_satisfied(MultiItemExistsCircuit::<3>::empty()));
+    }
+
+    #[test]
+    fn test_three_claims_satisfied() {
+        let claims = [(1, 50), (42, 5), (300, 1)];
+        let circuit = MultiItemExistsCircuit::from_tree(&tree(), 1000, Fr::from(12345u64), claims);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // A single public input (plus the constant) covers all three claims
+        let inputs = cs.borrow().unwrap().instance_assignment.clone();
+        assert_eq!(inputs.len(), 2);
+
+        let commitment = create_smt_commitment(tree().root(), 1000, Fr::from(12345u64));
+        assert_eq!(inputs[1], compute_multi_item_exists_hash(commitment, &claims));
+    }
+
+    #[test]
+    fn test_one_failing_claim_rejected() {
+        // Only 5 of item 42 are held
+        let claims = [(1, 50), (42, 6), (300, 1)];
+        let circuit = MultiItemExistsCircuit::from_tree(&tree(), 1000, Fr::from(12345u64), claims);
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_absent_item_rejected() {
+        let claims = [(1, 50), (42, 5), (7, 1)];
+        let circuit = MultiItemExistsCircuit::from_tree(&tree(), 1000, Fr::from(12345u64), claims);
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_inflated_quantity_rejected() {
+        // Claiming 6 of item 42 by lying about its actual quantity
+        let mut circuit = MultiItemExistsCircuit::from_tree(
+            &tree(),
+            1000,
+            Fr::from(12345u64),
+            [(1, 50), (42, 6), (300, 1)],
+        );
+        circuit.actual_quantities = Some([100, 6, 1]);
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_claims_bound_to_public_hash() {
+        // Witnesses for min 5 under the public hash for min 6
+        let mut circuit = MultiItemExistsCircuit::from_tree(
+            &tree(),
+            1000,
+            Fr::from(12345u64),
+            [(1, 50), (42, 5), (300, 1)],
+        );
+        circuit.public_hash = MultiItemExistsCircuit::from_tree(
+            &tree(),
+            1000,
+            Fr::from(12345u64),
+            [(1, 50), (42, 6), (300, 1)],
+        )
+        .public_hash;
+        assert!(!is_satisfied(circuit));
+    }
+}
@@ -160,7 +160,7 @@ fn bench_smt_depth(depth: usize) -> usize {
     let leaf_hash = FpVar::new_witness(cs.clone(), || Ok(Fr::from(456u64))).unwrap();
 
     let before = cs.num_constraints();
-    compute_root_from_path_depth(cs.clone(), &leaf_hash, &siblings, &indices).unwrap();
+    let _ = compute_root_from_path_depth(cs.clone(), &leaf_hash, &siblings, &indices).unwrap();
     let after = cs.num_constraints();
 
     after - before
@@ -366,3 +366,91 @@ fn bench_combined_optimizations() {
     println!("  After range optimization: ~{} constraints", 8255 - extrapolated_savings);
     println!();
 }
+
+// ============================================================================
+// LAZY INVENTORY MEMORY
+// ============================================================================
+
+#[test]
+fn bench_lazy_inventory_memory() {
+    use crate::smt::{LazyInventory, SparseMerkleTree, TreeCache, DEFAULT_DEPTH};
+    use std::mem::{size_of, size_of_val};
+    use std::sync::Arc;
+
+    println!("\n========================================");
+    println!("LAZY INVENTORY MEMORY (10k idle inventories)");
+    println!("========================================\n");
+
+    const INVENTORIES: usize = 10_000;
+    let items = [(1, 100), (5, 50), (42, 7), (100, 25), (1000, 3)];
+
+    // Every idle inventory has the same shape, so measure one and scale
+    let tree = SparseMerkleTree::from_items(&items, DEFAULT_DEPTH);
+    let node_bytes = tree.node_count() * (size_of::<(usize, u64)>() + size_of::<Fr>());
+    let leaf_bytes = tree.len() * size_of::<(u64, u64)>();
+    let default_bytes = (DEFAULT_DEPTH + 1) * size_of::<Fr>();
+    let eager_bytes = size_of::<SparseMerkleTree>() + node_bytes + leaf_bytes + default_bytes;
+
+    let cache = Arc::new(TreeCache::new(16));
+    let lazy = LazyInventory::from_items(&items, DEFAULT_DEPTH, cache);
+    assert_eq!(lazy.root(), tree.root());
+    let lazy_bytes = size_of::<LazyInventory>() + size_of_val(lazy.leaves());
+
+    println!("Per inventory ({} items, {} nodes):", items.len(), tree.node_count());
+    println!("  Full tree:      ~{} bytes", eager_bytes);
+    println!("  LazyInventory:  ~{} bytes", lazy_bytes);
+    println!("Total for {} inventories (excluding hash map overhead):", INVENTORIES);
+    println!("  Full trees:     ~{} KiB", eager_bytes * INVENTORIES / 1024);
+    println!(
+        "  LazyInventory:  ~{} KiB (+ up to 16 cached trees)",
+        lazy_bytes * INVENTORIES / 1024
+    );
+    println!();
+
+    assert!(lazy_bytes < eager_bytes);
+}
+
+// ============================================================================
+// SMT BATCH UPDATE
+// ============================================================================
+
+#[test]
+fn bench_smt_update_batch() {
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::time::Instant;
+
+    println!("\n========================================");
+    println!("SMT BATCH UPDATE (load 200 items)");
+    println!("========================================\n");
+
+    let mut rng = StdRng::seed_from_u64(42);
+    let items: Vec<(u64, u64)> = (0..200)
+        .map(|_| (rng.gen_range(0..1u64 << DEFAULT_DEPTH), rng.gen_range(1..100)))
+        .collect();
+
+    let start = Instant::now();
+    let mut sequential = SparseMerkleTree::new(DEFAULT_DEPTH);
+    for &(item_id, quantity) in &items {
+        sequential.update(item_id, quantity);
+    }
+    let sequential_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut batched = SparseMerkleTree::new(DEFAULT_DEPTH);
+    batched.update_batch(&items);
+    let batch_time = start.elapsed();
+
+    assert_eq!(batched.root(), sequential.root());
+
+    // Internal node hashes; leaf hashes are the same for both
+    let sequential_hashes = items.len() * DEFAULT_DEPTH;
+    let batch_hashes = batched.node_count() - batched.len();
+    println!("  Sequential update: {:?} ({} hashes)", sequential_time, sequential_hashes);
+    println!("  update_batch:      {:?} ({} hashes)", batch_time, batch_hashes);
+    println!(
+        "  Speedup:           {:.1}x",
+        sequential_time.as_secs_f64() / batch_time.as_secs_f64()
+    );
+    println!();
+}
@@ -1,10 +1,12 @@
 //! Poseidon configuration for BN254.
 //!
-//! Uses standard parameters for 128-bit security.
+//! Uses standard parameters for 128-bit security by default. Other widths and
+//! round counts can be built with `poseidon_config_with` to trade security
+//! margin for constraint cost.
 
 use ark_bn254::Fr;
 use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
-use ark_ff::MontFp;
+use ark_ff::{Field, MontFp};
 
 /// Number of full rounds (beginning + end)
 const FULL_ROUNDS: usize = 8;
@@ -24,8 +26,64 @@ const ALPHA: u64 = 5;
 /// - Partial rounds: 57
 /// - Alpha: 5 (x^5 S-box)
 pub fn poseidon_config() -> PoseidonConfig<Fr> {
-    // MDS matrix (3x3 for rate=2, capacity=1)
-    let mds = vec![
+    poseidon_config_with(2, 1, FULL_ROUNDS, PARTIAL_ROUNDS)
+}
+
+/// Reduced-round preset for benchmarking constraint cost. INSECURE.
+///
+/// Same width as `poseidon_config` with 31 partial rounds instead of 57, far
+/// below the ~56 partial rounds needed for 128-bit security at width 3 with
+/// x^5. Never use it for hashes that must be collision or preimage resistant.
+pub fn poseidon_config_insecure_benchmark_only() -> PoseidonConfig<Fr> {
+    poseidon_config_with(2, 1, FULL_ROUNDS, 31)
+}
+
+/// Wider preset with a two-element capacity and extra partial rounds.
+///
+/// Uses the unvetted Cauchy MDS matrix and the ad-hoc round constants of
+/// `poseidon_config_with`, neither of which comes from the reference Grain
+/// LFSR script or its invariant-subspace checks. Not a security upgrade over
+/// `poseidon_config`.
+pub fn poseidon_config_wide() -> PoseidonConfig<Fr> {
+    poseidon_config_with(2, 2, FULL_ROUNDS, 60)
+}
+
+/// Build a Poseidon configuration with custom width and round counts.
+///
+/// Width 3 (rate 2, capacity 1) uses the standard MDS matrix, so
+/// `poseidon_config_with(2, 1, 8, 57)` equals `poseidon_config()`. Other
+/// widths use a Cauchy MDS matrix. Round constants are generated with the
+/// same deterministic method for every width.
+///
+/// # Panics
+/// Panics if `rate` or `capacity` is zero or `full_rounds` is odd.
+pub fn poseidon_config_with(
+    rate: usize,
+    capacity: usize,
+    full_rounds: usize,
+    partial_rounds: usize,
+) -> PoseidonConfig<Fr> {
+    assert!(rate > 0 && capacity > 0, "rate and capacity must be non-zero");
+    assert!(full_rounds.is_multiple_of(2), "full rounds are split evenly between start and end");
+
+    let width = rate + capacity;
+    let mds = if width == 3 { standard_mds() } else { cauchy_mds(width) };
+    let ark = generate_round_constants(full_rounds + partial_rounds, width);
+
+    PoseidonConfig {
+        full_rounds,
+        partial_rounds,
+        alpha: ALPHA,
+        ark,
+        mds,
+        rate,
+        capacity,
+    }
+}
+
+/// MDS matrix (3x3 for rate=2, capacity=1)
+fn standard_mds() -> Vec<Vec<Fr>> {
+    vec![
         vec![
             MontFp!("7511745149465107256748700652201246547602992235352608707588321460060273774987"),
             MontFp!("10370080108974718697676803824769673834027675643658433702224577712625900127200"),
@@ -41,28 +99,29 @@ pub fn poseidon_config() -> PoseidonConfig<Fr> {
             MontFp!("10595341252162738537912664445405114076324478519622938027420701542910180337937"),
             MontFp!("11597556804922396090267472882856054602429588299176362916247939723151043581408"),
         ],
-    ];
-
-    // Round constants (ARK) - generated using standard Poseidon method
-    let ark = generate_round_constants();
+    ]
+}
 
-    PoseidonConfig {
-        full_rounds: FULL_ROUNDS,
-        partial_rounds: PARTIAL_ROUNDS,
-        alpha: ALPHA,
-        ark,
-        mds,
-        rate: 2,
-        capacity: 1,
-    }
+/// Cauchy matrix M[i][j] = 1 / (x_i + y_j) with x_i = i and y_j = width + j.
+/// Distinct x's and y's with non-zero sums make every square submatrix
+/// invertible, which is the MDS property.
+fn cauchy_mds(width: usize) -> Vec<Vec<Fr>> {
+    (0..width)
+        .map(|i| {
+            (0..width)
+                .map(|j| {
+                    Fr::from((i + width + j) as u64)
+                        .inverse()
+                        .expect("x_i + y_j is non-zero")
+                })
+                .collect()
+        })
+        .collect()
 }
 
 /// Generate round constants using a simple deterministic method.
 /// In production, these should come from a proper generation ceremony.
-fn generate_round_constants() -> Vec<Vec<Fr>> {
-    let num_rounds = FULL_ROUNDS + PARTIAL_ROUNDS;
-    let width = 3; // rate + capacity
-
+fn generate_round_constants(num_rounds: usize, width: usize) -> Vec<Vec<Fr>> {
     let mut ark = Vec::with_capacity(num_rounds);
 
     // Use a simple hash-based generation for reproducibility
@@ -96,4 +155,22 @@ mod tests {
         assert_eq!(config.mds.len(), 3);
         assert_eq!(config.ark.len(), FULL_ROUNDS + PARTIAL_ROUNDS);
     }
+
+    #[test]
+    fn test_presets_valid() {
+        let default = poseidon_config();
+        let custom = poseidon_config_with(2, 1, FULL_ROUNDS, PARTIAL_ROUNDS);
+        assert_eq!(custom.mds, default.mds);
+        assert_eq!(custom.ark, default.ark);
+
+        let benchmark = poseidon_config_insecure_benchmark_only();
+        assert_eq!(benchmark.ark.len(), FULL_ROUNDS + 31);
+        assert_eq!(benchmark.mds, default.mds);
+
+        let wide = poseidon_config_wide();
+        assert_eq!(wide.capacity, 2);
+        assert_eq!(wide.mds.len(), 4);
+        assert!(wide.mds.iter().all(|row| row.len() == 4));
+        assert!(wide.ark.iter().all(|round| round.len() == 4));
+    }
 }
@@ -4,9 +4,11 @@ use ark_bn254::Fr;
 use ark_r1cs_std::fields::fp::FpVar;
 use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
 use ark_crypto_primitives::sponge::poseidon::constraints::PoseidonSpongeVar;
+use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
 use ark_crypto_primitives::sponge::constraints::CryptographicSpongeVar;
 
 use super::config::poseidon_config;
+use super::domain::Domain;
 
 /// Hash a single field element in-circuit.
 pub fn poseidon_hash_var(
@@ -38,9 +40,38 @@ pub fn poseidon_hash_two_var(
 pub fn poseidon_hash_many_var(
     cs: ConstraintSystemRef<Fr>,
     inputs: &[FpVar<Fr>],
+) -> Result<FpVar<Fr>, SynthesisError> {
+    poseidon_hash_many_var_with(cs, &poseidon_config(), inputs)
+}
+
+/// Hash multiple field elements in-circuit under a custom configuration.
+///
+/// Pair with `poseidon_hash_many_with` using the same config.
+pub fn poseidon_hash_many_var_with(
+    cs: ConstraintSystemRef<Fr>,
+    config: &PoseidonConfig<Fr>,
+    inputs: &[FpVar<Fr>],
+) -> Result<FpVar<Fr>, SynthesisError> {
+    let mut sponge = PoseidonSpongeVar::new(cs, config);
+    for input in inputs {
+        sponge.absorb(input)?;
+    }
+    let result = sponge.squeeze_field_elements(1)?;
+    Ok(result[0].clone())
+}
+
+/// Hash multiple field elements in `domain` in-circuit.
+///
+/// Pair with `poseidon_hash_domain` for the same domain. The tag is a
+/// constant, so it adds no constraints.
+pub fn poseidon_hash_domain_var(
+    cs: ConstraintSystemRef<Fr>,
+    domain: Domain,
+    inputs: &[FpVar<Fr>],
 ) -> Result<FpVar<Fr>, SynthesisError> {
     let config = poseidon_config();
     let mut sponge = PoseidonSpongeVar::new(cs, &config);
+    sponge.state[0] = FpVar::Constant(domain.tag());
     for input in inputs {
         sponge.absorb(input)?;
     }
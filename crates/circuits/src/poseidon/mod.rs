@@ -2,14 +2,27 @@
 //!
 //! This module provides Poseidon hash functions optimized for ZK circuits.
 //! We use arkworks' built-in Poseidon sponge with standard parameters.
+//! Protocol hashes go through `poseidon_hash_domain`, which separates each
+//! use by a `Domain` tag.
 
 mod config;
+mod domain;
 mod native;
 mod gadgets;
 
 #[cfg(test)]
 mod tests;
 
-pub use native::{poseidon_hash, poseidon_hash_two, poseidon_hash_many};
-pub use gadgets::{poseidon_hash_var, poseidon_hash_two_var, poseidon_hash_many_var};
-pub use config::poseidon_config;
+pub use native::{
+    poseidon_hash, poseidon_hash_two, poseidon_hash_many, poseidon_hash_many_with,
+    poseidon_hash_domain,
+};
+pub use gadgets::{
+    poseidon_hash_var, poseidon_hash_two_var, poseidon_hash_many_var, poseidon_hash_many_var_with,
+    poseidon_hash_domain_var,
+};
+pub use domain::Domain;
+pub use config::{
+    poseidon_config, poseidon_config_insecure_benchmark_only, poseidon_config_wide,
+    poseidon_config_with,
+};
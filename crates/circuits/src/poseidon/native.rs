@@ -1,10 +1,11 @@
 //! Native Poseidon hash functions (outside circuits).
 
 use ark_bn254::Fr;
-use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+use ark_crypto_primitives::sponge::poseidon::{PoseidonConfig, PoseidonSponge};
 use ark_crypto_primitives::sponge::CryptographicSponge;
 
 use super::config::poseidon_config;
+use super::domain::Domain;
 
 /// Hash a single field element.
 pub fn poseidon_hash(input: Fr) -> Fr {
@@ -25,8 +26,27 @@ pub fn poseidon_hash_two(a: Fr, b: Fr) -> Fr {
 
 /// Hash multiple field elements.
 pub fn poseidon_hash_many(inputs: &[Fr]) -> Fr {
+    poseidon_hash_many_with(&poseidon_config(), inputs)
+}
+
+/// Hash multiple field elements under a custom configuration.
+///
+/// Pair with `poseidon_hash_many_var_with` using the same config.
+pub fn poseidon_hash_many_with(config: &PoseidonConfig<Fr>, inputs: &[Fr]) -> Fr {
+    let mut sponge = PoseidonSponge::new(config);
+    for input in inputs {
+        sponge.absorb(input);
+    }
+    sponge.squeeze_field_elements(1)[0]
+}
+
+/// Hash multiple field elements in `domain`.
+///
+/// Pair with `poseidon_hash_domain_var` for the same domain.
+pub fn poseidon_hash_domain(domain: Domain, inputs: &[Fr]) -> Fr {
     let config = poseidon_config();
     let mut sponge = PoseidonSponge::new(&config);
+    sponge.state[0] = domain.tag();
     for input in inputs {
         sponge.absorb(input);
     }
@@ -113,3 +113,103 @@ fn test_hash_of_one() {
     assert_ne!(h, Fr::one());
     assert_ne!(h, Fr::from(0u64));
 }
+
+#[test]
+fn test_presets_native_and_gadget_consistency() {
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_r1cs_std::eq::EqGadget;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    let inputs = vec![Fr::from(1u64), Fr::from(2u64), Fr::from(3u64)];
+    let presets = [
+        poseidon_config(),
+        poseidon_config_insecure_benchmark_only(),
+        poseidon_config_wide(),
+        poseidon_config_with(4, 1, 8, 60),
+    ];
+
+    let mut outputs = Vec::new();
+    for config in &presets {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let native_result = poseidon_hash_many_with(config, &inputs);
+
+        let input_vars: Vec<FpVar<Fr>> = inputs
+            .iter()
+            .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)).unwrap())
+            .collect();
+        let gadget_result = poseidon_hash_many_var_with(cs.clone(), config, &input_vars).unwrap();
+        let expected_var = FpVar::new_input(cs.clone(), || Ok(native_result)).unwrap();
+        gadget_result.enforce_equal(&expected_var).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        outputs.push(native_result);
+    }
+
+    // Default config is unchanged, and each preset hashes differently
+    assert_eq!(outputs[0], poseidon_hash_many(&inputs));
+    for i in 0..outputs.len() {
+        for j in i + 1..outputs.len() {
+            assert_ne!(outputs[i], outputs[j]);
+        }
+    }
+}
+
+#[test]
+fn test_domains_separate_equal_inputs() {
+    let inputs = [Fr::from(1u64), Fr::from(2u64)];
+
+    let mut outputs: Vec<Fr> = Domain::ALL
+        .iter()
+        .map(|&domain| poseidon_hash_domain(domain, &inputs))
+        .collect();
+    // Untagged hashing is a domain of its own
+    outputs.push(poseidon_hash_two(inputs[0], inputs[1]));
+
+    for i in 0..outputs.len() {
+        for j in i + 1..outputs.len() {
+            assert_ne!(outputs[i], outputs[j], "domains {} and {} collide", i, j);
+        }
+    }
+    for (i, domain) in Domain::ALL.iter().enumerate() {
+        assert_eq!(domain.tag(), Fr::from(i as u64 + 1));
+    }
+}
+
+#[test]
+fn test_domain_native_and_gadget_consistency() {
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_r1cs_std::eq::EqGadget;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    let inputs = [Fr::from(7u64), Fr::from(8u64), Fr::from(9u64)];
+    for domain in Domain::ALL {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let input_vars: Vec<FpVar<Fr>> = inputs
+            .iter()
+            .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)).unwrap())
+            .collect();
+
+        let gadget_result = poseidon_hash_domain_var(cs.clone(), domain, &input_vars).unwrap();
+        let native_result = poseidon_hash_domain(domain, &inputs);
+        let expected_var = FpVar::new_input(cs.clone(), || Ok(native_result)).unwrap();
+        gadget_result.enforce_equal(&expected_var).unwrap();
+
+        assert!(cs.is_satisfied().unwrap(), "{:?} gadget disagrees with native", domain);
+    }
+
+    // The tag is a constant: no constraints beyond the untagged hash
+    let count = |tagged: bool| {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let a = FpVar::new_witness(cs.clone(), || Ok(inputs[0])).unwrap();
+        let b = FpVar::new_witness(cs.clone(), || Ok(inputs[1])).unwrap();
+        let _ = if tagged {
+            poseidon_hash_domain_var(cs.clone(), Domain::SmtNode, &[a, b]).unwrap()
+        } else {
+            poseidon_hash_two_var(cs.clone(), &a, &b).unwrap()
+        };
+        cs.num_constraints()
+    };
+    assert!(count(true) <= count(false));
+}
@@ -0,0 +1,66 @@
+//! Constraint system inspection for external analysis.
+//!
+//! `synthesize` runs a circuit's constraint generation and hands back the
+//! populated constraint system, so auditors can inspect the R1CS of any
+//! circuit without re-implementing synthesis.
+
+use ark_bn254::Fr;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError,
+};
+
+/// Size summary of a synthesized constraint system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct R1csStats {
+    /// Number of R1CS constraints
+    pub num_constraints: usize,
+    /// Number of instance variables, including the constant `1`
+    pub num_instance_variables: usize,
+    /// Number of witness variables
+    pub num_witness_variables: usize,
+}
+
+impl R1csStats {
+    /// Read the sizes of a constraint system.
+    pub fn from_cs(cs: &ConstraintSystemRef<Fr>) -> Self {
+        Self {
+            num_constraints: cs.num_constraints(),
+            num_instance_variables: cs.num_instance_variables(),
+            num_witness_variables: cs.num_witness_variables(),
+        }
+    }
+}
+
+/// Generate the constraints of `circuit` into a fresh constraint system.
+///
+/// The system is not finalized, so linear combinations are left as built and
+/// `is_satisfied` can still be queried for the given witness.
+pub fn synthesize<C: ConstraintSynthesizer<Fr>>(
+    circuit: C,
+) -> Result<ConstraintSystemRef<Fr>, SynthesisError> {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(cs.clone())?;
+    Ok(cs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capacity_smt::CapacitySMTCircuit;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+
+    #[test]
+    fn test_capacity_stats() {
+        let tree = SparseMerkleTree::from_items(&[(1, 100), (2, 50)], DEFAULT_DEPTH);
+        let circuit = CapacitySMTCircuit::new(tree.root(), 500, Fr::from(12345u64), 1000);
+
+        let cs = synthesize(circuit).unwrap();
+        let stats = R1csStats::from_cs(&cs);
+
+        // One public input (the capacity hash) plus the constant
+        assert_eq!(stats.num_instance_variables, 2);
+        assert!((500..1500).contains(&stats.num_constraints));
+        assert!(stats.num_witness_variables > 0);
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
@@ -5,7 +5,9 @@
 //! These gadgets ensure values stay within expected bounds.
 //!
 //! We use 32-bit range checks which support values up to ~4.29 billion - sufficient for
-//! game inventories where quantities rarely exceed millions.
+//! game inventories where quantities rarely exceed millions. Remaining capacity is
+//! the exception: `enforce_within_capacity` checks it to 64 bits with
+//! `enforce_fits_in_bits` so any u64 max_capacity works.
 //!
 //! ## Optimization
 //!
@@ -22,6 +24,10 @@ use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
 /// Supports quantities up to 4,294,967,295 (~4.29 billion)
 pub const RANGE_BITS: usize = 32;
 
+/// Number of bits the remaining capacity is proven to fit in, so any u64
+/// max_capacity is usable.
+pub const CAPACITY_BITS: usize = 64;
+
 /// Enforce that a field element fits in `num_bits` bits.
 ///
 /// This uses an optimized approach that only allocates the bits we need:
@@ -69,6 +75,25 @@ pub fn enforce_range<F: PrimeField>(
     Ok(())
 }
 
+/// Enforce that a field element is below 2^num_bits, for any width.
+///
+/// `enforce_range` with its precondition checked: `num_bits` must be below
+/// `F::MODULUS_BIT_SIZE`, so every bit pattern reconstructs to a distinct
+/// element below the modulus. A wider check would accept any element, so it
+/// fails with `SynthesisError::Unsatisfiable` instead.
+///
+/// Constraint cost: ~num_bits constraints
+pub fn enforce_fits_in_bits<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    value: &FpVar<F>,
+    num_bits: usize,
+) -> Result<(), SynthesisError> {
+    if num_bits >= F::MODULUS_BIT_SIZE as usize {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    enforce_range(cs, value, num_bits)
+}
+
 /// Enforce that a value is non-negative and fits in 32 bits.
 ///
 /// This prevents underflow attacks where (small - large) wraps to a huge number.
@@ -106,6 +131,25 @@ pub fn enforce_geq<F: PrimeField>(
     enforce_u32_range(cs, &diff)
 }
 
+/// Enforce that used <= max_capacity, for any u64 max_capacity.
+///
+/// remaining = max_capacity - used is proven to fit in `CAPACITY_BITS` bits.
+///
+/// Precondition: `used` must already be bounded far below the field modulus,
+/// e.g. built from range-checked values. An over-capacity difference then
+/// wraps to p - (used - max_capacity), far outside the 64-bit range; an
+/// unbounded `used` could instead wrap back into it.
+///
+/// Constraint cost: ~65 constraints
+pub fn enforce_within_capacity<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    max_capacity: &FpVar<F>,
+    used: &FpVar<F>,
+) -> Result<(), SynthesisError> {
+    let remaining = max_capacity - used;
+    enforce_fits_in_bits(cs, &remaining, CAPACITY_BITS)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -221,4 +265,64 @@ mod tests {
             num_constraints
         );
     }
+
+    #[test]
+    fn test_fits_in_bits() {
+        let fits = |value: Fr, num_bits: usize| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let value = FpVar::new_witness(cs.clone(), || Ok(value)).unwrap();
+            enforce_fits_in_bits(cs.clone(), &value, num_bits).unwrap();
+            cs.is_satisfied().unwrap()
+        };
+        let two_pow_64 = Fr::from(u64::MAX) + Fr::from(1u64);
+
+        assert!(fits(Fr::from(u64::MAX), 64));
+        assert!(!fits(two_pow_64, 64));
+        assert!(fits(two_pow_64, 65));
+        assert!(!fits(Fr::from(1u64).neg(), 128));
+    }
+
+    #[test]
+    fn test_fits_in_bits_rejects_full_field_width() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let value = FpVar::new_witness(cs.clone(), || Ok(Fr::from(1u64))).unwrap();
+
+        // Every element fits in MODULUS_BIT_SIZE bits, so the check would be vacuous
+        let num_bits = Fr::MODULUS_BIT_SIZE as usize;
+        assert!(matches!(
+            enforce_fits_in_bits(cs.clone(), &value, num_bits),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+        assert!(enforce_fits_in_bits(cs, &value, num_bits - 1).is_ok());
+    }
+
+    fn within_capacity(max_capacity: Fr, used: Fr) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let max_capacity = FpVar::new_witness(cs.clone(), || Ok(max_capacity)).unwrap();
+        let used = FpVar::new_witness(cs.clone(), || Ok(used)).unwrap();
+        enforce_within_capacity(cs.clone(), &max_capacity, &used).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_within_capacity_64_bit_boundary() {
+        let max_u32 = Fr::from(u32::MAX as u64);
+
+        // Remaining capacity of exactly 2^64 - 1 fits
+        assert!(within_capacity(Fr::from(u64::MAX), Fr::from(0u64)));
+        assert!(within_capacity(Fr::from(u64::MAX), max_u32));
+
+        // 2^64 does not
+        let two_pow_64 = Fr::from(u64::MAX) + Fr::from(1u64);
+        assert!(!within_capacity(two_pow_64, Fr::from(0u64)));
+
+        // Over capacity by one, at both ends of the range
+        assert!(!within_capacity(Fr::from(0u64), Fr::from(1u64)));
+        assert!(!within_capacity(Fr::from(u32::MAX as u64 - 1), max_u32));
+
+        // A used volume past 64 bits, e.g. a sum of u64 values, is still compared
+        let two_pow_64 = Fr::from(u64::MAX) + Fr::from(1u64);
+        assert!(!within_capacity(Fr::from(u64::MAX), two_pow_64));
+        assert!(within_capacity(Fr::from(u64::MAX), Fr::from(u64::MAX)));
+    }
 }
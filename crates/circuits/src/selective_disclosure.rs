@@ -0,0 +1,176 @@
+//! Selective Disclosure Circuit for SMT-based inventory.
+//!
+//! Reveals the exact quantity of one item to a counterparty, bound to the
+//! inventory's public commitment. Every other item, the root, the volume and
+//! the blinding stay private.
+//!
+//! Public inputs (in order):
+//! - commitment: Poseidon(inventory_root, current_volume, blinding)
+//! - item_id: The disclosed item
+//! - quantity: The disclosed item's exact quantity
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::smt::{verify_membership, MerkleProof, MerkleProofVar};
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+
+/// Selective Disclosure Circuit.
+///
+/// Proves that the committed inventory holds exactly `quantity` of `item_id`.
+#[derive(Clone)]
+pub struct SelectiveDisclosureCircuit {
+    // Public inputs
+    /// Inventory commitment
+    pub commitment: Option<Fr>,
+    /// Disclosed item ID
+    pub item_id: Option<u64>,
+    /// Disclosed quantity
+    pub quantity: Option<u64>,
+
+    // Commitment components (witnesses)
+    /// Inventory SMT root
+    pub inventory_root: Option<Fr>,
+    /// Current volume
+    pub current_volume: Option<u64>,
+    /// Blinding factor
+    pub blinding: Option<Fr>,
+
+    // Merkle proof
+    /// Proof for the disclosed item in the SMT
+    pub proof: Option<MerkleProof<Fr>>,
+}
+
+impl SelectiveDisclosureCircuit {
+    /// Create an empty circuit for setup.
+    /// Discloses item 0 in a tree holding only that item, so the dummy
+    /// witnesses produce a valid constraint structure and also satisfy it.
+    pub fn empty() -> Self {
+        use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+
+        let tree = SparseMerkleTree::from_items(&[(0, 1)], DEFAULT_DEPTH);
+        let proof = tree.get_proof(0);
+
+        Self::new(tree.root(), 0, Fr::from(0u64), 0, 1, proof)
+    }
+
+    /// Create a new circuit with witnesses.
+    pub fn new(
+        inventory_root: Fr,
+        current_volume: u64,
+        blinding: Fr,
+        item_id: u64,
+        quantity: u64,
+        proof: MerkleProof<Fr>,
+    ) -> Self {
+        let commitment = create_smt_commitment(inventory_root, current_volume, blinding);
+
+        Self {
+            commitment: Some(commitment),
+            item_id: Some(item_id),
+            quantity: Some(quantity),
+            inventory_root: Some(inventory_root),
+            current_volume: Some(current_volume),
+            blinding: Some(blinding),
+            proof: Some(proof),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for SelectiveDisclosureCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public inputs ===
+        // Order matters: commitment, item_id, quantity
+        let commitment_var = FpVar::new_input(cs.clone(), || {
+            self.commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let item_id_var = FpVar::new_input(cs.clone(), || {
+            self.item_id
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let quantity_var = FpVar::new_input(cs.clone(), || {
+            self.quantity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate commitment witnesses ===
+        let root_var = FpVar::new_witness(cs.clone(), || {
+            self.inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let volume_var = FpVar::new_witness(cs.clone(), || {
+            self.current_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate Merkle proof ===
+        let proof_var = MerkleProofVar::new_witness(cs.clone(), self.proof.as_ref().unwrap())?;
+
+        // === Constraint 1: Disclosed leaf is in the SMT ===
+        verify_membership(cs.clone(), &root_var, &item_id_var, &quantity_var, &proof_var)?;
+
+        // === Constraint 2: The SMT is the committed one ===
+        let computed_commitment =
+            create_smt_commitment_var(cs, &root_var, &volume_var, &blinding_var)?;
+        computed_commitment.enforce_equal(&commitment_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    fn test_empty_circuit_satisfied() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        SelectiveDisclosureCircuit::empty()
+            .generate_constraints(cs.clone())
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_disclose_true_quantity() {
+        let tree = SparseMerkleTree::from_items(&[(1, 100), (5, 50), (42, 7)], DEFAULT_DEPTH);
+        let circuit = SelectiveDisclosureCircuit::new(
+            tree.root(),
+            1000,
+            Fr::from(12345u64),
+            5,
+            50,
+            tree.get_proof(5),
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_disclose_false_quantity_rejected() {
+        let tree = SparseMerkleTree::from_items(&[(1, 100), (5, 50), (42, 7)], DEFAULT_DEPTH);
+        let circuit = SelectiveDisclosureCircuit::new(
+            tree.root(),
+            1000,
+            Fr::from(12345u64),
+            5,
+            51,
+            tree.get_proof(5),
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
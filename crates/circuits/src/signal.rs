@@ -21,15 +21,29 @@
 //!     nonce,           // replay protection
 //!     inventory_id     // cross-inventory protection
 //! )
+//!
+//! Delta-capped transitions (see `StateTransitionCircuit::with_max_delta`) bind
+//! the growth limit on top: Poseidon(signal_hash, max_delta).
+//!
+//! Mints (see `MintCircuit`) bind the issuance so far the same way:
+//! Poseidon(signal_hash, already_minted).
+
+use std::fmt;
+use std::str::FromStr;
 
 use ark_bn254::Fr;
 use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
 use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use serde::{Deserialize, Serialize};
 
-use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::poseidon::{poseidon_hash_domain, poseidon_hash_domain_var, Domain};
 
 /// Operation types for state transitions.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///
+/// Serialized as `"deposit"` / `"withdraw"`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 #[repr(u8)]
 pub enum OpType {
     /// Deposit: add items to inventory
@@ -43,6 +57,63 @@ impl OpType {
     pub fn to_field(self) -> Fr {
         Fr::from(self as u64)
     }
+
+    /// Convert from the on-chain / in-circuit numeric representation.
+    pub fn try_from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(OpType::Deposit),
+            1 => Some(OpType::Withdraw),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name, as used in JSON.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OpType::Deposit => "deposit",
+            OpType::Withdraw => "withdraw",
+        }
+    }
+}
+
+impl fmt::Display for OpType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned when parsing an unknown op type name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseOpTypeError(String);
+
+impl fmt::Display for ParseOpTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown op_type '{}', expected 'deposit' (0) or 'withdraw' (1)", self.0)
+    }
+}
+
+impl std::error::Error for ParseOpTypeError {}
+
+impl FromStr for OpType {
+    type Err = ParseOpTypeError;
+
+    /// Parse "deposit" or "withdraw" (case-insensitive).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "deposit" => Ok(OpType::Deposit),
+            "withdraw" => Ok(OpType::Withdraw),
+            _ => Err(ParseOpTypeError(s.to_string())),
+        }
+    }
+}
+
+impl TryFrom<u8> for OpType {
+    type Error = ParseOpTypeError;
+
+    /// Convert 0 (deposit) or 1 (withdraw).
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        OpType::try_from_u8(value).ok_or_else(|| ParseOpTypeError(value.to_string()))
+    }
 }
 
 /// Inputs for computing the signal hash.
@@ -69,9 +140,9 @@ pub struct SignalInputs {
 }
 
 impl SignalInputs {
-    /// Compute the signal hash from these inputs.
-    pub fn compute_hash(&self) -> Fr {
-        let inputs = vec![
+    /// Field elements absorbed by the signal hash, in absorption order.
+    pub fn to_field_elements(&self) -> Vec<Fr> {
+        vec![
             self.old_commitment,
             self.new_commitment,
             self.registry_root,
@@ -81,9 +152,47 @@ impl SignalInputs {
             self.op_type.to_field(),
             Fr::from(self.nonce),
             self.inventory_id,
-        ];
+        ]
+    }
 
-        poseidon_hash_many(&inputs)
+    /// Compute the signal hash from these inputs.
+    pub fn compute_hash(&self) -> Fr {
+        poseidon_hash_domain(Domain::Signal, &self.to_field_elements())
+    }
+}
+
+/// Names of the signal hash elements, in absorption order.
+pub const SIGNAL_ELEMENT_NAMES: [&str; 9] = [
+    "old_commitment",
+    "new_commitment",
+    "registry_root",
+    "max_capacity",
+    "item_id",
+    "amount",
+    "op_type",
+    "nonce",
+    "inventory_id",
+];
+
+/// Breakdown of a signal hash computation for debugging.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignalHashDebug {
+    /// (name, value) for each absorbed element, in absorption order
+    pub elements: Vec<(&'static str, Fr)>,
+    /// Resulting signal hash
+    pub hash: Fr,
+}
+
+/// Return the ordered elements absorbed into the signal hash plus the final hash.
+///
+/// Diagnostic helper so clients can compare their own computation element by element.
+pub fn signal_hash_debug(inputs: &SignalInputs) -> SignalHashDebug {
+    let values = inputs.to_field_elements();
+    let hash = poseidon_hash_domain(Domain::Signal, &values);
+
+    SignalHashDebug {
+        elements: SIGNAL_ELEMENT_NAMES.iter().copied().zip(values).collect(),
+        hash,
     }
 }
 
@@ -154,7 +263,7 @@ impl SignalInputsVar {
             self.inventory_id.clone(),
         ];
 
-        poseidon_hash_many_var(cs, &inputs)
+        poseidon_hash_domain_var(cs, Domain::Signal, &inputs)
     }
 }
 
@@ -213,10 +322,48 @@ pub fn compute_signal_hash_var(
     inputs.compute_hash(cs)
 }
 
+/// Bind a per-operation volume growth limit into a signal hash.
+pub fn compute_delta_capped_signal_hash(signal_hash: Fr, max_delta: u64) -> Fr {
+    poseidon_hash_domain(Domain::DeltaCap, &[signal_hash, Fr::from(max_delta)])
+}
+
+/// Bind a per-operation volume growth limit into a signal hash in-circuit.
+pub fn compute_delta_capped_signal_hash_var(
+    cs: ConstraintSystemRef<Fr>,
+    signal_hash: &FpVar<Fr>,
+    max_delta: &FpVar<Fr>,
+) -> Result<FpVar<Fr>, SynthesisError> {
+    poseidon_hash_domain_var(cs, Domain::DeltaCap, &[signal_hash.clone(), max_delta.clone()])
+}
+
+/// Bind the supply already issued into a mint's signal hash.
+pub fn compute_mint_signal_hash(signal_hash: Fr, already_minted: u64) -> Fr {
+    poseidon_hash_domain(Domain::Mint, &[signal_hash, Fr::from(already_minted)])
+}
+
+/// Bind the supply already issued into a mint's signal hash in-circuit.
+pub fn compute_mint_signal_hash_var(
+    cs: ConstraintSystemRef<Fr>,
+    signal_hash: &FpVar<Fr>,
+    already_minted: &FpVar<Fr>,
+) -> Result<FpVar<Fr>, SynthesisError> {
+    poseidon_hash_domain_var(cs, Domain::Mint, &[signal_hash.clone(), already_minted.clone()])
+}
+
+/// Enforce that `op_type` is a valid `OpType` (0 = deposit, 1 = withdraw).
+///
+/// Returns whether the operation is a deposit. Every circuit that branches on
+/// op_type should go through this so no circuit accepts a third value.
+pub fn enforce_valid_op_type(op_type: &FpVar<Fr>) -> Result<Boolean<Fr>, SynthesisError> {
+    let is_deposit = op_type.is_eq(&FpVar::zero())?;
+    let is_withdraw = op_type.is_eq(&FpVar::one())?;
+    is_deposit.or(&is_withdraw)?.enforce_equal(&Boolean::TRUE)?;
+    Ok(is_deposit)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_r1cs_std::prelude::*;
     use ark_relations::r1cs::ConstraintSystem;
 
     #[test]
@@ -394,4 +541,87 @@ mod tests {
         assert!(cs.is_satisfied().unwrap());
         println!("Signal hash constraints: {}", cs.num_constraints());
     }
+
+    #[test]
+    fn test_signal_hash_debug_order() {
+        let inputs = SignalInputs {
+            old_commitment: Fr::from(100u64),
+            new_commitment: Fr::from(200u64),
+            registry_root: Fr::from(300u64),
+            max_capacity: 1000,
+            item_id: 42,
+            amount: 50,
+            op_type: OpType::Withdraw,
+            nonce: 5,
+            inventory_id: Fr::from(999u64),
+        };
+
+        let debug = signal_hash_debug(&inputs);
+
+        let expected = vec![
+            ("old_commitment", Fr::from(100u64)),
+            ("new_commitment", Fr::from(200u64)),
+            ("registry_root", Fr::from(300u64)),
+            ("max_capacity", Fr::from(1000u64)),
+            ("item_id", Fr::from(42u64)),
+            ("amount", Fr::from(50u64)),
+            ("op_type", Fr::from(1u64)),
+            ("nonce", Fr::from(5u64)),
+            ("inventory_id", Fr::from(999u64)),
+        ];
+        assert_eq!(debug.elements, expected);
+        assert_eq!(debug.hash, inputs.compute_hash());
+    }
+
+    #[test]
+    fn test_op_type_serde_round_trip() {
+        for op in [OpType::Deposit, OpType::Withdraw] {
+            let json = serde_json::to_string(&op).unwrap();
+            assert_eq!(json, format!("\"{}\"", op));
+            assert_eq!(serde_json::from_str::<OpType>(&json).unwrap(), op);
+            assert_eq!(op.to_string().parse::<OpType>().unwrap(), op);
+            assert_eq!(OpType::try_from_u8(op as u8), Some(op));
+        }
+
+        assert!(serde_json::from_str::<OpType>("\"transfer\"").is_err());
+        assert!("transfer".parse::<OpType>().is_err());
+        assert_eq!(OpType::try_from_u8(2), None);
+    }
+
+    #[test]
+    fn test_op_type_conversions() {
+        assert_eq!(OpType::try_from(0u8), Ok(OpType::Deposit));
+        assert_eq!(OpType::try_from(1u8), Ok(OpType::Withdraw));
+        assert!(OpType::try_from(2u8).is_err());
+        assert!(OpType::try_from(u8::MAX).is_err());
+
+        assert_eq!("Deposit".parse::<OpType>(), Ok(OpType::Deposit));
+        assert_eq!("WITHDRAW".parse::<OpType>(), Ok(OpType::Withdraw));
+        assert!("".parse::<OpType>().is_err());
+        assert!("1".parse::<OpType>().is_err());
+        assert!(" deposit".parse::<OpType>().is_err());
+
+        let err = OpType::try_from(7u8).unwrap_err();
+        assert!(err.to_string().contains("'7'"));
+    }
+
+    #[test]
+    fn test_enforce_valid_op_type() {
+        for (value, valid, deposit) in [(0u64, true, true), (1, true, false), (2, false, false)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let op_type = FpVar::new_witness(cs.clone(), || Ok(Fr::from(value))).unwrap();
+            let is_deposit = enforce_valid_op_type(&op_type).unwrap();
+
+            assert_eq!(cs.is_satisfied().unwrap(), valid, "op_type {}", value);
+            if valid {
+                assert_eq!(is_deposit.value().unwrap(), deposit);
+            }
+        }
+
+        // Field elements far from {0, 1} are rejected as well
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let op_type = FpVar::new_witness(cs.clone(), || Ok(-Fr::from(1u64))).unwrap();
+        let _ = enforce_valid_op_type(&op_type).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
 }
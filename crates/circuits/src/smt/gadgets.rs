@@ -10,14 +10,14 @@ use ark_r1cs_std::{
 };
 use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
 
-use crate::poseidon::{poseidon_hash_two, poseidon_hash_two_var};
+use crate::poseidon::{poseidon_hash_domain, poseidon_hash_domain_var, Domain};
 use super::proof::MerkleProof;
 
 /// Compute the default leaf hash H(0, 0) natively using Poseidon.
 /// This is the hash of an empty slot and is constant.
 /// Precomputing this saves constraints per verify_and_update call.
 pub fn compute_default_leaf_hash() -> Fr {
-    poseidon_hash_two(Fr::from(0u64), Fr::from(0u64))
+    poseidon_hash_domain(Domain::SmtLeaf, &[Fr::from(0u64), Fr::from(0u64)])
 }
 
 /// Circuit variable representation of a Merkle proof.
@@ -32,10 +32,17 @@ pub struct MerkleProofVar {
 
 impl MerkleProofVar {
     /// Allocate a Merkle proof as witness variables.
+    ///
+    /// Fails with `SynthesisError::Unsatisfiable` if the proof's path and
+    /// indices differ in length, since the root would silently be wrong.
     pub fn new_witness(
         cs: ConstraintSystemRef<Fr>,
         proof: &MerkleProof<Fr>,
     ) -> Result<Self, SynthesisError> {
+        if proof.path().len() != proof.indices().len() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
         let path = proof
             .path()
             .iter()
@@ -67,13 +74,13 @@ impl MerkleProofVar {
     }
 }
 
-/// Hash two field elements using Poseidon in-circuit.
+/// Hash two child nodes using Poseidon in-circuit.
 pub fn hash_two(
     cs: ConstraintSystemRef<Fr>,
     left: &FpVar<Fr>,
     right: &FpVar<Fr>,
 ) -> Result<FpVar<Fr>, SynthesisError> {
-    poseidon_hash_two_var(cs, left, right)
+    poseidon_hash_domain_var(cs, Domain::SmtNode, &[left.clone(), right.clone()])
 }
 
 /// Hash a leaf (item_id, quantity) using Poseidon in-circuit.
@@ -82,7 +89,21 @@ pub fn hash_leaf(
     item_id: &FpVar<Fr>,
     quantity: &FpVar<Fr>,
 ) -> Result<FpVar<Fr>, SynthesisError> {
-    hash_two(cs, item_id, quantity)
+    poseidon_hash_domain_var(cs, Domain::SmtLeaf, &[item_id.clone(), quantity.clone()])
+}
+
+/// Hash a leaf with attributes (item_id, quantity, attr_hash) using Poseidon in-circuit.
+pub fn hash_leaf_with_attributes(
+    cs: ConstraintSystemRef<Fr>,
+    item_id: &FpVar<Fr>,
+    quantity: &FpVar<Fr>,
+    attr_hash: &FpVar<Fr>,
+) -> Result<FpVar<Fr>, SynthesisError> {
+    poseidon_hash_domain_var(
+        cs,
+        Domain::SmtLeaf,
+        &[item_id.clone(), quantity.clone(), attr_hash.clone()],
+    )
 }
 
 /// Compute the root hash from a leaf and Merkle path in-circuit.
@@ -128,6 +149,24 @@ pub fn verify_membership(
     Ok(())
 }
 
+/// Verify that a leaf with given item_id, quantity and attribute hash exists in the tree.
+///
+/// This constrains: compute_root(H(item_id, quantity, attr_hash), proof) == expected_root
+pub fn verify_membership_with_attributes(
+    cs: ConstraintSystemRef<Fr>,
+    expected_root: &FpVar<Fr>,
+    item_id: &FpVar<Fr>,
+    quantity: &FpVar<Fr>,
+    attr_hash: &FpVar<Fr>,
+    proof: &MerkleProofVar,
+) -> Result<(), SynthesisError> {
+    let leaf_hash = hash_leaf_with_attributes(cs.clone(), item_id, quantity, attr_hash)?;
+    let computed_root = compute_root_from_path(cs, &leaf_hash, proof)?;
+    computed_root.enforce_equal(expected_root)?;
+
+    Ok(())
+}
+
 /// Verify membership and compute the new root after updating the leaf.
 ///
 /// This is used for state transitions (deposit/withdraw).
@@ -145,6 +184,29 @@ pub fn verify_and_update(
     new_quantity: &FpVar<Fr>,
     proof: &MerkleProofVar,
 ) -> Result<FpVar<Fr>, SynthesisError> {
+    let (old_leaf_hash, new_leaf_hash) =
+        transition_leaf_hashes(cs.clone(), item_id, old_quantity, new_quantity)?;
+
+    // Verify old state
+    let computed_old_root = compute_root_from_path(cs.clone(), &old_leaf_hash, proof)?;
+    computed_old_root.enforce_equal(old_root)?;
+
+    // Compute new root using the same path (siblings unchanged)
+    let new_root = compute_root_from_path(cs, &new_leaf_hash, proof)?;
+
+    Ok(new_root)
+}
+
+/// Old and new leaf hashes for a quantity change at `item_id`.
+///
+/// A zero quantity is an empty slot and hashes to the default leaf H(0, 0),
+/// matching the native tree, so a later re-deposit is an insertion again.
+pub(super) fn transition_leaf_hashes(
+    cs: ConstraintSystemRef<Fr>,
+    item_id: &FpVar<Fr>,
+    old_quantity: &FpVar<Fr>,
+    new_quantity: &FpVar<Fr>,
+) -> Result<(FpVar<Fr>, FpVar<Fr>), SynthesisError> {
     // For insertions (old_quantity == 0), use precomputed default leaf hash H(0, 0)
     // For updates (old_quantity > 0), use regular hash H(item_id, old_quantity)
     let zero = FpVar::zero();
@@ -153,33 +215,34 @@ pub fn verify_and_update(
     // Use precomputed constant instead of computing hash_leaf(0, 0) in-circuit
     let default_leaf_hash_var = FpVar::constant(compute_default_leaf_hash());
     let regular_old_hash = hash_leaf(cs.clone(), item_id, old_quantity)?;
-
     let old_leaf_hash = is_insertion.select(&default_leaf_hash_var, &regular_old_hash)?;
 
-    // Verify old state
-    let computed_old_root = compute_root_from_path(cs.clone(), &old_leaf_hash, proof)?;
-    computed_old_root.enforce_equal(old_root)?;
-
-    // Compute new leaf hash (always uses item_id)
-    let new_leaf_hash = hash_leaf(cs.clone(), item_id, new_quantity)?;
+    let is_removal = new_quantity.is_eq(&zero)?;
+    let regular_new_hash = hash_leaf(cs, item_id, new_quantity)?;
+    let new_leaf_hash = is_removal.select(&default_leaf_hash_var, &regular_new_hash)?;
 
-    // Compute new root using the same path (siblings unchanged)
-    let new_root = compute_root_from_path(cs, &new_leaf_hash, proof)?;
-
-    Ok(new_root)
+    Ok((old_leaf_hash, new_leaf_hash))
 }
 
 /// Verify that an item is NOT in the tree (quantity = 0).
 ///
-/// This proves non-membership by showing the leaf at item_id is empty.
+/// This proves non-membership by showing the leaf at item_id is the default leaf H(0, 0).
+/// Since the empty leaf does not contain item_id, the path indices are constrained
+/// to be the little-endian bits of item_id.
 pub fn verify_non_membership(
     cs: ConstraintSystemRef<Fr>,
     expected_root: &FpVar<Fr>,
     item_id: &FpVar<Fr>,
     proof: &MerkleProofVar,
 ) -> Result<(), SynthesisError> {
-    let zero = FpVar::zero();
-    verify_membership(cs, expected_root, item_id, &zero, proof)
+    let position = Boolean::le_bits_to_fp_var(&proof.indices)?;
+    position.enforce_equal(item_id)?;
+
+    let default_leaf_hash_var = FpVar::constant(compute_default_leaf_hash());
+    let computed_root = compute_root_from_path(cs, &default_leaf_hash_var, proof)?;
+    computed_root.enforce_equal(expected_root)?;
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -188,6 +251,20 @@ mod gadget_tests {
     use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
     use ark_relations::r1cs::ConstraintSystem;
 
+    #[test]
+    fn test_new_witness_rejects_mismatched_lengths() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+
+        let proof = MerkleProof::new(vec![Fr::from(1u64); 4], vec![false; 4]);
+        assert_eq!(MerkleProofVar::new_witness(cs.clone(), &proof).unwrap().depth(), 4);
+
+        let proof = MerkleProof::new_unchecked(vec![Fr::from(1u64); 4], vec![false; 3]);
+        assert!(matches!(
+            MerkleProofVar::new_witness(cs, &proof),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+
     #[test]
     fn test_verify_membership_valid() {
         let mut tree = SparseMerkleTree::new(DEFAULT_DEPTH);
@@ -286,6 +363,42 @@ mod gadget_tests {
         assert!(cs.is_satisfied().unwrap());
     }
 
+    #[test]
+    fn test_verify_and_update_withdraw_to_zero_matches_native() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100), (7, 3)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+        let proof = tree.get_proof(7);
+
+        // Native removal stores the default leaf H(0, 0), not H(7, 0)
+        tree.update(7, 0);
+        let expected_new_root = tree.root();
+        assert_eq!(
+            expected_new_root,
+            SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH).root()
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+
+        let old_root_var = FpVar::new_input(cs.clone(), || Ok(old_root)).unwrap();
+        let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(7u64))).unwrap();
+        let old_qty_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(3u64))).unwrap();
+        let new_qty_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(0u64))).unwrap();
+        let proof_var = MerkleProofVar::new_witness(cs.clone(), &proof).unwrap();
+
+        let computed_new_root = verify_and_update(
+            cs.clone(),
+            &old_root_var,
+            &item_id_var,
+            &old_qty_var,
+            &new_qty_var,
+            &proof_var,
+        )
+        .unwrap();
+
+        assert_eq!(computed_new_root.value().unwrap(), expected_new_root);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
     #[test]
     fn test_constraint_count() {
         let mut tree = SparseMerkleTree::new(DEFAULT_DEPTH);
@@ -0,0 +1,221 @@
+//! Lazily materialized inventories for servers tracking many idle inventories.
+//!
+//! A `LazyInventory` stores only its sorted (item_id, quantity) leaves. The full
+//! `SparseMerkleTree` (whose node map dominates memory) is built on demand and
+//! kept in a shared, bounded `TreeCache`; least recently used trees are evicted.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use ark_bn254::Fr;
+
+use super::proof::MerkleProof;
+use super::tree::SparseMerkleTree;
+
+/// Bounded LRU cache of materialized trees shared by many `LazyInventory`s.
+pub struct TreeCache {
+    /// Maximum number of materialized trees kept
+    capacity: usize,
+    /// (inventory key, tree), most recently used at the front
+    trees: Mutex<VecDeque<(u64, SparseMerkleTree)>>,
+    /// Next key handed out to a new inventory
+    next_key: AtomicU64,
+}
+
+impl TreeCache {
+    /// Create a cache holding at most `capacity` materialized trees.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "cache capacity must be non-zero");
+        Self {
+            capacity,
+            trees: Mutex::new(VecDeque::with_capacity(capacity)),
+            next_key: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of trees currently materialized.
+    pub fn len(&self) -> usize {
+        self.trees.lock().unwrap().len()
+    }
+
+    /// Check if no trees are materialized.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Run `f` on the materialized tree for `key`, building it from `leaves` on a miss.
+    fn with_tree<T>(
+        &self,
+        key: u64,
+        leaves: &[(u64, u64)],
+        depth: usize,
+        f: impl FnOnce(&mut SparseMerkleTree) -> T,
+    ) -> T {
+        let mut trees = self.trees.lock().unwrap();
+
+        let entry = match trees.iter().position(|(k, _)| *k == key) {
+            Some(pos) => trees.remove(pos).unwrap(),
+            None => {
+                if trees.len() == self.capacity {
+                    trees.pop_back();
+                }
+                (key, SparseMerkleTree::from_items(leaves, depth))
+            }
+        };
+        trees.push_front(entry);
+
+        f(&mut trees[0].1)
+    }
+
+    /// Drop the materialized tree for `key`, if any.
+    fn evict(&self, key: u64) {
+        self.trees.lock().unwrap().retain(|(k, _)| *k != key);
+    }
+}
+
+/// An inventory that stores only its leaves and builds its SMT on demand.
+pub struct LazyInventory {
+    /// Cache key for this inventory
+    key: u64,
+    /// Tree depth
+    depth: usize,
+    /// Non-empty leaves sorted by item_id
+    leaves: Vec<(u64, u64)>,
+    /// Shared cache of materialized trees
+    cache: Arc<TreeCache>,
+}
+
+impl LazyInventory {
+    /// Create a lazy inventory from (item_id, quantity) pairs.
+    ///
+    /// Zero quantities are dropped; later duplicates overwrite earlier ones,
+    /// matching `SparseMerkleTree::from_items`.
+    pub fn from_items(items: &[(u64, u64)], depth: usize, cache: Arc<TreeCache>) -> Self {
+        let mut inventory = Self {
+            key: cache.next_key.fetch_add(1, Ordering::Relaxed),
+            depth,
+            leaves: Vec::with_capacity(items.len()),
+            cache,
+        };
+        for &(item_id, quantity) in items {
+            inventory.set_leaf(item_id, quantity);
+        }
+        inventory
+    }
+
+    /// Insert, update or remove a leaf in the sorted list.
+    fn set_leaf(&mut self, item_id: u64, quantity: u64) {
+        assert!(item_id < (1u64 << self.depth), "item_id exceeds tree capacity");
+
+        match self.leaves.binary_search_by_key(&item_id, |&(id, _)| id) {
+            Ok(pos) if quantity == 0 => {
+                self.leaves.remove(pos);
+            }
+            Ok(pos) => self.leaves[pos].1 = quantity,
+            Err(_) if quantity == 0 => {}
+            Err(pos) => self.leaves.insert(pos, (item_id, quantity)),
+        }
+    }
+
+    /// Get the quantity for an item, or 0 if not present.
+    pub fn get(&self, item_id: u64) -> u64 {
+        self.leaves
+            .binary_search_by_key(&item_id, |&(id, _)| id)
+            .map(|pos| self.leaves[pos].1)
+            .unwrap_or(0)
+    }
+
+    /// Get the non-empty leaves, sorted by item_id.
+    pub fn leaves(&self) -> &[(u64, u64)] {
+        &self.leaves
+    }
+
+    /// Get the current root hash.
+    pub fn root(&self) -> Fr {
+        self.cache
+            .with_tree(self.key, &self.leaves, self.depth, |tree| tree.root())
+    }
+
+    /// Generate a Merkle proof for the given item.
+    pub fn get_proof(&self, item_id: u64) -> MerkleProof<Fr> {
+        self.cache
+            .with_tree(self.key, &self.leaves, self.depth, |tree| tree.get_proof(item_id))
+    }
+
+    /// Update the quantity for an item. Returns the new root hash.
+    pub fn update(&mut self, item_id: u64, quantity: u64) -> Fr {
+        self.set_leaf(item_id, quantity);
+        self.cache
+            .with_tree(self.key, &self.leaves, self.depth, |tree| tree.update(item_id, quantity))
+    }
+
+    /// Build a standalone tree for this inventory, bypassing the cache.
+    pub fn materialize(&self) -> SparseMerkleTree {
+        SparseMerkleTree::from_items(&self.leaves, self.depth)
+    }
+
+    /// Get the tree depth.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+impl Drop for LazyInventory {
+    fn drop(&mut self) {
+        self.cache.evict(self.key);
+    }
+}
+
+#[cfg(test)]
+mod lazy_tests {
+    use super::*;
+    use crate::smt::DEFAULT_DEPTH;
+
+    #[test]
+    fn test_matches_eager_tree() {
+        let cache = Arc::new(TreeCache::new(2));
+        let items = [(100, 25), (1, 100), (5, 50)];
+
+        let mut lazy = LazyInventory::from_items(&items, DEFAULT_DEPTH, cache.clone());
+        let mut eager = SparseMerkleTree::from_items(&items, DEFAULT_DEPTH);
+
+        assert_eq!(lazy.leaves(), &[(1, 100), (5, 50), (100, 25)]);
+        assert_eq!(lazy.root(), eager.root());
+
+        for item_id in [1, 5, 100, 7] {
+            let lazy_proof = lazy.get_proof(item_id);
+            let eager_proof = eager.get_proof(item_id);
+            assert_eq!(lazy_proof.path(), eager_proof.path());
+            assert_eq!(lazy_proof.indices(), eager_proof.indices());
+        }
+
+        assert_eq!(lazy.update(5, 0), eager.update(5, 0));
+        assert_eq!(lazy.update(7, 3), eager.update(7, 3));
+        assert_eq!(lazy.get(7), 3);
+        assert_eq!(lazy.get(5), 0);
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let cache = Arc::new(TreeCache::new(2));
+
+        let a = LazyInventory::from_items(&[(1, 10)], DEFAULT_DEPTH, cache.clone());
+        let b = LazyInventory::from_items(&[(2, 20)], DEFAULT_DEPTH, cache.clone());
+        let c = LazyInventory::from_items(&[(3, 30)], DEFAULT_DEPTH, cache.clone());
+        assert!(cache.is_empty());
+
+        let root_a = a.root();
+        b.root();
+        c.root(); // evicts a
+        assert_eq!(cache.len(), 2);
+
+        // a is rebuilt from its leaves with the same root
+        assert_eq!(a.root(), root_a);
+        assert_eq!(a.root(), a.materialize().root());
+        assert_eq!(cache.len(), 2);
+
+        drop(a);
+        assert_eq!(cache.len(), 1);
+    }
+}
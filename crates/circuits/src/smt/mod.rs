@@ -1,20 +1,27 @@
 //! Sparse Merkle Tree implementation for inventory privacy circuits.
 //!
 //! This module provides:
-//! - Native SMT operations (insert, update, proof generation) using Anemoi hash
-//! - In-circuit SMT verification gadgets using Anemoi (~2x fewer constraints vs Poseidon)
+//! - Native SMT operations (insert, update, proof generation) using Poseidon hash
+//! - In-circuit SMT verification gadgets using Poseidon
 //! - Merkle proof structures
+//! - Lazily materialized inventories sharing a bounded tree cache
+//! - Two-level trees splitting large item ID spaces into bucket subtrees
 
 mod tree;
 mod proof;
 mod gadgets;
+mod lazy;
+mod two_level;
 
 #[cfg(test)]
 mod tests;
 
-pub use tree::{SparseMerkleTree, DEFAULT_DEPTH};
-pub use proof::MerkleProof;
+pub use tree::{empty_root, LeafArity, SparseMerkleTree, DEFAULT_DEPTH};
+pub use proof::{verify_proof_against_root, MerkleProof, ProofLengthMismatch, PROOF_BYTES_HEADER};
+pub use lazy::{LazyInventory, TreeCache};
+pub use two_level::{verify_and_update_two_level, TwoLevelProof, TwoLevelProofVar, TwoLevelSMT};
 pub use gadgets::{
-    MerkleProofVar, verify_membership, verify_and_update, compute_root_from_path,
-    compute_default_leaf_hash, hash_two, hash_leaf,
+    MerkleProofVar, verify_membership, verify_membership_with_attributes, verify_non_membership,
+    verify_and_update, compute_root_from_path, compute_default_leaf_hash, hash_two, hash_leaf,
+    hash_leaf_with_attributes,
 };
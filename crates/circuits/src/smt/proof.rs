@@ -2,14 +2,39 @@
 //!
 //! Uses Poseidon hash function for ZK-friendly hashing.
 
+use std::fmt;
+
 use ark_bn254::Fr;
 use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+
+use crate::poseidon::{poseidon_hash_domain, Domain};
+
+/// A proof whose sibling path and direction bits disagree in length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofLengthMismatch {
+    /// Number of sibling hashes
+    pub path_len: usize,
+    /// Number of direction bits
+    pub indices_len: usize,
+}
 
-use crate::poseidon::poseidon_hash_two;
+impl fmt::Display for ProofLengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "proof has {} siblings but {} direction bits",
+            self.path_len, self.indices_len
+        )
+    }
+}
+
+impl std::error::Error for ProofLengthMismatch {}
 
 /// A Merkle proof for an SMT leaf.
 ///
 /// Contains the sibling hashes from leaf to root and direction indices.
+/// Proofs from trees with attributes also carry the leaf's attribute hash.
 #[derive(Clone, Debug)]
 pub struct MerkleProof<F: PrimeField> {
     /// Sibling hashes from leaf level (0) to root level (depth-1)
@@ -17,14 +42,46 @@ pub struct MerkleProof<F: PrimeField> {
 
     /// Direction at each level: true = current node is right child
     indices: Vec<bool>,
+
+    /// Attribute hash of the leaf, for H(item_id, quantity, attr_hash) leaves
+    attr_hash: Option<F>,
 }
 
 // Accessors that work for any field
 impl<F: PrimeField> MerkleProof<F> {
     /// Create a new Merkle proof.
+    ///
+    /// `path` and `indices` must have the same length; use `try_new` for
+    /// untrusted input.
     pub fn new(path: Vec<F>, indices: Vec<bool>) -> Self {
-        assert_eq!(path.len(), indices.len(), "Path and indices must have same length");
-        Self { path, indices }
+        debug_assert_eq!(path.len(), indices.len(), "Path and indices must have same length");
+        Self {
+            path,
+            indices,
+            attr_hash: None,
+        }
+    }
+
+    /// Create a new Merkle proof, rejecting a path and indices of different lengths.
+    pub fn try_new(path: Vec<F>, indices: Vec<bool>) -> Result<Self, ProofLengthMismatch> {
+        if path.len() != indices.len() {
+            return Err(ProofLengthMismatch {
+                path_len: path.len(),
+                indices_len: indices.len(),
+            });
+        }
+        Ok(Self::new(path, indices))
+    }
+
+    /// Attach the leaf's attribute hash to this proof.
+    pub fn with_attr_hash(mut self, attr_hash: F) -> Self {
+        self.attr_hash = Some(attr_hash);
+        self
+    }
+
+    /// Get the attribute hash, if the leaf has attributes.
+    pub fn attr_hash(&self) -> Option<F> {
+        self.attr_hash
     }
 
     /// Get the proof path (sibling hashes).
@@ -42,22 +99,118 @@ impl<F: PrimeField> MerkleProof<F> {
         self.path.len()
     }
 
+    /// Create a proof without checking lengths, for testing malformed proofs.
+    #[cfg(test)]
+    pub(crate) fn new_unchecked(path: Vec<F>, indices: Vec<bool>) -> Self {
+        Self {
+            path,
+            indices,
+            attr_hash: None,
+        }
+    }
+
     /// Create an empty proof for testing.
     #[cfg(test)]
     pub fn empty(depth: usize) -> Self {
         Self {
             path: vec![F::zero(); depth],
             indices: vec![false; depth],
+            attr_hash: None,
         }
     }
 }
 
+/// Header of `MerkleProof::to_bytes`: depth, then an attribute-hash flag.
+pub const PROOF_BYTES_HEADER: usize = 2;
+
 // Poseidon hashing methods (specialized for Fr)
 impl MerkleProof<Fr> {
+    /// Encode the proof in a fixed layout for on-chain verifiers.
+    ///
+    /// Layout: depth (u8), attribute flag (u8), each sibling from leaf to root
+    /// as 32 little-endian bytes, the directions packed LSB-first (bit i set
+    /// means the node at level i is a right child), then the attribute hash
+    /// as 32 bytes if the flag is 1.
+    ///
+    /// Fails with `InvalidData` for proofs deeper than 255 levels, which the
+    /// one-byte depth cannot encode.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SerializationError> {
+        let depth = u8::try_from(self.depth()).map_err(|_| SerializationError::InvalidData)?;
+
+        let mut bytes = vec![depth, self.attr_hash.is_some() as u8];
+        for sibling in &self.path {
+            sibling.serialize_compressed(&mut bytes)?;
+        }
+
+        let mut directions = vec![0u8; self.depth().div_ceil(8)];
+        for (level, _) in self.indices.iter().enumerate().filter(|&(_, &is_right)| is_right) {
+            directions[level / 8] |= 1 << (level % 8);
+        }
+        bytes.extend(directions);
+
+        if let Some(attr_hash) = self.attr_hash {
+            attr_hash.serialize_compressed(&mut bytes)?;
+        }
+        Ok(bytes)
+    }
+
+    /// Decode a proof produced by `to_bytes`.
+    ///
+    /// Rejects non-canonical field elements, set padding bits and trailing bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let (header, mut rest) = bytes
+            .split_first_chunk::<PROOF_BYTES_HEADER>()
+            .ok_or(SerializationError::InvalidData)?;
+        let depth = header[0] as usize;
+        let has_attr = match header[1] {
+            0 => false,
+            1 => true,
+            _ => return Err(SerializationError::InvalidData),
+        };
+
+        let path = (0..depth)
+            .map(|_| Fr::deserialize_compressed(&mut rest))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (directions, mut rest) = rest
+            .split_at_checked(depth.div_ceil(8))
+            .ok_or(SerializationError::InvalidData)?;
+        let indices: Vec<bool> = (0..depth)
+            .map(|level| (directions[level / 8] >> (level % 8)) & 1 == 1)
+            .collect();
+        let padding_bits = directions.len() * 8 - depth;
+        if padding_bits > 0 && directions[directions.len() - 1] >> (8 - padding_bits) != 0 {
+            return Err(SerializationError::InvalidData);
+        }
+
+        let attr_hash = if has_attr {
+            Some(Fr::deserialize_compressed(&mut rest)?)
+        } else {
+            None
+        };
+        if !rest.is_empty() {
+            return Err(SerializationError::InvalidData);
+        }
+
+        Ok(Self {
+            path,
+            indices,
+            attr_hash,
+        })
+    }
+
     /// Compute the root hash from this proof and the leaf value using Poseidon.
+    ///
+    /// Uses H(item_id, quantity, attr_hash) when the proof carries an attribute hash.
+    /// A quantity of 0 is an empty slot and uses the canonical empty leaf.
     pub fn compute_root(&self, item_id: u64, quantity: u64) -> Fr {
         // Start with leaf hash
-        let mut current = Self::hash_leaf(item_id, quantity);
+        let mut current = match (self.attr_hash, quantity) {
+            (Some(_), 0) => Self::hash_leaf_with_attributes(0, 0, Fr::from(0u64)),
+            (None, 0) => Self::hash_leaf(0, 0),
+            (Some(attr_hash), _) => Self::hash_leaf_with_attributes(item_id, quantity, attr_hash),
+            (None, _) => Self::hash_leaf(item_id, quantity),
+        };
 
         // Work up the tree
         for (sibling, &is_right) in self.path.iter().zip(self.indices.iter()) {
@@ -90,15 +243,42 @@ impl MerkleProof<Fr> {
 
     /// Hash a leaf: H(item_id, quantity) using Poseidon
     fn hash_leaf(item_id: u64, quantity: u64) -> Fr {
-        poseidon_hash_two(Fr::from(item_id), Fr::from(quantity))
+        poseidon_hash_domain(Domain::SmtLeaf, &[Fr::from(item_id), Fr::from(quantity)])
+    }
+
+    /// Hash a leaf with attributes: H(item_id, quantity, attr_hash) using Poseidon
+    fn hash_leaf_with_attributes(item_id: u64, quantity: u64, attr_hash: Fr) -> Fr {
+        poseidon_hash_domain(Domain::SmtLeaf, &[Fr::from(item_id), Fr::from(quantity), attr_hash])
     }
 
     /// Hash two nodes: H(left, right) using Poseidon
     fn hash_nodes(left: Fr, right: Fr) -> Fr {
-        poseidon_hash_two(left, right)
+        poseidon_hash_domain(Domain::SmtNode, &[left, right])
     }
 }
 
+/// Check a claimed leaf against a known root, without a tree.
+///
+/// Besides recomputing the root, requires the proof's path to lead to
+/// `item_id`'s slot: an empty leaf does not contain the item_id, so otherwise
+/// any empty-slot proof would verify for any absent item.
+pub fn verify_proof_against_root(
+    root: Fr,
+    item_id: u64,
+    quantity: u64,
+    proof: &MerkleProof<Fr>,
+) -> bool {
+    let depth = proof.depth() as u32;
+    let position_matches = item_id.checked_shr(depth).unwrap_or(0) == 0
+        && proof
+            .indices()
+            .iter()
+            .enumerate()
+            .all(|(level, &is_right)| is_right == ((item_id >> level) & 1 == 1));
+
+    position_matches && proof.compute_root(item_id, quantity) == root
+}
+
 #[cfg(test)]
 mod proof_tests {
     use super::*;
@@ -115,6 +295,15 @@ mod proof_tests {
         assert_eq!(proof.indices(), &indices);
     }
 
+    #[test]
+    fn test_try_new_checks_lengths() {
+        let proof = MerkleProof::try_new(vec![Fr::from(1u64); 3], vec![true, false, true]).unwrap();
+        assert_eq!(proof.depth(), 3);
+
+        let err = MerkleProof::try_new(vec![Fr::from(1u64); 3], vec![true, false]).unwrap_err();
+        assert_eq!(err, ProofLengthMismatch { path_len: 3, indices_len: 2 });
+    }
+
     #[test]
     fn test_compute_root_deterministic() {
         let path = vec![Fr::from(1u64), Fr::from(2u64)];
@@ -140,4 +329,49 @@ mod proof_tests {
 
         assert_ne!(root1, root2);
     }
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        use crate::smt::{LeafArity, SparseMerkleTree, DEFAULT_DEPTH};
+
+        let tree = SparseMerkleTree::from_items(&[(5, 50), (2049, 7)], DEFAULT_DEPTH);
+        for item_id in [5, 2049, 9] {
+            let proof = tree.get_proof(item_id);
+            let decoded = MerkleProof::from_bytes(&proof.to_bytes().unwrap()).unwrap();
+            assert_eq!(decoded.path(), proof.path());
+            assert_eq!(decoded.indices(), proof.indices());
+            assert_eq!(decoded.attr_hash(), None);
+            assert!(tree.verify_proof(item_id, tree.get(item_id), &decoded));
+        }
+
+        let mut tree = SparseMerkleTree::with_arity(DEFAULT_DEPTH, LeafArity::Three);
+        tree.update_with_attributes(3, 10, Fr::from(77u64));
+        let proof = tree.get_proof(3);
+        let decoded = MerkleProof::from_bytes(&proof.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.attr_hash(), Some(Fr::from(77u64)));
+        assert!(tree.verify_proof(3, 10, &decoded));
+    }
+
+    #[test]
+    fn test_bytes_length() {
+        for depth in [1, 8, 12, 20] {
+            let proof = MerkleProof::new(vec![Fr::from(1u64); depth], vec![true; depth]);
+            let bytes = proof.to_bytes().unwrap();
+            assert_eq!(bytes.len(), depth * 32 + depth.div_ceil(8) + PROOF_BYTES_HEADER);
+
+            // Padding bits past the depth must be clear, and nothing may follow
+            if depth % 8 != 0 {
+                let mut padded = bytes.clone();
+                padded[PROOF_BYTES_HEADER + depth * 32 + depth / 8] |= 0x80;
+                assert!(MerkleProof::from_bytes(&padded).is_err());
+            }
+            let mut trailing = bytes;
+            trailing.push(0);
+            assert!(MerkleProof::from_bytes(&trailing).is_err());
+        }
+
+        // The depth byte cannot encode more than 255 levels
+        let proof = MerkleProof::new(vec![Fr::from(1u64); 256], vec![false; 256]);
+        assert!(matches!(proof.to_bytes(), Err(SerializationError::InvalidData)));
+    }
 }
@@ -273,3 +273,162 @@ fn test_item_id_overflow() {
     // 4096 is out of bounds for depth 12 (max is 4095)
     tree.update(4096, 100);
 }
+
+#[test]
+fn test_attribute_leaf_membership() {
+    let sword_attrs = Fr::from(0xd00du64); // e.g. hash of (durability, enchantment)
+
+    let mut tree = SparseMerkleTree::with_arity(DEFAULT_DEPTH, LeafArity::Three);
+    tree.update_with_attributes(7, 1, sword_attrs);
+    tree.update(3, 20);
+
+    let root = tree.root();
+    let proof = tree.get_proof(7);
+    assert_eq!(proof.attr_hash(), Some(sword_attrs));
+    assert!(tree.verify_proof(7, 1, &proof));
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+
+    let root_var = FpVar::new_input(cs.clone(), || Ok(root)).unwrap();
+    let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(7u64))).unwrap();
+    let qty_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(1u64))).unwrap();
+    let attr_var = FpVar::new_witness(cs.clone(), || Ok(sword_attrs)).unwrap();
+    let proof_var = MerkleProofVar::new_witness(cs.clone(), &proof).unwrap();
+
+    verify_membership_with_attributes(
+        cs.clone(),
+        &root_var,
+        &item_id_var,
+        &qty_var,
+        &attr_var,
+        &proof_var,
+    ).unwrap();
+
+    assert!(cs.is_satisfied().unwrap());
+}
+
+#[test]
+fn test_attribute_leaf_wrong_attributes_rejected() {
+    let mut tree = SparseMerkleTree::with_arity(DEFAULT_DEPTH, LeafArity::Three);
+    tree.update_with_attributes(7, 1, Fr::from(100u64));
+
+    let root = tree.root();
+    let proof = tree.get_proof(7);
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+
+    let root_var = FpVar::new_input(cs.clone(), || Ok(root)).unwrap();
+    let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(7u64))).unwrap();
+    let qty_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(1u64))).unwrap();
+    // WRONG attribute hash!
+    let attr_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(101u64))).unwrap();
+    let proof_var = MerkleProofVar::new_witness(cs.clone(), &proof).unwrap();
+
+    verify_membership_with_attributes(
+        cs.clone(),
+        &root_var,
+        &item_id_var,
+        &qty_var,
+        &attr_var,
+        &proof_var,
+    ).unwrap();
+
+    assert!(!cs.is_satisfied().unwrap());
+}
+
+#[test]
+fn test_attribute_change_changes_root() {
+    let mut tree = SparseMerkleTree::with_arity(DEFAULT_DEPTH, LeafArity::Three);
+
+    tree.update_with_attributes(7, 1, Fr::from(100u64));
+    let root1 = tree.root();
+
+    // Same quantity, different attributes (e.g. durability dropped)
+    tree.update_with_attributes(7, 1, Fr::from(99u64));
+    let root2 = tree.root();
+
+    assert_ne!(root1, root2);
+
+    // Quantity-only updates keep the current attributes
+    tree.update(7, 2);
+    assert_eq!(tree.get_attributes(7), Fr::from(99u64));
+}
+
+#[test]
+fn test_default_arity_unchanged() {
+    let tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+
+    assert_eq!(tree.arity(), LeafArity::Two);
+    assert_eq!(tree.get_proof(1).attr_hash(), None);
+}
+
+#[test]
+fn test_withdraw_to_zero_restores_default_leaf() {
+    let mut tree = SparseMerkleTree::from_items(&[(3, 20)], DEFAULT_DEPTH);
+    let root_before = tree.root();
+
+    tree.update(5, 10);
+    tree.update(5, 0);
+
+    assert_eq!(tree.root(), root_before);
+
+    // The emptied slot proves non-membership natively and in-circuit
+    let proof = tree.get_proof(5);
+    assert!(tree.verify_proof(5, 0, &proof));
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+
+    let root_var = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+    let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(5u64))).unwrap();
+    let proof_var = MerkleProofVar::new_witness(cs.clone(), &proof).unwrap();
+
+    verify_non_membership(cs.clone(), &root_var, &item_id_var, &proof_var).unwrap();
+
+    assert!(cs.is_satisfied().unwrap());
+}
+
+#[test]
+fn test_non_membership_binds_item_id() {
+    let tree = SparseMerkleTree::from_items(&[(3, 20)], DEFAULT_DEPTH);
+
+    // Valid empty-slot proof for item 5, claimed for item 3
+    let proof = tree.get_proof(5);
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+
+    let root_var = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+    let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(3u64))).unwrap();
+    let proof_var = MerkleProofVar::new_witness(cs.clone(), &proof).unwrap();
+
+    verify_non_membership(cs.clone(), &root_var, &item_id_var, &proof_var).unwrap();
+
+    assert!(!cs.is_satisfied().unwrap());
+}
+
+#[test]
+fn test_verify_proof_against_root() {
+    let tree = SparseMerkleTree::from_items(&[(1, 100), (5, 50)], DEFAULT_DEPTH);
+    let root = tree.root();
+
+    let proof = tree.get_proof(5);
+    assert!(verify_proof_against_root(root, 5, 50, &proof));
+    assert!(!verify_proof_against_root(root, 5, 49, &proof));
+    assert!(!verify_proof_against_root(root, 1, 50, &proof));
+
+    // Empty-slot proofs only verify for their own slot
+    let empty = tree.get_proof(7);
+    assert!(verify_proof_against_root(root, 7, 0, &empty));
+    assert!(!verify_proof_against_root(root, 9, 0, &empty));
+    assert!(!verify_proof_against_root(root, 7 + (1 << DEFAULT_DEPTH), 0, &empty));
+}
+
+#[test]
+fn test_verify_proof_against_other_root() {
+    let tree = SparseMerkleTree::from_items(&[(1, 100), (5, 50)], DEFAULT_DEPTH);
+    let other = SparseMerkleTree::from_items(&[(1, 100), (5, 50), (6, 1)], DEFAULT_DEPTH);
+
+    // Same leaf, but the proof's siblings belong to the other tree
+    let proof = other.get_proof(5);
+    assert!(verify_proof_against_root(other.root(), 5, 50, &proof));
+    assert!(!verify_proof_against_root(tree.root(), 5, 50, &proof));
+}
@@ -5,16 +5,41 @@
 //! for sparse data like inventory items.
 //!
 //! Uses Poseidon hash function for ZK-friendly hashing.
+//!
+//! Leaves are H(item_id, quantity) by default. Trees created with
+//! `LeafArity::Three` hash leaves as H(item_id, quantity, attr_hash) so that
+//! non-fungible items can commit to per-item attributes.
 
 use ark_bn254::Fr;
-use std::collections::HashMap;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 
-use crate::poseidon::poseidon_hash_two;
-use super::proof::MerkleProof;
+use crate::poseidon::{poseidon_hash_domain, Domain};
+use super::proof::{verify_proof_against_root, MerkleProof};
 
 /// Default tree depth (12 levels = 4,096 possible items)
 pub const DEFAULT_DEPTH: usize = 12;
 
+/// Root of an empty tree with the given depth and leaf layout.
+///
+/// Equal to `SparseMerkleTree::with_arity(depth, arity).root()`, without
+/// building the tree or its per-level default table.
+pub fn empty_root(depth: usize, arity: LeafArity) -> Fr {
+    (0..depth).fold(SparseMerkleTree::empty_leaf(arity), |node, _| {
+        SparseMerkleTree::hash_nodes(node, node)
+    })
+}
+
+/// Number of field elements hashed into each leaf.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LeafArity {
+    /// H(item_id, quantity)
+    #[default]
+    Two,
+    /// H(item_id, quantity, attr_hash)
+    Three,
+}
+
 /// Sparse Merkle Tree for inventory storage.
 ///
 /// Keys are item IDs (0 to 2^depth - 1).
@@ -32,41 +57,61 @@ pub struct SparseMerkleTree {
     /// Leaf values: item_id -> quantity
     leaves: HashMap<u64, u64>,
 
+    /// Leaf layout used for hashing
+    arity: LeafArity,
+
+    /// Attribute hashes: item_id -> attr_hash (only used with `LeafArity::Three`)
+    attributes: HashMap<u64, Fr>,
+
     /// Precomputed default hashes for each level
     /// defaults[0] = hash of empty leaf
     /// defaults[i] = hash(defaults[i-1], defaults[i-1])
     defaults: Vec<Fr>,
+
+    /// Number of mutations applied (`update`, `update_with_attributes`, `update_batch`)
+    version: usize,
+
+    /// Roots of the most recent versions, oldest first (empty unless history is enabled)
+    history: VecDeque<Fr>,
+
+    /// Maximum number of retained roots (0 = history disabled)
+    history_capacity: usize,
 }
 
 impl SparseMerkleTree {
     /// Create a new empty SMT with the given depth.
     pub fn new(depth: usize) -> Self {
-        let defaults = Self::compute_defaults(depth);
+        Self::with_arity(depth, LeafArity::Two)
+    }
+
+    /// Create a new empty SMT with the given depth and leaf layout.
+    pub fn with_arity(depth: usize, arity: LeafArity) -> Self {
+        let defaults = Self::compute_defaults(depth, arity);
 
         Self {
             depth,
             nodes: HashMap::new(),
             leaves: HashMap::new(),
+            arity,
+            attributes: HashMap::new(),
             defaults,
+            version: 0,
+            history: VecDeque::new(),
+            history_capacity: 0,
         }
     }
 
     /// Create an SMT from a list of (item_id, quantity) pairs.
     pub fn from_items(items: &[(u64, u64)], depth: usize) -> Self {
         let mut tree = Self::new(depth);
-        for &(item_id, quantity) in items {
-            tree.update(item_id, quantity);
-        }
+        tree.update_batch(items);
         tree
     }
 
     /// Compute default hashes for each level of an empty tree.
-    fn compute_defaults(depth: usize) -> Vec<Fr> {
+    fn compute_defaults(depth: usize, arity: LeafArity) -> Vec<Fr> {
         let mut defaults = Vec::with_capacity(depth + 1);
-
-        // Default leaf = H(0, 0) representing empty item
-        let empty_leaf = Self::hash_leaf(0, 0);
-        defaults.push(empty_leaf);
+        defaults.push(Self::empty_leaf(arity));
 
         // Build up default hashes for each level
         for _ in 0..depth {
@@ -78,14 +123,27 @@ impl SparseMerkleTree {
         defaults
     }
 
+    /// Default leaf = H(0, 0) (or H(0, 0, 0)) representing empty item
+    fn empty_leaf(arity: LeafArity) -> Fr {
+        match arity {
+            LeafArity::Two => Self::hash_leaf(0, 0),
+            LeafArity::Three => Self::hash_leaf_with_attributes(0, 0, Fr::from(0u64)),
+        }
+    }
+
     /// Hash a leaf: H(item_id, quantity) using Poseidon
     fn hash_leaf(item_id: u64, quantity: u64) -> Fr {
-        poseidon_hash_two(Fr::from(item_id), Fr::from(quantity))
+        poseidon_hash_domain(Domain::SmtLeaf, &[Fr::from(item_id), Fr::from(quantity)])
+    }
+
+    /// Hash a leaf with attributes: H(item_id, quantity, attr_hash) using Poseidon
+    fn hash_leaf_with_attributes(item_id: u64, quantity: u64, attr_hash: Fr) -> Fr {
+        poseidon_hash_domain(Domain::SmtLeaf, &[Fr::from(item_id), Fr::from(quantity), attr_hash])
     }
 
     /// Hash two child nodes: H(left, right) using Poseidon
-    fn hash_nodes(left: Fr, right: Fr) -> Fr {
-        poseidon_hash_two(left, right)
+    pub(super) fn hash_nodes(left: Fr, right: Fr) -> Fr {
+        poseidon_hash_domain(Domain::SmtNode, &[left, right])
     }
 
     /// Get the quantity for an item, or 0 if not present.
@@ -93,24 +151,105 @@ impl SparseMerkleTree {
         self.leaves.get(&item_id).copied().unwrap_or(0)
     }
 
+    /// Get the attribute hash for an item, or 0 if not set.
+    pub fn get_attributes(&self, item_id: u64) -> Fr {
+        self.attributes
+            .get(&item_id)
+            .copied()
+            .unwrap_or(Fr::from(0u64))
+    }
+
     /// Update the quantity for an item and recompute affected hashes.
     /// Returns the new root hash.
+    ///
+    /// For trees with attributes, the item keeps its current attribute hash.
     pub fn update(&mut self, item_id: u64, quantity: u64) -> Fr {
+        let attr_hash = self.get_attributes(item_id);
+        self.set_leaf(item_id, quantity, attr_hash)
+    }
+
+    /// Update the quantity and attribute hash for an item.
+    /// Returns the new root hash.
+    pub fn update_with_attributes(&mut self, item_id: u64, quantity: u64, attr_hash: Fr) -> Fr {
+        assert_eq!(self.arity, LeafArity::Three, "tree does not store attributes");
+        self.set_leaf(item_id, quantity, attr_hash)
+    }
+
+    /// Update the quantities of several items, recomputing each affected
+    /// internal node once. Returns the new root hash.
+    ///
+    /// The result is identical to calling `update` for each entry in order
+    /// (a repeated item_id keeps its last quantity), but ancestors shared by
+    /// several items are hashed once instead of once per item.
+    pub fn update_batch(&mut self, updates: &[(u64, u64)]) -> Fr {
+        let mut dirty: HashSet<u64> = HashSet::with_capacity(updates.len());
+        for &(item_id, quantity) in updates {
+            let attr_hash = self.get_attributes(item_id);
+            self.store_leaf(item_id, quantity, attr_hash);
+            dirty.insert(item_id);
+        }
+
+        // Bottom-up: each level's dirty nodes mark their parents dirty
+        for level in 0..self.depth {
+            let parents: HashSet<u64> = dirty.iter().map(|&index| index >> 1).collect();
+            for &parent_index in &parents {
+                let left = self.get_node(level, parent_index << 1);
+                let right = self.get_node(level, (parent_index << 1) | 1);
+                self.nodes
+                    .insert((level + 1, parent_index), Self::hash_nodes(left, right));
+            }
+            dirty = parents;
+        }
+
+        self.record_root()
+    }
+
+    /// Store a leaf and recompute hashes up to the root.
+    fn set_leaf(&mut self, item_id: u64, quantity: u64, attr_hash: Fr) -> Fr {
+        self.store_leaf(item_id, quantity, attr_hash);
+        self.recompute_path(item_id);
+        self.record_root()
+    }
+
+    /// Bump the version and, if history is enabled, retain the new root.
+    fn record_root(&mut self) -> Fr {
+        let root = self.root();
+        self.version += 1;
+        if self.history_capacity > 0 {
+            if self.history.len() == self.history_capacity {
+                self.history.pop_front();
+            }
+            self.history.push_back(root);
+        }
+        root
+    }
+
+    /// Store a leaf value and hash without touching its ancestors.
+    fn store_leaf(&mut self, item_id: u64, quantity: u64, attr_hash: Fr) {
         assert!(item_id < (1u64 << self.depth), "item_id exceeds tree capacity");
 
         // Update leaf value
         if quantity == 0 {
             self.leaves.remove(&item_id);
+            self.attributes.remove(&item_id);
         } else {
             self.leaves.insert(item_id, quantity);
+            if self.arity == LeafArity::Three {
+                self.attributes.insert(item_id, attr_hash);
+            }
         }
 
-        // Compute new leaf hash
-        let leaf_hash = Self::hash_leaf(item_id, quantity);
-        self.nodes.insert((0, item_id), leaf_hash);
-
-        // Recompute hashes up to root
-        self.recompute_path(item_id)
+        // Compute new leaf hash. Emptied slots store the canonical empty leaf
+        // (not H(item_id, 0)) so a later re-deposit takes the insertion branch.
+        if quantity == 0 {
+            self.nodes.remove(&(0, item_id));
+        } else {
+            let leaf_hash = match self.arity {
+                LeafArity::Two => Self::hash_leaf(item_id, quantity),
+                LeafArity::Three => Self::hash_leaf_with_attributes(item_id, quantity, attr_hash),
+            };
+            self.nodes.insert((0, item_id), leaf_hash);
+        }
     }
 
     /// Recompute hashes from a leaf up to the root.
@@ -168,7 +307,11 @@ impl SparseMerkleTree {
             current_index >>= 1;
         }
 
-        MerkleProof::new(path, indices)
+        let proof = MerkleProof::new(path, indices);
+        match self.arity {
+            LeafArity::Two => proof,
+            LeafArity::Three => proof.with_attr_hash(self.get_attributes(item_id)),
+        }
     }
 
     /// Verify a proof for a given item and quantity.
@@ -182,16 +325,44 @@ impl SparseMerkleTree {
         computed_root == self.root()
     }
 
+    /// Generate a proof that an item is absent (its slot holds the empty leaf).
+    ///
+    /// Returns `None` if the item is present.
+    pub fn prove_non_membership(&self, item_id: u64) -> Option<MerkleProof<Fr>> {
+        (self.get(item_id) == 0).then(|| self.get_proof(item_id))
+    }
+
+    /// Verify a proof that an item is absent from this tree.
+    ///
+    /// The proof must come from a tree of this depth and lead to item_id's slot.
+    pub fn verify_non_membership(&self, item_id: u64, proof: &MerkleProof<Fr>) -> bool {
+        proof.depth() == self.depth && verify_proof_against_root(self.root(), item_id, 0, proof)
+    }
+
     /// Get the tree depth.
     pub fn depth(&self) -> usize {
         self.depth
     }
 
+    /// Get the leaf layout.
+    pub fn arity(&self) -> LeafArity {
+        self.arity
+    }
+
     /// Get default hash for a level.
     pub fn default_at_level(&self, level: usize) -> Fr {
         self.defaults[level]
     }
 
+    /// Get the default hash of every level, from the empty leaf up to the empty root.
+    ///
+    /// Any sibling not known to be non-empty is the default for its level, so a
+    /// client holding these plus the few non-empty siblings can assemble a proof
+    /// without the full node map.
+    pub fn defaults(&self) -> &[Fr] {
+        &self.defaults
+    }
+
     /// Get all non-empty items.
     pub fn items(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
         self.leaves.iter().map(|(&k, &v)| (k, v))
@@ -206,6 +377,109 @@ impl SparseMerkleTree {
     pub fn is_empty(&self) -> bool {
         self.leaves.is_empty()
     }
+
+    /// List the leaves whose quantity differs from `other`, as
+    /// `(item_id, old_qty, new_qty)` sorted by item ID.
+    ///
+    /// `self` is the old tree; added items have `old_qty` 0 and removed items
+    /// `new_qty` 0. Attribute hashes are not compared.
+    pub fn diff(&self, other: &Self) -> Vec<(u64, u64, u64)> {
+        let item_ids: BTreeSet<u64> =
+            self.leaves.keys().chain(other.leaves.keys()).copied().collect();
+        item_ids
+            .into_iter()
+            .map(|item_id| (item_id, self.get(item_id), other.get(item_id)))
+            .filter(|&(_, old_qty, new_qty)| old_qty != new_qty)
+            .collect()
+    }
+
+    /// Get the number of stored (non-default) nodes across all levels.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Retain the roots of the last `capacity` versions, starting with the
+    /// current one. Older roots are evicted first; a capacity of 0 disables
+    /// history. Re-enabling discards previously retained roots.
+    pub fn enable_history(&mut self, capacity: usize) {
+        self.history_capacity = capacity;
+        self.history.clear();
+        if capacity > 0 {
+            self.history.push_back(self.root());
+        }
+    }
+
+    /// Get the number of mutations applied to this tree.
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    /// Get the root as of `version`, if it is still retained.
+    pub fn root_at(&self, version: usize) -> Option<Fr> {
+        let oldest = (self.version + 1).checked_sub(self.history.len())?;
+        if version < oldest {
+            return None;
+        }
+        self.history.get(version - oldest).copied()
+    }
+
+    /// Serialize the tree to bytes.
+    ///
+    /// Stores the depth, leaf layout, leaves and attribute hashes, sorted by
+    /// item ID so equal trees serialize identically. Internal nodes are not
+    /// stored; `deserialize` recomputes them.
+    pub fn serialize(&self) -> Result<Vec<u8>, SerializationError> {
+        let mut leaves: Vec<(u64, u64)> = self.items().collect();
+        leaves.sort_unstable();
+        let mut attributes: Vec<(u64, Fr)> =
+            self.attributes.iter().map(|(&k, &v)| (k, v)).collect();
+        attributes.sort_unstable_by_key(|&(item_id, _)| item_id);
+
+        let arity: u8 = match self.arity {
+            LeafArity::Two => 2,
+            LeafArity::Three => 3,
+        };
+
+        let mut bytes = Vec::new();
+        (self.depth as u64).serialize_compressed(&mut bytes)?;
+        arity.serialize_compressed(&mut bytes)?;
+        leaves.serialize_compressed(&mut bytes)?;
+        attributes.serialize_compressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Rebuild a tree from `serialize` output, recomputing every node hash.
+    ///
+    /// Rejects depths above 63, unknown leaf layouts, item IDs outside the
+    /// tree, zero quantities and trailing bytes.
+    pub fn deserialize(mut bytes: &[u8]) -> Result<Self, SerializationError> {
+        let depth = u64::deserialize_compressed(&mut bytes)?;
+        let arity = match u8::deserialize_compressed(&mut bytes)? {
+            2 => LeafArity::Two,
+            3 => LeafArity::Three,
+            _ => return Err(SerializationError::InvalidData),
+        };
+        let leaves = Vec::<(u64, u64)>::deserialize_compressed(&mut bytes)?;
+        let attributes = Vec::<(u64, Fr)>::deserialize_compressed(&mut bytes)?;
+        if !bytes.is_empty() || depth > 63 {
+            return Err(SerializationError::InvalidData);
+        }
+
+        let depth = depth as usize;
+        let in_range = |item_id: u64| item_id < (1u64 << depth);
+        if leaves.iter().any(|&(item_id, quantity)| !in_range(item_id) || quantity == 0)
+            || (arity == LeafArity::Two && !attributes.is_empty())
+            || attributes.iter().any(|&(item_id, _)| !in_range(item_id))
+        {
+            return Err(SerializationError::InvalidData);
+        }
+
+        let mut tree = Self::with_arity(depth, arity);
+        // update_batch hashes each leaf with its stored attribute hash
+        tree.attributes.extend(attributes);
+        tree.update_batch(&leaves);
+        Ok(tree)
+    }
 }
 
 #[cfg(test)]
@@ -222,6 +496,28 @@ mod tree_tests {
         assert_eq!(tree.get(100), 0);
     }
 
+    #[test]
+    fn test_defaults() {
+        let tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        let defaults = tree.defaults();
+
+        assert_eq!(defaults.len(), DEFAULT_DEPTH + 1);
+        assert_eq!(defaults[0], SparseMerkleTree::hash_leaf(0, 0));
+        assert_eq!(defaults[DEFAULT_DEPTH], tree.root());
+    }
+
+    #[test]
+    fn test_proof_from_defaults() {
+        let tree = SparseMerkleTree::from_items(&[(5, 7)], DEFAULT_DEPTH);
+
+        // Item 4's only non-empty sibling is the leaf of item 5
+        let mut path = tree.defaults()[..DEFAULT_DEPTH].to_vec();
+        path[0] = SparseMerkleTree::hash_leaf(5, 7);
+        let indices = (0..DEFAULT_DEPTH).map(|level| (4u64 >> level) & 1 == 1).collect();
+
+        assert!(tree.verify_proof(4, 0, &MerkleProof::new(path, indices)));
+    }
+
     #[test]
     fn test_single_insert() {
         let mut tree = SparseMerkleTree::new(DEFAULT_DEPTH);
@@ -318,4 +614,164 @@ mod tree_tests {
 
         assert_eq!(tree1.root(), tree2.root());
     }
+
+    #[test]
+    fn test_non_membership_empty_slot() {
+        let tree = SparseMerkleTree::from_items(&[(1, 100), (42, 50)], DEFAULT_DEPTH);
+
+        let proof = tree.prove_non_membership(7).unwrap();
+        assert!(tree.verify_non_membership(7, &proof));
+
+        // The proof is bound to its slot
+        assert!(!tree.verify_non_membership(8, &proof));
+
+        // A held item cannot be shown absent
+        assert!(tree.prove_non_membership(42).is_none());
+        assert!(!tree.verify_non_membership(42, &tree.get_proof(42)));
+    }
+
+    #[test]
+    fn test_non_membership_after_delete() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100), (42, 50)], DEFAULT_DEPTH);
+        let stale_proof = tree.prove_non_membership(5).unwrap();
+
+        tree.update(5, 10);
+        assert!(!tree.verify_non_membership(5, &stale_proof));
+
+        tree.update(5, 0);
+        let proof = tree.prove_non_membership(5).unwrap();
+        assert!(tree.verify_non_membership(5, &proof));
+        assert!(tree.verify_non_membership(5, &stale_proof));
+    }
+
+    #[test]
+    fn test_update_batch_matches_sequential() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut tree = SparseMerkleTree::from_items(&[(3, 7), (900, 1)], DEFAULT_DEPTH);
+        let updates: Vec<(u64, u64)> = (0..50)
+            .map(|_| (rng.gen_range(0..1000), rng.gen_range(0..5)))
+            .collect();
+
+        let mut sequential = tree.clone();
+        for &(item_id, quantity) in &updates {
+            sequential.update(item_id, quantity);
+        }
+
+        assert_eq!(tree.update_batch(&updates), sequential.root());
+        assert_eq!(tree.len(), sequential.len());
+        for &(item_id, _) in &updates {
+            assert_eq!(tree.get(item_id), sequential.get(item_id));
+            assert!(tree.verify_proof(item_id, tree.get(item_id), &tree.get_proof(item_id)));
+        }
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let items: Vec<(u64, u64)> = (0..100).map(|i| (i * 37 % 4096, i + 1)).collect();
+        let tree = SparseMerkleTree::from_items(&items, DEFAULT_DEPTH);
+
+        let bytes = tree.serialize().unwrap();
+        let restored = SparseMerkleTree::deserialize(&bytes).unwrap();
+
+        assert_eq!(restored.root(), tree.root());
+        assert_eq!(restored.depth(), tree.depth());
+        assert_eq!(restored.len(), 100);
+        assert_eq!(restored.get_proof(37).path(), tree.get_proof(37).path());
+        assert_eq!(restored.serialize().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_serialize_round_trip_with_attributes() {
+        let mut tree = SparseMerkleTree::with_arity(8, LeafArity::Three);
+        tree.update_with_attributes(3, 1, Fr::from(777u64));
+        tree.update_with_attributes(200, 1, Fr::from(888u64));
+
+        let restored = SparseMerkleTree::deserialize(&tree.serialize().unwrap()).unwrap();
+
+        assert_eq!(restored.arity(), LeafArity::Three);
+        assert_eq!(restored.get_attributes(200), Fr::from(888u64));
+        assert_eq!(restored.root(), tree.root());
+    }
+
+    #[test]
+    fn test_deserialize_rejects_invalid_data() {
+        let tree = SparseMerkleTree::from_items(&[(1, 100)], 4);
+        let mut bytes = tree.serialize().unwrap();
+
+        bytes.push(0);
+        assert!(SparseMerkleTree::deserialize(&bytes).is_err());
+
+        // Item 16 does not fit in a depth-4 tree
+        let out_of_range = SparseMerkleTree::from_items(&[(16, 1)], 5).serialize().unwrap();
+        let mut bytes = out_of_range.clone();
+        bytes[0] = 4;
+        assert!(SparseMerkleTree::deserialize(&bytes).is_err());
+
+        assert!(SparseMerkleTree::deserialize(&[]).is_err());
+    }
+
+    #[test]
+    fn test_empty_root_matches_empty_tree() {
+        for depth in [8, 12, 16] {
+            assert_eq!(empty_root(depth, LeafArity::Two), SparseMerkleTree::new(depth).root());
+            assert_eq!(
+                empty_root(depth, LeafArity::Three),
+                SparseMerkleTree::with_arity(depth, LeafArity::Three).root()
+            );
+        }
+        assert_ne!(empty_root(8, LeafArity::Two), empty_root(12, LeafArity::Two));
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = SparseMerkleTree::from_items(&[(1, 10), (5, 3), (9, 7)], DEFAULT_DEPTH);
+        let new = SparseMerkleTree::from_items(&[(1, 10), (4, 2), (9, 8)], DEFAULT_DEPTH);
+
+        // 4 added, 5 removed, 9 modified, 1 unchanged
+        assert_eq!(old.diff(&new), vec![(4, 0, 2), (5, 3, 0), (9, 7, 8)]);
+        assert_eq!(new.diff(&old), vec![(4, 2, 0), (5, 0, 3), (9, 8, 7)]);
+        assert!(old.diff(&old.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_root_history() {
+        let mut tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        tree.enable_history(8);
+        let empty_root = tree.root();
+
+        let root1 = tree.update(1, 100);
+        let root2 = tree.update_batch(&[(2, 50), (3, 25)]);
+        let root3 = tree.update(1, 0);
+
+        assert_eq!(tree.version(), 3);
+        assert_eq!(tree.root_at(0), Some(empty_root));
+        assert_eq!(tree.root_at(1), Some(root1));
+        assert_eq!(tree.root_at(2), Some(root2));
+        assert_eq!(tree.root_at(3), Some(root3));
+        assert_eq!(tree.root_at(4), None);
+    }
+
+    #[test]
+    fn test_root_history_evicts_oldest() {
+        let mut tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        tree.enable_history(3);
+
+        let roots: Vec<Fr> = (1..=5).map(|i| tree.update(i, i * 10)).collect();
+
+        assert_eq!(tree.version(), 5);
+        assert_eq!(tree.root_at(2), None);
+        assert_eq!(tree.root_at(3), Some(roots[2]));
+        assert_eq!(tree.root_at(5), Some(roots[4]));
+    }
+
+    #[test]
+    fn test_root_history_disabled_by_default() {
+        let mut tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        tree.update(1, 100);
+
+        assert_eq!(tree.version(), 1);
+        assert_eq!(tree.root_at(1), None);
+    }
 }
@@ -0,0 +1,471 @@
+//! Two-level Sparse Merkle Tree for large item ID spaces.
+//!
+//! An item_id splits into a high bucket (selecting a subtree) and a low index
+//! within that bucket. Each non-empty bucket is an ordinary `SparseMerkleTree`
+//! of depth `bucket_depth` whose leaves are H(low, quantity); its root is a
+//! leaf of the top tree of depth `top_depth`, and empty buckets hash to the
+//! empty bucket root. Only touched buckets are materialized, so each can be
+//! cached or loaded on its own.
+//!
+//! A proof is a bucket path followed by a top path, so verifying one costs the
+//! same hashes as a flat tree of depth `top_depth + bucket_depth`.
+
+use std::collections::HashMap;
+
+use ark_bn254::Fr;
+use ark_r1cs_std::{boolean::Boolean, fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use super::gadgets::{compute_root_from_path, transition_leaf_hashes, MerkleProofVar};
+use super::proof::MerkleProof;
+use super::tree::{empty_root, LeafArity, SparseMerkleTree};
+
+/// Membership proof in a `TwoLevelSMT`: the bucket path, then the top path.
+#[derive(Clone, Debug)]
+pub struct TwoLevelProof {
+    /// Path from the leaf to its bucket root
+    pub bucket: MerkleProof<Fr>,
+    /// Path from the bucket root to the top root
+    pub top: MerkleProof<Fr>,
+}
+
+impl TwoLevelProof {
+    /// Compute the top root from this proof and the leaf value.
+    pub fn compute_root(&self, item_id: u64, quantity: u64) -> Fr {
+        let low = item_id & ((1u64 << self.bucket.depth()) - 1);
+        let bucket_root = self.bucket.compute_root(low, quantity);
+        self.top.compute_root_from_leaf(bucket_root)
+    }
+
+    /// Check that the path indices spell out `item_id`, low bits first.
+    fn position_matches(&self, item_id: u64) -> bool {
+        let depth = (self.bucket.depth() + self.top.depth()) as u32;
+        item_id.checked_shr(depth).unwrap_or(0) == 0
+            && self
+                .bucket
+                .indices()
+                .iter()
+                .chain(self.top.indices())
+                .enumerate()
+                .all(|(level, &is_right)| is_right == ((item_id >> level) & 1 == 1))
+    }
+}
+
+/// Sparse Merkle Tree split into a top tree of bucket subtrees.
+///
+/// Keys are item IDs (0 to 2^(top_depth + bucket_depth) - 1).
+#[derive(Clone)]
+pub struct TwoLevelSMT {
+    /// Depth of the top tree (number of bucket bits)
+    top_depth: usize,
+
+    /// Depth of each bucket subtree (number of low index bits)
+    bucket_depth: usize,
+
+    /// Non-empty buckets: bucket index -> subtree
+    buckets: HashMap<u64, SparseMerkleTree>,
+
+    /// Sparse top tree storage: (level, index) -> hash
+    /// Level 0 = bucket roots, level `top_depth` = root
+    top_nodes: HashMap<(usize, u64), Fr>,
+
+    /// Default top tree hashes for each level
+    /// top_defaults[0] = root of an empty bucket
+    top_defaults: Vec<Fr>,
+}
+
+impl TwoLevelSMT {
+    /// Create a new empty tree with the given top and bucket depths.
+    pub fn new(top_depth: usize, bucket_depth: usize) -> Self {
+        let top_defaults = (0..=top_depth)
+            .map(|level| empty_root(bucket_depth + level, LeafArity::Two))
+            .collect();
+
+        Self {
+            top_depth,
+            bucket_depth,
+            buckets: HashMap::new(),
+            top_nodes: HashMap::new(),
+            top_defaults,
+        }
+    }
+
+    /// Create a tree from a list of (item_id, quantity) pairs.
+    pub fn from_items(items: &[(u64, u64)], top_depth: usize, bucket_depth: usize) -> Self {
+        let mut tree = Self::new(top_depth, bucket_depth);
+        for &(item_id, quantity) in items {
+            tree.update(item_id, quantity);
+        }
+        tree
+    }
+
+    /// Split an item_id into its (bucket, low index) pair.
+    pub fn split(&self, item_id: u64) -> (u64, u64) {
+        assert!(item_id < (1u64 << self.depth()), "item_id exceeds tree capacity");
+        (item_id >> self.bucket_depth, item_id & ((1u64 << self.bucket_depth) - 1))
+    }
+
+    /// Get the quantity for an item, or 0 if not present.
+    pub fn get(&self, item_id: u64) -> u64 {
+        let (bucket, low) = self.split(item_id);
+        self.buckets.get(&bucket).map_or(0, |tree| tree.get(low))
+    }
+
+    /// Update the quantity for an item and recompute affected hashes.
+    /// Returns the new root hash.
+    pub fn update(&mut self, item_id: u64, quantity: u64) -> Fr {
+        let (bucket, low) = self.split(item_id);
+
+        let tree = self
+            .buckets
+            .entry(bucket)
+            .or_insert_with(|| SparseMerkleTree::new(self.bucket_depth));
+        let bucket_root = tree.update(low, quantity);
+
+        // Emptied buckets are dropped and fall back to the default leaf
+        if tree.is_empty() {
+            self.buckets.remove(&bucket);
+            self.top_nodes.remove(&(0, bucket));
+        } else {
+            self.top_nodes.insert((0, bucket), bucket_root);
+        }
+
+        self.recompute_top_path(bucket)
+    }
+
+    /// Recompute top tree hashes from a bucket root up to the root.
+    fn recompute_top_path(&mut self, bucket: u64) -> Fr {
+        let mut current_index = bucket;
+        let mut current_hash = self.get_top_node(0, bucket);
+
+        for level in 0..self.top_depth {
+            let sibling_hash = self.get_top_node(level, current_index ^ 1);
+            let parent_index = current_index >> 1;
+            current_hash = if current_index & 1 == 0 {
+                SparseMerkleTree::hash_nodes(current_hash, sibling_hash)
+            } else {
+                SparseMerkleTree::hash_nodes(sibling_hash, current_hash)
+            };
+
+            // Subtrees that are empty again keep no stored nodes
+            if current_hash == self.top_defaults[level + 1] {
+                self.top_nodes.remove(&(level + 1, parent_index));
+            } else {
+                self.top_nodes.insert((level + 1, parent_index), current_hash);
+            }
+            current_index = parent_index;
+        }
+
+        current_hash
+    }
+
+    /// Get a top tree node hash, returning default if not present.
+    fn get_top_node(&self, level: usize, index: u64) -> Fr {
+        self.top_nodes
+            .get(&(level, index))
+            .copied()
+            .unwrap_or(self.top_defaults[level])
+    }
+
+    /// Get the current root hash.
+    pub fn root(&self) -> Fr {
+        self.get_top_node(self.top_depth, 0)
+    }
+
+    /// Generate a proof for the given item.
+    pub fn get_proof(&self, item_id: u64) -> TwoLevelProof {
+        let (bucket, low) = self.split(item_id);
+
+        let bucket_proof = match self.buckets.get(&bucket) {
+            Some(tree) => tree.get_proof(low),
+            None => SparseMerkleTree::new(self.bucket_depth).get_proof(low),
+        };
+
+        let mut path = Vec::with_capacity(self.top_depth);
+        let mut indices = Vec::with_capacity(self.top_depth);
+        let mut current_index = bucket;
+        for level in 0..self.top_depth {
+            path.push(self.get_top_node(level, current_index ^ 1));
+            indices.push((current_index & 1) == 1);
+            current_index >>= 1;
+        }
+
+        TwoLevelProof {
+            bucket: bucket_proof,
+            top: MerkleProof::new(path, indices),
+        }
+    }
+
+    /// Verify a proof for a given item and quantity.
+    pub fn verify_proof(&self, item_id: u64, quantity: u64, proof: &TwoLevelProof) -> bool {
+        proof.bucket.depth() == self.bucket_depth
+            && proof.top.depth() == self.top_depth
+            && proof.position_matches(item_id)
+            && proof.compute_root(item_id, quantity) == self.root()
+    }
+
+    /// Get the materialized subtree for a bucket, if it holds any items.
+    pub fn bucket(&self, bucket: u64) -> Option<&SparseMerkleTree> {
+        self.buckets.get(&bucket)
+    }
+
+    /// Number of materialized (non-empty) buckets.
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Total depth (top_depth + bucket_depth).
+    pub fn depth(&self) -> usize {
+        self.top_depth + self.bucket_depth
+    }
+
+    /// Depth of the top tree.
+    pub fn top_depth(&self) -> usize {
+        self.top_depth
+    }
+
+    /// Depth of each bucket subtree.
+    pub fn bucket_depth(&self) -> usize {
+        self.bucket_depth
+    }
+}
+
+/// Circuit variable representation of a `TwoLevelProof`.
+#[derive(Clone)]
+pub struct TwoLevelProofVar {
+    /// Path from the leaf to its bucket root
+    pub bucket: MerkleProofVar,
+    /// Path from the bucket root to the top root
+    pub top: MerkleProofVar,
+}
+
+impl TwoLevelProofVar {
+    /// Allocate a two-level proof as witness variables.
+    pub fn new_witness(
+        cs: ConstraintSystemRef<Fr>,
+        proof: &TwoLevelProof,
+    ) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            bucket: MerkleProofVar::new_witness(cs.clone(), &proof.bucket)?,
+            top: MerkleProofVar::new_witness(cs, &proof.top)?,
+        })
+    }
+}
+
+/// Verify membership in a two-level tree and compute the new root after
+/// updating the leaf.
+///
+/// The bucket and top path indices are constrained to be the little-endian
+/// bits of item_id; the low bits give the index hashed into the leaf. Like
+/// `verify_and_update`, a zero quantity uses the default leaf H(0, 0).
+///
+/// Returns the new top root after setting the leaf to new_quantity.
+pub fn verify_and_update_two_level(
+    cs: ConstraintSystemRef<Fr>,
+    old_root: &FpVar<Fr>,
+    item_id: &FpVar<Fr>,
+    old_quantity: &FpVar<Fr>,
+    new_quantity: &FpVar<Fr>,
+    proof: &TwoLevelProofVar,
+) -> Result<FpVar<Fr>, SynthesisError> {
+    // Bind the full path to item_id, then take the bucket bits as the low index
+    let bits: Vec<Boolean<Fr>> =
+        proof.bucket.indices().iter().chain(proof.top.indices()).cloned().collect();
+    Boolean::le_bits_to_fp_var(&bits)?.enforce_equal(item_id)?;
+    let low = Boolean::le_bits_to_fp_var(proof.bucket.indices())?;
+
+    let (old_leaf_hash, new_leaf_hash) =
+        transition_leaf_hashes(cs.clone(), &low, old_quantity, new_quantity)?;
+
+    // Verify old state through the bucket root
+    let old_bucket_root = compute_root_from_path(cs.clone(), &old_leaf_hash, &proof.bucket)?;
+    compute_root_from_path(cs.clone(), &old_bucket_root, &proof.top)?.enforce_equal(old_root)?;
+
+    // Compute new root using the same paths (siblings unchanged)
+    let new_bucket_root = compute_root_from_path(cs.clone(), &new_leaf_hash, &proof.bucket)?;
+    compute_root_from_path(cs, &new_bucket_root, &proof.top)
+}
+
+#[cfg(test)]
+mod two_level_tests {
+    use super::*;
+    use crate::smt::verify_and_update;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Top depth 4, bucket depth 4: buckets hold 16 items each.
+    fn small_tree() -> TwoLevelSMT {
+        TwoLevelSMT::from_items(&[(15, 100), (16, 50), (200, 7)], 4, 4)
+    }
+
+    /// Synthesize a two-level update of `item_id` and report satisfaction.
+    fn update_satisfied(
+        tree: &TwoLevelSMT,
+        item_id: u64,
+        old_quantity: u64,
+        new_quantity: u64,
+        proof: &TwoLevelProof,
+        expected_new_root: Fr,
+    ) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let old_root = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+        let item_id = FpVar::new_witness(cs.clone(), || Ok(Fr::from(item_id))).unwrap();
+        let old_qty = FpVar::new_witness(cs.clone(), || Ok(Fr::from(old_quantity))).unwrap();
+        let new_qty = FpVar::new_witness(cs.clone(), || Ok(Fr::from(new_quantity))).unwrap();
+        let proof_var = TwoLevelProofVar::new_witness(cs.clone(), proof).unwrap();
+
+        let new_root = verify_and_update_two_level(
+            cs.clone(),
+            &old_root,
+            &item_id,
+            &old_qty,
+            &new_qty,
+            &proof_var,
+        )
+        .unwrap();
+        let expected = FpVar::new_input(cs.clone(), || Ok(expected_new_root)).unwrap();
+        new_root.enforce_equal(&expected).unwrap();
+
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_empty_root_matches_flat_tree() {
+        let tree = TwoLevelSMT::new(4, 8);
+        assert_eq!(tree.root(), empty_root(12, LeafArity::Two));
+        assert_eq!(tree.bucket_count(), 0);
+    }
+
+    #[test]
+    fn test_split_across_bucket_boundary() {
+        let tree = TwoLevelSMT::new(4, 4);
+        assert_eq!(tree.split(15), (0, 15));
+        assert_eq!(tree.split(16), (1, 0));
+        assert_eq!(tree.split(255), (15, 15));
+    }
+
+    #[test]
+    #[should_panic(expected = "item_id exceeds tree capacity")]
+    fn test_item_id_overflow() {
+        TwoLevelSMT::new(4, 4).update(256, 1);
+    }
+
+    #[test]
+    fn test_updates_across_buckets() {
+        let mut tree = small_tree();
+        assert_eq!(tree.get(15), 100);
+        assert_eq!(tree.get(16), 50);
+        assert_eq!(tree.get(17), 0);
+        assert_eq!(tree.bucket_count(), 3);
+        assert_eq!(tree.bucket(0).unwrap().get(15), 100);
+        assert_eq!(tree.bucket(1).unwrap().get(0), 50);
+
+        for (item_id, quantity) in [(15, 100), (16, 50), (200, 7), (17, 0), (255, 0)] {
+            let proof = tree.get_proof(item_id);
+            assert!(tree.verify_proof(item_id, quantity, &proof));
+        }
+
+        // Emptying a bucket drops it and restores the earlier root
+        let before = tree.root();
+        tree.update(201, 3);
+        assert_eq!(tree.bucket_count(), 3);
+        tree.update(201, 0);
+        assert_eq!(tree.root(), before);
+
+        tree.update(16, 0);
+        tree.update(200, 0);
+        tree.update(15, 0);
+        assert_eq!(tree.bucket_count(), 0);
+        assert_eq!(tree.root(), TwoLevelSMT::new(4, 4).root());
+    }
+
+    #[test]
+    fn test_proof_bound_to_item_id() {
+        let tree = small_tree();
+
+        // Same low index in a neighbouring bucket
+        let proof = tree.get_proof(16);
+        assert!(tree.verify_proof(16, 50, &proof));
+        assert!(!tree.verify_proof(0, 50, &proof));
+        assert!(!tree.verify_proof(32, 50, &proof));
+
+        // Empty-slot proofs only verify for their own slot
+        let proof = tree.get_proof(17);
+        assert!(tree.verify_proof(17, 0, &proof));
+        assert!(!tree.verify_proof(18, 0, &proof));
+    }
+
+    #[test]
+    fn test_gadget_update_across_buckets() {
+        // Update in the last slot of bucket 0, the first of bucket 1,
+        // and an insertion into an untouched bucket
+        for (item_id, new_quantity) in [(15, 60), (16, 0), (100, 9)] {
+            let tree = small_tree();
+            let old_quantity = tree.get(item_id);
+            let proof = tree.get_proof(item_id);
+
+            let mut updated = tree.clone();
+            let new_root = updated.update(item_id, new_quantity);
+
+            assert!(update_satisfied(&tree, item_id, old_quantity, new_quantity, &proof, new_root));
+        }
+    }
+
+    #[test]
+    fn test_gadget_rejects_wrong_item_id() {
+        let tree = small_tree();
+        let proof = tree.get_proof(16);
+
+        // A proof for item 16 must not update item 0 (same low index, bucket 0)
+        let mut updated = tree.clone();
+        let new_root = updated.update(0, 10);
+        assert!(!update_satisfied(&tree, 0, 0, 10, &proof, new_root));
+    }
+
+    #[test]
+    fn test_gadget_rejects_wrong_old_quantity() {
+        let tree = small_tree();
+        let proof = tree.get_proof(15);
+
+        let mut updated = tree.clone();
+        let new_root = updated.update(15, 60);
+        assert!(!update_satisfied(&tree, 15, 99, 60, &proof, new_root));
+    }
+
+    #[test]
+    fn test_constraints_comparable_to_flat_tree() {
+        let two_level = TwoLevelSMT::from_items(&[(5000, 10)], 12, 12);
+        let flat = SparseMerkleTree::from_items(&[(5000, 10)], 24);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let old_root = FpVar::new_input(cs.clone(), || Ok(two_level.root())).unwrap();
+        let item_id = FpVar::new_witness(cs.clone(), || Ok(Fr::from(5000u64))).unwrap();
+        let old_qty = FpVar::new_witness(cs.clone(), || Ok(Fr::from(10u64))).unwrap();
+        let new_qty = FpVar::new_witness(cs.clone(), || Ok(Fr::from(20u64))).unwrap();
+        let proof = TwoLevelProofVar::new_witness(cs.clone(), &two_level.get_proof(5000)).unwrap();
+        let _ = verify_and_update_two_level(
+            cs.clone(),
+            &old_root,
+            &item_id,
+            &old_qty,
+            &new_qty,
+            &proof,
+        )
+        .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        let two_level_constraints = cs.num_constraints();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let old_root = FpVar::new_input(cs.clone(), || Ok(flat.root())).unwrap();
+        let item_id = FpVar::new_witness(cs.clone(), || Ok(Fr::from(5000u64))).unwrap();
+        let old_qty = FpVar::new_witness(cs.clone(), || Ok(Fr::from(10u64))).unwrap();
+        let new_qty = FpVar::new_witness(cs.clone(), || Ok(Fr::from(20u64))).unwrap();
+        let proof = MerkleProofVar::new_witness(cs.clone(), &flat.get_proof(5000)).unwrap();
+        let _ = verify_and_update(cs.clone(), &old_root, &item_id, &old_qty, &new_qty, &proof)
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        let flat_constraints = cs.num_constraints();
+
+        // Same hashes; only the item_id bit binding is extra
+        assert!(two_level_constraints <= flat_constraints + 1);
+    }
+}
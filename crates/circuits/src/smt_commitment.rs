@@ -1,18 +1,22 @@
 //! SMT-based commitment scheme for inventories.
 //!
-//! The commitment scheme uses Poseidon hash:
+//! The commitment scheme uses Poseidon hash in the `Domain::Commitment` domain:
 //! commitment = Poseidon(inventory_root, current_volume, blinding)
 //!
 //! Where:
 //! - inventory_root: Root of the Sparse Merkle Tree containing all items
 //! - current_volume: Total volume of all items in the inventory
 //! - blinding: Random value for hiding the commitment
+//!
+//! `create_smt_commitment_with_id` additionally binds the commitment to an
+//! inventory id, in the separate `Domain::CommitmentWithId` domain so that no
+//! id (not even zero) reproduces an unbound commitment.
 
 use ark_bn254::Fr;
 use ark_r1cs_std::fields::fp::FpVar;
 use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
 
-use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::poseidon::{poseidon_hash_domain, poseidon_hash_domain_var, Domain};
 
 /// Create an SMT-based inventory commitment using Poseidon.
 ///
@@ -27,7 +31,7 @@ pub fn create_smt_commitment(
         Fr::from(current_volume),
         blinding,
     ];
-    poseidon_hash_many(&inputs)
+    poseidon_hash_domain(Domain::Commitment, &inputs)
 }
 
 /// Compute SMT commitment in-circuit using Poseidon.
@@ -42,7 +46,45 @@ pub fn create_smt_commitment_var(
         current_volume.clone(),
         blinding.clone(),
     ];
-    poseidon_hash_many_var(cs, &inputs)
+    poseidon_hash_domain_var(cs, Domain::Commitment, &inputs)
+}
+
+/// Create an SMT-based inventory commitment bound to an inventory id.
+///
+/// commitment = Poseidon(inventory_root, current_volume, blinding, inventory_id)
+///
+/// Equal contents under different ids commit to different values, so a
+/// commitment cannot be presented as belonging to another inventory.
+pub fn create_smt_commitment_with_id(
+    inventory_root: Fr,
+    current_volume: u64,
+    blinding: Fr,
+    inventory_id: Fr,
+) -> Fr {
+    let inputs = vec![
+        inventory_root,
+        Fr::from(current_volume),
+        blinding,
+        inventory_id,
+    ];
+    poseidon_hash_domain(Domain::CommitmentWithId, &inputs)
+}
+
+/// Compute an id-bound SMT commitment in-circuit using Poseidon.
+pub fn create_smt_commitment_with_id_var(
+    cs: ConstraintSystemRef<Fr>,
+    inventory_root: &FpVar<Fr>,
+    current_volume: &FpVar<Fr>,
+    blinding: &FpVar<Fr>,
+    inventory_id: &FpVar<Fr>,
+) -> Result<FpVar<Fr>, SynthesisError> {
+    let inputs = vec![
+        inventory_root.clone(),
+        current_volume.clone(),
+        blinding.clone(),
+        inventory_id.clone(),
+    ];
+    poseidon_hash_domain_var(cs, Domain::CommitmentWithId, &inputs)
 }
 
 /// Inventory state for SMT-based design.
@@ -248,6 +290,60 @@ mod tests {
         println!("SMT commitment constraints: {}", cs.num_constraints());
     }
 
+    #[test]
+    fn test_different_ids_different_commitments() {
+        let root = Fr::from(12345u64);
+        let blinding = Fr::from(99999u64);
+
+        let commitment1 = create_smt_commitment_with_id(root, 100, blinding, Fr::from(1u64));
+        let commitment2 = create_smt_commitment_with_id(root, 100, blinding, Fr::from(2u64));
+
+        assert_ne!(commitment1, commitment2);
+    }
+
+    #[test]
+    fn test_id_commitment_differs_from_unbound() {
+        let root = Fr::from(12345u64);
+        let blinding = Fr::from(99999u64);
+
+        let unbound = create_smt_commitment(root, 100, blinding);
+        for id in [0u64, 1] {
+            let bound = create_smt_commitment_with_id(root, 100, blinding, Fr::from(id));
+            assert_ne!(bound, unbound, "id {} reproduces the unbound commitment", id);
+        }
+    }
+
+    #[test]
+    fn test_id_commitment_in_circuit_matches_native() {
+        let root = Fr::from(12345u64);
+        let volume = 100u64;
+        let blinding = Fr::from(99999u64);
+        let inventory_id = Fr::from(7u64);
+
+        let native_commitment = create_smt_commitment_with_id(root, volume, blinding, inventory_id);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+
+        let root_var = FpVar::new_witness(cs.clone(), || Ok(root)).unwrap();
+        let volume_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(volume))).unwrap();
+        let blinding_var = FpVar::new_witness(cs.clone(), || Ok(blinding)).unwrap();
+        let id_var = FpVar::new_witness(cs.clone(), || Ok(inventory_id)).unwrap();
+
+        let circuit_commitment = create_smt_commitment_with_id_var(
+            cs.clone(),
+            &root_var,
+            &volume_var,
+            &blinding_var,
+            &id_var,
+        )
+        .unwrap();
+
+        let expected_var = FpVar::new_input(cs.clone(), || Ok(native_commitment)).unwrap();
+        circuit_commitment.enforce_equal(&expected_var).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+    }
+
     #[test]
     fn test_inventory_state_workflow() {
         // Create empty inventory
@@ -0,0 +1,196 @@
+//! Spend-Only Transition Circuit for escrow-style inventories.
+//!
+//! Same public inputs and constraints as `StateTransitionCircuit`, plus:
+//! - op_type must be Withdraw (bound through the signal hash)
+//! - new_volume <= old_volume
+//!
+//! An inventory verified only with this circuit's key can never grow.
+
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::signal::OpType;
+use crate::smt::MerkleProof;
+use crate::state_transition::{StateTransitionCircuit, TransitionKind};
+use crate::volume_registry::{VolumeRegistry, MAX_ITEM_TYPES};
+
+/// Spend-Only Transition Circuit.
+///
+/// Proves a valid withdrawal; any deposit-direction change is unsatisfiable.
+#[derive(Clone)]
+pub struct SpendOnlyTransitionCircuit {
+    /// Underlying state transition witnesses
+    pub transition: StateTransitionCircuit,
+}
+
+impl SpendOnlyTransitionCircuit {
+    /// Create a new empty circuit for setup.
+    /// Uses a zero-amount withdrawal from an empty tree, so the dummy
+    /// witnesses also satisfy the spend-only constraints.
+    pub fn empty() -> Self {
+        use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+
+        let tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        let root = tree.root();
+        let proof = tree.get_proof(0);
+
+        Self::new(
+            root,
+            0,
+            Fr::from(0u64),
+            root,
+            0,
+            Fr::from(0u64),
+            0,
+            0,
+            0,
+            0,
+            proof,
+            &VolumeRegistry::new([0; MAX_ITEM_TYPES]),
+            0,
+            0,
+            Fr::from(0u64),
+        )
+    }
+
+    /// Create a new withdrawal circuit with all witnesses.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        old_inventory_root: Fr,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_inventory_root: Fr,
+        new_volume: u64,
+        new_blinding: Fr,
+        item_id: u64,
+        old_quantity: u64,
+        new_quantity: u64,
+        amount: u64,
+        inventory_proof: MerkleProof<Fr>,
+        registry: &VolumeRegistry,
+        max_capacity: u64,
+        nonce: u64,
+        inventory_id: Fr,
+    ) -> Self {
+        Self::from_transition(StateTransitionCircuit::new(
+            old_inventory_root,
+            old_volume,
+            old_blinding,
+            new_inventory_root,
+            new_volume,
+            new_blinding,
+            item_id,
+            old_quantity,
+            new_quantity,
+            amount,
+            OpType::Withdraw,
+            inventory_proof,
+            registry,
+            max_capacity,
+            nonce,
+            inventory_id,
+        ))
+    }
+
+    /// Wrap an existing state transition.
+    ///
+    /// The constraints reject the witnesses unless the transition is a withdrawal.
+    pub fn from_transition(transition: StateTransitionCircuit) -> Self {
+        Self { transition }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for SpendOnlyTransitionCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        self.transition.synthesize(cs, TransitionKind::SpendOnly)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    fn test_empty_circuit_satisfied() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        SpendOnlyTransitionCircuit::empty()
+            .generate_constraints(cs.clone())
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_spend_only_withdraw() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+        let proof = tree.get_proof(1);
+
+        tree.update(1, 70);
+        let new_root = tree.root();
+
+        let circuit = SpendOnlyTransitionCircuit::new(
+            old_root,
+            1000,
+            Fr::from(12345u64),
+            new_root,
+            700,
+            Fr::from(67890u64),
+            1,
+            100,
+            70,
+            30,
+            proof,
+            &VolumeRegistry::from_entries(&[(1, 10)]),
+            10000,
+            0,
+            Fr::from(12345678u64),
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_spend_only_rejects_deposit() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+        let proof = tree.get_proof(1);
+
+        tree.update(1, 150);
+        let new_root = tree.root();
+
+        // A valid regular deposit...
+        let deposit = StateTransitionCircuit::new(
+            old_root,
+            1000,
+            Fr::from(12345u64),
+            new_root,
+            1500,
+            Fr::from(67890u64),
+            1,
+            100,
+            150,
+            50,
+            OpType::Deposit,
+            proof,
+            &VolumeRegistry::from_entries(&[(1, 10)]),
+            10000,
+            0,
+            Fr::from(12345678u64),
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        deposit.clone().generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // ...is rejected by the spend-only circuit
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        SpendOnlyTransitionCircuit::from_transition(deposit)
+            .generate_constraints(cs.clone())
+            .unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
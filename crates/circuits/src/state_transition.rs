@@ -4,7 +4,7 @@
 //! It combines the functionality of the old deposit, withdraw, and capacity circuits.
 //!
 //! Public inputs:
-//! - signal_hash: Anemoi hash binding all operation parameters
+//! - signal_hash: Poseidon hash binding all operation parameters
 //! - nonce: Replay protection (verified on-chain against inventory.nonce)
 //! - inventory_id: Cross-inventory protection (verified on-chain)
 //! - registry_root: Volume registry commitment (verified against VolumeRegistry)
@@ -22,10 +22,18 @@ use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::prelude::*;
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
 
-use crate::range_check::{enforce_geq, enforce_u32_range};
-use crate::signal::{compute_signal_hash, OpType};
+use crate::range_check::{
+    enforce_fits_in_bits, enforce_geq, enforce_u32_range, enforce_within_capacity, CAPACITY_BITS,
+};
+use crate::signal::{
+    compute_delta_capped_signal_hash, compute_delta_capped_signal_hash_var,
+    compute_mint_signal_hash_var, compute_signal_hash, enforce_valid_op_type, OpType,
+};
 use crate::smt::{verify_and_update, MerkleProof, MerkleProofVar};
 use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+use crate::volume_registry::{
+    alloc_volumes_var, compute_registry_hash, lookup_volume_var, VolumeRegistry, MAX_ITEM_TYPES,
+};
 
 /// State Transition Circuit.
 ///
@@ -77,47 +85,68 @@ pub struct StateTransitionCircuit {
     pub item_volume: Option<u64>,
     /// Registry root (commitment to volume table)
     pub registry_root: Option<Fr>,
+    /// Full volume table; must hash to `registry_root` and hold `item_volume`
+    /// at `item_id`
+    pub registry_volumes: Option<[u64; MAX_ITEM_TYPES]>,
 
     // Capacity
     /// Maximum allowed capacity
     pub max_capacity: Option<u64>,
+
+    // Growth limit
+    /// Maximum volume a single deposit may add. `None` builds the uncapped
+    /// circuit; capped and uncapped circuits need separate keys.
+    pub max_delta: Option<u64>,
+
+    // Tree shape
+    /// SMT depth; `inventory_proof` must have exactly this many siblings.
+    /// Keys are specific to a depth, so setup must be re-run to change it.
+    pub depth: usize,
 }
 
 impl StateTransitionCircuit {
     /// Create a new empty circuit for setup.
-    /// Uses dummy values that produce valid constraint structure.
+    /// Uses a zero-amount deposit into an empty tree, so the dummy witnesses
+    /// produce a valid constraint structure and also satisfy it.
     pub fn empty() -> Self {
-        use crate::smt::DEFAULT_DEPTH;
-
-        // Create dummy proof with correct depth
-        let dummy_proof = MerkleProof::new(
-            vec![Fr::from(0u64); DEFAULT_DEPTH],
-            vec![false; DEFAULT_DEPTH],
-        );
+        Self::empty_with_depth(crate::smt::DEFAULT_DEPTH)
+    }
 
-        Self {
-            signal_hash: Some(Fr::from(0u64)),
-            nonce: Some(0),
-            inventory_id: Some(Fr::from(0u64)),
-            old_inventory_root: Some(Fr::from(0u64)),
-            old_volume: Some(0),
-            old_blinding: Some(Fr::from(0u64)),
-            new_inventory_root: Some(Fr::from(0u64)),
-            new_volume: Some(0),
-            new_blinding: Some(Fr::from(0u64)),
-            item_id: Some(0),
-            old_quantity: Some(0),
-            new_quantity: Some(0),
-            amount: Some(0),
-            op_type: Some(OpType::Deposit),
-            inventory_proof: Some(dummy_proof),
-            item_volume: Some(0),
-            registry_root: Some(Fr::from(0u64)),
-            max_capacity: Some(0),
-        }
+    /// Create an empty circuit for a tree of the given depth.
+    ///
+    /// Setup with this circuit produces keys for `depth`-deep trees only.
+    pub fn empty_with_depth(depth: usize) -> Self {
+        use crate::smt::SparseMerkleTree;
+
+        let tree = SparseMerkleTree::new(depth);
+        let root = tree.root();
+        let proof = tree.get_proof(0);
+
+        Self::new(
+            root,
+            0,
+            Fr::from(0u64),
+            root,
+            0,
+            Fr::from(0u64),
+            0,
+            0,
+            0,
+            0,
+            OpType::Deposit,
+            proof,
+            &VolumeRegistry::new([0; MAX_ITEM_TYPES]),
+            0,
+            0,
+            Fr::from(0u64),
+        )
+        .with_depth(depth)
     }
 
     /// Create a new circuit with all witnesses.
+    ///
+    /// The item's volume and the registry root are taken from `registry`.
+    /// The circuit expects a `DEFAULT_DEPTH` tree; use `with_depth` for others.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         old_inventory_root: Fr,
@@ -132,13 +161,15 @@ impl StateTransitionCircuit {
         amount: u64,
         op_type: OpType,
         inventory_proof: MerkleProof<Fr>,
-        item_volume: u64,
-        registry_root: Fr,
+        registry: &VolumeRegistry,
         max_capacity: u64,
         nonce: u64,
         inventory_id: Fr,
     ) -> Self {
-        // Compute commitments using Anemoi
+        let item_volume = registry.get_volume(item_id);
+        let registry_root = compute_registry_hash(registry);
+
+        // Compute commitments using Poseidon
         let old_commitment = create_smt_commitment(
             old_inventory_root,
             old_volume,
@@ -181,13 +212,67 @@ impl StateTransitionCircuit {
             inventory_proof: Some(inventory_proof),
             item_volume: Some(item_volume),
             registry_root: Some(registry_root),
+            registry_volumes: Some(*registry.volumes()),
             max_capacity: Some(max_capacity),
+            max_delta: None,
+            depth: crate::smt::DEFAULT_DEPTH,
         }
     }
+
+    /// Expect an inventory proof for a tree of the given depth.
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Cap the volume a deposit may add at `max_delta`.
+    ///
+    /// The cap is bound into the signal hash
+    /// (see `compute_delta_capped_signal_hash`), so the proof commits to it.
+    pub fn with_max_delta(mut self, max_delta: u64) -> Self {
+        self.signal_hash = self
+            .signal_hash
+            .map(|signal_hash| compute_delta_capped_signal_hash(signal_hash, max_delta));
+        self.max_delta = Some(max_delta);
+        self
+    }
 }
 
 impl ConstraintSynthesizer<Fr> for StateTransitionCircuit {
     fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        self.synthesize(cs, TransitionKind::Regular)
+    }
+}
+
+/// Constraints layered on top of the base state transition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TransitionKind {
+    /// Deposit or withdrawal
+    Regular,
+    /// Withdrawal that never grows the volume (see `SpendOnlyTransitionCircuit`)
+    SpendOnly,
+    /// Spend-only, with the amount as a public input (see `BurnCircuit`)
+    Burn,
+    /// Deposit within a supply cap, with the amount and cap as public inputs
+    /// (see `MintCircuit`)
+    Mint {
+        max_supply: Option<u64>,
+        already_minted: Option<u64>,
+    },
+}
+
+impl StateTransitionCircuit {
+    /// Generate the state transition constraints.
+    ///
+    /// `SpendOnly` and `Burn` additionally force op_type to Withdraw and
+    /// new_volume <= old_volume; `Burn` also exposes the amount as a fifth
+    /// public input. `Mint` forces a deposit and exposes the amount and
+    /// max_supply as fifth and sixth public inputs.
+    pub(crate) fn synthesize(
+        self,
+        cs: ConstraintSystemRef<Fr>,
+        kind: TransitionKind,
+    ) -> Result<(), SynthesisError> {
         // === Allocate public inputs ===
         // Order matters: signal_hash, nonce, inventory_id, registry_root
         let signal_hash_var = FpVar::new_input(cs.clone(), || {
@@ -256,8 +341,12 @@ impl ConstraintSynthesizer<Fr> for StateTransitionCircuit {
         })?;
 
         // === Allocate Merkle proof ===
-        let proof = self.inventory_proof.as_ref();
-        let inventory_proof_var = MerkleProofVar::new_witness(cs.clone(), proof.unwrap())?;
+        // The proof length fixes the constraint structure, so it must match the keys' depth
+        let proof = self.inventory_proof.as_ref().unwrap();
+        if proof.path().len() != self.depth {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let inventory_proof_var = MerkleProofVar::new_witness(cs.clone(), proof)?;
 
         // === Allocate registry public input ===
         // registry_root is a public input so it can be verified on-chain against VolumeRegistry
@@ -265,73 +354,68 @@ impl ConstraintSynthesizer<Fr> for StateTransitionCircuit {
             self.registry_root.ok_or(SynthesisError::AssignmentMissing)
         })?;
 
+        // === Allocate burn/mint public inputs ===
+        // Appended after registry_root so the first four inputs keep their positions
+        let public_amount_var = match kind {
+            TransitionKind::Burn | TransitionKind::Mint { .. } => {
+                Some(FpVar::new_input(cs.clone(), || {
+                    self.amount
+                        .map(Fr::from)
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })?)
+            }
+            _ => None,
+        };
+        let mint_vars = match kind {
+            TransitionKind::Mint { max_supply, already_minted } => {
+                let max_supply_var = FpVar::new_input(cs.clone(), || {
+                    max_supply
+                        .map(Fr::from)
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })?;
+                let already_minted_var = FpVar::new_witness(cs.clone(), || {
+                    already_minted
+                        .map(Fr::from)
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })?;
+                Some((max_supply_var, already_minted_var))
+            }
+            _ => None,
+        };
+
         // === Allocate registry witnesses ===
         let item_volume_var = FpVar::new_witness(cs.clone(), || {
             self.item_volume
                 .map(Fr::from)
                 .ok_or(SynthesisError::AssignmentMissing)
         })?;
+        let registry_volume_vars = alloc_volumes_var(cs.clone(), self.registry_volumes.as_ref())?;
         let max_capacity_var = FpVar::new_witness(cs.clone(), || {
             self.max_capacity
                 .map(Fr::from)
                 .ok_or(SynthesisError::AssignmentMissing)
         })?;
 
-        // === Constraint 1: Verify and update inventory SMT ===
-        // This verifies the old state and computes the new root
-        let computed_new_root = verify_and_update(
-            cs.clone(),
-            &old_root_var,
-            &item_id_var,
-            &old_qty_var,
-            &new_qty_var,
-            &inventory_proof_var,
-        )?;
-
-        // Enforce computed new root matches claimed new root
-        computed_new_root.enforce_equal(&new_root_var)?;
-
-        // === Constraint 2: Verify quantity change matches operation ===
-        // For deposit: new_qty = old_qty + amount
-        // For withdraw: new_qty = old_qty - amount
-        let zero = FpVar::zero();
-        let one = FpVar::one();
-        let is_deposit = op_type_var.is_eq(&zero)?;
-
-        // Compute expected new quantity based on operation type
-        let qty_plus_amount = &old_qty_var + &amount_var;
-        let qty_minus_amount = &old_qty_var - &amount_var;
-        let expected_new_qty = is_deposit.select(&qty_plus_amount, &qty_minus_amount)?;
-
-        new_qty_var.enforce_equal(&expected_new_qty)?;
-
-        // === Constraint 3: Range check on new quantity ===
-        // Prevents underflow attacks where withdraw > current quantity
-        // If qty_minus_amount wrapped around (negative), it won't fit in 32 bits
-        enforce_u32_range(cs.clone(), &new_qty_var)?;
-
-        // === Constraint 4: Verify volume change ===
-        // volume_delta = item_volume * amount
-        let volume_delta = &item_volume_var * &amount_var;
-
-        // For deposit: new_volume = old_volume + volume_delta
-        // For withdraw: new_volume = old_volume - volume_delta
-        let vol_plus_delta = &old_volume_var + &volume_delta;
-        let vol_minus_delta = &old_volume_var - &volume_delta;
-        let expected_new_volume = is_deposit.select(&vol_plus_delta, &vol_minus_delta)?;
-
-        new_volume_var.enforce_equal(&expected_new_volume)?;
-
-        // === Constraint 5: Range check on new volume ===
-        // Prevents underflow attacks on volume
-        enforce_u32_range(cs.clone(), &new_volume_var)?;
-
-        // === Constraint 6: Capacity check ===
-        // new_volume <= max_capacity
-        // enforce_geq checks that (max_capacity - new_volume) fits in 32 bits
-        enforce_geq(cs.clone(), &max_capacity_var, &new_volume_var)?;
-
-        // === Constraint 7: Compute commitments using Anemoi ===
+        // === Constraints 1-6, 9-10, 15: SMT update, quantities, volume, capacity,
+        // op_type, registry lookup ===
+        let step = TransitionStepVars {
+            old_root: old_root_var.clone(),
+            new_root: new_root_var.clone(),
+            old_volume: old_volume_var.clone(),
+            new_volume: new_volume_var.clone(),
+            item_id: item_id_var.clone(),
+            old_quantity: old_qty_var,
+            new_quantity: new_qty_var,
+            amount: amount_var.clone(),
+            op_type: op_type_var.clone(),
+            item_volume: item_volume_var,
+            registry_root: registry_root_var.clone(),
+            registry_volumes: registry_volume_vars,
+            proof: inventory_proof_var,
+        };
+        let is_withdraw = enforce_transition_step(cs.clone(), &step, &max_capacity_var)?;
+
+        // === Constraint 7: Compute commitments using Poseidon ===
         let old_commitment_var = create_smt_commitment_var(
             cs.clone(),
             &old_root_var,
@@ -346,7 +430,7 @@ impl ConstraintSynthesizer<Fr> for StateTransitionCircuit {
             &new_blinding_var,
         )?;
 
-        // === Constraint 8: Compute and verify signal hash ===
+        // === Constraint 8: Compute signal hash (enforced after the optional delta cap) ===
         // Signal hash now includes nonce and inventory_id for replay/cross-inventory protection
         let computed_signal = crate::signal::compute_signal_hash_var(
             cs.clone(),
@@ -361,23 +445,242 @@ impl ConstraintSynthesizer<Fr> for StateTransitionCircuit {
             &inventory_id_var,
         )?;
 
-        computed_signal.enforce_equal(&signal_hash_var)?;
+        // === Constraint 11 (spend-only): withdraw only, volume never grows ===
+        // op_type is absorbed into the signal hash, so the proof is bound to a withdraw
+        if matches!(kind, TransitionKind::SpendOnly | TransitionKind::Burn) {
+            is_withdraw.enforce_equal(&Boolean::TRUE)?;
+            enforce_geq(cs.clone(), &old_volume_var, &new_volume_var)?;
+        }
 
-        // === Constraint 9: Ensure op_type is valid (0 or 1) ===
-        let is_withdraw = op_type_var.is_eq(&one)?;
-        let is_valid_op = is_deposit.or(&is_withdraw)?;
-        is_valid_op.enforce_equal(&Boolean::TRUE)?;
+        // === Constraint 13 (burn/mint): the public amount is the moved amount ===
+        if let Some(public_amount_var) = public_amount_var {
+            public_amount_var.enforce_equal(&amount_var)?;
+        }
+
+        // === Constraint 14 (mint): deposit only, already_minted + amount <= max_supply ===
+        // Both terms fit in 64 bits, so their sum cannot wrap around and any
+        // u64 max_supply is usable
+        if let Some((max_supply_var, already_minted_var)) = &mint_vars {
+            is_withdraw.enforce_equal(&Boolean::FALSE)?;
+            enforce_fits_in_bits(cs.clone(), already_minted_var, CAPACITY_BITS)?;
+            enforce_fits_in_bits(cs.clone(), &amount_var, CAPACITY_BITS)?;
+            let minted_var = already_minted_var + &amount_var;
+            enforce_within_capacity(cs.clone(), max_supply_var, &minted_var)?;
+        }
+
+        // === Constraint 12 (delta-capped): deposit growth <= max_delta ===
+        // max_delta is bound on top of the signal hash below
+        let computed_signal = match self.max_delta {
+            None => computed_signal,
+            Some(max_delta) => {
+                let max_delta_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(max_delta)))?;
+                let growth = &new_volume_var - &old_volume_var;
+                let capped_growth = is_withdraw.select(&FpVar::zero(), &growth)?;
+                enforce_geq(cs.clone(), &max_delta_var, &capped_growth)?;
+
+                compute_delta_capped_signal_hash_var(cs.clone(), &computed_signal, &max_delta_var)?
+            }
+        };
+
+        // already_minted is private, so the signal hash binds it to the contract's counter
+        let computed_signal = match &mint_vars {
+            None => computed_signal,
+            Some((_, already_minted_var)) => {
+                compute_mint_signal_hash_var(cs.clone(), &computed_signal, already_minted_var)?
+            }
+        };
+
+        computed_signal.enforce_equal(&signal_hash_var)?;
 
         Ok(())
     }
 }
 
+/// Witness variables for a single deposit/withdraw step.
+pub(crate) struct TransitionStepVars {
+    pub old_root: FpVar<Fr>,
+    pub new_root: FpVar<Fr>,
+    pub old_volume: FpVar<Fr>,
+    pub new_volume: FpVar<Fr>,
+    pub item_id: FpVar<Fr>,
+    pub old_quantity: FpVar<Fr>,
+    pub new_quantity: FpVar<Fr>,
+    pub amount: FpVar<Fr>,
+    pub op_type: FpVar<Fr>,
+    pub item_volume: FpVar<Fr>,
+    pub registry_root: FpVar<Fr>,
+    /// Volume table hashing to `registry_root`, as allocated by `alloc_volumes_var`
+    pub registry_volumes: Vec<FpVar<Fr>>,
+    pub proof: MerkleProofVar,
+}
+
+/// Enforce one deposit/withdraw step: SMT update, quantity and volume change,
+/// range and capacity checks, op_type validity, and that item_volume is
+/// item_id's entry in the registry.
+///
+/// Returns whether the step is a withdrawal.
+pub(crate) fn enforce_transition_step(
+    cs: ConstraintSystemRef<Fr>,
+    step: &TransitionStepVars,
+    max_capacity_var: &FpVar<Fr>,
+) -> Result<Boolean<Fr>, SynthesisError> {
+    let TransitionStepVars {
+        old_root: old_root_var,
+        new_root: new_root_var,
+        old_volume: old_volume_var,
+        new_volume: new_volume_var,
+        item_id: item_id_var,
+        old_quantity: old_qty_var,
+        new_quantity: new_qty_var,
+        amount: amount_var,
+        op_type: op_type_var,
+        item_volume: item_volume_var,
+        registry_root: registry_root_var,
+        registry_volumes: registry_volume_vars,
+        proof: inventory_proof_var,
+    } = step;
+
+    // === Constraint 15: item_volume is item_id's entry under registry_root ===
+    // Checked here so no circuit built on this step can price an item freely
+    let registry_volume_var = lookup_volume_var(
+        cs.clone(),
+        registry_root_var,
+        item_id_var,
+        registry_volume_vars,
+    )?;
+    item_volume_var.enforce_equal(&registry_volume_var)?;
+
+    // === Constraint 1: Verify and update inventory SMT ===
+    // This verifies the old state and computes the new root
+    let computed_new_root = verify_and_update(
+        cs.clone(),
+        old_root_var,
+        item_id_var,
+        old_qty_var,
+        new_qty_var,
+        inventory_proof_var,
+    )?;
+
+    // Enforce computed new root matches claimed new root
+    computed_new_root.enforce_equal(new_root_var)?;
+
+    // === Constraint 9: Ensure op_type is valid (0 or 1) ===
+    // Validated before the first select on is_deposit below
+    let zero = FpVar::zero();
+    let is_deposit = enforce_valid_op_type(op_type_var)?;
+
+    // === Constraint 2: Verify quantity change matches operation ===
+    // For deposit: new_qty = old_qty + amount
+    // For withdraw: new_qty = old_qty - amount
+
+    // Compute expected new quantity based on operation type
+    let qty_plus_amount = old_qty_var + amount_var;
+    let qty_minus_amount = old_qty_var - amount_var;
+    let expected_new_qty = is_deposit.select(&qty_plus_amount, &qty_minus_amount)?;
+
+    new_qty_var.enforce_equal(&expected_new_qty)?;
+
+    // === Constraint 3: Range check on new quantity ===
+    // Prevents underflow attacks where withdraw > current quantity
+    // If qty_minus_amount wrapped around (negative), it won't fit in 32 bits
+    enforce_u32_range(cs.clone(), new_qty_var)?;
+
+    // === Constraint 4: Verify volume change ===
+    // volume_delta = item_volume * amount
+    let volume_delta = item_volume_var * amount_var;
+
+    // item_volume and amount are not range checked on their own, so bound the
+    // product before it is added to or subtracted from a volume
+    enforce_u32_range(cs.clone(), &volume_delta)?;
+
+    // For deposit: new_volume = old_volume + volume_delta
+    // For withdraw: new_volume = old_volume - volume_delta
+    let vol_plus_delta = old_volume_var + &volume_delta;
+    let vol_minus_delta = old_volume_var - &volume_delta;
+    let expected_new_volume = is_deposit.select(&vol_plus_delta, &vol_minus_delta)?;
+
+    new_volume_var.enforce_equal(&expected_new_volume)?;
+
+    // For withdraw: old_volume >= volume_delta, so the subtraction never
+    // wraps around the field (a deposit compares against zero instead)
+    let withdrawn_volume = is_deposit.select(&zero, &volume_delta)?;
+    enforce_geq(cs.clone(), old_volume_var, &withdrawn_volume)?;
+
+    // === Constraint 5: Range check on new volume ===
+    // Prevents underflow attacks on volume
+    enforce_u32_range(cs.clone(), new_volume_var)?;
+
+    // === Constraint 6: Capacity check ===
+    // new_volume <= max_capacity
+    // new_volume is range checked above, so (max_capacity - new_volume)
+    // fitting in 64 bits means it did not wrap around
+    enforce_within_capacity(cs.clone(), max_capacity_var, new_volume_var)?;
+
+    // === Constraint 10: op_type's effect matches the volume direction ===
+    // A non-zero amount must move a non-zero volume, otherwise a deposit
+    // could pair with item_volume = 0 and skip capacity growth.
+    let amount_is_zero = amount_var.is_eq(&zero)?;
+    let item_volume_is_zero = item_volume_var.is_eq(&zero)?;
+    item_volume_is_zero
+        .and(&amount_is_zero.not())?
+        .enforce_equal(&Boolean::FALSE)?;
+
+    // Deposit: new_volume >= old_volume; withdraw: old_volume >= new_volume
+    let larger_volume = is_deposit.select(new_volume_var, old_volume_var)?;
+    let smaller_volume = is_deposit.select(old_volume_var, new_volume_var)?;
+    enforce_geq(cs, &larger_volume, &smaller_volume)?;
+
+    Ok(is_deposit.not())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use crate::volume_registry::{compute_registry_hash, VolumeRegistry};
     use ark_relations::r1cs::ConstraintSystem;
 
+    /// Build a transition for `amount` of `item_id`, pricing volume with `registry`.
+    fn transition_with_registry(
+        tree: &mut SparseMerkleTree,
+        item_id: u64,
+        amount: u64,
+        op_type: OpType,
+        registry: &VolumeRegistry,
+        old_volume: u64,
+        max_capacity: u64,
+    ) -> StateTransitionCircuit {
+        let old_root = tree.root();
+        let proof = tree.get_proof(item_id);
+        let old_qty = tree.get(item_id);
+        let item_volume = registry.get_volume(item_id);
+
+        let (new_qty, new_volume) = match op_type {
+            OpType::Deposit => (old_qty + amount, old_volume + item_volume * amount),
+            OpType::Withdraw => (old_qty - amount, old_volume - item_volume * amount),
+        };
+        tree.update(item_id, new_qty);
+
+        StateTransitionCircuit::new(
+            old_root,
+            old_volume,
+            Fr::from(111u64),
+            tree.root(),
+            new_volume,
+            Fr::from(222u64),
+            item_id,
+            old_qty,
+            new_qty,
+            amount,
+            op_type,
+            proof,
+            registry,
+            max_capacity,
+            0,
+            Fr::from(12345678u64),
+        )
+    }
+
     #[test]
     fn test_state_transition_deposit() {
         // Create initial inventory with 1 item
@@ -396,9 +699,9 @@ mod tests {
         let old_blinding = Fr::from(12345u64);
         let new_blinding = Fr::from(67890u64);
         let item_volume = 10u64;
+        let registry = VolumeRegistry::from_entries(&[(1, item_volume)]);
         let old_volume = 100 * item_volume; // 100 items * 10 volume each
         let new_volume = 150 * item_volume; // 150 items * 10 volume each
-        let registry_root = Fr::from(99999u64);
         let max_capacity = 10000u64;
 
         let nonce = 0u64;
@@ -417,8 +720,7 @@ mod tests {
             50,  // amount
             OpType::Deposit,
             proof,
-            item_volume,
-            registry_root,
+            &registry,
             max_capacity,
             nonce,
             inventory_id,
@@ -449,9 +751,9 @@ mod tests {
         let old_blinding = Fr::from(12345u64);
         let new_blinding = Fr::from(67890u64);
         let item_volume = 10u64;
+        let registry = VolumeRegistry::from_entries(&[(1, item_volume)]);
         let old_volume = 100 * item_volume;
         let new_volume = 70 * item_volume;
-        let registry_root = Fr::from(99999u64);
         let max_capacity = 10000u64;
         let nonce = 5u64;
         let inventory_id = Fr::from(12345678u64);
@@ -469,8 +771,7 @@ mod tests {
             30,  // amount
             OpType::Withdraw,
             proof,
-            item_volume,
-            registry_root,
+            &registry,
             max_capacity,
             nonce,
             inventory_id,
@@ -488,18 +789,18 @@ mod tests {
         // Create empty inventory
         let mut tree = SparseMerkleTree::new(DEFAULT_DEPTH);
         let old_root = tree.root();
-        let proof = tree.get_proof(42); // Proof for empty slot
+        let proof = tree.get_proof(7); // Proof for empty slot
 
         // Add new item
-        tree.update(42, 100);
+        tree.update(7, 100);
         let new_root = tree.root();
 
         let old_blinding = Fr::from(12345u64);
         let new_blinding = Fr::from(67890u64);
         let item_volume = 5u64;
+        let registry = VolumeRegistry::from_entries(&[(7, item_volume)]);
         let old_volume = 0u64;
         let new_volume = 100 * item_volume;
-        let registry_root = Fr::from(99999u64);
         let max_capacity = 10000u64;
         let nonce = 0u64;
         let inventory_id = Fr::from(12345678u64);
@@ -511,14 +812,13 @@ mod tests {
             new_root,
             new_volume,
             new_blinding,
-            42,  // item_id
+            7,   // item_id
             0,   // old_quantity (empty slot)
             100, // new_quantity
             100, // amount
             OpType::Deposit,
             proof,
-            item_volume,
-            registry_root,
+            &registry,
             max_capacity,
             nonce,
             inventory_id,
@@ -546,9 +846,9 @@ mod tests {
         let old_blinding = Fr::from(12345u64);
         let new_blinding = Fr::from(67890u64);
         let item_volume = 10u64;
+        let registry = VolumeRegistry::from_entries(&[(1, item_volume)]);
         let old_volume = 100 * item_volume;
         let new_volume = 150 * item_volume;
-        let registry_root = Fr::from(99999u64);
         let max_capacity = 10000u64;
         let nonce = 0u64;
         let inventory_id = Fr::from(12345678u64);
@@ -567,8 +867,7 @@ mod tests {
             60, // WRONG amount
             OpType::Deposit,
             proof,
-            item_volume,
-            registry_root,
+            &registry,
             max_capacity,
             nonce,
             inventory_id,
@@ -596,8 +895,8 @@ mod tests {
         let old_blinding = Fr::from(12345u64);
         let new_blinding = Fr::from(67890u64);
         let item_volume = 10u64;
+        let registry = VolumeRegistry::from_entries(&[(1, item_volume)]);
         let old_volume = 100 * item_volume;
-        let registry_root = Fr::from(99999u64);
         let max_capacity = 10000u64;
         let nonce = 0u64;
         let inventory_id = Fr::from(12345678u64);
@@ -616,8 +915,7 @@ mod tests {
             50,
             OpType::Deposit,
             proof,
-            item_volume,
-            registry_root,
+            &registry,
             max_capacity,
             nonce,
             inventory_id,
@@ -630,6 +928,196 @@ mod tests {
         assert!(!cs.is_satisfied().unwrap());
     }
 
+    #[test]
+    fn test_deposit_with_zero_item_volume_rejected() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+        let proof = tree.get_proof(1);
+
+        tree.update(1, 150);
+        let new_root = tree.root();
+
+        // Deposit 50 units while claiming they take no space
+        let circuit = StateTransitionCircuit::new(
+            old_root,
+            1000,
+            Fr::from(12345u64),
+            new_root,
+            1000, // volume unchanged
+            Fr::from(67890u64),
+            1,
+            100,
+            150,
+            50,
+            OpType::Deposit,
+            proof,
+            &VolumeRegistry::from_slice(&[]), // item 1 has no volume
+            1000, // already full
+            0,
+            Fr::from(12345678u64),
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_item_volume_bound_to_registry() {
+        let registry = VolumeRegistry::from_slice(&[0, 10]);
+        let satisfied = |circuit: StateTransitionCircuit| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            cs.is_satisfied().unwrap()
+        };
+
+        // Withdraw 30 at the registered volume of 10: 1000 -> 700
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let honest =
+            transition_with_registry(&mut tree, 1, 30, OpType::Withdraw, &registry, 1000, 10000);
+        assert!(satisfied(honest.clone()));
+
+        // Claiming a volume of 20 frees 600 instead, inconsistent with registry_root
+        let mut understated = honest.clone();
+        understated.item_volume = Some(20);
+        understated.new_volume = Some(400);
+        assert!(!satisfied(understated));
+
+        // A volume table that does not hash to registry_root
+        let mut forged = honest.clone();
+        forged.registry_volumes = Some(*VolumeRegistry::from_slice(&[0, 20]).volumes());
+        assert!(!satisfied(forged));
+
+        // The table carries the registry root bound into the signal hash
+        assert_eq!(honest.registry_root, Some(compute_registry_hash(&registry)));
+    }
+
+    #[test]
+    fn test_transfer_with_distinct_registries() {
+        // A transfer is a withdraw from the source plus a deposit into the
+        // destination, each bound to its own registry_root.
+        let src_registry = VolumeRegistry::from_slice(&[0, 5]);
+        let dst_registry = VolumeRegistry::from_slice(&[0, 20]);
+
+        let mut src_tree = SparseMerkleTree::from_items(&[(1, 30)], DEFAULT_DEPTH);
+        let withdraw = transition_with_registry(
+            &mut src_tree, 1, 10, OpType::Withdraw, &src_registry, 150, 1000,
+        );
+
+        // Destination holds 50 volume; 10 units at 20 each fill it to exactly 250
+        let mut dst_tree = SparseMerkleTree::from_items(&[(2, 50)], DEFAULT_DEPTH);
+        let deposit = transition_with_registry(
+            &mut dst_tree, 1, 10, OpType::Deposit, &dst_registry, 50, 250,
+        );
+
+        assert_ne!(withdraw.registry_root, deposit.registry_root);
+        assert_eq!(deposit.new_volume, Some(250));
+
+        for circuit in [withdraw, deposit] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_transfer_destination_capacity_uses_destination_registry() {
+        // Under the source registry (5 per unit) the deposit would only reach 100,
+        // but the destination prices the item at 20 per unit.
+        let dst_registry = VolumeRegistry::from_slice(&[0, 20]);
+
+        let mut dst_tree = SparseMerkleTree::from_items(&[(2, 50)], DEFAULT_DEPTH);
+        let deposit = transition_with_registry(
+            &mut dst_tree, 1, 10, OpType::Deposit, &dst_registry, 50, 200,
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        deposit.generate_constraints(cs.clone()).unwrap();
+
+        // 250 > 200: destination capacity exceeded
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_withdraw_reasserts_capacity() {
+        // Start over a lowered cap of 800: 100 units at volume 10
+        let registry = VolumeRegistry::from_slice(&[0, 10]);
+        let satisfied = |amount| {
+            let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+            let circuit =
+                transition_with_registry(&mut tree, 1, amount, OpType::Withdraw, &registry, 1000, 800);
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            cs.is_satisfied().unwrap()
+        };
+
+        // Withdrawing 30 lands at 700, under the cap
+        assert!(satisfied(30));
+        // Withdrawing 10 still leaves 900 > 800
+        assert!(!satisfied(10));
+    }
+
+    #[test]
+    fn test_deposit_under_u64_max_capacity() {
+        let registry = VolumeRegistry::from_slice(&[0, 10]);
+
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let circuit =
+            transition_with_registry(&mut tree, 1, 50, OpType::Deposit, &registry, 1000, u64::MAX);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_deposit_within_delta_cap() {
+        let registry = VolumeRegistry::from_slice(&[0, 10]);
+
+        // 10 units at 10 each add exactly 100
+        let mut tree = SparseMerkleTree::from_items(&[(1, 50)], DEFAULT_DEPTH);
+        let deposit = transition_with_registry(
+            &mut tree, 1, 10, OpType::Deposit, &registry, 500, 1000,
+        );
+        let uncapped_signal = deposit.signal_hash;
+        let capped = deposit.with_max_delta(100);
+
+        // The cap is bound into the signal hash
+        assert_ne!(capped.signal_hash, uncapped_signal);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        capped.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // Withdrawals are not limited by the cap
+        let mut tree = SparseMerkleTree::from_items(&[(1, 50)], DEFAULT_DEPTH);
+        let withdraw = transition_with_registry(
+            &mut tree, 1, 50, OpType::Withdraw, &registry, 500, 1000,
+        )
+        .with_max_delta(0);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        withdraw.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_deposit_exceeding_delta_cap_rejected() {
+        let registry = VolumeRegistry::from_slice(&[0, 10]);
+
+        // 11 units at 10 each add 110 > 100, well within capacity
+        let mut tree = SparseMerkleTree::from_items(&[(1, 50)], DEFAULT_DEPTH);
+        let deposit = transition_with_registry(
+            &mut tree, 1, 11, OpType::Deposit, &registry, 500, 1000,
+        )
+        .with_max_delta(100);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        deposit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
     #[test]
     fn test_underflow_attack_blocked() {
         // This test verifies that the range check prevents underflow attacks
@@ -662,4 +1150,93 @@ mod tests {
 
         assert!(!cs.is_satisfied().unwrap(), "Range check should reject wrapped negative value");
     }
+
+    /// Synthesize a withdrawal of 50 units of item 1 (volume 10 each) from
+    /// 100 held, with freely chosen volume witnesses.
+    fn withdraw_step_satisfied(old_volume: Fr, new_volume: Fr) -> bool {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+        let proof = tree.get_proof(1);
+        tree.update(1, 50);
+
+        let registry = VolumeRegistry::from_slice(&[0, 10]);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let witness = |value: Fr| FpVar::new_witness(cs.clone(), || Ok(value)).unwrap();
+        let step = TransitionStepVars {
+            old_root: witness(old_root),
+            new_root: witness(tree.root()),
+            old_volume: witness(old_volume),
+            new_volume: witness(new_volume),
+            item_id: witness(Fr::from(1u64)),
+            old_quantity: witness(Fr::from(100u64)),
+            new_quantity: witness(Fr::from(50u64)),
+            amount: witness(Fr::from(50u64)),
+            op_type: witness(Fr::from(1u64)),
+            item_volume: witness(Fr::from(10u64)),
+            registry_root: witness(compute_registry_hash(&registry)),
+            registry_volumes: alloc_volumes_var(cs.clone(), Some(registry.volumes())).unwrap(),
+            proof: MerkleProofVar::new_witness(cs.clone(), &proof).unwrap(),
+        };
+        let max_capacity = witness(Fr::from(u32::MAX as u64));
+
+        let _ = enforce_transition_step(cs.clone(), &step, &max_capacity).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_volume_underflow_rejected() {
+        // Honest withdrawal: 1000 - 500
+        assert!(withdraw_step_satisfied(Fr::from(1000u64), Fr::from(500u64)));
+
+        // Withdrawing 500 volume from 200, with the wrapped field value as new_volume
+        let wrapped = Fr::from(200u64) - Fr::from(500u64);
+        assert!(!withdraw_step_satisfied(Fr::from(200u64), wrapped));
+    }
+
+    #[test]
+    fn test_configurable_depth() {
+        let registry = VolumeRegistry::from_slice(&[0, 10]);
+
+        for depth in [8, 16] {
+            let mut tree = SparseMerkleTree::from_items(&[(1, 100)], depth);
+            let circuit = transition_with_registry(
+                &mut tree,
+                1,
+                50,
+                OpType::Deposit,
+                &registry,
+                1000,
+                10000,
+            )
+            .with_depth(depth);
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            assert!(cs.is_satisfied().unwrap(), "depth {} transition rejected", depth);
+        }
+    }
+
+    #[test]
+    fn test_proof_depth_mismatch_rejected() {
+        let registry = VolumeRegistry::from_slice(&[0, 10]);
+
+        // A depth-8 proof in a circuit expecting DEFAULT_DEPTH
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], 8);
+        let circuit = transition_with_registry(
+            &mut tree,
+            1,
+            50,
+            OpType::Deposit,
+            &registry,
+            1000,
+            10000,
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        assert!(matches!(
+            circuit.generate_constraints(cs),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
 }
@@ -0,0 +1,336 @@
+//! Subset Capacity Circuit for compartment limits.
+//!
+//! Proves that the volume of a chosen subset of item types (e.g. a "heavy
+//! items" compartment) is within `subset_capacity`. Items outside the subset
+//! are unconstrained.
+//!
+//! The subset is a bitmask over the registry: bit i selects item_id i, for
+//! i < MAX_ITEM_TYPES. For every selected item the circuit opens its leaf
+//! (quantity 0 for absent items) and prices it with the registry volumes.
+//!
+//! Public input: Poseidon(commitment, registry_root, mask, subset_capacity)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_domain, poseidon_hash_domain_var, Domain};
+use crate::range_check::{enforce_u32_range, enforce_within_capacity};
+use crate::smt::{
+    compute_default_leaf_hash, compute_root_from_path, hash_leaf, MerkleProof, MerkleProofVar,
+    SparseMerkleTree,
+};
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+use crate::volume_registry::{
+    compute_registry_hash, compute_registry_hash_var, VolumeRegistry, MAX_ITEM_TYPES,
+};
+
+/// Compute the public input hash for a subset capacity proof.
+pub fn compute_subset_capacity_hash(
+    commitment: Fr,
+    registry_root: Fr,
+    mask: u16,
+    subset_capacity: u64,
+) -> Fr {
+    poseidon_hash_domain(Domain::SubsetCapacity, &[
+        commitment,
+        registry_root,
+        Fr::from(mask),
+        Fr::from(subset_capacity),
+    ])
+}
+
+/// Subset Capacity Circuit.
+///
+/// Proves sum(quantity_i * volume_i for i in mask) <= subset_capacity.
+#[derive(Clone)]
+pub struct SubsetCapacityCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Commitment components (witnesses)
+    /// Inventory SMT root
+    pub inventory_root: Option<Fr>,
+    /// Current volume
+    pub current_volume: Option<u64>,
+    /// Blinding factor
+    pub blinding: Option<Fr>,
+
+    // Subset parameters (witnesses, bound through the public hash)
+    /// Volume table hashed into the registry root
+    pub volumes: Option<[u64; MAX_ITEM_TYPES]>,
+    /// Bit i selects item_id i
+    pub mask: Option<u16>,
+    /// Maximum volume of the selected items
+    pub subset_capacity: Option<u64>,
+
+    // Leaf openings, one per item type
+    /// Quantity of each item type
+    pub quantities: Option<[u64; MAX_ITEM_TYPES]>,
+    /// Proof for each item type in the SMT
+    pub proofs: Option<Vec<MerkleProof<Fr>>>,
+}
+
+impl SubsetCapacityCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses an empty tree with an empty subset, so the dummy witnesses
+    /// produce a valid constraint structure and also satisfy it.
+    pub fn empty() -> Self {
+        use crate::smt::DEFAULT_DEPTH;
+
+        let tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        let registry = VolumeRegistry::new([0; MAX_ITEM_TYPES]);
+
+        Self::from_tree(&tree, 0, Fr::from(0u64), &registry, 0, 0)
+    }
+
+    /// Create a circuit opening every item type of `tree`.
+    pub fn from_tree(
+        tree: &SparseMerkleTree,
+        current_volume: u64,
+        blinding: Fr,
+        registry: &VolumeRegistry,
+        mask: u16,
+        subset_capacity: u64,
+    ) -> Self {
+        let quantities = std::array::from_fn(|i| tree.get(i as u64));
+        let proofs = (0..MAX_ITEM_TYPES as u64).map(|i| tree.get_proof(i)).collect();
+
+        Self::new(
+            tree.root(),
+            current_volume,
+            blinding,
+            *registry.volumes(),
+            mask,
+            subset_capacity,
+            quantities,
+            proofs,
+        )
+    }
+
+    /// Create a new circuit with witnesses.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        inventory_root: Fr,
+        current_volume: u64,
+        blinding: Fr,
+        volumes: [u64; MAX_ITEM_TYPES],
+        mask: u16,
+        subset_capacity: u64,
+        quantities: [u64; MAX_ITEM_TYPES],
+        proofs: Vec<MerkleProof<Fr>>,
+    ) -> Self {
+        let commitment = create_smt_commitment(inventory_root, current_volume, blinding);
+        let registry_root = compute_registry_hash(&VolumeRegistry::new(volumes));
+        let public_hash =
+            compute_subset_capacity_hash(commitment, registry_root, mask, subset_capacity);
+
+        Self {
+            public_hash: Some(public_hash),
+            inventory_root: Some(inventory_root),
+            current_volume: Some(current_volume),
+            blinding: Some(blinding),
+            volumes: Some(volumes),
+            mask: Some(mask),
+            subset_capacity: Some(subset_capacity),
+            quantities: Some(quantities),
+            proofs: Some(proofs),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for SubsetCapacityCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate commitment witnesses ===
+        let root_var = FpVar::new_witness(cs.clone(), || {
+            self.inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let volume_var = FpVar::new_witness(cs.clone(), || {
+            self.current_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate subset parameters ===
+        let volume_vars = (0..MAX_ITEM_TYPES)
+            .map(|i| {
+                FpVar::new_witness(cs.clone(), || {
+                    self.volumes
+                        .map(|volumes| Fr::from(volumes[i]))
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mask_bits = (0..MAX_ITEM_TYPES)
+            .map(|i| {
+                Boolean::new_witness(cs.clone(), || {
+                    self.mask
+                        .map(|mask| (mask >> i) & 1 == 1)
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mask_var = Boolean::le_bits_to_fp_var(&mask_bits)?;
+        let subset_capacity_var = FpVar::new_witness(cs.clone(), || {
+            self.subset_capacity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Constraint 1: Sum the volume of the selected items ===
+        let proofs = self.proofs.as_ref().unwrap();
+        let default_leaf_hash_var = FpVar::constant(compute_default_leaf_hash());
+        let mut subset_volume = FpVar::zero();
+
+        for (i, (selected, item_volume)) in mask_bits.iter().zip(&volume_vars).enumerate() {
+            let item_id_var = FpVar::constant(Fr::from(i as u64));
+            let quantity_var = FpVar::new_witness(cs.clone(), || {
+                self.quantities
+                    .map(|quantities| Fr::from(quantities[i]))
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            let proof_var = MerkleProofVar::new_witness(cs.clone(), &proofs[i])?;
+
+            // Absent items open to the default leaf
+            let is_empty = quantity_var.is_eq(&FpVar::zero())?;
+            let regular_leaf_hash = hash_leaf(cs.clone(), &item_id_var, &quantity_var)?;
+            let leaf_hash = is_empty.select(&default_leaf_hash_var, &regular_leaf_hash)?;
+
+            // Selected items must open at their own position in the committed tree
+            let computed_root = compute_root_from_path(cs.clone(), &leaf_hash, &proof_var)?;
+            computed_root.conditional_enforce_equal(&root_var, selected)?;
+            let position = Boolean::le_bits_to_fp_var(proof_var.indices())?;
+            position.conditional_enforce_equal(&item_id_var, selected)?;
+
+            // Range checks keep the sum from wrapping around the field
+            let selected_quantity = selected.select(&quantity_var, &FpVar::zero())?;
+            enforce_u32_range(cs.clone(), &selected_quantity)?;
+            enforce_u32_range(cs.clone(), item_volume)?;
+
+            subset_volume += &selected_quantity * item_volume;
+        }
+
+        // === Constraint 2: subset_volume <= subset_capacity ===
+        enforce_within_capacity(cs.clone(), &subset_capacity_var, &subset_volume)?;
+
+        // === Constraint 3: Compute commitment and registry root ===
+        let commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &root_var,
+            &volume_var,
+            &blinding_var,
+        )?;
+        let registry_root_var = compute_registry_hash_var(cs.clone(), &volume_vars)?;
+
+        // === Constraint 4: Verify public hash ===
+        let computed_hash = poseidon_hash_domain_var(
+            cs,
+            Domain::SubsetCapacity,
+            &[commitment_var, registry_root_var, mask_var, subset_capacity_var],
+        )?;
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capacity_smt::CapacitySMTCircuit;
+    use crate::smt::DEFAULT_DEPTH;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Item 1 is heavy (50 per unit), item 2 is light (1 per unit)
+    fn registry() -> VolumeRegistry {
+        VolumeRegistry::from_slice(&[0, 50, 1])
+    }
+
+    fn subset_circuit(
+        tree: &SparseMerkleTree,
+        mask: u16,
+        subset_capacity: u64,
+    ) -> SubsetCapacityCircuit {
+        let volume = registry().calculate_used_volume(tree).unwrap();
+        SubsetCapacityCircuit::from_tree(
+            tree,
+            volume,
+            Fr::from(111u64),
+            &registry(),
+            mask,
+            subset_capacity,
+        )
+    }
+
+    fn is_satisfied(circuit: impl ConstraintSynthesizer<Fr>) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_empty_circuit_satisfied() {
+        assert!(is_satisfied(SubsetCapacityCircuit::empty()));
+    }
+
+    #[test]
+    fn test_subset_within_capacity() {
+        let tree = SparseMerkleTree::from_items(&[(1, 10), (2, 100)], DEFAULT_DEPTH);
+        let heavy = 1 << 1;
+
+        // Heavy items use 500 of a 500 compartment
+        let circuit = subset_circuit(&tree, heavy, 500);
+        assert!(is_satisfied(circuit));
+
+        // The compartment limit may use the full u64 range
+        assert!(is_satisfied(subset_circuit(&tree, heavy, u64::MAX)));
+    }
+
+    #[test]
+    fn test_subset_exceeds_capacity_while_total_fits() {
+        let tree = SparseMerkleTree::from_items(&[(1, 10), (2, 100)], DEFAULT_DEPTH);
+        let volume = registry().calculate_used_volume(&tree).unwrap();
+        assert_eq!(volume, 600);
+
+        // The whole inventory fits in 1000...
+        assert!(is_satisfied(CapacitySMTCircuit::new(
+            tree.root(),
+            volume,
+            Fr::from(111u64),
+            1000,
+        )));
+
+        // ...but the heavy compartment (500) exceeds its 400 limit
+        let heavy = 1 << 1;
+        let circuit = subset_circuit(&tree, heavy, 400);
+        assert!(!is_satisfied(circuit));
+
+        // The light items alone are unconstrained by the heavy limit
+        let light = 1 << 2;
+        let circuit = subset_circuit(&tree, light, 400);
+        assert!(is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_understated_subset_quantity_rejected() {
+        let tree = SparseMerkleTree::from_items(&[(1, 10), (2, 100)], DEFAULT_DEPTH);
+        let heavy = 1 << 1;
+
+        // Claim only 8 heavy items so the subset seems to fit in 400
+        let mut circuit = subset_circuit(&tree, heavy, 400);
+        let mut quantities = circuit.quantities.unwrap();
+        quantities[1] = 8;
+        circuit.quantities = Some(quantities);
+
+        assert!(!is_satisfied(circuit));
+    }
+}
@@ -0,0 +1,525 @@
+//! Swap Circuit: atomically exchange item A for item B within one inventory.
+//!
+//! Proves old_root -> mid_root -> new_root, where the first step withdraws
+//! `amount_a` of item A and the second deposits `amount_b` of item B. Both
+//! steps are full state transitions (SMT update, quantity and volume change,
+//! capacity check); the intermediate state is never committed.
+//!
+//! Public inputs (in order):
+//! - signal_hash: Poseidon(old_commitment, new_commitment, registry_root,
+//!   max_capacity, item_a, amount_a, item_b, amount_b, nonce, inventory_id)
+//! - nonce: Current inventory nonce (replay protection)
+//! - inventory_id: Inventory object ID (cross-inventory protection)
+//! - registry_root: Volume registry commitment shared by both items
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::double_transition::TransitionStep;
+use crate::poseidon::{poseidon_hash_domain, poseidon_hash_domain_var, Domain};
+use crate::signal::OpType;
+use crate::smt::MerkleProofVar;
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+use crate::state_transition::{enforce_transition_step, TransitionStepVars};
+use crate::volume_registry::{
+    alloc_volumes_var, compute_registry_hash, VolumeRegistry, MAX_ITEM_TYPES,
+};
+
+/// Compute the signal hash of a swap.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_swap_signal_hash(
+    old_commitment: Fr,
+    new_commitment: Fr,
+    registry_root: Fr,
+    max_capacity: u64,
+    item_a: u64,
+    amount_a: u64,
+    item_b: u64,
+    amount_b: u64,
+    nonce: u64,
+    inventory_id: Fr,
+) -> Fr {
+    poseidon_hash_domain(Domain::Swap, &[
+        old_commitment,
+        new_commitment,
+        registry_root,
+        Fr::from(max_capacity),
+        Fr::from(item_a),
+        Fr::from(amount_a),
+        Fr::from(item_b),
+        Fr::from(amount_b),
+        Fr::from(nonce),
+        inventory_id,
+    ])
+}
+
+/// Swap Circuit.
+///
+/// Proves a withdrawal of item A followed by a deposit of a different item B.
+#[derive(Clone)]
+pub struct SwapCircuit {
+    // Public inputs
+    /// Signal hash binding both commitments and both legs of the swap
+    pub signal_hash: Option<Fr>,
+    /// Current inventory nonce
+    pub nonce: Option<u64>,
+    /// Inventory object ID
+    pub inventory_id: Option<Fr>,
+    /// Registry root (commitment to volume table)
+    pub registry_root: Option<Fr>,
+
+    // Witnesses
+    /// Maximum allowed capacity, enforced after each step
+    pub max_capacity: Option<u64>,
+    /// Full volume table; must hash to `registry_root` and hold both legs'
+    /// `item_volume` at their `item_id`
+    pub registry_volumes: Option<[u64; MAX_ITEM_TYPES]>,
+    /// Old inventory SMT root
+    pub old_inventory_root: Option<Fr>,
+    /// Old total volume
+    pub old_volume: Option<u64>,
+    /// Old blinding factor
+    pub old_blinding: Option<Fr>,
+    /// Inventory SMT root after withdrawing item A (never committed)
+    pub mid_inventory_root: Option<Fr>,
+    /// Total volume after withdrawing item A
+    pub mid_volume: Option<u64>,
+    /// New inventory SMT root
+    pub new_inventory_root: Option<Fr>,
+    /// New total volume
+    pub new_volume: Option<u64>,
+    /// New blinding factor
+    pub new_blinding: Option<Fr>,
+    /// Withdrawal of item A, proven against the old root
+    pub give: Option<TransitionStep>,
+    /// Deposit of item B, proven against the intermediate root
+    pub take: Option<TransitionStep>,
+}
+
+impl SwapCircuit {
+    /// Create an empty circuit for setup.
+    /// Swaps nothing of item 0 for nothing of item 1 in an empty tree, so the
+    /// dummy witnesses produce a valid constraint structure and also satisfy it.
+    pub fn empty() -> Self {
+        use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+
+        let tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        let step = |item_id, op_type| TransitionStep {
+            item_id,
+            old_quantity: 0,
+            new_quantity: 0,
+            amount: 0,
+            op_type,
+            inventory_proof: tree.get_proof(item_id),
+            item_volume: 0,
+        };
+
+        Self::new(
+            tree.root(),
+            0,
+            Fr::from(0u64),
+            Fr::from(0u64),
+            step(0, OpType::Withdraw),
+            step(1, OpType::Deposit),
+            &VolumeRegistry::new([0; MAX_ITEM_TYPES]),
+            0,
+            0,
+            Fr::from(0u64),
+        )
+    }
+
+    /// Create a new circuit from the old state and the two legs of the swap.
+    ///
+    /// The registry root is taken from `registry`.
+    /// `give` must be proven against the old root and `take` against the root
+    /// after `give`; the intermediate and new roots and volumes are derived
+    /// from the steps. A volume that underflows or does not fit in a u64
+    /// leaves it and the signal hash unset, so synthesis fails with
+    /// `AssignmentMissing`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        old_inventory_root: Fr,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_blinding: Fr,
+        give: TransitionStep,
+        take: TransitionStep,
+        registry: &VolumeRegistry,
+        max_capacity: u64,
+        nonce: u64,
+        inventory_id: Fr,
+    ) -> Self {
+        let mid_inventory_root = give.inventory_proof.compute_root(give.item_id, give.new_quantity);
+        let mid_volume = give
+            .amount
+            .checked_mul(give.item_volume)
+            .and_then(|delta| old_volume.checked_sub(delta));
+        let new_inventory_root = take.inventory_proof.compute_root(take.item_id, take.new_quantity);
+        let new_volume = mid_volume.and_then(|mid_volume| {
+            mid_volume.checked_add(take.amount.checked_mul(take.item_volume)?)
+        });
+
+        let registry_root = compute_registry_hash(registry);
+        let old_commitment = create_smt_commitment(old_inventory_root, old_volume, old_blinding);
+        let signal_hash = new_volume.map(|new_volume| {
+            compute_swap_signal_hash(
+                old_commitment,
+                create_smt_commitment(new_inventory_root, new_volume, new_blinding),
+                registry_root,
+                max_capacity,
+                give.item_id,
+                give.amount,
+                take.item_id,
+                take.amount,
+                nonce,
+                inventory_id,
+            )
+        });
+
+        Self {
+            signal_hash,
+            nonce: Some(nonce),
+            inventory_id: Some(inventory_id),
+            registry_root: Some(registry_root),
+            max_capacity: Some(max_capacity),
+            registry_volumes: Some(*registry.volumes()),
+            old_inventory_root: Some(old_inventory_root),
+            old_volume: Some(old_volume),
+            old_blinding: Some(old_blinding),
+            mid_inventory_root: Some(mid_inventory_root),
+            mid_volume,
+            new_inventory_root: Some(new_inventory_root),
+            new_volume,
+            new_blinding: Some(new_blinding),
+            give: Some(give),
+            take: Some(take),
+        }
+    }
+}
+
+/// Allocate the witnesses of one leg; `op_type` is fixed by the circuit.
+#[allow(clippy::too_many_arguments)]
+fn alloc_leg(
+    cs: ConstraintSystemRef<Fr>,
+    step: Option<&TransitionStep>,
+    op_type: OpType,
+    old_root: &FpVar<Fr>,
+    new_root: &FpVar<Fr>,
+    old_volume: &FpVar<Fr>,
+    new_volume: &FpVar<Fr>,
+    registry_root: &FpVar<Fr>,
+    registry_volumes: &[FpVar<Fr>],
+) -> Result<TransitionStepVars, SynthesisError> {
+    let witness = |value: Option<u64>| {
+        FpVar::new_witness(cs.clone(), || {
+            value.map(Fr::from).ok_or(SynthesisError::AssignmentMissing)
+        })
+    };
+
+    Ok(TransitionStepVars {
+        old_root: old_root.clone(),
+        new_root: new_root.clone(),
+        old_volume: old_volume.clone(),
+        new_volume: new_volume.clone(),
+        item_id: witness(step.map(|s| s.item_id))?,
+        old_quantity: witness(step.map(|s| s.old_quantity))?,
+        new_quantity: witness(step.map(|s| s.new_quantity))?,
+        amount: witness(step.map(|s| s.amount))?,
+        op_type: FpVar::constant(op_type.to_field()),
+        item_volume: witness(step.map(|s| s.item_volume))?,
+        registry_root: registry_root.clone(),
+        registry_volumes: registry_volumes.to_vec(),
+        proof: MerkleProofVar::new_witness(cs.clone(), &step.unwrap().inventory_proof)?,
+    })
+}
+
+impl ConstraintSynthesizer<Fr> for SwapCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public inputs ===
+        // Order matters: signal_hash, nonce, inventory_id, registry_root
+        let signal_hash_var = FpVar::new_input(cs.clone(), || {
+            self.signal_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let nonce_var = FpVar::new_input(cs.clone(), || {
+            self.nonce
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let inventory_id_var = FpVar::new_input(cs.clone(), || {
+            self.inventory_id.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let registry_root_var = FpVar::new_input(cs.clone(), || {
+            self.registry_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate state witnesses ===
+        let max_capacity_var = FpVar::new_witness(cs.clone(), || {
+            self.max_capacity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let old_root_var = FpVar::new_witness(cs.clone(), || {
+            self.old_inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let old_volume_var = FpVar::new_witness(cs.clone(), || {
+            self.old_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let old_blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.old_blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let mid_root_var = FpVar::new_witness(cs.clone(), || {
+            self.mid_inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let mid_volume_var = FpVar::new_witness(cs.clone(), || {
+            self.mid_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let new_root_var = FpVar::new_witness(cs.clone(), || {
+            self.new_inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let new_volume_var = FpVar::new_witness(cs.clone(), || {
+            self.new_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let new_blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.new_blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let registry_volume_vars = alloc_volumes_var(cs.clone(), self.registry_volumes.as_ref())?;
+
+        // === Constraint 1: Withdraw amount_a of item A ===
+        let give = alloc_leg(
+            cs.clone(),
+            self.give.as_ref(),
+            OpType::Withdraw,
+            &old_root_var,
+            &mid_root_var,
+            &old_volume_var,
+            &mid_volume_var,
+            &registry_root_var,
+            &registry_volume_vars,
+        )?;
+        let _ = enforce_transition_step(cs.clone(), &give, &max_capacity_var)?;
+
+        // === Constraint 2: Deposit amount_b of item B ===
+        let take = alloc_leg(
+            cs.clone(),
+            self.take.as_ref(),
+            OpType::Deposit,
+            &mid_root_var,
+            &new_root_var,
+            &mid_volume_var,
+            &new_volume_var,
+            &registry_root_var,
+            &registry_volume_vars,
+        )?;
+        let _ = enforce_transition_step(cs.clone(), &take, &max_capacity_var)?;
+
+        // === Constraint 3: A swap exchanges two different items ===
+        give.item_id
+            .is_eq(&take.item_id)?
+            .enforce_equal(&Boolean::FALSE)?;
+
+        // === Constraint 4: Commitments to the old and new states ===
+        let old_commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &old_root_var,
+            &old_volume_var,
+            &old_blinding_var,
+        )?;
+        let new_commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &new_root_var,
+            &new_volume_var,
+            &new_blinding_var,
+        )?;
+
+        // === Constraint 5: Signal hash binds both legs ===
+        let computed_signal = poseidon_hash_domain_var(
+            cs,
+            Domain::Swap,
+            &[
+                old_commitment_var,
+                new_commitment_var,
+                registry_root_var,
+                max_capacity_var,
+                give.item_id,
+                give.amount,
+                take.item_id,
+                take.amount,
+                nonce_var,
+                inventory_id_var,
+            ],
+        )?;
+        computed_signal.enforce_equal(&signal_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Build the two legs of a swap against `tree`, updating it.
+    fn legs(
+        tree: &mut SparseMerkleTree,
+        (item_a, amount_a, volume_a): (u64, u64, u64),
+        (item_b, amount_b, volume_b): (u64, u64, u64),
+    ) -> (TransitionStep, TransitionStep) {
+        let old_a = tree.get(item_a);
+        let give = TransitionStep {
+            item_id: item_a,
+            old_quantity: old_a,
+            new_quantity: old_a.saturating_sub(amount_a),
+            amount: amount_a,
+            op_type: OpType::Withdraw,
+            inventory_proof: tree.get_proof(item_a),
+            item_volume: volume_a,
+        };
+        tree.update(item_a, give.new_quantity);
+
+        let old_b = tree.get(item_b);
+        let take = TransitionStep {
+            item_id: item_b,
+            old_quantity: old_b,
+            new_quantity: old_b + amount_b,
+            amount: amount_b,
+            op_type: OpType::Deposit,
+            inventory_proof: tree.get_proof(item_b),
+            item_volume: volume_b,
+        };
+        tree.update(item_b, take.new_quantity);
+
+        (give, take)
+    }
+
+    fn swap(give: TransitionStep, take: TransitionStep, old_root: Fr) -> SwapCircuit {
+        SwapCircuit::new(
+            old_root,
+            1000,
+            Fr::from(111u64),
+            Fr::from(222u64),
+            give,
+            take,
+            &VolumeRegistry::from_entries(&[(1, 10), (2, 5)]),
+            1200,
+            0,
+            Fr::from(12345u64),
+        )
+    }
+
+    fn is_satisfied(circuit: SwapCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_empty_circuit_satisfied() {
+        assert!(is_satisfied(SwapCircuit::empty()));
+    }
+
+    #[test]
+    fn test_valid_swap() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100), (2, 5)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+
+        // Trade 30 of item 1 (volume 10) for 40 of item 2 (volume 5)
+        let (give, take) = legs(&mut tree, (1, 30, 10), (2, 40, 5));
+        let circuit = swap(give, take, old_root);
+
+        assert_eq!(circuit.new_inventory_root, Some(tree.root()));
+        assert_eq!(circuit.new_volume, Some(900));
+        assert!(is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_insufficient_item_a_rejected() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 10)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+
+        let (give, take) = legs(&mut tree, (1, 30, 10), (2, 40, 5));
+        assert!(!is_satisfied(swap(give, take, old_root)));
+    }
+
+    #[test]
+    fn test_tampered_amount_b_rejected() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+
+        let (give, take) = legs(&mut tree.clone(), (1, 30, 10), (2, 40, 5));
+        let claimed = swap(give, take, old_root);
+
+        // Consistent witnesses for 50 of item 2 under the signal hash for 40
+        let (give, take) = legs(&mut tree, (1, 30, 10), (2, 50, 5));
+        let mut circuit = swap(give, take, old_root);
+        circuit.signal_hash = claimed.signal_hash;
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_volume_overflow_unprovable() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+
+        // 1000 - 300 + u64::MAX / 5 * 5 overflows u64
+        let (give, take) = legs(&mut tree, (1, 30, 10), (2, u64::MAX / 5, 5));
+        let circuit = swap(give, take, old_root);
+        assert_eq!(circuit.mid_volume, Some(700));
+        assert_eq!(circuit.new_volume, None);
+        assert_eq!(circuit.signal_hash, None);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        assert!(circuit.generate_constraints(cs).is_err());
+    }
+
+    #[test]
+    fn test_volume_underflow_unprovable() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 200)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+
+        // 150 * 10 = 1500 > 1000
+        let (give, take) = legs(&mut tree, (1, 150, 10), (2, 40, 5));
+        let circuit = swap(give, take, old_root);
+        assert_eq!(circuit.mid_volume, None);
+        assert_eq!(circuit.signal_hash, None);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        assert!(circuit.generate_constraints(cs).is_err());
+    }
+
+    #[test]
+    fn test_same_item_rejected() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+
+        let (give, take) = legs(&mut tree, (1, 30, 10), (1, 30, 10));
+        assert!(!is_satisfied(swap(give, take, old_root)));
+    }
+
+    #[test]
+    fn test_leg_volume_must_match_registry() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+
+        // Item 2 claimed at volume 1 instead of its registered 5
+        let (give, take) = legs(&mut tree, (1, 30, 10), (2, 40, 1));
+        let circuit = swap(give, take, old_root);
+
+        assert_eq!(circuit.new_volume, Some(740));
+        assert!(!is_satisfied(circuit));
+    }
+}
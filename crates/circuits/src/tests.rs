@@ -10,6 +10,7 @@ use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
 use crate::state_transition::StateTransitionCircuit;
 use crate::item_exists_smt::ItemExistsSMTCircuit;
 use crate::capacity_smt::CapacitySMTCircuit;
+use crate::volume_registry::{compute_registry_hash, VolumeRegistry};
 
 /// Test full Groth16 proof generation and verification for StateTransitionCircuit (deposit)
 #[test]
@@ -37,7 +38,8 @@ fn test_state_transition_deposit_full_proof() {
     let item_volume = 10u64;
     let old_volume = 100 * item_volume;
     let new_volume = 150 * item_volume;
-    let registry_root = Fr::from(99999u64);
+    let registry = VolumeRegistry::from_entries(&[(1, item_volume)]);
+    let registry_root = compute_registry_hash(&registry);
     let max_capacity = 10000u64;
     let nonce = 0u64;
     let inventory_id = Fr::from(12345678u64);
@@ -56,8 +58,7 @@ fn test_state_transition_deposit_full_proof() {
         50,  // amount
         OpType::Deposit,
         proof,
-        item_volume,
-        registry_root,
+        &registry,
         max_capacity,
         nonce,
         inventory_id,
@@ -100,7 +101,8 @@ fn test_state_transition_withdraw_full_proof() {
     let item_volume = 10u64;
     let old_volume = 100 * item_volume;
     let new_volume = 70 * item_volume;
-    let registry_root = Fr::from(99999u64);
+    let registry = VolumeRegistry::from_entries(&[(1, item_volume)]);
+    let registry_root = compute_registry_hash(&registry);
     let max_capacity = 10000u64;
     let nonce = 5u64;
     let inventory_id = Fr::from(12345678u64);
@@ -118,8 +120,7 @@ fn test_state_transition_withdraw_full_proof() {
         30,
         OpType::Withdraw,
         proof,
-        item_volume,
-        registry_root,
+        &registry,
         max_capacity,
         nonce,
         inventory_id,
@@ -135,6 +136,145 @@ fn test_state_transition_withdraw_full_proof() {
     assert!(valid, "StateTransition withdraw proof verification failed");
 }
 
+/// Regression: deposit, withdraw to zero, then re-deposit the same item.
+///
+/// The emptied slot must hold the default leaf so the re-deposit verifies via
+/// the insertion branch of verify_and_update.
+#[test]
+fn test_redeposit_after_withdraw_to_zero_full_proof() {
+    let mut rng = thread_rng();
+
+    let empty_circuit = StateTransitionCircuit::empty();
+    let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(empty_circuit, &mut rng).unwrap();
+
+    let item_volume = 10u64;
+    let registry = VolumeRegistry::from_entries(&[(1, item_volume)]);
+    let registry_root = compute_registry_hash(&registry);
+    let max_capacity = 10000u64;
+    let inventory_id = Fr::from(12345678u64);
+
+    let mut tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+    let empty_root = tree.root();
+
+    // (old_qty, new_qty, amount, op_type) for each step
+    let steps = [
+        (0, 40, 40, OpType::Deposit),
+        (40, 0, 40, OpType::Withdraw),
+        (0, 25, 25, OpType::Deposit),
+    ];
+
+    for (nonce, &(old_qty, new_qty, amount, op_type)) in steps.iter().enumerate() {
+        let old_root = tree.root();
+        let proof = tree.get_proof(1);
+        tree.update(1, new_qty);
+        let new_root = tree.root();
+
+        if new_qty == 0 {
+            assert_eq!(new_root, empty_root, "emptied slot must restore the empty root");
+        }
+
+        let circuit = StateTransitionCircuit::new(
+            old_root,
+            old_qty * item_volume,
+            Fr::from(nonce as u64 + 1),
+            new_root,
+            new_qty * item_volume,
+            Fr::from(nonce as u64 + 2),
+            1,
+            old_qty,
+            new_qty,
+            amount,
+            op_type,
+            proof,
+            &registry,
+            max_capacity,
+            nonce as u64,
+            inventory_id,
+        );
+
+        let signal_hash = circuit.signal_hash.unwrap();
+        let groth_proof = Groth16::<Bn254>::prove(&pk, circuit, &mut rng).unwrap();
+
+        let public_inputs = vec![signal_hash, Fr::from(nonce as u64), inventory_id, registry_root];
+        let valid = Groth16::<Bn254>::verify(&vk, &public_inputs, &groth_proof).unwrap();
+        assert!(valid, "step {} proof verification failed", nonce);
+    }
+}
+
+/// Replaying an accepted proof is detectable once the on-chain nonce advances.
+///
+/// The nonce is a public input and is folded into the signal hash, so the old
+/// proof neither verifies at the next nonce nor with a re-derived signal hash.
+#[test]
+fn test_state_transition_replay_rejected() {
+    use crate::signal::SignalInputs;
+    use crate::smt_commitment::create_smt_commitment;
+
+    let mut rng = thread_rng();
+
+    let empty_circuit = StateTransitionCircuit::empty();
+    let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(empty_circuit, &mut rng).unwrap();
+
+    let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+    let old_root = tree.root();
+    let proof = tree.get_proof(1);
+    tree.update(1, 150);
+    let new_root = tree.root();
+
+    let item_volume = 10u64;
+    let registry = VolumeRegistry::from_entries(&[(1, item_volume)]);
+    let registry_root = compute_registry_hash(&registry);
+    let (old_blinding, new_blinding) = (Fr::from(12345u64), Fr::from(67890u64));
+    let nonce = 5u64;
+    let inventory_id = Fr::from(12345678u64);
+
+    let circuit = StateTransitionCircuit::new(
+        old_root,
+        100 * item_volume,
+        old_blinding,
+        new_root,
+        150 * item_volume,
+        new_blinding,
+        1,
+        100,
+        150,
+        50,
+        OpType::Deposit,
+        proof,
+        &registry,
+        10000,
+        nonce,
+        inventory_id,
+    );
+    let signal_hash = circuit.signal_hash.unwrap();
+    let groth_proof = Groth16::<Bn254>::prove(&pk, circuit, &mut rng).unwrap();
+
+    // Accepted at the current nonce
+    let accepted = vec![signal_hash, Fr::from(nonce), inventory_id, registry_root];
+    assert!(Groth16::<Bn254>::verify(&vk, &accepted, &groth_proof).unwrap());
+
+    // Replayed after the nonce advanced
+    let replayed = vec![signal_hash, Fr::from(nonce + 1), inventory_id, registry_root];
+    assert!(!Groth16::<Bn254>::verify(&vk, &replayed, &groth_proof).unwrap());
+
+    // Re-deriving the signal hash for the new nonce does not help either
+    let resigned = SignalInputs {
+        old_commitment: create_smt_commitment(old_root, 100 * item_volume, old_blinding),
+        new_commitment: create_smt_commitment(new_root, 150 * item_volume, new_blinding),
+        registry_root,
+        max_capacity: 10000,
+        item_id: 1,
+        amount: 50,
+        op_type: OpType::Deposit,
+        nonce: nonce + 1,
+        inventory_id,
+    }
+    .compute_hash();
+    assert_ne!(resigned, signal_hash);
+    let resigned_inputs = vec![resigned, Fr::from(nonce + 1), inventory_id, registry_root];
+    assert!(!Groth16::<Bn254>::verify(&vk, &resigned_inputs, &groth_proof).unwrap());
+}
+
 /// Test full Groth16 proof for ItemExistsSMTCircuit
 #[test]
 fn test_item_exists_smt_full_proof() {
@@ -175,6 +315,54 @@ fn test_item_exists_smt_full_proof() {
     assert!(valid, "ItemExists SMT proof verification failed");
 }
 
+/// Test full Groth16 proofs for StateTransitionCircuit at non-default depths
+#[test]
+fn test_state_transition_configurable_depth_full_proof() {
+    let mut rng = thread_rng();
+
+    for depth in [8, 16] {
+        // Keys are specific to the depth
+        let empty_circuit = StateTransitionCircuit::empty_with_depth(depth);
+        let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(empty_circuit, &mut rng).unwrap();
+
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], depth);
+        let old_root = tree.root();
+        let proof = tree.get_proof(1);
+        tree.update(1, 150);
+
+        let registry = VolumeRegistry::from_entries(&[(1, 10)]);
+        let registry_root = compute_registry_hash(&registry);
+        let inventory_id = Fr::from(12345678u64);
+
+        let circuit = StateTransitionCircuit::new(
+            old_root,
+            1000,
+            Fr::from(12345u64),
+            tree.root(),
+            1500,
+            Fr::from(67890u64),
+            1,   // item_id
+            100, // old_quantity
+            150, // new_quantity
+            50,  // amount
+            OpType::Deposit,
+            proof,
+            &registry,
+            10000, // max_capacity
+            0,     // nonce
+            inventory_id,
+        )
+        .with_depth(depth);
+
+        let signal_hash = circuit.signal_hash.unwrap();
+        let groth_proof = Groth16::<Bn254>::prove(&pk, circuit, &mut rng).unwrap();
+
+        let public_inputs = vec![signal_hash, Fr::from(0u64), inventory_id, registry_root];
+        let valid = Groth16::<Bn254>::verify(&vk, &public_inputs, &groth_proof).unwrap();
+        assert!(valid, "StateTransition proof at depth {} failed", depth);
+    }
+}
+
 /// Test full Groth16 proof for CapacitySMTCircuit
 #[test]
 fn test_capacity_smt_full_proof() {
@@ -0,0 +1,659 @@
+//! Transfer Circuit: atomically move items from one inventory to another.
+//!
+//! Proves a withdrawal of `amount` units of `item_id` from the source and a
+//! deposit of the same units into the destination. Both legs are full state
+//! transitions (SMT update, quantity and volume change); the destination is
+//! checked against its capacity.
+//!
+//! Each inventory prices the item with its own volume registry: the
+//! withdrawal frees the source registry's volume and the deposit takes the
+//! destination registry's. Inventories sharing a registry pass the same root
+//! twice.
+//!
+//! Public inputs (in order):
+//! - signal_hash: Poseidon(src_old_commitment, src_new_commitment,
+//!   dst_old_commitment, dst_new_commitment, src_registry_root,
+//!   dst_registry_root, max_capacity, item_id, amount, src_nonce,
+//!   src_inventory_id, dst_nonce, dst_inventory_id)
+//! - src_nonce: Source inventory nonce (replay protection)
+//! - src_inventory_id: Source inventory object ID
+//! - dst_nonce: Destination inventory nonce (replay protection)
+//! - dst_inventory_id: Destination inventory object ID
+//! - src_registry_root: Volume registry commitment of the source
+//! - dst_registry_root: Volume registry commitment of the destination
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_domain, poseidon_hash_domain_var, Domain};
+use crate::signal::OpType;
+use crate::smt::{MerkleProof, MerkleProofVar};
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+use crate::state_transition::{enforce_transition_step, TransitionStepVars};
+use crate::volume_registry::{
+    alloc_volumes_var, compute_registry_hash, VolumeRegistry, MAX_ITEM_TYPES,
+};
+
+/// Compute the signal hash of a transfer.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_transfer_signal_hash(
+    src_old_commitment: Fr,
+    src_new_commitment: Fr,
+    dst_old_commitment: Fr,
+    dst_new_commitment: Fr,
+    src_registry_root: Fr,
+    dst_registry_root: Fr,
+    max_capacity: u64,
+    item_id: u64,
+    amount: u64,
+    src_nonce: u64,
+    src_inventory_id: Fr,
+    dst_nonce: u64,
+    dst_inventory_id: Fr,
+) -> Fr {
+    poseidon_hash_domain(Domain::Transfer, &[
+        src_old_commitment,
+        src_new_commitment,
+        dst_old_commitment,
+        dst_new_commitment,
+        src_registry_root,
+        dst_registry_root,
+        Fr::from(max_capacity),
+        Fr::from(item_id),
+        Fr::from(amount),
+        Fr::from(src_nonce),
+        src_inventory_id,
+        Fr::from(dst_nonce),
+        dst_inventory_id,
+    ])
+}
+
+/// Witnesses for one inventory in a `TransferSMTCircuit`.
+#[derive(Clone)]
+pub struct TransferSide {
+    /// SMT root before the transfer
+    pub old_root: Fr,
+    /// Total volume before the transfer
+    pub old_volume: u64,
+    /// Blinding factor of the old commitment
+    pub old_blinding: Fr,
+    /// Blinding factor of the new commitment
+    pub new_blinding: Fr,
+    /// Quantity of the transferred item before the transfer
+    pub old_quantity: u64,
+    /// Proof for the transferred item against `old_root`
+    pub proof: MerkleProof<Fr>,
+    /// Current inventory nonce
+    pub nonce: u64,
+    /// Inventory object ID
+    pub inventory_id: Fr,
+}
+
+/// Transfer Circuit.
+///
+/// Proves `amount` of `item_id` leaves the source and arrives at the destination.
+#[derive(Clone)]
+pub struct TransferSMTCircuit {
+    // Public inputs
+    /// Signal hash binding all four commitments and the transfer
+    pub signal_hash: Option<Fr>,
+    /// Source registry root (commitment to its volume table)
+    pub src_registry_root: Option<Fr>,
+    /// Destination registry root (commitment to its volume table)
+    pub dst_registry_root: Option<Fr>,
+
+    // Witnesses
+    /// Destination capacity limit
+    pub max_capacity: Option<u64>,
+    /// Item ID being transferred
+    pub item_id: Option<u64>,
+    /// Units transferred
+    pub amount: Option<u64>,
+    /// Volume per unit of the item in the source registry
+    pub src_item_volume: Option<u64>,
+    /// Volume per unit of the item in the destination registry
+    pub dst_item_volume: Option<u64>,
+    /// Full source volume table; must hash to `src_registry_root` and hold
+    /// `src_item_volume` at `item_id`
+    pub src_registry_volumes: Option<[u64; MAX_ITEM_TYPES]>,
+    /// Full destination volume table; must hash to `dst_registry_root` and
+    /// hold `dst_item_volume` at `item_id`
+    pub dst_registry_volumes: Option<[u64; MAX_ITEM_TYPES]>,
+    /// Source inventory before the transfer
+    pub source: Option<TransferSide>,
+    /// Destination inventory before the transfer
+    pub destination: Option<TransferSide>,
+    /// Source SMT root after the withdrawal
+    pub src_new_root: Option<Fr>,
+    /// Source volume after the withdrawal
+    pub src_new_volume: Option<u64>,
+    /// Destination SMT root after the deposit
+    pub dst_new_root: Option<Fr>,
+    /// Destination volume after the deposit
+    pub dst_new_volume: Option<u64>,
+}
+
+impl TransferSMTCircuit {
+    /// Create an empty circuit for setup.
+    /// Transfers nothing of item 0 between two empty trees, so the dummy
+    /// witnesses produce a valid constraint structure and also satisfy it.
+    pub fn empty() -> Self {
+        use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+
+        let tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        let side = |inventory_id: u64| TransferSide {
+            old_root: tree.root(),
+            old_volume: 0,
+            old_blinding: Fr::from(0u64),
+            new_blinding: Fr::from(0u64),
+            old_quantity: 0,
+            proof: tree.get_proof(0),
+            nonce: 0,
+            inventory_id: Fr::from(inventory_id),
+        };
+
+        let registry = VolumeRegistry::new([0; MAX_ITEM_TYPES]);
+        Self::new(side(0), side(1), 0, 0, &registry, &registry, 0)
+    }
+
+    /// Create a new circuit from both inventories and the transfer.
+    ///
+    /// The withdrawal is priced with `src_registry` and the deposit with
+    /// `dst_registry`; pass the same registry twice when both inventories
+    /// share one. New roots and volumes are derived from the sides; a withdrawal larger
+    /// than the source quantity yields witnesses the constraints reject. A
+    /// destination quantity or volume that does not fit in a u64, or a source
+    /// volume that underflows, leaves the affected witnesses and the signal
+    /// hash unset, so synthesis fails with `AssignmentMissing`.
+    pub fn new(
+        source: TransferSide,
+        destination: TransferSide,
+        item_id: u64,
+        amount: u64,
+        src_registry: &VolumeRegistry,
+        dst_registry: &VolumeRegistry,
+        max_capacity: u64,
+    ) -> Self {
+        let src_item_volume = src_registry.get_volume(item_id);
+        let dst_item_volume = dst_registry.get_volume(item_id);
+        let src_registry_root = compute_registry_hash(src_registry);
+        let dst_registry_root = compute_registry_hash(dst_registry);
+
+        let src_new_root = source
+            .proof
+            .compute_root(item_id, source.old_quantity.saturating_sub(amount));
+        let src_new_volume = amount
+            .checked_mul(src_item_volume)
+            .and_then(|delta| source.old_volume.checked_sub(delta));
+        let dst_new_root = destination
+            .old_quantity
+            .checked_add(amount)
+            .map(|quantity| destination.proof.compute_root(item_id, quantity));
+        let dst_new_volume = amount
+            .checked_mul(dst_item_volume)
+            .and_then(|delta| destination.old_volume.checked_add(delta));
+
+        let dst_new = dst_new_root.zip(dst_new_volume);
+        let signal_hash = src_new_volume.zip(dst_new).map(|(src_volume, (dst_root, dst_volume))| {
+            compute_transfer_signal_hash(
+                create_smt_commitment(source.old_root, source.old_volume, source.old_blinding),
+                create_smt_commitment(src_new_root, src_volume, source.new_blinding),
+                create_smt_commitment(
+                    destination.old_root,
+                    destination.old_volume,
+                    destination.old_blinding,
+                ),
+                create_smt_commitment(dst_root, dst_volume, destination.new_blinding),
+                src_registry_root,
+                dst_registry_root,
+                max_capacity,
+                item_id,
+                amount,
+                source.nonce,
+                source.inventory_id,
+                destination.nonce,
+                destination.inventory_id,
+            )
+        });
+
+        Self {
+            signal_hash,
+            src_registry_root: Some(src_registry_root),
+            dst_registry_root: Some(dst_registry_root),
+            max_capacity: Some(max_capacity),
+            item_id: Some(item_id),
+            amount: Some(amount),
+            src_item_volume: Some(src_item_volume),
+            dst_item_volume: Some(dst_item_volume),
+            src_registry_volumes: Some(*src_registry.volumes()),
+            dst_registry_volumes: Some(*dst_registry.volumes()),
+            source: Some(source),
+            destination: Some(destination),
+            src_new_root: Some(src_new_root),
+            src_new_volume,
+            dst_new_root,
+            dst_new_volume,
+        }
+    }
+}
+
+/// Allocated witnesses of one side, before and after the transfer.
+struct SideVars {
+    old_root: FpVar<Fr>,
+    old_volume: FpVar<Fr>,
+    old_blinding: FpVar<Fr>,
+    new_root: FpVar<Fr>,
+    new_volume: FpVar<Fr>,
+    new_blinding: FpVar<Fr>,
+    old_quantity: FpVar<Fr>,
+    proof: MerkleProofVar,
+}
+
+/// Allocate the witnesses of one side.
+fn alloc_side(
+    cs: ConstraintSystemRef<Fr>,
+    side: Option<&TransferSide>,
+    new_root: Option<Fr>,
+    new_volume: Option<u64>,
+) -> Result<SideVars, SynthesisError> {
+    let witness = |value: Option<Fr>| {
+        FpVar::new_witness(cs.clone(), || value.ok_or(SynthesisError::AssignmentMissing))
+    };
+
+    Ok(SideVars {
+        old_root: witness(side.map(|s| s.old_root))?,
+        old_volume: witness(side.map(|s| Fr::from(s.old_volume)))?,
+        old_blinding: witness(side.map(|s| s.old_blinding))?,
+        new_root: witness(new_root)?,
+        new_volume: witness(new_volume.map(Fr::from))?,
+        new_blinding: witness(side.map(|s| s.new_blinding))?,
+        old_quantity: witness(side.map(|s| Fr::from(s.old_quantity)))?,
+        proof: MerkleProofVar::new_witness(cs.clone(), &side.unwrap().proof)?,
+    })
+}
+
+impl ConstraintSynthesizer<Fr> for TransferSMTCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public inputs ===
+        // Order matters: signal_hash, src_nonce, src_inventory_id, dst_nonce,
+        // dst_inventory_id, src_registry_root, dst_registry_root
+        let signal_hash_var = FpVar::new_input(cs.clone(), || {
+            self.signal_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let src_nonce_var = FpVar::new_input(cs.clone(), || {
+            self.source
+                .as_ref()
+                .map(|s| Fr::from(s.nonce))
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let src_inventory_id_var = FpVar::new_input(cs.clone(), || {
+            self.source
+                .as_ref()
+                .map(|s| s.inventory_id)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let dst_nonce_var = FpVar::new_input(cs.clone(), || {
+            self.destination
+                .as_ref()
+                .map(|s| Fr::from(s.nonce))
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let dst_inventory_id_var = FpVar::new_input(cs.clone(), || {
+            self.destination
+                .as_ref()
+                .map(|s| s.inventory_id)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let src_registry_root_var = FpVar::new_input(cs.clone(), || {
+            self.src_registry_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let dst_registry_root_var = FpVar::new_input(cs.clone(), || {
+            self.dst_registry_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate transfer witnesses ===
+        let witness = |value: Option<u64>| {
+            FpVar::new_witness(cs.clone(), || {
+                value.map(Fr::from).ok_or(SynthesisError::AssignmentMissing)
+            })
+        };
+        let max_capacity_var = witness(self.max_capacity)?;
+        let item_id_var = witness(self.item_id)?;
+        let amount_var = witness(self.amount)?;
+        let src_item_volume_var = witness(self.src_item_volume)?;
+        let dst_item_volume_var = witness(self.dst_item_volume)?;
+        let src_registry_volume_vars =
+            alloc_volumes_var(cs.clone(), self.src_registry_volumes.as_ref())?;
+        let dst_registry_volume_vars =
+            alloc_volumes_var(cs.clone(), self.dst_registry_volumes.as_ref())?;
+
+        let src = alloc_side(
+            cs.clone(),
+            self.source.as_ref(),
+            self.src_new_root,
+            self.src_new_volume,
+        )?;
+        let dst = alloc_side(
+            cs.clone(),
+            self.destination.as_ref(),
+            self.dst_new_root,
+            self.dst_new_volume,
+        )?;
+
+        // === Constraint 1: Withdraw from the source, priced by its registry ===
+        // Same item and amount as the deposit. A withdrawal never grows the
+        // volume, so the source's old volume serves as its capacity.
+        let withdraw = TransitionStepVars {
+            old_root: src.old_root.clone(),
+            new_root: src.new_root.clone(),
+            old_volume: src.old_volume.clone(),
+            new_volume: src.new_volume.clone(),
+            item_id: item_id_var.clone(),
+            old_quantity: src.old_quantity.clone(),
+            new_quantity: &src.old_quantity - &amount_var,
+            amount: amount_var.clone(),
+            op_type: FpVar::constant(OpType::Withdraw.to_field()),
+            item_volume: src_item_volume_var,
+            registry_root: src_registry_root_var.clone(),
+            registry_volumes: src_registry_volume_vars,
+            proof: src.proof.clone(),
+        };
+        let _ = enforce_transition_step(cs.clone(), &withdraw, &src.old_volume)?;
+
+        // === Constraint 2: Deposit into the destination, priced by its registry,
+        // within its capacity ===
+        let deposit = TransitionStepVars {
+            old_root: dst.old_root.clone(),
+            new_root: dst.new_root.clone(),
+            old_volume: dst.old_volume.clone(),
+            new_volume: dst.new_volume.clone(),
+            item_id: item_id_var.clone(),
+            old_quantity: dst.old_quantity.clone(),
+            new_quantity: &dst.old_quantity + &amount_var,
+            amount: amount_var.clone(),
+            op_type: FpVar::constant(OpType::Deposit.to_field()),
+            item_volume: dst_item_volume_var,
+            registry_root: dst_registry_root_var.clone(),
+            registry_volumes: dst_registry_volume_vars,
+            proof: dst.proof.clone(),
+        };
+        let _ = enforce_transition_step(cs.clone(), &deposit, &max_capacity_var)?;
+
+        // === Constraint 3: Source and destination are different inventories ===
+        src_inventory_id_var
+            .is_eq(&dst_inventory_id_var)?
+            .enforce_equal(&Boolean::FALSE)?;
+
+        // === Constraint 4: Commitments to all four states ===
+        let mut commitments = Vec::with_capacity(4);
+        for side in [&src, &dst] {
+            commitments.push(create_smt_commitment_var(
+                cs.clone(),
+                &side.old_root,
+                &side.old_volume,
+                &side.old_blinding,
+            )?);
+            commitments.push(create_smt_commitment_var(
+                cs.clone(),
+                &side.new_root,
+                &side.new_volume,
+                &side.new_blinding,
+            )?);
+        }
+
+        // === Constraint 5: Signal hash binds both inventories and the transfer ===
+        commitments.extend([
+            src_registry_root_var,
+            dst_registry_root_var,
+            max_capacity_var,
+            item_id_var,
+            amount_var,
+            src_nonce_var,
+            src_inventory_id_var,
+            dst_nonce_var,
+            dst_inventory_id_var,
+        ]);
+        let computed_signal = poseidon_hash_domain_var(cs, Domain::Transfer, &commitments)?;
+        computed_signal.enforce_equal(&signal_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Items 1 and 2 both have volume 10 per unit.
+    fn registry() -> VolumeRegistry {
+        VolumeRegistry::from_entries(&[(1, 10), (2, 10)])
+    }
+
+    /// One side of a transfer of item 1 against `tree`.
+    fn side(tree: &SparseMerkleTree, volume: u64, inventory_id: u64) -> TransferSide {
+        TransferSide {
+            old_root: tree.root(),
+            old_volume: volume,
+            old_blinding: Fr::from(100 + inventory_id),
+            new_blinding: Fr::from(200 + inventory_id),
+            old_quantity: tree.get(1),
+            proof: tree.get_proof(1),
+            nonce: 3,
+            inventory_id: Fr::from(inventory_id),
+        }
+    }
+
+    fn transfer(amount: u64, max_capacity: u64) -> TransferSMTCircuit {
+        transfer_from(550, amount, max_capacity)
+    }
+
+    /// Transfer out of a source whose total volume is recorded as `src_volume`.
+    fn transfer_from(src_volume: u64, amount: u64, max_capacity: u64) -> TransferSMTCircuit {
+        let src = SparseMerkleTree::from_items(&[(1, 50), (2, 5)], DEFAULT_DEPTH);
+        let dst = SparseMerkleTree::from_items(&[(1, 10)], DEFAULT_DEPTH);
+
+        TransferSMTCircuit::new(
+            side(&src, src_volume, 1),
+            side(&dst, 100, 2),
+            1,
+            amount,
+            &registry(),
+            &registry(),
+            max_capacity,
+        )
+    }
+
+    /// Recompute the signal hash from the circuit's current witnesses.
+    fn resign(circuit: &mut TransferSMTCircuit) {
+        let source = circuit.source.clone().unwrap();
+        let destination = circuit.destination.clone().unwrap();
+        circuit.signal_hash = Some(compute_transfer_signal_hash(
+            create_smt_commitment(source.old_root, source.old_volume, source.old_blinding),
+            create_smt_commitment(
+                circuit.src_new_root.unwrap(),
+                circuit.src_new_volume.unwrap(),
+                source.new_blinding,
+            ),
+            create_smt_commitment(
+                destination.old_root,
+                destination.old_volume,
+                destination.old_blinding,
+            ),
+            create_smt_commitment(
+                circuit.dst_new_root.unwrap(),
+                circuit.dst_new_volume.unwrap(),
+                destination.new_blinding,
+            ),
+            circuit.src_registry_root.unwrap(),
+            circuit.dst_registry_root.unwrap(),
+            circuit.max_capacity.unwrap(),
+            circuit.item_id.unwrap(),
+            circuit.amount.unwrap(),
+            source.nonce,
+            source.inventory_id,
+            destination.nonce,
+            destination.inventory_id,
+        ));
+    }
+
+    fn is_satisfied(circuit: TransferSMTCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_empty_circuit_satisfied() {
+        assert!(is_satisfied(TransferSMTCircuit::empty()));
+    }
+
+    #[test]
+    fn test_valid_transfer() {
+        let circuit = transfer(30, 1000);
+
+        let mut src = SparseMerkleTree::from_items(&[(1, 50), (2, 5)], DEFAULT_DEPTH);
+        src.update(1, 20);
+        let mut dst = SparseMerkleTree::from_items(&[(1, 10)], DEFAULT_DEPTH);
+        dst.update(1, 40);
+        assert_eq!(circuit.src_new_root, Some(src.root()));
+        assert_eq!(circuit.dst_new_root, Some(dst.root()));
+        assert_eq!(circuit.src_new_volume, Some(250));
+        assert_eq!(circuit.dst_new_volume, Some(400));
+
+        assert!(is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_insufficient_source_rejected() {
+        // Enough volume recorded for 60 units, but only 50 held
+        assert!(!is_satisfied(transfer_from(600, 60, 1000)));
+    }
+
+    #[test]
+    fn test_source_volume_underflow_unprovable() {
+        // 60 * 10 = 600 > 550
+        let circuit = transfer(60, 1000);
+        assert_eq!(circuit.src_new_volume, None);
+        assert_eq!(circuit.signal_hash, None);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        assert!(circuit.generate_constraints(cs).is_err());
+    }
+
+    #[test]
+    fn test_destination_over_capacity_rejected() {
+        // 100 + 30 * 10 = 400 fits exactly; one unit less of headroom does not
+        assert!(is_satisfied(transfer(30, 400)));
+        assert!(!is_satisfied(transfer(30, 399)));
+    }
+
+    #[test]
+    fn test_volume_overflow_unprovable() {
+        let tree = SparseMerkleTree::from_items(&[(1, 50)], DEFAULT_DEPTH);
+        let circuit = TransferSMTCircuit::new(
+            side(&tree, 500, 1),
+            side(&tree, u64::MAX - 10, 2),
+            1,
+            2,
+            &registry(),
+            &registry(),
+            u64::MAX,
+        );
+        assert_eq!(circuit.dst_new_volume, None);
+        assert_eq!(circuit.signal_hash, None);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        assert!(circuit.generate_constraints(cs).is_err());
+    }
+
+    #[test]
+    fn test_quantity_overflow_unprovable() {
+        let tree = SparseMerkleTree::from_items(&[(1, 50)], DEFAULT_DEPTH);
+        let mut destination = side(&tree, 100, 2);
+        destination.old_quantity = u64::MAX;
+        let circuit = TransferSMTCircuit::new(
+            side(&tree, 500, 1),
+            destination,
+            1,
+            1,
+            &registry(),
+            &registry(),
+            1000,
+        );
+        assert_eq!(circuit.dst_new_root, None);
+        assert_eq!(circuit.signal_hash, None);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        assert!(circuit.generate_constraints(cs).is_err());
+    }
+
+    #[test]
+    fn test_same_inventory_rejected() {
+        let tree = SparseMerkleTree::from_items(&[(1, 50)], DEFAULT_DEPTH);
+        let circuit = TransferSMTCircuit::new(
+            side(&tree, 500, 1),
+            side(&tree, 500, 1),
+            1,
+            10,
+            &registry(),
+            &registry(),
+            1000,
+        );
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_item_volume_must_match_registry() {
+        // Price item 1 at 1 instead of its registered 10 on both sides, with
+        // new volumes and a signal hash consistent with that claim
+        let mut circuit = transfer(30, 1000);
+        circuit.src_item_volume = Some(1);
+        circuit.dst_item_volume = Some(1);
+        circuit.src_new_volume = Some(520);
+        circuit.dst_new_volume = Some(130);
+        resign(&mut circuit);
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_distinct_registries() {
+        // Item 1 weighs 5 per unit at the source and 20 at the destination
+        let src_registry = VolumeRegistry::from_entries(&[(1, 5)]);
+        let dst_registry = VolumeRegistry::from_entries(&[(1, 20)]);
+        let src = SparseMerkleTree::from_items(&[(1, 50)], DEFAULT_DEPTH);
+        let dst = SparseMerkleTree::from_items(&[(1, 10)], DEFAULT_DEPTH);
+        let with_capacity = |max_capacity: u64| {
+            TransferSMTCircuit::new(
+                side(&src, 250, 1),
+                side(&dst, 200, 2),
+                1,
+                10,
+                &src_registry,
+                &dst_registry,
+                max_capacity,
+            )
+        };
+
+        // The source frees 10 * 5 and the destination takes 10 * 20
+        let circuit = with_capacity(400);
+        assert_ne!(circuit.src_registry_root, circuit.dst_registry_root);
+        assert_eq!(circuit.src_new_volume, Some(200));
+        assert_eq!(circuit.dst_new_volume, Some(400));
+        assert!(is_satisfied(circuit));
+
+        // Priced by the source registry the deposit would reach only 250
+        assert!(!is_satisfied(with_capacity(399)));
+
+        // Pricing the deposit with the source registry's volume is rejected
+        let mut cross_priced = with_capacity(400);
+        cross_priced.dst_item_volume = Some(5);
+        cross_priced.dst_new_volume = Some(250);
+        resign(&mut cross_priced);
+        assert!(!is_satisfied(cross_priced));
+    }
+}
@@ -0,0 +1,552 @@
+//! Volume registry mapping item types to their per-unit volume.
+//!
+//! Mirrors the on-chain `inventory::volume_registry` object: a fixed table of
+//! `MAX_ITEM_TYPES` volumes whose Poseidon hash is the registry root bound
+//! into state transition proofs.
+//!
+//! registry_hash = Poseidon(volumes[0], volumes[1], ..., volumes[MAX_ITEM_TYPES - 1])
+//!
+//! Volumes are always absorbed in ascending item_id order, independent of how
+//! the registry was built. Any other registry representation must hash in the
+//! same order or its root will not match the on-chain registry.
+//!
+//! `VolumeRegistryN<N>` holds a table of any width for games with more item
+//! types; `VolumeRegistry` is the `MAX_ITEM_TYPES` table used by the contract.
+
+use std::fmt;
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_domain, poseidon_hash_domain_var, Domain};
+use crate::smt::SparseMerkleTree;
+
+/// Maximum number of item types (matches the Move contract constant)
+pub const MAX_ITEM_TYPES: usize = 16;
+
+/// A registry that cannot account for an inventory's volume.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    /// A populated item has no (zero) volume, so it would weigh nothing
+    MissingVolume { item_id: u64 },
+    /// The inventory's total volume does not fit in a u64
+    VolumeOverflow,
+    /// An item_id has no slot in the registry table
+    ItemOutOfRange { item_id: u64 },
+    /// Serialized registry bytes are not exactly one u64 per item type
+    InvalidLength { expected: usize, found: usize },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::MissingVolume { item_id } => {
+                write!(f, "no volume registered for item {}", item_id)
+            }
+            RegistryError::VolumeOverflow => write!(f, "total inventory volume exceeds u64"),
+            RegistryError::ItemOutOfRange { item_id } => {
+                write!(f, "item {} has no slot in the registry", item_id)
+            }
+            RegistryError::InvalidLength { expected, found } => {
+                write!(f, "registry bytes must be {} long, got {}", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// Per-unit volumes for `N` item types.
+///
+/// Index i contains the volume for item_id i. Item types outside the
+/// table have volume 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VolumeRegistryN<const N: usize> {
+    volumes: [u64; N],
+}
+
+/// Registry with the contract's `MAX_ITEM_TYPES` item types.
+pub type VolumeRegistry = VolumeRegistryN<MAX_ITEM_TYPES>;
+
+impl<const N: usize> VolumeRegistryN<N> {
+    /// Create a registry from a full volume table.
+    pub fn new(volumes: [u64; N]) -> Self {
+        Self { volumes }
+    }
+
+    /// Create a registry from a slice of volumes.
+    ///
+    /// Missing entries default to 0 and extra entries are ignored.
+    pub fn from_slice(volumes: &[u64]) -> Self {
+        let mut table = [0u64; N];
+        for (slot, &volume) in table.iter_mut().zip(volumes) {
+            *slot = volume;
+        }
+        Self { volumes: table }
+    }
+
+    /// Create a registry from (item_id, volume) entries in any order.
+    ///
+    /// Entries with item_id >= N are ignored. If an item_id appears
+    /// more than once, the last entry wins.
+    pub fn from_entries(entries: &[(u64, u64)]) -> Self {
+        let mut table = [0u64; N];
+        for &(item_id, volume) in entries {
+            if let Some(slot) = usize::try_from(item_id).ok().and_then(|idx| table.get_mut(idx)) {
+                *slot = volume;
+            }
+        }
+        Self { volumes: table }
+    }
+
+    /// Start building a registry whose entries are validated on `build`.
+    pub fn builder() -> RegistryBuilder<N> {
+        RegistryBuilder::default()
+    }
+
+    /// Set the volume for an item, rejecting item_ids outside the table.
+    pub fn try_set(&mut self, item_id: u64, volume: u64) -> Result<(), RegistryError> {
+        let slot = usize::try_from(item_id)
+            .ok()
+            .and_then(|idx| self.volumes.get_mut(idx))
+            .ok_or(RegistryError::ItemOutOfRange { item_id })?;
+        *slot = volume;
+        Ok(())
+    }
+
+    /// Get the volume per unit for an item, or 0 if out of range.
+    pub fn get_volume(&self, item_id: u64) -> u64 {
+        usize::try_from(item_id)
+            .ok()
+            .and_then(|idx| self.volumes.get(idx))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Get the full volume table.
+    pub fn volumes(&self) -> &[u64; N] {
+        &self.volumes
+    }
+
+    /// Calculate the total volume used by all items in a tree.
+    ///
+    /// Fails with `VolumeOverflow` instead of wrapping when a per-item product
+    /// or the total exceeds u64.
+    pub fn calculate_used_volume(&self, tree: &SparseMerkleTree) -> Result<u64, RegistryError> {
+        self.checked_volume(tree.items())
+    }
+
+    /// Volume still free in `tree` under `max_capacity`, for display without a proof.
+    ///
+    /// Returns `None` when the used volume exceeds `max_capacity` (including
+    /// when it overflows u64).
+    pub fn remaining_capacity(&self, tree: &SparseMerkleTree, max_capacity: u64) -> Option<u64> {
+        let used = self.calculate_used_volume(tree).ok()?;
+        max_capacity.checked_sub(used)
+    }
+
+    /// Calculate the volume used by the items selected by `mask` (bit i selects item_id i).
+    ///
+    /// The mask has one bit per item type up to `u16::BITS`. When `N` is
+    /// larger, item_ids at or above `u16::BITS` cannot be selected and never
+    /// count towards the masked volume.
+    pub fn calculate_masked_volume(
+        &self,
+        tree: &SparseMerkleTree,
+        mask: u16,
+    ) -> Result<u64, RegistryError> {
+        self.checked_volume(tree.items().filter(|&(item_id, _)| {
+            item_id < u64::from(u16::BITS) && (mask >> item_id) & 1 == 1
+        }))
+    }
+
+    /// Sum quantity * volume over `items`, rejecting u64 overflow.
+    fn checked_volume(
+        &self,
+        mut items: impl Iterator<Item = (u64, u64)>,
+    ) -> Result<u64, RegistryError> {
+        items
+            .try_fold(0u64, |total, (item_id, quantity)| {
+                total.checked_add(quantity.checked_mul(self.get_volume(item_id))?)
+            })
+            .ok_or(RegistryError::VolumeOverflow)
+    }
+
+    /// Check that every item held in `tree` has a registered volume.
+    ///
+    /// `get_volume` returns 0 for unset entries, which would let those items
+    /// bypass capacity checks entirely. Reports the lowest uncovered item_id.
+    pub fn validate_covers(&self, tree: &SparseMerkleTree) -> Result<(), RegistryError> {
+        let missing = tree
+            .items()
+            .map(|(item_id, _)| item_id)
+            .filter(|&item_id| self.get_volume(item_id) == 0)
+            .min();
+        match missing {
+            Some(item_id) => Err(RegistryError::MissingVolume { item_id }),
+            None => Ok(()),
+        }
+    }
+
+    /// Convert the volume table to field elements for hashing, in ascending item_id order.
+    pub fn to_field_elements(&self) -> Vec<Fr> {
+        self.volumes.iter().map(|&v| Fr::from(v)).collect()
+    }
+
+    /// Serialize the volume table as N little-endian u64s in ascending item_id order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.volumes.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    /// Deserialize a volume table written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RegistryError> {
+        if bytes.len() != N * 8 {
+            return Err(RegistryError::InvalidLength { expected: N * 8, found: bytes.len() });
+        }
+        let mut volumes = [0u64; N];
+        for (volume, chunk) in volumes.iter_mut().zip(bytes.chunks_exact(8)) {
+            *volume = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        Ok(Self { volumes })
+    }
+
+    /// Check that this registry hashes to `expected`, e.g. an on-chain registry root.
+    ///
+    /// A stored registry that fails this check would produce proofs the
+    /// verifier rejects, so clients should call it before proving.
+    pub fn verify_hash(&self, expected: Fr) -> bool {
+        compute_registry_hash(self) == expected
+    }
+}
+
+/// Builder for a `VolumeRegistryN` that rejects out-of-range item_ids.
+///
+/// Unlike `from_entries`, which ignores them, `build` fails on the first
+/// entry with item_id >= N. Later entries for the same item_id win.
+#[derive(Clone, Debug, Default)]
+pub struct RegistryBuilder<const N: usize = MAX_ITEM_TYPES> {
+    entries: Vec<(u64, u64)>,
+}
+
+impl<const N: usize> RegistryBuilder<N> {
+    /// Add the volume for an item.
+    pub fn with(mut self, item_id: u64, volume: u64) -> Self {
+        self.entries.push((item_id, volume));
+        self
+    }
+
+    /// Build the registry, checking every item_id.
+    pub fn build(self) -> Result<VolumeRegistryN<N>, RegistryError> {
+        let mut registry = VolumeRegistryN::new([0; N]);
+        for (item_id, volume) in self.entries {
+            registry.try_set(item_id, volume)?;
+        }
+        Ok(registry)
+    }
+}
+
+/// Compute the registry hash used as `registry_root` in proofs.
+///
+/// Absorbs volumes in ascending item_id order (see module docs).
+pub fn compute_registry_hash<const N: usize>(registry: &VolumeRegistryN<N>) -> Fr {
+    poseidon_hash_domain(Domain::Registry, &registry.to_field_elements())
+}
+
+/// Compute the registry hash in-circuit from volume variables in ascending item_id order.
+pub fn compute_registry_hash_var(
+    cs: ConstraintSystemRef<Fr>,
+    volumes: &[FpVar<Fr>],
+) -> Result<FpVar<Fr>, SynthesisError> {
+    poseidon_hash_domain_var(cs, Domain::Registry, volumes)
+}
+
+/// Look up an item's volume in-circuit from the full volume table.
+///
+/// Enforces that `volumes` (in ascending item_id order) hashes to
+/// `registry_root`, then returns the entry for `item_id`. Item types outside
+/// the table have volume 0, matching `VolumeRegistryN::get_volume`.
+pub fn lookup_volume_var(
+    cs: ConstraintSystemRef<Fr>,
+    registry_root: &FpVar<Fr>,
+    item_id: &FpVar<Fr>,
+    volumes: &[FpVar<Fr>],
+) -> Result<FpVar<Fr>, SynthesisError> {
+    compute_registry_hash_var(cs, volumes)?.enforce_equal(registry_root)?;
+
+    let mut volume = FpVar::zero();
+    for (i, entry) in volumes.iter().enumerate() {
+        let selected = item_id.is_eq(&FpVar::constant(Fr::from(i as u64)))?;
+        volume += selected.select(entry, &FpVar::zero())?;
+    }
+
+    Ok(volume)
+}
+
+/// Allocate a `MAX_ITEM_TYPES` volume table as witnesses for `lookup_volume_var`.
+pub fn alloc_volumes_var(
+    cs: ConstraintSystemRef<Fr>,
+    volumes: Option<&[u64; MAX_ITEM_TYPES]>,
+) -> Result<Vec<FpVar<Fr>>, SynthesisError> {
+    (0..MAX_ITEM_TYPES)
+        .map(|i| {
+            FpVar::new_witness(cs.clone(), || {
+                volumes
+                    .map(|volumes| Fr::from(volumes[i]))
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::DEFAULT_DEPTH;
+
+    #[test]
+    fn test_from_slice_pads_with_zero() {
+        let registry = VolumeRegistry::from_slice(&[0, 5, 10]);
+
+        assert_eq!(registry.get_volume(1), 5);
+        assert_eq!(registry.get_volume(2), 10);
+        assert_eq!(registry.get_volume(3), 0);
+        assert_eq!(registry.get_volume(100), 0);
+    }
+
+    #[test]
+    fn test_lookup_volume_var() {
+        use ark_r1cs_std::alloc::AllocVar;
+        use ark_relations::r1cs::ConstraintSystem;
+
+        let registry = VolumeRegistry::from_slice(&[0, 5, 10]);
+        let lookup = |root: Fr, item_id: u64, expected: u64| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let volumes: Vec<FpVar<Fr>> = registry
+                .volumes()
+                .iter()
+                .map(|&v| FpVar::new_witness(cs.clone(), || Ok(Fr::from(v))).unwrap())
+                .collect();
+            let root = FpVar::new_input(cs.clone(), || Ok(root)).unwrap();
+            let item_id = FpVar::new_witness(cs.clone(), || Ok(Fr::from(item_id))).unwrap();
+
+            let volume = lookup_volume_var(cs.clone(), &root, &item_id, &volumes).unwrap();
+            volume.enforce_equal(&FpVar::constant(Fr::from(expected))).unwrap();
+            cs.is_satisfied().unwrap()
+        };
+
+        let root = compute_registry_hash(&registry);
+        assert!(lookup(root, 2, 10));
+        assert!(!lookup(root, 2, 5));
+        // Out-of-table items have no volume
+        assert!(lookup(root, 100, 0));
+        // The table must match the root
+        assert!(!lookup(Fr::from(99999u64), 2, 10));
+    }
+
+    #[test]
+    fn test_try_set_bounds() {
+        let mut registry = VolumeRegistry::from_slice(&[]);
+
+        assert_eq!(registry.try_set(15, 7), Ok(()));
+        assert_eq!(registry.get_volume(15), 7);
+        assert_eq!(
+            registry.try_set(16, 7),
+            Err(RegistryError::ItemOutOfRange { item_id: 16 })
+        );
+        assert_eq!(
+            registry.try_set(u64::MAX, 7),
+            Err(RegistryError::ItemOutOfRange { item_id: u64::MAX })
+        );
+    }
+
+    #[test]
+    fn test_builder_validates_item_ids() {
+        let registry = VolumeRegistry::builder().with(1, 5).with(15, 9).build().unwrap();
+        assert_eq!(registry, VolumeRegistry::from_entries(&[(1, 5), (15, 9)]));
+
+        let result = VolumeRegistry::builder().with(1, 5).with(16, 9).build();
+        assert_eq!(result, Err(RegistryError::ItemOutOfRange { item_id: 16 }));
+    }
+
+    #[test]
+    fn test_calculate_used_volume() {
+        let registry = VolumeRegistry::from_slice(&[0, 5, 10]);
+        let tree = SparseMerkleTree::from_items(&[(1, 10), (2, 3)], DEFAULT_DEPTH);
+
+        // 10 * 5 + 3 * 10
+        assert_eq!(registry.calculate_used_volume(&tree), Ok(80));
+        assert_eq!(registry.calculate_masked_volume(&tree, 1 << 2), Ok(30));
+    }
+
+    #[test]
+    fn test_masked_volume_wider_registry() {
+        let registry = VolumeRegistryN::<32>::from_entries(&[(15, 2), (16, 3), (20, 7)]);
+        let tree = SparseMerkleTree::from_items(&[(15, 1), (16, 1), (20, 1)], DEFAULT_DEPTH);
+
+        // Items past the mask width are never selected, even with every bit set
+        assert_eq!(registry.calculate_used_volume(&tree), Ok(12));
+        assert_eq!(registry.calculate_masked_volume(&tree, u16::MAX), Ok(2));
+    }
+
+    #[test]
+    fn test_remaining_capacity() {
+        let registry = VolumeRegistry::from_slice(&[0, 5, 10]);
+        let tree = SparseMerkleTree::from_items(&[(1, 10), (2, 3)], DEFAULT_DEPTH);
+
+        // 80 used
+        assert_eq!(registry.remaining_capacity(&tree, 100), Some(20));
+        assert_eq!(registry.remaining_capacity(&tree, 80), Some(0));
+        assert_eq!(registry.remaining_capacity(&tree, 79), None);
+
+        let overflowing = SparseMerkleTree::from_items(&[(2, u64::MAX)], DEFAULT_DEPTH);
+        assert_eq!(registry.remaining_capacity(&overflowing, u64::MAX), None);
+    }
+
+    #[test]
+    fn test_validate_covers() {
+        let registry = VolumeRegistry::from_slice(&[0, 5, 10]);
+
+        let tree = SparseMerkleTree::from_items(&[(1, 10), (2, 3)], DEFAULT_DEPTH);
+        assert_eq!(registry.validate_covers(&tree), Ok(()));
+
+        let tree = SparseMerkleTree::from_items(&[(1, 10), (3, 1)], DEFAULT_DEPTH);
+        assert_eq!(
+            registry.validate_covers(&tree),
+            Err(RegistryError::MissingVolume { item_id: 3 })
+        );
+    }
+
+    #[test]
+    fn test_used_volume_overflow() {
+        let registry = VolumeRegistry::from_slice(&[0, 2, 1, 3]);
+
+        let tree = SparseMerkleTree::from_items(&[(1, u64::MAX / 2 + 1)], DEFAULT_DEPTH);
+        assert_eq!(registry.calculate_used_volume(&tree), Err(RegistryError::VolumeOverflow));
+
+        let tree = SparseMerkleTree::from_items(&[(1, u64::MAX / 2), (2, 2)], DEFAULT_DEPTH);
+        assert_eq!(registry.calculate_used_volume(&tree), Err(RegistryError::VolumeOverflow));
+    }
+
+    #[test]
+    fn test_used_volume_near_u64_max() {
+        let registry = VolumeRegistry::from_slice(&[0, 2, 1, 3]);
+
+        // The largest quantity whose volume still fits
+        let tree = SparseMerkleTree::from_items(&[(3, u64::MAX / 3)], DEFAULT_DEPTH);
+        assert_eq!(registry.calculate_used_volume(&tree), Ok(u64::MAX / 3 * 3));
+
+        // One more unit wraps in u64 arithmetic
+        let tree = SparseMerkleTree::from_items(&[(3, u64::MAX / 3 + 1)], DEFAULT_DEPTH);
+        assert_eq!(registry.calculate_used_volume(&tree), Err(RegistryError::VolumeOverflow));
+        assert_eq!(
+            registry.calculate_masked_volume(&tree, 1 << 3),
+            Err(RegistryError::VolumeOverflow)
+        );
+
+        // Unselected items are not counted
+        assert_eq!(registry.calculate_masked_volume(&tree, 1 << 1), Ok(0));
+    }
+
+    #[test]
+    fn test_wide_registry_in_circuit() {
+        use ark_r1cs_std::fields::fp::FpVar;
+        use ark_r1cs_std::prelude::*;
+        use ark_relations::r1cs::ConstraintSystem;
+
+        let registry = VolumeRegistryN::<32>::builder()
+            .with(3, 5)
+            .with(20, 7)
+            .with(31, 2)
+            .build()
+            .unwrap();
+        assert_eq!(
+            VolumeRegistryN::<32>::builder().with(32, 1).build(),
+            Err(RegistryError::ItemOutOfRange { item_id: 32 })
+        );
+
+        let items = [(3, 10), (20, 4), (31, 1)];
+        let tree = SparseMerkleTree::from_items(&items, DEFAULT_DEPTH);
+        assert_eq!(registry.calculate_used_volume(&tree), Ok(10 * 5 + 4 * 7 + 2));
+
+        // Recompute the registry root and the used volume from witnesses
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let volume_vars = registry
+            .volumes()
+            .iter()
+            .map(|&v| FpVar::new_witness(cs.clone(), || Ok(Fr::from(v))))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let root_var = compute_registry_hash_var(cs.clone(), &volume_vars).unwrap();
+        root_var
+            .enforce_equal(&FpVar::constant(compute_registry_hash(&registry)))
+            .unwrap();
+
+        let mut used_var = FpVar::constant(Fr::from(0u64));
+        for (item_id, quantity) in items {
+            let quantity_var =
+                FpVar::new_witness(cs.clone(), || Ok(Fr::from(quantity))).unwrap();
+            used_var += quantity_var * &volume_vars[item_id as usize];
+        }
+        used_var
+            .enforce_equal(&FpVar::constant(Fr::from(
+                registry.calculate_used_volume(&tree).unwrap(),
+            )))
+            .unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let registry = VolumeRegistry::from_entries(&[(1, 5), (2, 10), (15, u64::MAX)]);
+        let bytes = registry.to_bytes();
+
+        assert_eq!(bytes.len(), MAX_ITEM_TYPES * 8);
+        assert_eq!(bytes[8..16], 5u64.to_le_bytes());
+        let decoded = VolumeRegistry::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, registry);
+        assert!(decoded.verify_hash(compute_registry_hash(&registry)));
+
+        assert_eq!(
+            VolumeRegistry::from_bytes(&bytes[1..]),
+            Err(RegistryError::InvalidLength { expected: 128, found: 127 })
+        );
+    }
+
+    #[test]
+    fn test_verify_hash_mismatch() {
+        let registry = VolumeRegistry::from_slice(&[0, 5, 10]);
+        let other = VolumeRegistry::from_slice(&[0, 5, 11]);
+
+        assert!(registry.verify_hash(compute_registry_hash(&registry)));
+        assert!(!registry.verify_hash(compute_registry_hash(&other)));
+        assert!(!registry.verify_hash(Fr::from(0u64)));
+    }
+
+    #[test]
+    fn test_registry_hash_changes_with_volumes() {
+        let registry1 = VolumeRegistry::from_slice(&[0, 5, 10]);
+        let registry2 = VolumeRegistry::from_slice(&[0, 5, 11]);
+
+        assert_ne!(
+            compute_registry_hash(&registry1),
+            compute_registry_hash(&registry2)
+        );
+    }
+
+    #[test]
+    fn test_registry_hash_independent_of_insertion_order() {
+        let forward = VolumeRegistry::from_entries(&[(1, 5), (2, 10), (7, 3)]);
+        let reverse = VolumeRegistry::from_entries(&[(7, 3), (2, 10), (1, 5)]);
+
+        assert_eq!(forward, reverse);
+        assert_eq!(compute_registry_hash(&forward), compute_registry_hash(&reverse));
+        assert_eq!(
+            compute_registry_hash(&forward),
+            compute_registry_hash(&VolumeRegistry::from_slice(&[0, 5, 10, 0, 0, 0, 0, 3]))
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Server configuration from environment variables.
+//!
+//! - `PROOF_SERVER_WORKER_THREADS`: tokio worker threads handling requests
+//! - `PROOF_SERVER_PROVING_THREADS`: threads in the `ProvingPool` used by arkworks
+//!
+//! - `ALLOWED_ORIGINS`: comma-separated CORS origins allowed to call the API
+//! - `PROOF_SERVER_REQUEST_TIMEOUT_SECS`: seconds before a request is answered with 408
+//! - `PROOF_SERVER_SMT_CACHE_CAPACITY`: inventories whose latest SMT and head are kept in memory
+//!
+//! Thread counts default to the number of available cores
+//! (`available_parallelism`, which respects cgroup CPU quotas on Linux). Any
+//! origin is allowed when `ALLOWED_ORIGINS` is unset or empty. Requests time
+//! out after `DEFAULT_REQUEST_TIMEOUT` and up to `DEFAULT_SMT_CACHE_CAPACITY`
+//! trees are cached unless overridden.
+
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use axum::http::HeaderValue;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tower_http::timeout::TimeoutLayer;
+
+/// Env var for the tokio worker thread count
+pub const WORKER_THREADS_VAR: &str = "PROOF_SERVER_WORKER_THREADS";
+/// Env var for the Rayon proving thread count
+pub const PROVING_THREADS_VAR: &str = "PROOF_SERVER_PROVING_THREADS";
+/// Env var for the CORS origin allowlist
+pub const ALLOWED_ORIGINS_VAR: &str = "ALLOWED_ORIGINS";
+/// Env var for the per-request timeout, in seconds
+pub const REQUEST_TIMEOUT_VAR: &str = "PROOF_SERVER_REQUEST_TIMEOUT_SECS";
+/// Env var for the number of inventory SMTs cached between transitions
+pub const SMT_CACHE_CAPACITY_VAR: &str = "PROOF_SERVER_SMT_CACHE_CAPACITY";
+
+/// Per-request timeout when `REQUEST_TIMEOUT_VAR` is unset
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Cached inventory SMTs when `SMT_CACHE_CAPACITY_VAR` is unset
+pub const DEFAULT_SMT_CACHE_CAPACITY: usize = 1024;
+
+/// Thread pool sizes, CORS policy, request timeout and SMT cache size for the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    pub worker_threads: usize,
+    pub proving_threads: usize,
+    /// Origins allowed by CORS; `None` allows any origin
+    pub allowed_origins: Option<Vec<String>>,
+    /// How long a request may run before the server answers 408
+    pub request_timeout: Duration,
+    /// How many inventories' SMTs and latest heads are kept between transitions
+    pub smt_cache_capacity: usize,
+}
+
+impl ServerConfig {
+    /// Read the configuration from the process environment.
+    pub fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Read the configuration using `lookup` to resolve variables.
+    ///
+    /// Missing, unparsable or zero values fall back to the core count (thread
+    /// counts), `DEFAULT_REQUEST_TIMEOUT` (timeout) or `DEFAULT_SMT_CACHE_CAPACITY`.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let read = |name: &str, default: usize| match lookup(name) {
+            None => default,
+            Some(value) => match value.trim().parse::<NonZeroUsize>() {
+                Ok(n) => n.get(),
+                Err(_) => {
+                    println!("Ignoring invalid {}={:?}, using {}", name, value, default);
+                    default
+                }
+            },
+        };
+        let timeout_secs = read(REQUEST_TIMEOUT_VAR, DEFAULT_REQUEST_TIMEOUT.as_secs() as usize);
+
+        Self {
+            worker_threads: read(WORKER_THREADS_VAR, available_cores()),
+            proving_threads: read(PROVING_THREADS_VAR, available_cores()),
+            allowed_origins: lookup(ALLOWED_ORIGINS_VAR).and_then(|value| parse_origins(&value)),
+            request_timeout: Duration::from_secs(timeout_secs as u64),
+            smt_cache_capacity: read(SMT_CACHE_CAPACITY_VAR, DEFAULT_SMT_CACHE_CAPACITY),
+        }
+    }
+}
+
+/// Split a comma-separated origin list, dropping blank entries.
+///
+/// Returns `None` if no origins remain, so the server falls back to any origin.
+pub fn parse_origins(value: &str) -> Option<Vec<String>> {
+    let origins: Vec<String> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(String::from)
+        .collect();
+    (!origins.is_empty()).then_some(origins)
+}
+
+/// Build the CORS layer, restricted to `origins` if given.
+///
+/// Origins that are not valid header values are skipped.
+pub fn build_cors_layer(origins: Option<Vec<String>>) -> CorsLayer {
+    let allow_origin = match origins {
+        None => AllowOrigin::from(Any),
+        Some(origins) => AllowOrigin::list(origins.iter().filter_map(|origin| {
+            HeaderValue::from_str(origin)
+                .map_err(|_| println!("Ignoring invalid CORS origin {:?}", origin))
+                .ok()
+        })),
+    };
+
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(Any)
+        .allow_headers(Any)
+}
+
+/// Build the layer that answers 408 Request Timeout once `timeout` elapses.
+///
+/// The response is sent and the handler future dropped, which releases any
+/// inventory lock it held. A proof already running on the `ProvingPool`
+/// cannot be interrupted; it finishes and its result is discarded.
+pub fn build_timeout_layer(timeout: Duration) -> TimeoutLayer {
+    TimeoutLayer::new(timeout)
+}
+
+/// Number of cores available to this process.
+pub fn available_cores() -> usize {
+    std::thread::available_parallelism()
+        .map(NonZeroUsize::get)
+        .unwrap_or(1)
+}
+
+/// Build the tokio runtime with the configured worker count.
+pub fn build_runtime(config: &ServerConfig) -> std::io::Result<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(config.worker_threads)
+        .enable_all()
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn test_defaults_to_available_cores() {
+        let config = ServerConfig::from_lookup(lookup(&[]));
+
+        assert_eq!(config.worker_threads, available_cores());
+        assert_eq!(config.proving_threads, available_cores());
+        assert_eq!(config.request_timeout, DEFAULT_REQUEST_TIMEOUT);
+        assert_eq!(config.smt_cache_capacity, DEFAULT_SMT_CACHE_CAPACITY);
+    }
+
+    #[test]
+    fn test_reads_env_and_rejects_invalid() {
+        let config = ServerConfig::from_lookup(lookup(&[
+            (WORKER_THREADS_VAR, "3"),
+            (PROVING_THREADS_VAR, "0"),
+            (REQUEST_TIMEOUT_VAR, "120"),
+            (SMT_CACHE_CAPACITY_VAR, "16"),
+        ]));
+
+        assert_eq!(config.worker_threads, 3);
+        assert_eq!(config.proving_threads, available_cores());
+        assert_eq!(config.request_timeout, Duration::from_secs(120));
+        assert_eq!(config.smt_cache_capacity, 16);
+    }
+
+    #[test]
+    fn test_runtime_honors_worker_threads() {
+        let config = ServerConfig::from_lookup(lookup(&[(WORKER_THREADS_VAR, "3")]));
+        let runtime = build_runtime(&config).unwrap();
+
+        assert_eq!(runtime.metrics().num_workers(), 3);
+    }
+
+    #[test]
+    fn test_parse_origins() {
+        assert_eq!(
+            parse_origins(" https://a.example, https://b.example ,,"),
+            Some(vec!["https://a.example".to_string(), "https://b.example".to_string()])
+        );
+        assert_eq!(parse_origins(""), None);
+        assert_eq!(parse_origins(" , "), None);
+
+        assert_eq!(ServerConfig::from_lookup(lookup(&[])).allowed_origins, None);
+        let config = ServerConfig::from_lookup(lookup(&[(ALLOWED_ORIGINS_VAR, "https://a.example")]));
+        assert_eq!(config.allowed_origins, Some(vec!["https://a.example".to_string()]));
+    }
+
+    /// The Access-Control-Allow-Origin header returned to a request from `origin`.
+    async fn allowed_origin(layer: CorsLayer, origin: &str) -> Option<String> {
+        use axum::{body::Body, http::Request, routing::get, Router};
+        use tower::Service;
+
+        let mut app = Router::new().route("/", get(|| async {})).layer(layer);
+        let request = Request::get("/")
+            .header("origin", origin)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.call(request).await.unwrap();
+        response
+            .headers()
+            .get("access-control-allow-origin")
+            .map(|value| value.to_str().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn test_cors_layer_allowlist() {
+        let any = build_cors_layer(None);
+        assert_eq!(allowed_origin(any, "https://c.example").await.as_deref(), Some("*"));
+
+        let origins = parse_origins("https://a.example,https://b.example");
+        let layer = build_cors_layer(origins.clone());
+        assert_eq!(
+            allowed_origin(layer, "https://b.example").await.as_deref(),
+            Some("https://b.example")
+        );
+        let layer = build_cors_layer(origins);
+        assert_eq!(allowed_origin(layer, "https://c.example").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_slow_request_times_out() {
+        use axum::{body::Body, http::Request, http::StatusCode, routing::get, Router};
+        use tower::Service;
+
+        let slow = || async { tokio::time::sleep(Duration::from_secs(60)).await };
+        let mut app = Router::new()
+            .route("/slow", get(slow))
+            .route("/fast", get(|| async {}))
+            .layer(build_timeout_layer(Duration::from_millis(50)));
+
+        let request = |uri| Request::get(uri).body(Body::empty()).unwrap();
+        let response = tokio::time::timeout(Duration::from_secs(5), app.call(request("/slow")))
+            .await
+            .expect("timed-out request should not hang")
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+
+        let response = app.call(request("/fast")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -1,15 +1,15 @@
 //! HTTP request handlers for SMT-based proof generation.
 
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use ark_bn254::Fr;
 use ark_ff::PrimeField;
 use ark_serialize::CanonicalSerialize;
-use ark_std::rand::Rng;
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
@@ -19,19 +19,47 @@ use inventory_circuits::{
     signal::OpType,
     smt::{SparseMerkleTree, DEFAULT_DEPTH},
     smt_commitment::create_smt_commitment,
+    volume_registry::{compute_registry_hash, RegistryError, VolumeRegistry, MAX_ITEM_TYPES},
+};
+use inventory_prover::{
+    prove, public_input_from_hex, registry_hash_matches, verify_prepared, CircuitKind,
+    InventoryState, ProofWithInputs, ProveError, SetupError, VerifyError,
 };
-use inventory_prover::{prove, InventoryState};
 
+use crate::inventory_locks::InventoryHead;
 use crate::AppState;
 
 /// Health check response
 #[derive(Serialize)]
 pub struct HealthResponse {
     pub status: &'static str,
+    /// Verifying key fingerprint per circuit with loaded keys
+    pub circuits: BTreeMap<&'static str, String>,
 }
 
-pub async fn health() -> Json<HealthResponse> {
-    Json(HealthResponse { status: "ok" })
+/// Report the circuits with loaded keys and their verifying key fingerprints.
+///
+/// Clients can compare fingerprints to confirm the server uses the expected
+/// trusted setup.
+pub async fn health(State(state): State<Arc<RwLock<AppState>>>) -> Response {
+    let keys = state.read().await.keys.clone();
+    let circuits = [
+        ("state_transition", &keys.state_transition),
+        ("item_exists", &keys.item_exists),
+        ("capacity", &keys.capacity),
+    ]
+    .into_iter()
+    .map(|(name, pair)| Ok((name, pair.vk_fingerprint()?)))
+    .collect::<Result<_, SetupError>>();
+
+    match circuits {
+        Ok(circuits) => Json(HealthResponse { status: "ok", circuits }).into_response(),
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::SerializationFailed,
+            e.to_string(),
+        ),
+    }
 }
 
 /// Item in inventory for API requests
@@ -53,24 +81,119 @@ fn parse_inventory_state(items: &[ItemRequest], volume: u64, blinding: Fr) -> In
     }
 }
 
-/// Parse hex string to Fr (little-endian, for blinding factors etc)
-fn parse_fr(hex: &str) -> Result<Fr, String> {
-    let bytes = hex::decode(hex.trim_start_matches("0x"))
-        .map_err(|e| format!("Invalid hex: {}", e))?;
+/// Check that a transition's volumes fit in the u32 range the circuit enforces.
+///
+/// The volume delta (amount * item_volume) and the new total volume are
+/// computed natively before the circuit sees them, so neither may exceed
+/// u32::MAX and a withdrawal must not take more volume than is held.
+fn check_transition_volume(
+    current_volume: u64,
+    amount: u64,
+    item_volume: u64,
+    op_type: OpType,
+) -> Result<(), (ErrorCode, String)> {
+    let max = u64::from(u32::MAX);
+    let delta = amount.checked_mul(item_volume).filter(|&delta| delta <= max).ok_or_else(|| {
+        let error = format!("Volume overflow: amount {} * item_volume {}", amount, item_volume);
+        (ErrorCode::VolumeOverflow, error)
+    })?;
+    let new_volume = match op_type {
+        OpType::Deposit => current_volume.checked_add(delta).ok_or_else(|| {
+            let error = format!("Volume overflow: current_volume {} + {}", current_volume, delta);
+            (ErrorCode::VolumeOverflow, error)
+        })?,
+        OpType::Withdraw => current_volume.checked_sub(delta).ok_or_else(|| {
+            let error = format!("Volume underflow: current_volume {} - {}", current_volume, delta);
+            (ErrorCode::InvalidState, error)
+        })?,
+    };
+    if new_volume > max {
+        let error = format!("Volume overflow: new volume {} exceeds {}", new_volume, max);
+        return Err((ErrorCode::VolumeOverflow, error));
+    }
+    Ok(())
+}
 
-    if bytes.len() != 32 {
-        return Err("Field element must be 32 bytes".to_string());
+/// Error response with its machine-readable code
+fn error_response(status: StatusCode, error_code: ErrorCode, error: String) -> Response {
+    let response = ErrorResponse {
+        error,
+        error_code,
+        shortfall: None,
+    };
+    (status, Json(response)).into_response()
+}
+
+/// Response for undecodable hex fields
+fn invalid_hex(error: String) -> Response {
+    error_response(StatusCode::BAD_REQUEST, ErrorCode::InvalidHex, error)
+}
+
+/// Response for well-formed requests whose values are out of range
+fn unprocessable(error_code: ErrorCode, error: String) -> Response {
+    error_response(StatusCode::UNPROCESSABLE_ENTITY, error_code, error)
+}
+
+/// Response for a registry that cannot account for the request's inventory
+fn registry_error(error: RegistryError) -> Response {
+    unprocessable(registry_error_code(&error), error.to_string())
+}
+
+/// Error code for each way a registry can fail
+fn registry_error_code(error: &RegistryError) -> ErrorCode {
+    match error {
+        RegistryError::MissingVolume { .. } => ErrorCode::MissingVolume,
+        RegistryError::VolumeOverflow => ErrorCode::VolumeOverflow,
+        RegistryError::ItemOutOfRange { .. } => ErrorCode::ItemOutOfRange,
+        RegistryError::InvalidLength { .. } => ErrorCode::InvalidRegistry,
     }
+}
 
-    let mut arr = [0u8; 32];
-    arr.copy_from_slice(&bytes);
+/// Response for a failed proof: the request's fault unless proving itself failed
+fn prove_error(error: ProveError) -> Response {
+    let (status, error_code) = match error {
+        ProveError::InsufficientQuantity { have, need } => {
+            let response = ErrorResponse {
+                error: error.to_string(),
+                error_code: ErrorCode::InsufficientQuantity,
+                shortfall: Some(QuantityShortfall {
+                    available: have,
+                    requested: need,
+                }),
+            };
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(response)).into_response();
+        }
+        ProveError::CapacityExceeded { .. } => {
+            (StatusCode::UNPROCESSABLE_ENTITY, ErrorCode::CapacityExceeded)
+        }
+        ProveError::InvalidState(_) => (StatusCode::BAD_REQUEST, ErrorCode::InvalidState),
+        ProveError::Registry(ref e) => (StatusCode::BAD_REQUEST, registry_error_code(e)),
+        ProveError::ProofGeneration(_) | ProveError::SelfCheckFailed => {
+            (StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::ProofFailed)
+        }
+        ProveError::Serialization(_) => {
+            (StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::SerializationFailed)
+        }
+    };
+    error_response(status, error_code, error.to_string())
+}
 
-    Ok(Fr::from_le_bytes_mod_order(&arr))
+/// Response for transitions superseded by a newer state of the same inventory
+fn conflict(error: String) -> Response {
+    error_response(StatusCode::CONFLICT, ErrorCode::StateConflict, error)
+}
+
+/// Response for a circuit name with no loaded keys
+fn unknown_circuit(circuit: &str) -> Response {
+    error_response(
+        StatusCode::NOT_FOUND,
+        ErrorCode::UnknownCircuit,
+        format!("Unknown circuit: {}", circuit),
+    )
 }
 
-/// Parse hex string to Fr (big-endian, for Sui object IDs)
-/// Sui object IDs are big-endian, so we reverse bytes before interpreting as LE field element
-fn parse_fr_be(hex: &str) -> Result<Fr, String> {
+/// Parse hex string to Fr (little-endian, for blinding factors etc)
+fn parse_fr(hex: &str) -> Result<Fr, String> {
     let bytes = hex::decode(hex.trim_start_matches("0x"))
         .map_err(|e| format!("Invalid hex: {}", e))?;
 
@@ -80,7 +203,6 @@ fn parse_fr_be(hex: &str) -> Result<Fr, String> {
 
     let mut arr = [0u8; 32];
     arr.copy_from_slice(&bytes);
-    arr.reverse(); // Convert BE to LE
 
     Ok(Fr::from_le_bytes_mod_order(&arr))
 }
@@ -92,14 +214,6 @@ fn serialize_fr(f: &Fr) -> String {
     format!("0x{}", hex::encode(bytes))
 }
 
-/// Serialize Fr to hex string (big-endian, for Sui object IDs)
-fn serialize_fr_be(f: &Fr) -> String {
-    let mut bytes = Vec::new();
-    f.serialize_compressed(&mut bytes).unwrap();
-    bytes.reverse(); // Convert LE to BE
-    format!("0x{}", hex::encode(bytes))
-}
-
 /// Common proof response
 #[derive(Serialize)]
 pub struct ProofResponse {
@@ -107,10 +221,57 @@ pub struct ProofResponse {
     pub public_inputs: Vec<String>,
 }
 
+/// Machine-readable error kind, serialized as e.g. `"INVALID_HEX"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// A hex field failed to decode or has the wrong length
+    InvalidHex,
+    /// `op_type` is not `deposit` or `withdraw`
+    InvalidOpType,
+    /// The inventory holds less of the item than requested
+    InsufficientQuantity,
+    /// The resulting volume exceeds the capacity
+    CapacityExceeded,
+    /// Volume arithmetic overflows u64
+    VolumeOverflow,
+    /// A held item has no volume in the registry
+    MissingVolume,
+    /// An item_id has no slot in the registry
+    ItemOutOfRange,
+    /// Any other inconsistent inventory state
+    InvalidState,
+    /// The volume registry is malformed
+    InvalidRegistry,
+    /// Proof bytes do not decode to a proof
+    InvalidProof,
+    /// Wrong number of public inputs for the circuit
+    InputCountMismatch,
+    /// Proving failed or the proof did not pass its self-check
+    ProofFailed,
+    /// Encoding a proof or key failed
+    SerializationFailed,
+    /// The inventory state was superseded by a newer transition
+    StateConflict,
+    /// No keys are loaded for the requested circuit
+    UnknownCircuit,
+}
+
 /// Error response
 #[derive(Serialize)]
 pub struct ErrorResponse {
     pub error: String,
+    pub error_code: ErrorCode,
+    /// Held and requested amounts, for `INSUFFICIENT_QUANTITY`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shortfall: Option<QuantityShortfall>,
+}
+
+/// How much of an item the client holds versus what the request needed
+#[derive(Serialize)]
+pub struct QuantityShortfall {
+    pub available: u64,
+    pub requested: u64,
 }
 
 // ============ State Transition (Deposit/Withdraw) ============
@@ -129,10 +290,8 @@ pub struct StateTransitionRequest {
     pub item_id: u64,
     /// Amount to deposit/withdraw
     pub amount: u64,
-    /// Volume per unit of this item
-    pub item_volume: u64,
-    /// Registry root (for volume lookup verification)
-    pub registry_root: String,
+    /// Volume per unit for each item type (its hash must match on-chain)
+    pub volume_registry: [u64; MAX_ITEM_TYPES],
     /// Maximum allowed capacity
     pub max_capacity: u64,
     /// Current nonce from on-chain inventory (for replay protection)
@@ -161,53 +320,123 @@ pub async fn prove_state_transition(
     State(state): State<Arc<RwLock<AppState>>>,
     Json(req): Json<StateTransitionRequest>,
 ) -> impl IntoResponse {
+    match req.op_type.parse::<OpType>() {
+        Ok(op_type) => prove_transition_request(state, req, op_type).await,
+        Err(e) => error_response(StatusCode::BAD_REQUEST, ErrorCode::InvalidOpType, e.to_string()),
+    }
+}
+
+/// Prove a withdrawal that leaves the inventory within `max_capacity`.
+///
+/// Takes a state transition request whose op_type must be "withdraw". The
+/// capacity bound is re-asserted on the post-state, so this proves an
+/// inventory is back under a lowered cap.
+pub async fn prove_withdraw_with_capacity(
+    State(state): State<Arc<RwLock<AppState>>>,
+    Json(req): Json<StateTransitionRequest>,
+) -> impl IntoResponse {
+    match req.op_type.parse::<OpType>() {
+        Ok(OpType::Withdraw) => prove_transition_request(state, req, OpType::Withdraw).await,
+        _ => error_response(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidOpType,
+            format!("Expected op_type \"withdraw\", got {:?}", req.op_type),
+        ),
+    }
+}
+
+/// Shared body of the state transition handlers, once op_type is known.
+async fn prove_transition_request(
+    state: Arc<RwLock<AppState>>,
+    req: StateTransitionRequest,
+    op_type: OpType,
+) -> Response {
     let old_blinding = match parse_fr(&req.old_blinding) {
         Ok(b) => b,
-        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
+        Err(e) => return invalid_hex(e),
     };
 
     let new_blinding = match parse_fr(&req.new_blinding) {
         Ok(b) => b,
-        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
-    };
-
-    let registry_root = match parse_fr(&req.registry_root) {
-        Ok(r) => r,
-        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
+        Err(e) => return invalid_hex(e),
     };
 
     // Parse inventory_id - interpreted as LE field element (with modular reduction if needed)
     let inventory_id = match parse_fr(&req.inventory_id) {
         Ok(id) => id,
-        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
-    };
-
-    let op_type = match req.op_type.to_lowercase().as_str() {
-        "deposit" => OpType::Deposit,
-        "withdraw" => OpType::Withdraw,
-        _ => return (StatusCode::BAD_REQUEST, Json(ErrorResponse {
-            error: "op_type must be 'deposit' or 'withdraw'".to_string()
-        })).into_response(),
+        Err(e) => return invalid_hex(e),
     };
 
-    let inventory_state = parse_inventory_state(&req.inventory, req.current_volume, old_blinding);
+    let registry = VolumeRegistry::new(req.volume_registry);
+    let item_volume = registry.get_volume(req.item_id);
+    if let Err((error_code, e)) =
+        check_transition_volume(req.current_volume, req.amount, item_volume, op_type)
+    {
+        // A withdrawal short on quantity reports the shortfall, not the volume underflow
+        if error_code == ErrorCode::InvalidState {
+            let held = req.inventory.iter()
+                .find(|i| i.item_id == req.item_id)
+                .map_or(0, |i| i.quantity);
+            if held < req.amount {
+                let shortfall = ProveError::InsufficientQuantity { have: held, need: req.amount };
+                return prove_error(shortfall);
+            }
+        }
+        return unprocessable(error_code, e);
+    }
 
     let app_state = state.read().await;
 
-    match prove::prove_state_transition(
-        &app_state.keys.state_transition.proving_key,
-        &inventory_state,
-        new_blinding,
-        req.item_id,
-        req.amount,
-        req.item_volume,
-        registry_root,
-        req.max_capacity,
-        req.nonce,
-        inventory_id,
-        op_type,
-    ) {
+    // Transitions of one inventory are proven one at a time
+    let mut head = app_state.inventories.lock(inventory_id).await;
+
+    // A transition from an already-superseded state can no longer land on-chain,
+    // and the head cannot move while the lock is held, so refuse before proving
+    if let Some(latest) = *head {
+        if latest.supersedes(req.nonce) {
+            return conflict(format!(
+                "Inventory state superseded: nonce {} already proven, next is {} from {}",
+                req.nonce,
+                latest.nonce,
+                serialize_fr(&latest.commitment)
+            ));
+        }
+    }
+
+    // Under the lock, the cache holds the tree this inventory's last transition ended on
+    let pairs: Vec<(u64, u64)> = req.inventory.iter().map(|i| (i.item_id, i.quantity)).collect();
+    let inventory_state = InventoryState {
+        tree: app_state.smt_cache.get_or_build(inventory_id, &pairs),
+        current_volume: req.current_volume,
+        blinding: old_blinding,
+    };
+
+    let keys = app_state.keys.clone();
+    let proved = app_state.proving_pool.run(move || {
+        let result = prove::prove_state_transition(
+            &keys.state_transition.proving_key,
+            &inventory_state,
+            new_blinding,
+            req.item_id,
+            req.amount,
+            &registry,
+            req.max_capacity,
+            req.nonce,
+            inventory_id,
+            op_type,
+        )?;
+        prove::check_proof(&keys.state_transition.verifying_key, &result.proof)?;
+        Ok(result)
+    });
+
+    match proved.await {
         Ok(result) => {
+            *head = Some(InventoryHead {
+                nonce: result.nonce + 1,
+                commitment: result.new_commitment,
+            });
+            app_state.smt_cache.insert(inventory_id, result.new_state.tree.clone());
+
             let proof_bytes = result.proof.serialize_proof().unwrap();
             let response = StateTransitionResponse {
                 proof: format!("0x{}", hex::encode(proof_bytes)),
@@ -226,13 +455,7 @@ pub async fn prove_state_transition(
             };
             (StatusCode::OK, Json(response)).into_response()
         }
-        Err(e) => (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: e.to_string(),
-            }),
-        )
-            .into_response(),
+        Err(e) => prove_error(e),
     }
 }
 
@@ -258,19 +481,21 @@ pub async fn prove_item_exists(
 ) -> impl IntoResponse {
     let blinding = match parse_fr(&req.blinding) {
         Ok(b) => b,
-        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
+        Err(e) => return invalid_hex(e),
     };
 
     let inventory_state = parse_inventory_state(&req.inventory, req.current_volume, blinding);
 
     let app_state = state.read().await;
 
-    match prove::prove_item_exists(
-        &app_state.keys.item_exists.proving_key,
-        &inventory_state,
-        req.item_id,
-        req.min_quantity,
-    ) {
+    let keys = app_state.keys.clone();
+    let proved = app_state.proving_pool.run(move || {
+        prove::prove_and_verify(&keys.item_exists, |pk| {
+            prove::prove_item_exists(pk, &inventory_state, req.item_id, req.min_quantity)
+        })
+    });
+
+    match proved.await {
         Ok(proof_with_inputs) => {
             let proof_bytes = proof_with_inputs.serialize_proof().unwrap();
             let response = ProofResponse {
@@ -283,13 +508,7 @@ pub async fn prove_item_exists(
             };
             (StatusCode::OK, Json(response)).into_response()
         }
-        Err(e) => (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: e.to_string(),
-            }),
-        )
-            .into_response(),
+        Err(e) => prove_error(e),
     }
 }
 
@@ -313,18 +532,21 @@ pub async fn prove_capacity(
 ) -> impl IntoResponse {
     let blinding = match parse_fr(&req.blinding) {
         Ok(b) => b,
-        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
+        Err(e) => return invalid_hex(e),
     };
 
     let inventory_state = parse_inventory_state(&req.inventory, req.current_volume, blinding);
 
     let app_state = state.read().await;
 
-    match prove::prove_capacity(
-        &app_state.keys.capacity.proving_key,
-        &inventory_state,
-        req.max_capacity,
-    ) {
+    let keys = app_state.keys.clone();
+    let proved = app_state.proving_pool.run(move || {
+        prove::prove_and_verify(&keys.capacity, |pk| {
+            prove::prove_capacity(pk, &inventory_state, req.max_capacity)
+        })
+    });
+
+    match proved.await {
         Ok(proof_with_inputs) => {
             let proof_bytes = proof_with_inputs.serialize_proof().unwrap();
             let response = ProofResponse {
@@ -337,13 +559,7 @@ pub async fn prove_capacity(
             };
             (StatusCode::OK, Json(response)).into_response()
         }
-        Err(e) => (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: e.to_string(),
-            }),
-        )
-            .into_response(),
+        Err(e) => prove_error(e),
     }
 }
 
@@ -370,7 +586,7 @@ pub async fn create_commitment(
 ) -> impl IntoResponse {
     let blinding = match parse_fr(&req.blinding) {
         Ok(b) => b,
-        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
+        Err(e) => return invalid_hex(e),
     };
 
     let pairs: Vec<(u64, u64)> = req.inventory.iter().map(|i| (i.item_id, i.quantity)).collect();
@@ -393,16 +609,1046 @@ pub async fn create_commitment(
         .into_response()
 }
 
+#[derive(Deserialize)]
+pub struct CommitSmtInventoryRequest {
+    /// Inventory items
+    pub inventory: Vec<ItemRequest>,
+    /// Volume per unit for each item type
+    pub volume_registry: [u64; MAX_ITEM_TYPES],
+    /// Blinding factor
+    pub blinding: String,
+}
+
+#[derive(Serialize)]
+pub struct CommitSmtInventoryResponse {
+    pub root: String,
+    pub volume: u64,
+    pub commitment: String,
+}
+
+/// Build the SMT for an inventory and commit to it, computing the volume from the registry.
+pub async fn commit_smt_inventory(
+    Json(req): Json<CommitSmtInventoryRequest>,
+) -> impl IntoResponse {
+    let blinding = match parse_fr(&req.blinding) {
+        Ok(b) => b,
+        Err(e) => return invalid_hex(e),
+    };
+
+    let pairs: Vec<(u64, u64)> = req.inventory.iter().map(|i| (i.item_id, i.quantity)).collect();
+    let tree = SparseMerkleTree::from_items(&pairs, DEFAULT_DEPTH);
+    let registry = VolumeRegistry::new(req.volume_registry);
+
+    let volume = match registry.calculate_used_volume(&tree) {
+        Ok(v) => v,
+        Err(e) => return registry_error(e),
+    };
+    let root = tree.root();
+    let commitment = create_smt_commitment(root, volume, blinding);
+
+    (
+        StatusCode::OK,
+        Json(CommitSmtInventoryResponse {
+            root: serialize_fr(&root),
+            volume,
+            commitment: serialize_fr(&commitment),
+        }),
+    )
+        .into_response()
+}
+
+#[derive(Deserialize)]
+pub struct RemainingCapacityRequest {
+    /// Inventory items
+    pub inventory: Vec<ItemRequest>,
+    /// Volume per unit for each item type
+    pub volume_registry: [u64; MAX_ITEM_TYPES],
+    /// Maximum allowed capacity
+    pub max_capacity: u64,
+}
+
+#[derive(Serialize)]
+pub struct RemainingCapacityResponse {
+    pub used_volume: u64,
+    /// Volume still free, or null if the inventory is over capacity
+    pub remaining_capacity: Option<u64>,
+}
+
+/// Compute an inventory's used volume and remaining capacity without a proof.
+pub async fn remaining_capacity(Json(req): Json<RemainingCapacityRequest>) -> impl IntoResponse {
+    let pairs: Vec<(u64, u64)> = req.inventory.iter().map(|i| (i.item_id, i.quantity)).collect();
+    let tree = SparseMerkleTree::from_items(&pairs, DEFAULT_DEPTH);
+    let registry = VolumeRegistry::new(req.volume_registry);
+
+    let used_volume = match registry.calculate_used_volume(&tree) {
+        Ok(v) => v,
+        Err(e) => return registry_error(e),
+    };
+
+    (
+        StatusCode::OK,
+        Json(RemainingCapacityResponse {
+            used_volume,
+            remaining_capacity: registry.remaining_capacity(&tree, req.max_capacity),
+        }),
+    )
+        .into_response()
+}
+
+#[derive(Deserialize)]
+pub struct VerifyRegistryRequest {
+    /// Volume per unit for each item type, e.g. the canonical on-chain registry
+    pub volume_registry: [u64; MAX_ITEM_TYPES],
+    /// Registry root bound in a proof
+    pub registry_root: String,
+}
+
+#[derive(Serialize)]
+pub struct VerifyRegistryResponse {
+    /// Whether `registry_root` is the hash of `volume_registry`
+    pub matches: bool,
+    /// Hash of `volume_registry`
+    pub registry_root: String,
+}
+
+/// Check that a proof's registry root is the hash of an expected registry.
+pub async fn verify_registry(Json(req): Json<VerifyRegistryRequest>) -> impl IntoResponse {
+    let claimed = match parse_fr(&req.registry_root) {
+        Ok(root) => root,
+        Err(e) => return invalid_hex(e),
+    };
+
+    let registry = VolumeRegistry::new(req.volume_registry);
+    (
+        StatusCode::OK,
+        Json(VerifyRegistryResponse {
+            matches: registry_hash_matches(&registry, claimed),
+            registry_root: serialize_fr(&compute_registry_hash(&registry)),
+        }),
+    )
+        .into_response()
+}
+
+#[derive(Deserialize)]
+pub struct OpenInventoryRequest {
+    /// Disclosed inventory items
+    pub inventory: Vec<ItemRequest>,
+    /// Volume per unit for each item type
+    pub volume_registry: [u64; MAX_ITEM_TYPES],
+    /// Blinding factor
+    pub blinding: String,
+}
+
+/// Item in a disclosed inventory
+#[derive(Debug, Serialize)]
+pub struct ItemOpening {
+    pub item_id: u64,
+    pub quantity: u64,
+}
+
+/// Full opening of an inventory commitment for auditors.
+///
+/// commitment = Poseidon(inventory_root, volume, blinding), where
+/// inventory_root is the SMT root of `items`. The auditor checks `commitment`
+/// against the on-chain value.
+#[derive(Serialize)]
+pub struct OpenInventoryResponse {
+    pub commitment: String,
+    pub inventory_root: String,
+    /// Non-empty items sorted by item_id
+    pub items: Vec<ItemOpening>,
+    pub volume: u64,
+    pub blinding: String,
+}
+
+/// Disclose an inventory in full and return the commitment it opens (no ZK).
+pub async fn open_inventory(
+    Json(req): Json<OpenInventoryRequest>,
+) -> impl IntoResponse {
+    let blinding = match parse_fr(&req.blinding) {
+        Ok(b) => b,
+        Err(e) => return invalid_hex(e),
+    };
+
+    let pairs: Vec<(u64, u64)> = req.inventory.iter().map(|i| (i.item_id, i.quantity)).collect();
+    let tree = SparseMerkleTree::from_items(&pairs, DEFAULT_DEPTH);
+    let registry = VolumeRegistry::new(req.volume_registry);
+
+    let volume = match registry.calculate_used_volume(&tree) {
+        Ok(v) => v,
+        Err(e) => return registry_error(e),
+    };
+    let inventory_root = tree.root();
+    let commitment = create_smt_commitment(inventory_root, volume, blinding);
+
+    let mut items: Vec<ItemOpening> = tree
+        .items()
+        .map(|(item_id, quantity)| ItemOpening { item_id, quantity })
+        .collect();
+    items.sort_by_key(|item| item.item_id);
+
+    (
+        StatusCode::OK,
+        Json(OpenInventoryResponse {
+            commitment: serialize_fr(&commitment),
+            inventory_root: serialize_fr(&inventory_root),
+            items,
+            volume,
+            blinding: serialize_fr(&blinding),
+        }),
+    )
+        .into_response()
+}
+
+#[derive(Serialize)]
+pub struct VerifyingKeyResponse {
+    pub circuit: String,
+    /// Compressed `VerifyingKey<Bn254>` bytes as hex
+    pub vk: String,
+}
+
+/// Return a loaded verifying key for on-chain registration.
+///
+/// `circuit` is one of `state_transition`, `item_exists` or `capacity`.
+pub async fn get_verifying_key(
+    State(state): State<Arc<RwLock<AppState>>>,
+    Path(circuit): Path<String>,
+) -> impl IntoResponse {
+    let app_state = state.read().await;
+    let keys = match circuit.as_str() {
+        "state_transition" => &app_state.keys.state_transition,
+        "item_exists" => &app_state.keys.item_exists,
+        "capacity" => &app_state.keys.capacity,
+        _ => return unknown_circuit(&circuit),
+    };
+
+    match keys.serialize_vk() {
+        Ok(vk_bytes) => (
+            StatusCode::OK,
+            Json(VerifyingKeyResponse {
+                circuit,
+                vk: format!("0x{}", hex::encode(vk_bytes)),
+            }),
+        )
+            .into_response(),
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::SerializationFailed,
+            e.to_string(),
+        ),
+    }
+}
+
+// ============ Verification ============
+
+#[derive(Deserialize)]
+pub struct VerifyProofRequest {
+    /// Compressed proof bytes as hex
+    pub proof: String,
+    /// Public inputs as little-endian hex, in circuit order
+    pub public_inputs: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct VerifyProofResponse {
+    pub valid: bool,
+}
+
+/// Verify a proof against a loaded verifying key.
+///
+/// `circuit` is one of `state_transition`, `item_exists` or `capacity`. Malformed
+/// hex or the wrong number of public inputs is a 400; a well-formed proof that
+/// does not verify is `{ "valid": false }`.
+pub async fn verify_proof(
+    State(state): State<Arc<RwLock<AppState>>>,
+    Path(circuit): Path<String>,
+    Json(req): Json<VerifyProofRequest>,
+) -> impl IntoResponse {
+    let kind = match circuit.as_str() {
+        "state_transition" => CircuitKind::StateTransition,
+        "item_exists" => CircuitKind::ItemExists,
+        "capacity" => CircuitKind::Capacity,
+        _ => return unknown_circuit(&circuit),
+    };
+
+    let proof = match hex::decode(req.proof.trim_start_matches("0x")) {
+        Ok(bytes) => match ProofWithInputs::deserialize_proof(&bytes) {
+            Ok(proof) => proof,
+            Err(e) => {
+                return error_response(StatusCode::BAD_REQUEST, ErrorCode::InvalidProof, e.to_string())
+            }
+        },
+        Err(e) => return invalid_hex(format!("Invalid hex: {}", e)),
+    };
+    let public_inputs = match req
+        .public_inputs
+        .iter()
+        .map(|input| public_input_from_hex(input))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(inputs) => inputs,
+        Err(e) => return invalid_hex(e.to_string()),
+    };
+
+    let keys = state.read().await.prepared_keys.clone();
+    match verify_prepared(kind, &keys, &proof, &public_inputs) {
+        Ok(valid) => (StatusCode::OK, Json(VerifyProofResponse { valid })).into_response(),
+        Err(e @ VerifyError::InputCountMismatch { .. }) => {
+            error_response(StatusCode::BAD_REQUEST, ErrorCode::InputCountMismatch, e.to_string())
+        }
+        Err(e) => error_response(StatusCode::BAD_REQUEST, ErrorCode::InvalidProof, e.to_string()),
+    }
+}
+
 #[derive(Serialize)]
 pub struct GenerateBlindingResponse {
     pub blinding: String,
 }
 
 pub async fn generate_blinding() -> Json<GenerateBlindingResponse> {
-    let mut rng = ark_std::rand::thread_rng();
-    let blinding: Fr = rng.gen();
+    let blinding = inventory_prover::generate_blinding(&mut ark_std::rand::thread_rng());
 
     Json(GenerateBlindingResponse {
         blinding: serialize_fr(&blinding),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::OnceLock;
+
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use inventory_circuits::{compute_capacity_hash, CapacitySMTCircuit};
+    use inventory_prover::setup::{setup_all_circuits, CircuitKeys};
+    use crate::proving::ProvingPool;
+    use inventory_prover::{
+        verify_capacity, verify_item_exists, verify_state_transition, CircuitKeyPair,
+        PreparedCircuitKeys, ProofWithInputs,
+    };
+
+    /// Proving pool shared by every test's app state.
+    fn proving_pool() -> ProvingPool {
+        static POOL: OnceLock<ProvingPool> = OnceLock::new();
+        POOL.get_or_init(|| ProvingPool::new(2).unwrap()).clone()
+    }
+
+    /// Shared app state; setup is seeded, so one run serves every test.
+    fn app_state() -> Arc<RwLock<AppState>> {
+        static KEYS: OnceLock<Arc<CircuitKeys>> = OnceLock::new();
+        let keys = KEYS
+            .get_or_init(|| Arc::new(proving_pool().install(setup_all_circuits).unwrap()))
+            .clone();
+        Arc::new(RwLock::new(AppState {
+            prepared_keys: Arc::new(PreparedCircuitKeys::new(&keys)),
+            keys,
+            inventories: Default::default(),
+            smt_cache: Default::default(),
+            proving_pool: proving_pool(),
+        }))
+    }
+
+    fn decode_proof(body: &serde_json::Value) -> ProofWithInputs {
+        let proof_hex = body["proof"].as_str().unwrap().trim_start_matches("0x");
+        let proof = ProofWithInputs::deserialize_proof(&hex::decode(proof_hex).unwrap()).unwrap();
+        let public_inputs = body["public_inputs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| parse_fr(v.as_str().unwrap()).unwrap())
+            .collect();
+        ProofWithInputs { proof, public_inputs }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn state_transition_request(
+        inventory: &[(u64, u64)],
+        current_volume: u64,
+        item_id: u64,
+        amount: u64,
+        item_volume: u64,
+        max_capacity: u64,
+        inventory_id: Fr,
+        op_type: &str,
+    ) -> StateTransitionRequest {
+        let mut volume_registry = [0u64; MAX_ITEM_TYPES];
+        volume_registry[item_id as usize] = item_volume;
+
+        StateTransitionRequest {
+            inventory: items(inventory),
+            current_volume,
+            old_blinding: serialize_fr(&Fr::from(111u64)),
+            new_blinding: serialize_fr(&Fr::from(222u64)),
+            item_id,
+            amount,
+            volume_registry,
+            max_capacity,
+            nonce: 0,
+            inventory_id: serialize_fr(&inventory_id),
+            op_type: op_type.to_string(),
+        }
+    }
+
+    /// Call the state transition handler and verify the returned proof.
+    async fn prove_and_verify_transition(
+        state: Arc<RwLock<AppState>>,
+        req: StateTransitionRequest,
+    ) -> serde_json::Value {
+        let response = prove_state_transition(State(state.clone()), Json(req))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+
+        let ProofWithInputs { proof, public_inputs: inputs } = decode_proof(&body);
+        assert_eq!(inputs.len(), 4);
+        assert_eq!(inputs[3], parse_fr(body["registry_root"].as_str().unwrap()).unwrap());
+
+        let keys = state.read().await.keys.clone();
+        let valid = verify_state_transition(
+            &keys.state_transition.verifying_key,
+            &proof,
+            inputs[0],
+            body["nonce"].as_u64().unwrap(),
+            inputs[2],
+            inputs[3],
+        )
+        .unwrap();
+        assert!(valid);
+
+        body
+    }
+
+    async fn response_json(response: Response) -> (StatusCode, serde_json::Value) {
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    fn items(pairs: &[(u64, u64)]) -> Vec<ItemRequest> {
+        pairs
+            .iter()
+            .map(|&(item_id, quantity)| ItemRequest { item_id, quantity })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_commit_smt_inventory_opens_in_capacity_circuit() {
+        let blinding = Fr::from(12345u64);
+        let mut volume_registry = [0u64; MAX_ITEM_TYPES];
+        volume_registry[1] = 5;
+        volume_registry[2] = 10;
+
+        let req = CommitSmtInventoryRequest {
+            inventory: items(&[(1, 10), (2, 3)]),
+            volume_registry,
+            blinding: serialize_fr(&blinding),
+        };
+        let response = commit_smt_inventory(Json(req)).await.into_response();
+        let (status, body) = response_json(response).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["volume"], 80);
+
+        let root = parse_fr(body["root"].as_str().unwrap()).unwrap();
+        let commitment = parse_fr(body["commitment"].as_str().unwrap()).unwrap();
+        let volume = body["volume"].as_u64().unwrap();
+        let max_capacity = 100u64;
+
+        let circuit = CapacitySMTCircuit::new(root, volume, blinding, max_capacity);
+        assert_eq!(
+            circuit.public_hash.unwrap(),
+            compute_capacity_hash(commitment, max_capacity)
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_remaining_capacity() {
+        let mut volume_registry = [0u64; MAX_ITEM_TYPES];
+        volume_registry[1] = 5;
+        volume_registry[2] = 10;
+
+        // 10 * 5 + 3 * 10 = 80 used
+        for (max_capacity, remaining) in [(100, Some(20)), (80, Some(0)), (79, None)] {
+            let req = RemainingCapacityRequest {
+                inventory: items(&[(1, 10), (2, 3)]),
+                volume_registry,
+                max_capacity,
+            };
+            let response = remaining_capacity(Json(req)).await.into_response();
+            let (status, body) = response_json(response).await;
+
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["used_volume"], 80);
+            assert_eq!(body["remaining_capacity"], serde_json::json!(remaining));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_registry() {
+        let mut volume_registry = [0u64; MAX_ITEM_TYPES];
+        volume_registry[1] = 5;
+        let canonical = compute_registry_hash(&VolumeRegistry::new(volume_registry));
+
+        let verify = |volume_registry, registry_root: Fr| async move {
+            let req = VerifyRegistryRequest {
+                volume_registry,
+                registry_root: serialize_fr(&registry_root),
+            };
+            response_json(verify_registry(Json(req)).await.into_response()).await
+        };
+
+        let (status, body) = verify(volume_registry, canonical).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["matches"], true);
+        assert_eq!(body["registry_root"], serialize_fr(&canonical));
+
+        // A proof priced with a different registry is caught
+        let mut tampered = volume_registry;
+        tampered[1] = 4;
+        let (status, body) = verify(tampered, canonical).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["matches"], false);
+        assert_ne!(body["registry_root"], serialize_fr(&canonical));
+    }
+
+    #[tokio::test]
+    async fn test_open_inventory_matches_commitment() {
+        let blinding = Fr::from(12345u64);
+        let mut volume_registry = [0u64; MAX_ITEM_TYPES];
+        volume_registry[1] = 5;
+        volume_registry[2] = 10;
+
+        let open = |inventory: &[(u64, u64)]| OpenInventoryRequest {
+            inventory: items(inventory),
+            volume_registry,
+            blinding: serialize_fr(&blinding),
+        };
+
+        let response = open_inventory(Json(open(&[(2, 3), (1, 10)]))).await.into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let tree = SparseMerkleTree::from_items(&[(1, 10), (2, 3)], DEFAULT_DEPTH);
+        let expected = create_smt_commitment(tree.root(), 80, blinding);
+        assert_eq!(body["commitment"], serialize_fr(&expected));
+        assert_eq!(body["volume"], 80);
+        assert_eq!(
+            body["items"],
+            serde_json::json!([
+                { "item_id": 1, "quantity": 10 },
+                { "item_id": 2, "quantity": 3 },
+            ])
+        );
+
+        // Tampered disclosure opens a different commitment
+        let response = open_inventory(Json(open(&[(1, 11), (2, 3)]))).await.into_response();
+        let (_, tampered) = response_json(response).await;
+        assert_ne!(tampered["commitment"], body["commitment"]);
+    }
+
+    #[tokio::test]
+    async fn test_prove_item_exists_handler() {
+        let state = app_state();
+        let req = ItemExistsRequest {
+            inventory: items(&[(1, 100), (2, 5)]),
+            current_volume: 550,
+            blinding: serialize_fr(&Fr::from(12345u64)),
+            item_id: 1,
+            min_quantity: 50,
+        };
+
+        let response = prove_item_exists(State(state.clone()), Json(req))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+
+        let ProofWithInputs { proof, public_inputs: inputs } = decode_proof(&body);
+        let keys = state.read().await.keys.clone();
+        assert!(verify_item_exists(&keys.item_exists.verifying_key, &proof, inputs[0]).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_prove_capacity_handler() {
+        let state = app_state();
+        let req = CapacityRequest {
+            inventory: items(&[(1, 100)]),
+            current_volume: 500,
+            blinding: serialize_fr(&Fr::from(12345u64)),
+            max_capacity: 1000,
+        };
+
+        let response = prove_capacity(State(state.clone()), Json(req))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+
+        let ProofWithInputs { proof, public_inputs: inputs } = decode_proof(&body);
+        let keys = state.read().await.keys.clone();
+        assert!(verify_capacity(&keys.capacity.verifying_key, &proof, inputs[0]).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_keys_fail_self_check() {
+        // A capacity proving key paired with another circuit's verifying key
+        let keys = app_state().read().await.keys.clone();
+        let mismatched = CircuitKeys {
+            state_transition: keys.state_transition.clone(),
+            item_exists: keys.item_exists.clone(),
+            capacity: CircuitKeyPair {
+                proving_key: keys.capacity.proving_key.clone(),
+                verifying_key: keys.item_exists.verifying_key.clone(),
+            },
+        };
+        let state = Arc::new(RwLock::new(AppState {
+            prepared_keys: Arc::new(PreparedCircuitKeys::new(&mismatched)),
+            keys: Arc::new(mismatched),
+            inventories: Default::default(),
+            smt_cache: Default::default(),
+            proving_pool: proving_pool(),
+        }));
+
+        let req = CapacityRequest {
+            inventory: items(&[(1, 100)]),
+            current_volume: 500,
+            blinding: serialize_fr(&Fr::from(12345u64)),
+            max_capacity: 1000,
+        };
+        let response = prove_capacity(State(state), Json(req)).await.into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["error_code"], "PROOF_FAILED");
+    }
+
+    #[tokio::test]
+    async fn test_deposit_with_capacity_handler() {
+        let state = app_state();
+
+        // 100 * 10 + 50 * 10 = 1500, exactly at capacity
+        let req = state_transition_request(
+            &[(1, 100)], 1000, 1, 50, 10, 1500, Fr::from(42u64), "deposit",
+        );
+        let body = prove_and_verify_transition(state.clone(), req).await;
+        assert_eq!(body["new_volume"], 1500);
+
+        // One more unit exceeds capacity (another inventory, as nonce 0 of 42 is now taken)
+        let req = state_transition_request(
+            &[(1, 100)], 1000, 1, 51, 10, 1500, Fr::from(43u64), "deposit",
+        );
+        let response = prove_state_transition(State(state), Json(req)).await.into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error_code"], "CAPACITY_EXCEEDED");
+    }
+
+    #[tokio::test]
+    async fn test_transfer_with_capacity_handler() {
+        // A transfer is a withdraw from the source and a deposit into the
+        // destination; each side uses its own registry and capacity.
+        let state = app_state();
+
+        let withdraw = state_transition_request(
+            &[(1, 30)], 150, 1, 10, 5, 1000, Fr::from(1u64), "withdraw",
+        );
+        let src_body = prove_and_verify_transition(state.clone(), withdraw).await;
+        assert_eq!(src_body["new_volume"], 100);
+
+        let deposit = state_transition_request(
+            &[(2, 5)], 50, 1, 10, 20, 250, Fr::from(2u64), "deposit",
+        );
+        let dst_body = prove_and_verify_transition(state, deposit).await;
+        assert_eq!(dst_body["new_volume"], 250);
+    }
+
+    #[tokio::test]
+    async fn test_withdraw_with_capacity_handler() {
+        let state = app_state();
+
+        // 100 units at volume 10, above a lowered cap of 800
+        let req = state_transition_request(
+            &[(1, 100)], 1000, 1, 30, 10, 800, Fr::from(3u64), "withdraw",
+        );
+        let response = prove_withdraw_with_capacity(State(state.clone()), Json(req))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["new_volume"], 700);
+
+        // Withdrawing 10 leaves the inventory over the cap
+        let req = state_transition_request(
+            &[(1, 100)], 1000, 1, 10, 10, 800, Fr::from(4u64), "withdraw",
+        );
+        let response = prove_withdraw_with_capacity(State(state.clone()), Json(req))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error_code"], "CAPACITY_EXCEEDED");
+
+        let req = state_transition_request(
+            &[(1, 100)], 1000, 1, 10, 10, 2000, Fr::from(4u64), "deposit",
+        );
+        let response = prove_withdraw_with_capacity(State(state), Json(req))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error_code"], "INVALID_OP_TYPE");
+    }
+
+    #[tokio::test]
+    async fn test_transition_volume_overflow_rejected() {
+        let state = app_state();
+
+        // amount * item_volume overflows u64
+        let req = state_transition_request(
+            &[(1, 100)], 1000, 1, u64::MAX / 2, 3, u64::MAX, Fr::from(42u64),
+            "withdraw",
+        );
+        let response = prove_state_transition(State(state.clone()), Json(req))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error_code"], "VOLUME_OVERFLOW");
+        assert!(body["error"].as_str().unwrap().contains("overflow"));
+
+        // The delta fits, but the deposited total does not
+        let req = state_transition_request(
+            &[(1, 100)], u64::MAX - 10, 1, 11, 1, u64::MAX, Fr::from(42u64),
+            "deposit",
+        );
+        let response = prove_state_transition(State(state.clone()), Json(req))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // Fits in u64, but the circuit range checks volumes to u32
+        let above_u32 = u64::from(u32::MAX) + 1;
+        for (current_volume, amount) in [(u64::from(u32::MAX), 1), (0, above_u32)] {
+            let req = state_transition_request(
+                &[(1, 100)], current_volume, 1, amount, 1, u64::MAX, Fr::from(42u64),
+                "deposit",
+            );
+            let response = prove_state_transition(State(state.clone()), Json(req))
+                .await
+                .into_response();
+            let (status, body) = response_json(response).await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(body["error_code"], "VOLUME_OVERFLOW");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transition_volume_underflow_rejected() {
+        let state = app_state();
+
+        // Withdrawing 30 * 10 from a recorded volume of 200
+        let req = state_transition_request(
+            &[(1, 100)], 200, 1, 30, 10, 1000, Fr::from(42u64), "withdraw",
+        );
+        let response = prove_state_transition(State(state), Json(req)).await.into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error_code"], "INVALID_STATE");
+        assert!(body["error"].as_str().unwrap().contains("underflow"));
+    }
+
+    #[tokio::test]
+    async fn test_inventory_volume_overflow_rejected() {
+        let mut volume_registry = [0u64; MAX_ITEM_TYPES];
+        volume_registry[1] = 2;
+
+        let req = CommitSmtInventoryRequest {
+            inventory: items(&[(1, u64::MAX)]),
+            volume_registry,
+            blinding: serialize_fr(&Fr::from(12345u64)),
+        };
+        let response = commit_smt_inventory(Json(req)).await.into_response();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let req = OpenInventoryRequest {
+            inventory: items(&[(1, u64::MAX)]),
+            volume_registry,
+            blinding: serialize_fr(&Fr::from(12345u64)),
+        };
+        let response = open_inventory(Json(req)).await.into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error_code"], "VOLUME_OVERFLOW");
+    }
+
+    #[tokio::test]
+    async fn test_registry_errors_keep_their_code() {
+        let cases = [
+            (RegistryError::MissingVolume { item_id: 3 }, "MISSING_VOLUME"),
+            (RegistryError::VolumeOverflow, "VOLUME_OVERFLOW"),
+            (RegistryError::ItemOutOfRange { item_id: 16 }, "ITEM_OUT_OF_RANGE"),
+            (RegistryError::InvalidLength { expected: 128, found: 8 }, "INVALID_REGISTRY"),
+        ];
+        for (error, error_code) in cases {
+            let message = error.to_string();
+            let (status, body) = response_json(registry_error(error)).await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(body["error_code"], error_code);
+            assert_eq!(body["error"], message);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_concurrent_deposits_same_inventory() {
+        let state = app_state();
+        let inventory_id = Fr::from(77u64);
+        let deposit = || {
+            state_transition_request(
+                &[(1, 10)], 100, 1, 5, 10, 1000, inventory_id, "deposit",
+            )
+        };
+
+        // Both deposits start from the same state and nonce
+        let (first, second) = tokio::join!(
+            tokio::spawn(prove_state_transition(State(state.clone()), Json(deposit()))),
+            tokio::spawn(prove_state_transition(State(state.clone()), Json(deposit()))),
+        );
+        let (status_a, body_a) = response_json(first.unwrap().into_response()).await;
+        let (status_b, body_b) = response_json(second.unwrap().into_response()).await;
+
+        let mut statuses = [status_a, status_b];
+        statuses.sort();
+        assert_eq!(statuses, [StatusCode::OK, StatusCode::CONFLICT]);
+        let proven = if status_a == StatusCode::OK { body_a } else { body_b };
+
+        // Replaying the superseded state still conflicts
+        let response = prove_state_transition(State(state.clone()), Json(deposit()))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error_code"], "STATE_CONFLICT");
+
+        // The next transition starts from the proven state
+        let mut next = state_transition_request(
+            &[(1, 15)], 150, 1, 5, 10, 1000, inventory_id, "deposit",
+        );
+        next.nonce = 1;
+        next.old_blinding = serialize_fr(&Fr::from(222u64));
+        next.new_blinding = serialize_fr(&Fr::from(333u64));
+
+        let tree = SparseMerkleTree::from_items(&[(1, 15)], DEFAULT_DEPTH);
+        let old_commitment = create_smt_commitment(tree.root(), 150, Fr::from(222u64));
+        assert_eq!(serialize_fr(&old_commitment), proven["new_commitment"]);
+
+        let body = prove_and_verify_transition(state, next).await;
+        assert_eq!(body["nonce"], 1);
+        assert_eq!(body["new_volume"], 200);
+    }
+
+    #[tokio::test]
+    async fn test_transition_reuses_cached_tree() {
+        let state = app_state();
+        let inventory_id = Fr::from(78u64);
+
+        let first = state_transition_request(
+            &[(1, 10)], 100, 1, 5, 10, 1000, inventory_id, "deposit",
+        );
+        prove_and_verify_transition(state.clone(), first).await;
+        assert_eq!(state.read().await.smt_cache.hits(), 0);
+
+        // The second deposit starts from the tree the first one ended on
+        let mut second = state_transition_request(
+            &[(1, 15)], 150, 1, 5, 10, 1000, inventory_id, "deposit",
+        );
+        second.nonce = 1;
+        second.old_blinding = serialize_fr(&Fr::from(222u64));
+        let body = prove_and_verify_transition(state.clone(), second).await;
+        assert_eq!(state.read().await.smt_cache.hits(), 1);
+        assert_eq!(body["new_volume"], 200);
+
+        let tree = SparseMerkleTree::from_items(&[(1, 20)], DEFAULT_DEPTH);
+        let new_commitment = create_smt_commitment(tree.root(), 200, Fr::from(222u64));
+        assert_eq!(serialize_fr(&new_commitment), body["new_commitment"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_verifying_key() {
+        let state = app_state();
+
+        let response = get_verifying_key(State(state.clone()), Path("capacity".to_string()))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["circuit"], "capacity");
+
+        let vk_hex = body["vk"].as_str().unwrap().trim_start_matches("0x");
+        let vk = CircuitKeyPair::deserialize_vk(&hex::decode(vk_hex).unwrap()).unwrap();
+        let keys = state.read().await.keys.clone();
+        assert!(vk == keys.capacity.verifying_key);
+
+        let response = get_verifying_key(State(state), Path("deposit".to_string()))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_vk_fingerprints() {
+        let state = app_state();
+
+        let (status, body) = response_json(health(State(state.clone())).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "ok");
+
+        let keys = state.read().await.keys.clone();
+        for (name, pair) in [
+            ("state_transition", &keys.state_transition),
+            ("item_exists", &keys.item_exists),
+            ("capacity", &keys.capacity),
+        ] {
+            assert_eq!(body["circuits"][name], pair.vk_fingerprint().unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_proof_handler() {
+        let state = app_state();
+        let req = CapacityRequest {
+            inventory: items(&[(1, 100)]),
+            current_volume: 500,
+            blinding: serialize_fr(&Fr::from(12345u64)),
+            max_capacity: 1000,
+        };
+        let response = prove_capacity(State(state.clone()), Json(req))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+
+        let verify_request = |public_inputs: Vec<String>| VerifyProofRequest {
+            proof: body["proof"].as_str().unwrap().to_string(),
+            public_inputs,
+        };
+        let public_inputs: Vec<String> = body["public_inputs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap().to_string())
+            .collect();
+
+        let verify_with = |circuit: &str, req: VerifyProofRequest| {
+            verify_proof(State(state.clone()), Path(circuit.to_string()), Json(req))
+        };
+
+        let response = verify_with("capacity", verify_request(public_inputs.clone()))
+            .await
+            .into_response();
+        let (status, result) = response_json(response).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result["valid"], true);
+
+        // Flip a bit in the low byte of the public input
+        let mut bytes = hex::decode(public_inputs[0].trim_start_matches("0x")).unwrap();
+        bytes[0] ^= 1;
+        let flipped = vec![format!("0x{}", hex::encode(bytes))];
+        let response = verify_with("capacity", verify_request(flipped))
+            .await
+            .into_response();
+        let (status, result) = response_json(response).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result["valid"], false);
+
+        // Wrong input count and malformed hex are client errors
+        let response = verify_with("state_transition", verify_request(public_inputs))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = verify_with("capacity", verify_request(vec!["0xzz".to_string()]))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_error_codes() {
+        let state = app_state();
+
+        // Malformed blinding
+        let mut req = state_transition_request(
+            &[(1, 100)], 1000, 1, 5, 10, 2000, Fr::from(42u64), "deposit",
+        );
+        req.old_blinding = "0xnothex".to_string();
+        let response = prove_state_transition(State(state.clone()), Json(req))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error_code"], "INVALID_HEX");
+
+        let req = state_transition_request(
+            &[(1, 100)], 1000, 1, 5, 10, 2000, Fr::from(42u64), "burn",
+        );
+        let response = prove_state_transition(State(state.clone()), Json(req))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error_code"], "INVALID_OP_TYPE");
+        assert!(body.get("shortfall").is_none());
+
+        // Withdrawing more than held
+        let req = state_transition_request(
+            &[(1, 100)], 1000, 1, 101, 10, 2000, Fr::from(42u64), "withdraw",
+        );
+        let response = prove_state_transition(State(state.clone()), Json(req))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error_code"], "INSUFFICIENT_QUANTITY");
+        assert_eq!(body["shortfall"]["available"], 100);
+        assert_eq!(body["shortfall"]["requested"], 101);
+
+        let req = ItemExistsRequest {
+            inventory: items(&[(1, 100)]),
+            current_volume: 1000,
+            blinding: serialize_fr(&Fr::from(12345u64)),
+            item_id: 1,
+            min_quantity: 101,
+        };
+        let response = prove_item_exists(State(state.clone()), Json(req))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error_code"], "INSUFFICIENT_QUANTITY");
+
+        let req = CapacityRequest {
+            inventory: items(&[(1, 100)]),
+            current_volume: 1000,
+            blinding: serialize_fr(&Fr::from(12345u64)),
+            max_capacity: 999,
+        };
+        let response = prove_capacity(State(state.clone()), Json(req))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error_code"], "CAPACITY_EXCEEDED");
+
+        let response = get_verifying_key(State(state), Path("deposit".to_string()))
+            .await
+            .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error_code"], "UNKNOWN_CIRCUIT");
+    }
+}
@@ -0,0 +1,153 @@
+//! Per-inventory serialization of state transitions.
+//!
+//! Two transitions proven from the same old state conflict: only one can
+//! land on-chain. Each inventory gets its own async mutex, so transitions for
+//! one inventory run one at a time while different inventories prove in
+//! parallel. The mutex guards the latest state this server has proven.
+//!
+//! Inventory IDs come from clients, so the map is bounded: past its capacity
+//! the least recently used idle inventory is forgotten. A forgotten head only
+//! means a superseded nonce is caught on-chain instead of here.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use ark_bn254::Fr;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+use crate::config::DEFAULT_SMT_CACHE_CAPACITY;
+
+/// The latest state proven for an inventory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InventoryHead {
+    /// Nonce the next transition must use
+    pub nonce: u64,
+    /// Commitment the next transition must start from
+    pub commitment: Fr,
+}
+
+impl InventoryHead {
+    /// Whether a transition at `nonce` has been superseded by this head.
+    pub fn supersedes(&self, nonce: u64) -> bool {
+        nonce < self.nonce
+    }
+}
+
+type HeadLock = Arc<AsyncMutex<Option<InventoryHead>>>;
+
+/// Keyed mutex map from inventory ID to its latest proven head.
+pub struct InventoryLocks {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Default)]
+struct Entries {
+    /// Lock and the tick it was last requested at
+    heads: HashMap<Fr, (HeadLock, u64)>,
+    tick: u64,
+}
+
+impl Default for InventoryLocks {
+    fn default() -> Self {
+        Self::new(DEFAULT_SMT_CACHE_CAPACITY)
+    }
+}
+
+impl InventoryLocks {
+    /// Create a map tracking at most `capacity` idle inventories.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// Wait for exclusive access to `inventory_id`'s head.
+    ///
+    /// The map itself is only locked long enough to fetch the entry. Inventories
+    /// that are locked or waited on are never evicted, so a full map of them
+    /// grows past `capacity` until they are released.
+    pub async fn lock(&self, inventory_id: Fr) -> OwnedMutexGuard<Option<InventoryHead>> {
+        let entry = {
+            let mut entries = self.entries.lock().unwrap();
+            entries.tick += 1;
+            let tick = entries.tick;
+            if entries.heads.len() >= self.capacity && !entries.heads.contains_key(&inventory_id) {
+                // Only the map holds an idle entry's Arc
+                let oldest_idle = entries
+                    .heads
+                    .iter()
+                    .filter(|(_, (lock, _))| Arc::strong_count(lock) == 1)
+                    .min_by_key(|(_, (_, last_used))| *last_used)
+                    .map(|(&id, _)| id);
+                if let Some(oldest_idle) = oldest_idle {
+                    entries.heads.remove(&oldest_idle);
+                }
+            }
+            let (lock, last_used) = entries.heads.entry(inventory_id).or_default();
+            *last_used = tick;
+            lock.clone()
+        };
+        entry.lock_owned().await
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries.lock().unwrap().heads.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_same_inventory_serializes() {
+        let locks = InventoryLocks::default();
+
+        let mut guard = locks.lock(Fr::from(1u64)).await;
+        *guard = Some(InventoryHead { nonce: 1, commitment: Fr::from(7u64) });
+
+        // A different inventory is not blocked
+        assert!(locks.lock(Fr::from(2u64)).await.is_none());
+
+        // The same inventory waits until the guard is dropped
+        let pending = tokio::time::timeout(
+            std::time::Duration::from_millis(50),
+            locks.lock(Fr::from(1u64)),
+        );
+        assert!(pending.await.is_err());
+
+        drop(guard);
+        let head = locks.lock(Fr::from(1u64)).await.unwrap();
+        assert!(head.supersedes(0));
+        assert!(!head.supersedes(1));
+    }
+
+    #[tokio::test]
+    async fn test_evicts_least_recently_used_idle() {
+        let locks = InventoryLocks::new(2);
+        let (a, b, c) = (Fr::from(1u64), Fr::from(2u64), Fr::from(3u64));
+        let head = Some(InventoryHead { nonce: 1, commitment: Fr::from(7u64) });
+
+        let mut held = locks.lock(a).await;
+        *held = head;
+        *locks.lock(b).await = head;
+
+        // `a` is the least recently used, but held, so idle `b` is evicted
+        *locks.lock(c).await = head;
+        assert_eq!(locks.len(), 2);
+        drop(held);
+
+        // Tracking `b` again evicts `a`, now the least recently used idle entry
+        let mut guard = locks.lock(b).await;
+        assert!(guard.is_none());
+        *guard = head;
+        drop(guard);
+        assert_eq!(locks.len(), 2);
+        assert_eq!(*locks.lock(c).await, head);
+        assert_eq!(*locks.lock(b).await, head);
+        assert_eq!(*locks.lock(a).await, None);
+    }
+}
@@ -5,52 +5,93 @@ use std::sync::Arc;
 
 use axum::Router;
 use tokio::sync::RwLock;
-use tower_http::cors::{Any, CorsLayer};
 
+mod config;
 mod handlers;
+mod inventory_locks;
+mod proving;
 mod routes;
+mod smt_cache;
+
+use config::ServerConfig;
+use inventory_locks::InventoryLocks;
+use proving::ProvingPool;
+use smt_cache::SmtCache;
 
 use inventory_prover::setup::{setup_all_circuits, CircuitKeys};
+use inventory_prover::PreparedCircuitKeys;
 
 /// Application state shared across handlers
 pub struct AppState {
     pub keys: Arc<CircuitKeys>,
+    /// Verifying keys processed once at startup, for the verify endpoint
+    pub prepared_keys: Arc<PreparedCircuitKeys>,
+    /// Latest proven state per inventory, for serializing transitions
+    pub inventories: InventoryLocks,
+    /// SMT each inventory ended on, so its next transition skips the rebuild
+    pub smt_cache: SmtCache,
+    /// Pool that runs setup and proving, isolated from the tracing subscriber
+    pub proving_pool: ProvingPool,
 }
 
-#[tokio::main(flavor = "multi_thread", worker_threads = 2)]
-async fn main() {
-    // CRITICAL: tracing_subscriber causes 400x performance regression with Rayon!
-    // When enabled, proof generation takes 6+ seconds instead of 15-20ms.
-    // Issue: tracing's thread-local context interferes with Rayon's work-stealing.
-    // Using println! instead of tracing for server startup messages.
+fn main() {
+    // arkworks leaks a tracing span per constraint namespace, which made proofs
+    // take seconds instead of 15-20ms under a global subscriber. Setup and
+    // proving run on `ProvingPool`, whose threads ignore the subscriber, so
+    // tracing is safe to enable here (filtered by RUST_LOG).
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
 
     println!("Starting inventory proof server...");
 
+    let config = ServerConfig::from_env();
+    let proving_pool =
+        ProvingPool::new(config.proving_threads).expect("Failed to build proving thread pool");
+    println!(
+        "Using {} worker threads, {} proving threads",
+        config.worker_threads,
+        proving_pool.num_threads()
+    );
+
+    config::build_runtime(&config)
+        .expect("Failed to build tokio runtime")
+        .block_on(run(proving_pool, config));
+}
+
+async fn run(proving_pool: ProvingPool, config: ServerConfig) {
+
     // Load or generate circuit keys
     let keys_dir = std::path::Path::new("keys");
     let keys = if keys_dir.exists() {
         println!("Loading existing circuit keys from {:?}", keys_dir);
-        CircuitKeys::load_from_directory(keys_dir).expect("Failed to load circuit keys")
+        proving_pool
+            .install(|| CircuitKeys::load_from_directory_parallel(keys_dir))
+            .expect("Failed to load circuit keys")
     } else {
         println!("Running trusted setup (this may take a while)...");
-        let keys = setup_all_circuits().expect("Failed to setup circuits");
+        let keys = proving_pool
+            .install(setup_all_circuits)
+            .expect("Failed to setup circuits");
         keys.save_to_directory(keys_dir)
             .expect("Failed to save circuit keys");
         println!("Circuit keys saved to {:?}", keys_dir);
         keys
     };
 
-    let state = Arc::new(RwLock::new(AppState { keys: Arc::new(keys) }));
+    let state = Arc::new(RwLock::new(AppState {
+        prepared_keys: Arc::new(PreparedCircuitKeys::new(&keys)),
+        keys: Arc::new(keys),
+        inventories: InventoryLocks::new(config.smt_cache_capacity),
+        smt_cache: SmtCache::new(config.smt_cache_capacity),
+        proving_pool,
+    }));
 
     // Build router
     let app = Router::new()
         .merge(routes::api_routes())
-        .layer(
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(Any),
-        )
+        .layer(config::build_timeout_layer(config.request_timeout))
+        .layer(config::build_cors_layer(config.allowed_origins))
         .with_state(state);
 
     // Start server
@@ -58,5 +99,18 @@ async fn main() {
     println!("Listening on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .unwrap();
+    println!("Server stopped");
+}
+
+/// Resolve on Ctrl-C, after which the server stops accepting connections and
+/// drains in-flight requests (each bounded by the request timeout).
+async fn shutdown_signal() {
+    tokio::signal::ctrl_c()
+        .await
+        .expect("Failed to install Ctrl-C handler");
+    println!("Shutting down, draining in-flight requests...");
 }
@@ -0,0 +1,147 @@
+//! Dedicated Rayon pool for proof generation.
+//!
+//! arkworks opens an `info` span for every constraint namespace and never
+//! closes it (`ark_relations::ns!` forgets the guard). With a global tracing
+//! subscriber installed those spans pile up on the proving threads, and proofs
+//! took seconds instead of 15-20ms. The pool's threads run with a no-op
+//! default subscriber, so proving ignores whatever subscriber the server uses.
+
+use std::sync::Arc;
+
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+use tracing::subscriber::NoSubscriber;
+
+/// Rayon pool whose threads are isolated from the global tracing subscriber.
+#[derive(Clone)]
+pub struct ProvingPool {
+    pool: Arc<ThreadPool>,
+}
+
+impl ProvingPool {
+    /// Build a pool with `num_threads` proving threads.
+    pub fn new(num_threads: usize) -> Result<Self, ThreadPoolBuildError> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|index| format!("proving-{}", index))
+            .spawn_handler(|thread| {
+                let mut builder = std::thread::Builder::new();
+                if let Some(name) = thread.name() {
+                    builder = builder.name(name.to_string());
+                }
+                if let Some(stack_size) = thread.stack_size() {
+                    builder = builder.stack_size(stack_size);
+                }
+                builder.spawn(|| {
+                    tracing::subscriber::with_default(NoSubscriber::default(), || thread.run())
+                })?;
+                Ok(())
+            })
+            .build()?;
+
+        Ok(Self {
+            pool: Arc::new(pool),
+        })
+    }
+
+    /// Number of proving threads.
+    pub fn num_threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    /// Run `f` on the pool, blocking the calling thread until it finishes.
+    ///
+    /// Rayon work inside `f` (arkworks' `parallel` feature) stays on the pool.
+    pub fn install<T, F>(&self, f: F) -> T
+    where
+        F: FnOnce() -> T + Send,
+        T: Send,
+    {
+        self.pool.install(f)
+    }
+
+    /// Run `f` on the pool from a blocking task, keeping it off the tokio workers.
+    pub async fn run<T, F>(&self, f: F) -> T
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.pool.clone();
+        match tokio::task::spawn_blocking(move || pool.install(f)).await {
+            Ok(value) => value,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    use ark_bn254::Fr;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use inventory_prover::setup::setup_capacity;
+    use inventory_prover::{prove, InventoryState};
+
+    use crate::config::available_cores;
+
+    const RUNS: u32 = 3;
+
+    #[tokio::test]
+    async fn test_run_returns_result() {
+        let pool = ProvingPool::new(2).unwrap();
+
+        assert_eq!(pool.num_threads(), 2);
+        assert_eq!(pool.run(rayon::current_num_threads).await, 2);
+    }
+
+    #[test]
+    fn test_pool_threads_use_no_op_subscriber() {
+        let pool = ProvingPool::new(2).unwrap();
+        let is_no_op = || tracing::dispatcher::get_default(|d| d.is::<NoSubscriber>());
+
+        // A subscriber set on the calling thread does not reach the pool
+        let subscriber = tracing_subscriber::fmt().finish();
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(!is_no_op());
+            assert!(pool.install(is_no_op));
+            let (a, b) = pool.install(|| rayon::join(is_no_op, is_no_op));
+            assert!(a && b);
+        });
+    }
+
+    /// Timing comparison under a global subscriber; run with `--ignored`.
+    ///
+    /// Installs the subscriber for the whole test binary, so it stays out of
+    /// the default run.
+    #[test]
+    #[ignore]
+    fn test_proving_unaffected_by_global_subscriber() {
+        let keys = setup_capacity(&mut StdRng::seed_from_u64(42)).unwrap();
+        let mut state = InventoryState::new(Fr::from(12345u64));
+        state.tree.update(1, 100);
+        state.current_volume = 500;
+
+        let pool = ProvingPool::new(available_cores()).unwrap();
+        let mean_proof_time = || -> Duration {
+            let start = Instant::now();
+            for _ in 0..RUNS {
+                pool.install(|| prove::prove_capacity(&keys.proving_key, &state, 1000))
+                    .unwrap();
+            }
+            start.elapsed() / RUNS
+        };
+
+        let baseline = mean_proof_time();
+        let _ = tracing::subscriber::set_global_default(tracing_subscriber::fmt().finish());
+        let traced = mean_proof_time();
+
+        println!("mean proof time: {:?} untraced, {:?} traced", baseline, traced);
+        assert!(
+            traced < baseline * 2 + Duration::from_millis(50),
+            "proving regressed under tracing: {:?} vs {:?}",
+            traced,
+            baseline
+        );
+    }
+}
@@ -18,9 +18,21 @@ pub fn api_routes() -> Router<Arc<RwLock<AppState>>> {
         .route("/health", get(handlers::health))
         // SMT-based proof generation endpoints
         .route("/api/prove/state-transition", post(handlers::prove_state_transition))
+        .route(
+            "/api/prove/withdraw-with-capacity",
+            post(handlers::prove_withdraw_with_capacity),
+        )
         .route("/api/prove/item-exists", post(handlers::prove_item_exists))
         .route("/api/prove/capacity", post(handlers::prove_capacity))
+        // Verifying keys for on-chain registration
+        .route("/api/vk/:circuit", get(handlers::get_verifying_key))
+        // Off-chain verification for integration testing
+        .route("/api/verify/:circuit", post(handlers::verify_proof))
         // Utility endpoints
         .route("/api/commitment/create", post(handlers::create_commitment))
+        .route("/api/inventory/commit-smt", post(handlers::commit_smt_inventory))
+        .route("/api/inventory/open", post(handlers::open_inventory))
+        .route("/api/capacity/remaining", post(handlers::remaining_capacity))
+        .route("/api/registry/verify", post(handlers::verify_registry))
         .route("/api/blinding/generate", post(handlers::generate_blinding))
 }
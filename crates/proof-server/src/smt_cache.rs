@@ -0,0 +1,150 @@
+//! Per-inventory cache of the SMT a transition ends on.
+//!
+//! Building a tree from a request's item list hashes every leaf path,
+//! O(items * depth). A transition only changes one leaf, so the tree it
+//! produces is cached by inventory ID and reused when the next request for
+//! that inventory lists exactly the items it holds. Any other item list
+//! rebuilds the tree, so a stale entry can cost time but never correctness.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use ark_bn254::Fr;
+use inventory_circuits::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+
+use crate::config::DEFAULT_SMT_CACHE_CAPACITY;
+
+/// Least recently used map from inventory ID to its latest tree.
+pub struct SmtCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+    hits: AtomicU64,
+}
+
+#[derive(Default)]
+struct Entries {
+    /// Tree and the tick it was last used at
+    trees: HashMap<Fr, (SparseMerkleTree, u64)>,
+    tick: u64,
+}
+
+impl Entries {
+    fn touch(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+impl Default for SmtCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_SMT_CACHE_CAPACITY)
+    }
+}
+
+impl SmtCache {
+    /// Create a cache holding at most `capacity` trees.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::default(),
+            hits: AtomicU64::new(0),
+        }
+    }
+
+    /// Get `inventory_id`'s cached tree if it holds exactly `items`, or build one.
+    pub fn get_or_build(&self, inventory_id: Fr, items: &[(u64, u64)]) -> SparseMerkleTree {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            let tick = entries.touch();
+            if let Some((tree, last_used)) = entries.trees.get_mut(&inventory_id) {
+                if holds_exactly(tree, items) {
+                    *last_used = tick;
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return tree.clone();
+                }
+            }
+        }
+        SparseMerkleTree::from_items(items, DEFAULT_DEPTH)
+    }
+
+    /// Record the tree `inventory_id` holds after a transition.
+    ///
+    /// Evicts the least recently used inventory when full.
+    pub fn insert(&self, inventory_id: Fr, tree: SparseMerkleTree) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.trees.len() >= self.capacity && !entries.trees.contains_key(&inventory_id) {
+            let oldest = entries
+                .trees
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(&id, _)| id);
+            if let Some(oldest) = oldest {
+                entries.trees.remove(&oldest);
+            }
+        }
+        let tick = entries.touch();
+        entries.trees.insert(inventory_id, (tree, tick));
+    }
+
+    /// Number of requests served from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+}
+
+/// Whether `tree` holds exactly `items` (absent items count as quantity 0).
+fn holds_exactly(tree: &SparseMerkleTree, items: &[(u64, u64)]) -> bool {
+    let non_empty = items.iter().filter(|&&(_, quantity)| quantity != 0).count();
+    tree.len() == non_empty && items.iter().all(|&(item_id, quantity)| tree.get(item_id) == quantity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hit_only_for_matching_items() {
+        let cache = SmtCache::new(4);
+        let id = Fr::from(1u64);
+
+        let mut tree = cache.get_or_build(id, &[(1, 10)]);
+        assert_eq!(cache.hits(), 0);
+        tree.update(1, 15);
+        cache.insert(id, tree.clone());
+
+        let cached = cache.get_or_build(id, &[(1, 15)]);
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(cached.root(), tree.root());
+
+        // A stale or different item list is rebuilt, not served from the cache
+        let rebuilt = cache.get_or_build(id, &[(1, 10)]);
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(rebuilt.root(), SparseMerkleTree::from_items(&[(1, 10)], DEFAULT_DEPTH).root());
+        cache.get_or_build(id, &[(1, 15), (2, 1)]);
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = SmtCache::new(2);
+        let items = [(1, 10)];
+        let tree = SparseMerkleTree::from_items(&items, DEFAULT_DEPTH);
+        let (a, b, c) = (Fr::from(1u64), Fr::from(2u64), Fr::from(3u64));
+
+        cache.insert(a, tree.clone());
+        cache.insert(b, tree.clone());
+        // Using `a` leaves `b` as the least recently used
+        cache.get_or_build(a, &items);
+        cache.insert(c, tree);
+
+        cache.get_or_build(a, &items);
+        cache.get_or_build(c, &items);
+        assert_eq!(cache.hits(), 3);
+        cache.get_or_build(b, &items);
+        assert_eq!(cache.hits(), 3);
+    }
+}
@@ -8,22 +8,23 @@ use std::path::Path;
 use std::time::Instant;
 
 use ark_bn254::Fr;
-use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use ark_relations::r1cs::ConstraintSynthesizer;
 
 use inventory_circuits::{
+    synthesize,
     CapacitySMTCircuit,
     ItemExistsSMTCircuit,
     StateTransitionCircuit,
+    VolumeRegistry,
     DEFAULT_DEPTH,
     OpType,
 };
 
 fn count_constraints<C: ConstraintSynthesizer<Fr>>(circuit: C, name: &str) -> usize {
-    let cs = ConstraintSystem::<Fr>::new_ref();
-    circuit.generate_constraints(cs.clone()).unwrap();
+    let cs = synthesize(circuit).unwrap();
     let count = cs.num_constraints();
-    // Note: empty circuits use dummy values so they may not satisfy all constraints
-    // The constraint count is still accurate
+    // Empty circuits use dummy values that satisfy their own constraints
+    // (checked by setup::validate_empty_circuit), so the count is accurate
     println!("{:25} {:>8} constraints", name, count);
     count
 }
@@ -118,6 +119,7 @@ fn run_timing_benchmarks(keys_path: &Path) {
     let mut state = InventoryState::new(blinding);
     state.tree.update(42, 100);
     state.current_volume = 500;
+    let registry = VolumeRegistry::from_entries(&[(3, 1)]);
 
     // Warm up (first proof is slower due to caching)
     let _ = prove::prove_capacity(&keys.capacity.proving_key, &state, 1000);
@@ -126,7 +128,7 @@ fn run_timing_benchmarks(keys_path: &Path) {
     const RUNS: usize = 3;
 
     // Constraint counts for efficiency calculation
-    const CAP_CONSTRAINTS: u128 = 724;
+    const CAP_CONSTRAINTS: u128 = 790;
     const ITEM_CONSTRAINTS: u128 = 4124;
     const STATE_CONSTRAINTS: u128 = 8255;
 
@@ -173,10 +175,9 @@ fn run_timing_benchmarks(keys_path: &Path) {
             &keys.state_transition.proving_key,
             &state,
             Fr::from(99999u64), // new_blinding
-            42,                 // item_id
+            3,                  // item_id
             50,                 // amount
-            1,                  // item_volume
-            Fr::from(0u64),     // registry_root
+            &registry,          // registry
             1000,               // max_capacity
             0,                  // nonce
             Fr::from(12345u64), // inventory_id
@@ -0,0 +1,60 @@
+//! Key loading benchmark - compares the boot-time key loaders
+//!
+//! Usage:
+//!   cargo run --release --bin load-bench [keys_dir]
+//!
+//! Loads from `keys_dir` (default: keys). If it holds no keys, runs the
+//! seeded setup and saves keys to a temporary directory first.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use inventory_prover::setup::{setup_all_circuits, CircuitKeys, SetupError};
+
+type Loader = fn(&Path) -> Result<CircuitKeys, SetupError>;
+
+const RUNS: usize = 5;
+
+fn main() {
+    let mut keys_dir = PathBuf::from(std::env::args().nth(1).unwrap_or_else(|| "keys".into()));
+    if !keys_dir.join("state_transition.pk").exists() {
+        keys_dir = std::env::temp_dir().join("inventory-load-bench");
+        println!("No keys found, running setup into {:?}...", keys_dir);
+        setup_all_circuits()
+            .and_then(|keys| keys.save_to_directory(&keys_dir))
+            .expect("Failed to set up keys");
+    }
+
+    let loaders: [(&str, Loader); 3] = [
+        ("Sequential (buffered)", CircuitKeys::load_from_directory),
+        ("Sequential (streaming)", CircuitKeys::load_from_directory_streaming),
+        ("Parallel (streaming)", CircuitKeys::load_from_directory_parallel),
+    ];
+
+    println!("\nLoader                    Fastest     Mean");
+    println!("──────────────────────────────────────────────");
+
+    let mut baseline = None;
+    for (name, load) in loaders {
+        let times: Vec<Duration> = (0..RUNS)
+            .map(|_| {
+                let start = Instant::now();
+                load(&keys_dir).expect("Failed to load keys");
+                start.elapsed()
+            })
+            .collect();
+        let fastest = *times.iter().min().unwrap();
+        let mean = times.iter().sum::<Duration>() / RUNS as u32;
+        let baseline = *baseline.get_or_insert(fastest);
+
+        println!(
+            "{:24} {:>9.1?} {:>9.1?}   ({:.2}x)",
+            name,
+            fastest,
+            mean,
+            baseline.as_secs_f64() / fastest.as_secs_f64()
+        );
+    }
+
+    println!("\n({} runs each, {} rayon threads)", RUNS, rayon::current_num_threads());
+}
@@ -0,0 +1,83 @@
+//! Verification benchmark - reports verify latency against public-input count
+//!
+//! Usage:
+//!   cargo run --release --bin verify-bench
+//!
+//! Runs a seeded setup for each circuit, so no keys are needed.
+
+use ark_bn254::Fr;
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+use inventory_circuits::VolumeRegistry;
+use inventory_prover::setup::{
+    setup_capacity, setup_item_exists, setup_selective_disclosure, setup_state_transition,
+};
+use inventory_prover::{prove, proof_info, InventoryState, OpType, ProofWithInputs};
+
+const RUNS: usize = 50;
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(42);
+
+    let mut state = InventoryState::new(Fr::from(12345u64));
+    state.tree.update(42, 100);
+    state.current_volume = 500;
+    let registry = VolumeRegistry::from_entries(&[(3, 1)]);
+
+    println!("Running setup and proving once per circuit...\n");
+
+    let capacity = setup_capacity(&mut rng).unwrap();
+    let item_exists = setup_item_exists(&mut rng).unwrap();
+    let disclosure = setup_selective_disclosure(&mut rng).unwrap();
+    let transition = setup_state_transition(&mut rng).unwrap();
+
+    let cases: Vec<(&str, _, ProofWithInputs)> = vec![
+        (
+            "CapacityProof",
+            &capacity,
+            prove::prove_capacity(&capacity.proving_key, &state, 1000).unwrap(),
+        ),
+        (
+            "ItemExists",
+            &item_exists,
+            prove::prove_item_exists(&item_exists.proving_key, &state, 42, 50).unwrap(),
+        ),
+        (
+            "SelectiveDisclosure",
+            &disclosure,
+            prove::prove_disclose_item(&disclosure.proving_key, &state, 42).unwrap(),
+        ),
+        (
+            "StateTransition",
+            &transition,
+            prove::prove_state_transition(
+                &transition.proving_key,
+                &state,
+                Fr::from(99999u64), // new_blinding
+                3,                  // item_id
+                50,                 // amount
+                &registry,          // registry
+                1000,               // max_capacity
+                0,                  // nonce
+                Fr::from(12345u64), // inventory_id
+                OpType::Deposit,    // op_type
+            )
+            .unwrap()
+            .proof,
+        ),
+    ];
+
+    println!("Circuit              Inputs    Prepare inputs    Verify");
+    println!("────────────────────────────────────────────────────────");
+
+    for (name, keys, proof) in cases {
+        let info = proof_info(&keys.verifying_key, &proof.proof, &proof.public_inputs, RUNS)
+            .expect("benchmark proof must verify");
+        println!(
+            "{:20} {:>6}    {:>11.1?}    {:>9.1?}",
+            name, info.public_inputs, info.prepare_inputs_time, info.verify_time
+        );
+    }
+
+    println!("\n(Fastest of {} runs; inputs prepared at full width, one G1 mul each)", RUNS);
+}
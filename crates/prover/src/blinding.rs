@@ -0,0 +1,48 @@
+//! Blinding factors for inventory commitments.
+//!
+//! A commitment only hides the inventory if its blinding factor is uniform in
+//! the field and unpredictable, so `rng` must be a CSPRNG (`CryptoRng` is
+//! required for that reason). Never derive blindings from a seeded test RNG
+//! in production.
+
+use ark_bn254::Fr;
+use ark_ff::Zero;
+use ark_std::rand::{CryptoRng, RngCore};
+use ark_std::UniformRand;
+
+/// Sample a non-zero blinding factor uniformly from the field.
+///
+/// `Fr::rand` rejection-samples, so the result carries no modular bias.
+pub fn generate_blinding<R: RngCore + CryptoRng>(rng: &mut R) -> Fr {
+    loop {
+        let blinding = Fr::rand(rng);
+        if !blinding.is_zero() {
+            return blinding;
+        }
+    }
+}
+
+/// Sample independent (old, new) blinding factors for a state transition.
+pub fn generate_blinding_pair<R: RngCore + CryptoRng>(rng: &mut R) -> (Fr, Fr) {
+    (generate_blinding(rng), generate_blinding(rng))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_blindings_distinct_and_non_zero() {
+        let mut rng = StdRng::seed_from_u64(42);
+
+        let samples: Vec<Fr> = (0..1000).map(|_| generate_blinding(&mut rng)).collect();
+        assert!(samples.iter().all(|b| !b.is_zero()));
+        assert_eq!(samples.iter().collect::<HashSet<_>>().len(), samples.len());
+
+        let (old, new) = generate_blinding_pair(&mut rng);
+        assert_ne!(old, new);
+        assert!(!samples.contains(&old) && !samples.contains(&new));
+    }
+}
@@ -0,0 +1,202 @@
+//! Proving/verifying key containers and their serialization.
+
+use std::fs::File;
+use std::io::BufReader;
+
+use ark_bn254::Bn254;
+use ark_groth16::{ProvingKey, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use blake2::{Blake2b512, Digest};
+use thiserror::Error;
+
+use crate::par;
+
+/// Errors that can occur during setup
+#[derive(Error, Debug)]
+pub enum SetupError {
+    #[error("Circuit setup failed: {0}")]
+    CircuitSetup(String),
+    #[error("Empty circuit is not satisfiable: {0}")]
+    UnsatisfiableCircuit(String),
+    #[error("Serialization failed: {0}")]
+    Serialization(String),
+    #[error("Deserialization failed: {0}")]
+    Deserialization(String),
+    #[error("Keys were generated for tree depth {found}, but circuits use depth {expected}")]
+    DepthMismatch { expected: usize, found: usize },
+    #[error("Keys do not match the {0} circuit structure")]
+    KeyMismatch(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Keys for a single circuit
+#[derive(Clone)]
+pub struct CircuitKeyPair {
+    pub proving_key: ProvingKey<Bn254>,
+    pub verifying_key: VerifyingKey<Bn254>,
+}
+
+impl From<(ProvingKey<Bn254>, VerifyingKey<Bn254>)> for CircuitKeyPair {
+    fn from((proving_key, verifying_key): (ProvingKey<Bn254>, VerifyingKey<Bn254>)) -> Self {
+        Self { proving_key, verifying_key }
+    }
+}
+
+impl CircuitKeyPair {
+    /// Serialize proving key to bytes
+    pub fn serialize_pk(&self) -> Result<Vec<u8>, SetupError> {
+        let mut bytes = Vec::new();
+        self.proving_key
+            .serialize_compressed(&mut bytes)
+            .map_err(|e| SetupError::Serialization(e.to_string()))?;
+        Ok(bytes)
+    }
+
+    /// Serialize verifying key to bytes
+    pub fn serialize_vk(&self) -> Result<Vec<u8>, SetupError> {
+        let mut bytes = Vec::new();
+        self.verifying_key
+            .serialize_compressed(&mut bytes)
+            .map_err(|e| SetupError::Serialization(e.to_string()))?;
+        Ok(bytes)
+    }
+
+    /// Short fingerprint of the verifying key: hex of the first 8 bytes of its
+    /// Blake2b hash, so clients can check which trusted setup a server uses
+    pub fn vk_fingerprint(&self) -> Result<String, SetupError> {
+        let hash = Blake2b512::digest(self.serialize_vk()?);
+        Ok(hex::encode(&hash[..8]))
+    }
+
+    /// Deserialize proving key from bytes
+    pub fn deserialize_pk(bytes: &[u8]) -> Result<ProvingKey<Bn254>, SetupError> {
+        ProvingKey::deserialize_compressed(bytes)
+            .map_err(|e| SetupError::Deserialization(e.to_string()))
+    }
+
+    /// Deserialize verifying key from bytes
+    pub fn deserialize_vk(bytes: &[u8]) -> Result<VerifyingKey<Bn254>, SetupError> {
+        VerifyingKey::deserialize_compressed(bytes)
+            .map_err(|e| SetupError::Deserialization(e.to_string()))
+    }
+
+    /// Load `<name>.pk` and `<name>.vk` from `dir`, deserializing from buffered files.
+    fn load_streaming(dir: &std::path::Path, name: &str) -> Result<Self, SetupError> {
+        let open = |extension: &str| -> Result<BufReader<File>, SetupError> {
+            Ok(BufReader::new(File::open(dir.join(format!("{}.{}", name, extension)))?))
+        };
+
+        Ok(Self {
+            proving_key: ProvingKey::deserialize_compressed(open("pk")?)
+                .map_err(|e| SetupError::Deserialization(e.to_string()))?,
+            verifying_key: VerifyingKey::deserialize_compressed(open("vk")?)
+                .map_err(|e| SetupError::Deserialization(e.to_string()))?,
+        })
+    }
+}
+
+/// All circuit keys for SMT-based circuits
+pub struct CircuitKeys {
+    /// StateTransition circuit (deposit/withdraw with capacity)
+    pub state_transition: CircuitKeyPair,
+    /// ItemExists circuit (prove ownership of items)
+    pub item_exists: CircuitKeyPair,
+    /// Capacity circuit (prove volume compliance)
+    pub capacity: CircuitKeyPair,
+}
+
+impl CircuitKeys {
+    /// Save all keys to a directory
+    pub fn save_to_directory(&self, dir: &std::path::Path) -> Result<(), SetupError> {
+        std::fs::create_dir_all(dir)?;
+
+        std::fs::write(
+            dir.join("state_transition.pk"),
+            self.state_transition.serialize_pk()?,
+        )?;
+        std::fs::write(
+            dir.join("state_transition.vk"),
+            self.state_transition.serialize_vk()?,
+        )?;
+
+        std::fs::write(dir.join("item_exists.pk"), self.item_exists.serialize_pk()?)?;
+        std::fs::write(dir.join("item_exists.vk"), self.item_exists.serialize_vk()?)?;
+
+        std::fs::write(dir.join("capacity.pk"), self.capacity.serialize_pk()?)?;
+        std::fs::write(dir.join("capacity.vk"), self.capacity.serialize_vk()?)?;
+
+        Ok(())
+    }
+
+    /// Load all keys from a directory
+    pub fn load_from_directory(dir: &std::path::Path) -> Result<Self, SetupError> {
+        let load = |name: &str| -> Result<CircuitKeyPair, SetupError> {
+            Ok(CircuitKeyPair {
+                proving_key: CircuitKeyPair::deserialize_pk(&std::fs::read(
+                    dir.join(format!("{}.pk", name)),
+                )?)?,
+                verifying_key: CircuitKeyPair::deserialize_vk(&std::fs::read(
+                    dir.join(format!("{}.vk", name)),
+                )?)?,
+            })
+        };
+
+        Self::from_loaded(load("state_transition")?, load("item_exists")?, load("capacity")?)
+    }
+
+    /// Load all keys from a directory, deserializing straight from buffered files.
+    ///
+    /// Same result as `load_from_directory` without holding each key file's
+    /// bytes in memory alongside the decoded key, which lowers peak memory at
+    /// startup.
+    pub fn load_from_directory_streaming(dir: &std::path::Path) -> Result<Self, SetupError> {
+        let load = |name| CircuitKeyPair::load_streaming(dir, name);
+        Self::from_loaded(load("state_transition")?, load("item_exists")?, load("capacity")?)
+    }
+
+    /// Load all keys from a directory, deserializing the key pairs in parallel.
+    ///
+    /// Proving key deserialization is CPU-bound, so this cuts boot time on
+    /// multi-core machines. Each pair is streamed as in
+    /// `load_from_directory_streaming`; the result is identical.
+    pub fn load_from_directory_parallel(dir: &std::path::Path) -> Result<Self, SetupError> {
+        let load = |name| CircuitKeyPair::load_streaming(dir, name);
+        let (state_transition, (item_exists, capacity)) = par::join(
+            || load("state_transition"),
+            || par::join(|| load("item_exists"), || load("capacity")),
+        );
+        Self::from_loaded(state_transition?, item_exists?, capacity?)
+    }
+
+    /// Assemble loaded keys, checking they match the circuits' tree depth.
+    fn from_loaded(
+        state_transition: CircuitKeyPair,
+        item_exists: CircuitKeyPair,
+        capacity: CircuitKeyPair,
+    ) -> Result<Self, SetupError> {
+        // Catch keys generated for another tree depth before any proof attempt
+        #[cfg(feature = "prove")]
+        {
+            use crate::setup::check_key_depth;
+            use inventory_circuits::{ItemExistsSMTCircuit, StateTransitionCircuit};
+
+            check_key_depth(
+                "state_transition",
+                &state_transition.proving_key,
+                StateTransitionCircuit::empty_with_depth,
+            )?;
+            check_key_depth(
+                "item_exists",
+                &item_exists.proving_key,
+                ItemExistsSMTCircuit::empty_with_depth,
+            )?;
+        }
+
+        Ok(Self {
+            state_transition,
+            item_exists,
+            capacity,
+        })
+    }
+}
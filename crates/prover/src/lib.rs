@@ -4,18 +4,64 @@
 //! - Trusted setup (generating proving and verifying keys)
 //! - Proof generation for SMT-based circuits
 //! - Local proof verification (for testing)
+//! - Off-circuit signal hash computation (`signal`)
+//! - Commitment blinding factor generation (`blinding`)
+//!
+//! Setup and proving live behind the default `prove` feature. Verifier-only
+//! consumers build with `--no-default-features --features verify-only`, which
+//! keeps `verify`, `keys` and `proof` without pulling in the circuits.
+//!
+//! Browser wallets build with `--no-default-features --features single_threaded`
+//! (e.g. for wasm32-unknown-unknown). Proving then never spawns threads, at
+//! the cost of running MSMs and FFTs on a single core: expect proofs several
+//! times slower than the default `parallel` build on a multi-core machine.
+//! Features are additive, so `parallel` takes precedence when both are enabled.
 
+pub mod blinding;
+pub mod keys;
+mod par;
+pub mod proof;
+#[cfg(feature = "prove")]
 pub mod prove;
+#[cfg(feature = "prove")]
 pub mod setup;
+#[cfg(feature = "prove")]
+pub mod signal;
 pub mod verify;
 
+pub use blinding::{generate_blinding, generate_blinding_pair};
+pub use keys::{CircuitKeyPair, CircuitKeys, SetupError};
+pub use proof::{ProofEnvelope, ProofWithInputs, ProveError, PROOF_FORMAT_VERSION};
+
+#[cfg(feature = "prove")]
 pub use inventory_circuits::signal::OpType;
+#[cfg(feature = "prove")]
 pub use prove::{
-    prove_capacity, prove_item_exists, prove_state_transition, InventoryState, ProofWithInputs,
-    StateTransitionResult,
+    check_proof, prove_and_bundle, prove_and_verify, prove_batch_transition,
+    prove_batch_transition_with_rng, prove_capacity, prove_capacity_batch,
+    prove_capacity_batch_with_rng, prove_capacity_hidden_cap, prove_capacity_hidden_cap_with_rng,
+    prove_capacity_with_registry, prove_capacity_with_registry_with_rng, prove_capacity_with_rng,
+    prove_deposit_with_delta_cap, prove_deposit_with_delta_cap_with_rng, prove_disclose_item,
+    prove_disclose_item_with_rng, prove_double_transition, prove_double_transition_with_rng,
+    prove_item_exists, prove_item_exists_batch, prove_item_exists_batch_with_rng,
+    prove_item_exists_with_capacity, prove_item_exists_with_capacity_with_rng,
+    prove_item_exists_with_rng, prove_non_membership, prove_non_membership_with_rng,
+    prove_state_transition, prove_state_transition_from_items,
+    prove_state_transition_from_items_with_rng, prove_state_transition_with_rng,
+    prove_subset_capacity, prove_subset_capacity_with_rng, prove_swap, prove_swap_with_rng,
+    prove_withdraw_with_capacity, prove_withdraw_with_capacity_with_rng, CapacityRequest, DoubleTransitionResult, InventoryState, ItemExistsRequest,
+    StateTransitionResult, TransitionOp,
+};
+#[cfg(feature = "prove")]
+pub use setup::setup_all_circuits;
+#[cfg(feature = "prove")]
+pub use signal::{compute_state_transition_signal, registry_hash_matches, signal_hash_to_hex};
+pub use verify::{
+    proof_info, public_input_from_bytes, public_input_from_hex, public_inputs_from_bytes, verify,
+    verify_batch, verify_capacity, verify_detailed, verify_item_exists, verify_prepared,
+    verify_state_transition, CircuitKind, PreparedCircuitKeys, ProofInfo, VerifyError,
+    VerifyReport,
 };
-pub use setup::{setup_all_circuits, CircuitKeys, CircuitKeyPair, SetupError};
-pub use verify::{verify_capacity, verify_item_exists, verify_state_transition};
 
 use ark_bn254::Fr;
 
@@ -0,0 +1,17 @@
+//! Rayon shims that fall back to the calling thread without `parallel`.
+//!
+//! wasm32-unknown-unknown cannot spawn threads, so the `single_threaded`
+//! build runs every fork-join on the current thread instead.
+
+#[cfg(feature = "parallel")]
+pub(crate) use rayon::join;
+
+/// Run `a` then `b` on the current thread.
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA,
+    B: FnOnce() -> RB,
+{
+    (a(), b())
+}
@@ -0,0 +1,267 @@
+//! Proofs with their public inputs, and their serialization.
+
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::Proof;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use thiserror::Error;
+
+use crate::verify::CircuitKind;
+
+/// Errors during proof generation
+#[derive(Error, Debug)]
+pub enum ProveError {
+    #[error("Proof generation failed: {0}")]
+    ProofGeneration(String),
+    #[error("Invalid inventory state: {0}")]
+    InvalidState(String),
+    #[error("Insufficient quantity: have {have}, need {need}")]
+    InsufficientQuantity { have: u64, need: u64 },
+    #[error("Capacity exceeded: {volume} > {capacity}")]
+    CapacityExceeded { volume: u64, capacity: u64 },
+    #[error("Serialization failed: {0}")]
+    Serialization(String),
+    #[error("Generated proof does not verify against its verifying key")]
+    SelfCheckFailed,
+    #[cfg(feature = "prove")]
+    #[error("Invalid volume registry: {0}")]
+    Registry(#[from] inventory_circuits::RegistryError),
+}
+
+/// Leading byte of the `ProofWithInputs::serialize` format
+pub const PROOF_FORMAT_VERSION: u8 = 1;
+
+/// A proof with its public inputs (signal hash)
+#[derive(Clone)]
+pub struct ProofWithInputs {
+    pub proof: Proof<Bn254>,
+    pub public_inputs: Vec<Fr>,
+}
+
+impl ProofWithInputs {
+    /// Serialize proof to bytes
+    pub fn serialize_proof(&self) -> Result<Vec<u8>, ProveError> {
+        let mut bytes = Vec::new();
+        self.proof
+            .serialize_compressed(&mut bytes)
+            .map_err(|e| ProveError::Serialization(e.to_string()))?;
+        Ok(bytes)
+    }
+
+    /// Serialize public inputs to bytes (each Fr is 32 bytes)
+    pub fn serialize_public_inputs(&self) -> Result<Vec<u8>, ProveError> {
+        let mut bytes = Vec::new();
+        for input in &self.public_inputs {
+            input
+                .serialize_compressed(&mut bytes)
+                .map_err(|e| ProveError::Serialization(e.to_string()))?;
+        }
+        Ok(bytes)
+    }
+
+    /// Serialize the proof and its public inputs into a single blob.
+    ///
+    /// Layout: `PROOF_FORMAT_VERSION`, the compressed proof, the number of
+    /// public inputs as a little-endian u32, then each input as 32 bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, ProveError> {
+        let count = u32::try_from(self.public_inputs.len())
+            .map_err(|_| ProveError::Serialization("Too many public inputs".to_string()))?;
+
+        let mut bytes = vec![PROOF_FORMAT_VERSION];
+        bytes.extend(self.serialize_proof()?);
+        bytes.extend(count.to_le_bytes());
+        bytes.extend(self.serialize_public_inputs()?);
+        Ok(bytes)
+    }
+
+    /// Deserialize a blob produced by `serialize`
+    pub fn deserialize(bytes: &[u8]) -> Result<ProofWithInputs, ProveError> {
+        let (&version, mut reader) = bytes
+            .split_first()
+            .ok_or_else(|| ProveError::Serialization("Empty proof blob".to_string()))?;
+        if version != PROOF_FORMAT_VERSION {
+            return Err(ProveError::Serialization(format!(
+                "Unsupported proof format version: {}",
+                version
+            )));
+        }
+
+        let proof = Proof::deserialize_compressed(&mut reader)
+            .map_err(|e| ProveError::Serialization(e.to_string()))?;
+
+        let (count, mut reader) = reader
+            .split_first_chunk::<4>()
+            .ok_or_else(|| ProveError::Serialization("Missing public input count".to_string()))?;
+        let count = u32::from_le_bytes(*count) as usize;
+        if reader.len() != count * 32 {
+            return Err(ProveError::Serialization(format!(
+                "Expected {} public input bytes, got {}",
+                count * 32,
+                reader.len()
+            )));
+        }
+
+        let public_inputs = (0..count)
+            .map(|_| Fr::deserialize_compressed(&mut reader))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ProveError::Serialization(e.to_string()))?;
+
+        Ok(ProofWithInputs {
+            proof,
+            public_inputs,
+        })
+    }
+
+    /// Deserialize proof from bytes
+    pub fn deserialize_proof(bytes: &[u8]) -> Result<Proof<Bn254>, ProveError> {
+        Proof::deserialize_compressed(bytes).map_err(|e| ProveError::Serialization(e.to_string()))
+    }
+}
+
+/// A proof tagged with the circuit and trusted setup it was generated for.
+///
+/// A bare proof gives no hint of its verifying key. The envelope records it
+/// in-band, so a verifier can dispatch with `verify(envelope.circuit, ..)`
+/// and reject proofs meant for another circuit or setup up front.
+#[derive(Clone)]
+pub struct ProofEnvelope {
+    /// Circuit the proof was generated for
+    pub circuit: CircuitKind,
+    /// Trusted setup the proving key came from, as numbered by the deployer
+    pub setup_version: u32,
+    /// The proof and its public inputs
+    pub proof: ProofWithInputs,
+}
+
+impl ProofEnvelope {
+    /// Serialize the envelope into a single blob.
+    ///
+    /// Layout: the circuit's `CircuitKind::tag`, `setup_version` as a
+    /// little-endian u32, then the `ProofWithInputs::serialize` blob.
+    pub fn serialize(&self) -> Result<Vec<u8>, ProveError> {
+        let mut bytes = vec![self.circuit.tag()];
+        bytes.extend(self.setup_version.to_le_bytes());
+        bytes.extend(self.proof.serialize()?);
+        Ok(bytes)
+    }
+
+    /// Deserialize a blob produced by `serialize`.
+    ///
+    /// Fails if the tag names no circuit or the proof carries a different
+    /// number of public inputs than that circuit exposes.
+    pub fn deserialize(bytes: &[u8]) -> Result<ProofEnvelope, ProveError> {
+        let (&tag, reader) = bytes
+            .split_first()
+            .ok_or_else(|| ProveError::Serialization("Empty proof envelope".to_string()))?;
+        let circuit = CircuitKind::from_tag(tag)
+            .ok_or_else(|| ProveError::Serialization(format!("Unknown circuit tag: {}", tag)))?;
+
+        let (setup_version, reader) = reader
+            .split_first_chunk::<4>()
+            .ok_or_else(|| ProveError::Serialization("Missing setup version".to_string()))?;
+        let setup_version = u32::from_le_bytes(*setup_version);
+
+        let proof = ProofWithInputs::deserialize(reader)?;
+        if proof.public_inputs.len() != circuit.public_input_count() {
+            return Err(ProveError::Serialization(format!(
+                "{:?} expects {} public inputs, got {}",
+                circuit,
+                circuit.public_input_count(),
+                proof.public_inputs.len()
+            )));
+        }
+
+        Ok(ProofEnvelope {
+            circuit,
+            setup_version,
+            proof,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::{G1Affine, G2Affine};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use ark_std::UniformRand;
+
+    fn sample_proof() -> ProofWithInputs {
+        let mut rng = StdRng::seed_from_u64(42);
+        ProofWithInputs {
+            proof: Proof {
+                a: G1Affine::rand(&mut rng),
+                b: G2Affine::rand(&mut rng),
+                c: G1Affine::rand(&mut rng),
+            },
+            public_inputs: (0..4).map(|_| Fr::rand(&mut rng)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_serialize_roundtrip() {
+        let original = sample_proof();
+        let bytes = original.serialize().unwrap();
+        assert_eq!(bytes.len(), 1 + 128 + 4 + 4 * 32);
+
+        let restored = ProofWithInputs::deserialize(&bytes).unwrap();
+        assert_eq!(restored.proof, original.proof);
+        assert_eq!(restored.public_inputs, original.public_inputs);
+    }
+
+    #[test]
+    fn test_deserialize_rejects_malformed() {
+        let bytes = sample_proof().serialize().unwrap();
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[0] = PROOF_FORMAT_VERSION + 1;
+        assert!(ProofWithInputs::deserialize(&wrong_version).is_err());
+
+        assert!(ProofWithInputs::deserialize(&[]).is_err());
+        assert!(ProofWithInputs::deserialize(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(ProofWithInputs::deserialize(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_envelope_roundtrip() {
+        let envelope = ProofEnvelope {
+            circuit: CircuitKind::StateTransition,
+            setup_version: 3,
+            proof: sample_proof(),
+        };
+        let bytes = envelope.serialize().unwrap();
+
+        let restored = ProofEnvelope::deserialize(&bytes).unwrap();
+        assert_eq!(restored.circuit, CircuitKind::StateTransition);
+        assert_eq!(restored.setup_version, 3);
+        assert_eq!(restored.proof.proof, envelope.proof.proof);
+        assert_eq!(restored.proof.public_inputs, envelope.proof.public_inputs);
+    }
+
+    #[test]
+    fn test_envelope_circuit_mismatch() {
+        let mut proof = sample_proof();
+        proof.public_inputs.truncate(1);
+        let envelope = ProofEnvelope {
+            circuit: CircuitKind::ItemExists,
+            setup_version: 1,
+            proof,
+        };
+        let bytes = envelope.serialize().unwrap();
+
+        // A caller expecting a capacity proof can tell this one apart
+        let restored = ProofEnvelope::deserialize(&bytes).unwrap();
+        assert_ne!(restored.circuit, CircuitKind::Capacity);
+
+        // Relabelled as a state transition, the input count no longer fits
+        let mut relabelled = bytes.clone();
+        relabelled[0] = CircuitKind::StateTransition.tag();
+        assert!(ProofEnvelope::deserialize(&relabelled).is_err());
+
+        let mut unknown = bytes;
+        unknown[0] = u8::MAX;
+        assert!(ProofEnvelope::deserialize(&unknown).is_err());
+    }
+}
@@ -1,63 +1,26 @@
 //! Proof generation for SMT-based inventory circuits.
 
 use ark_bn254::{Bn254, Fr};
-use ark_groth16::{Groth16, Proof, ProvingKey};
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_groth16::{Groth16, ProvingKey, VerifyingKey};
 use ark_snark::SNARK;
-use ark_std::rand::{rngs::StdRng, SeedableRng};
-use thiserror::Error;
+use ark_std::rand::{rngs::StdRng, CryptoRng, RngCore, SeedableRng};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use inventory_circuits::{
     signal::OpType,
     smt::{MerkleProof, SparseMerkleTree, DEFAULT_DEPTH},
     smt_commitment::create_smt_commitment,
-    CapacitySMTCircuit, ItemExistsSMTCircuit, StateTransitionCircuit,
+    compute_registry_hash, BatchStateTransitionCircuit, CapacityHiddenSMTCircuit,
+    CapacitySMTCircuit, DoubleTransitionCircuit, ItemExistsSMTCircuit,
+    ItemExistsWithCapacityCircuit, NonMembershipSMTCircuit,
+    SelectiveDisclosureCircuit,
+    StateTransitionCircuit, SubsetCapacityCircuit, SwapCircuit, TransitionStep, VolumeRegistry,
+    MAX_BATCH_OPS,
 };
 
-/// Errors during proof generation
-#[derive(Error, Debug)]
-pub enum ProveError {
-    #[error("Proof generation failed: {0}")]
-    ProofGeneration(String),
-    #[error("Invalid inventory state: {0}")]
-    InvalidState(String),
-    #[error("Serialization failed: {0}")]
-    Serialization(String),
-}
-
-/// A proof with its public inputs (signal hash)
-#[derive(Clone)]
-pub struct ProofWithInputs {
-    pub proof: Proof<Bn254>,
-    pub public_inputs: Vec<Fr>,
-}
-
-impl ProofWithInputs {
-    /// Serialize proof to bytes
-    pub fn serialize_proof(&self) -> Result<Vec<u8>, ProveError> {
-        let mut bytes = Vec::new();
-        self.proof
-            .serialize_compressed(&mut bytes)
-            .map_err(|e| ProveError::Serialization(e.to_string()))?;
-        Ok(bytes)
-    }
-
-    /// Serialize public inputs to bytes (each Fr is 32 bytes)
-    pub fn serialize_public_inputs(&self) -> Result<Vec<u8>, ProveError> {
-        let mut bytes = Vec::new();
-        for input in &self.public_inputs {
-            input
-                .serialize_compressed(&mut bytes)
-                .map_err(|e| ProveError::Serialization(e.to_string()))?;
-        }
-        Ok(bytes)
-    }
-
-    /// Deserialize proof from bytes
-    pub fn deserialize_proof(bytes: &[u8]) -> Result<Proof<Bn254>, ProveError> {
-        Proof::deserialize_compressed(bytes).map_err(|e| ProveError::Serialization(e.to_string()))
-    }
-}
+use crate::keys::CircuitKeyPair;
+pub use crate::proof::{ProofWithInputs, ProveError};
 
 /// Client-side inventory state using SMT
 #[derive(Clone)]
@@ -105,6 +68,11 @@ impl InventoryState {
         self.tree.get_proof(item_id)
     }
 
+    /// Volume still free under `max_capacity`, or `None` if the inventory is over it
+    pub fn remaining_capacity(&self, max_capacity: u64) -> Option<u64> {
+        max_capacity.checked_sub(self.current_volume)
+    }
+
     /// Compute the commitment for this inventory state
     pub fn commitment(&self) -> Fr {
         create_smt_commitment(
@@ -134,7 +102,8 @@ impl InventoryState {
         new_tree.update(item_id, new_qty);
 
         // Update volume
-        let volume_delta = amount * item_volume;
+        let volume_delta = amount.checked_mul(item_volume)
+            .ok_or_else(|| ProveError::InvalidState("Volume overflow".into()))?;
         let new_volume = self.current_volume.checked_add(volume_delta)
             .ok_or_else(|| ProveError::InvalidState("Volume overflow".into()))?;
 
@@ -158,10 +127,10 @@ impl InventoryState {
     ) -> Result<(InventoryState, MerkleProof<Fr>), ProveError> {
         let old_qty = self.get_quantity(item_id);
         if old_qty < amount {
-            return Err(ProveError::InvalidState(format!(
-                "Insufficient quantity: have {}, need {}",
-                old_qty, amount
-            )));
+            return Err(ProveError::InsufficientQuantity {
+                have: old_qty,
+                need: amount,
+            });
         }
         let new_qty = old_qty - amount;
 
@@ -173,8 +142,10 @@ impl InventoryState {
         new_tree.update(item_id, new_qty);
 
         // Update volume
-        let volume_delta = amount * item_volume;
-        let new_volume = self.current_volume.saturating_sub(volume_delta);
+        let volume_delta = amount.checked_mul(item_volume)
+            .ok_or_else(|| ProveError::InvalidState("Volume overflow".into()))?;
+        let new_volume = self.current_volume.checked_sub(volume_delta)
+            .ok_or_else(|| ProveError::InvalidState("Volume underflow".into()))?;
 
         Ok((
             InventoryState {
@@ -185,11 +156,55 @@ impl InventoryState {
             proof,
         ))
     }
+
+    /// Apply a deposit or withdrawal in place, updating the volume incrementally
+    ///
+    /// The volume moves by `amount` times the item's volume in `registry`, so it
+    /// stays equal to `registry.calculate_used_volume(&self.tree)` as long as it
+    /// was before. Returns the new total volume; on error the state is unchanged.
+    pub fn apply_with_volume(
+        &mut self,
+        item_id: u64,
+        amount: u64,
+        op_type: OpType,
+        registry: &VolumeRegistry,
+    ) -> Result<u64, ProveError> {
+        let old_qty = self.get_quantity(item_id);
+        let volume_delta = amount
+            .checked_mul(registry.get_volume(item_id))
+            .ok_or_else(|| ProveError::InvalidState("Volume overflow".into()))?;
+
+        let (new_qty, new_volume) = match op_type {
+            OpType::Deposit => (
+                old_qty
+                    .checked_add(amount)
+                    .ok_or_else(|| ProveError::InvalidState("Quantity overflow".into()))?,
+                self.current_volume
+                    .checked_add(volume_delta)
+                    .ok_or_else(|| ProveError::InvalidState("Volume overflow".into()))?,
+            ),
+            OpType::Withdraw => (
+                old_qty.checked_sub(amount).ok_or(ProveError::InsufficientQuantity {
+                    have: old_qty,
+                    need: amount,
+                })?,
+                self.current_volume.checked_sub(volume_delta).ok_or_else(|| {
+                    ProveError::InvalidState("Volume underflow".into())
+                })?,
+            ),
+        };
+
+        self.tree.update(item_id, new_qty);
+        self.current_volume = new_volume;
+        Ok(new_volume)
+    }
 }
 
 /// Result of a state transition proof
 pub struct StateTransitionResult {
     pub proof: ProofWithInputs,
+    /// Commitment to the state before the operation
+    pub old_commitment: Fr,
     pub new_state: InventoryState,
     pub new_commitment: Fr,
     /// Nonce used in this proof (for on-chain verification)
@@ -208,9 +223,8 @@ pub struct StateTransitionResult {
 /// * `new_blinding` - New blinding factor for the updated commitment
 /// * `item_id` - Item being deposited/withdrawn
 /// * `amount` - Quantity being deposited/withdrawn
-/// * `item_volume` - Volume per unit of this item type
-/// * `registry_root` - VolumeRegistry hash (must match on-chain)
-/// * `max_capacity` - Maximum allowed volume (0 = unlimited)
+/// * `registry` - Volume registry; its hash must match on-chain and fixes the item's volume
+/// * `max_capacity` - Maximum allowed volume after the operation (0 admits only an empty inventory)
 /// * `nonce` - Current inventory nonce (must match on-chain, for replay protection)
 /// * `inventory_id` - Inventory object ID as field element (must match on-chain)
 /// * `op_type` - Deposit or Withdraw
@@ -221,47 +235,333 @@ pub fn prove_state_transition(
     new_blinding: Fr,
     item_id: u64,
     amount: u64,
-    item_volume: u64,
-    registry_root: Fr,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+    op_type: OpType,
+) -> Result<StateTransitionResult, ProveError> {
+    prove_state_transition_with_rng(
+        pk,
+        old_state,
+        new_blinding,
+        item_id,
+        amount,
+        registry,
+        max_capacity,
+        nonce,
+        inventory_id,
+        op_type,
+        &mut StdRng::from_entropy(),
+    )
+}
+
+/// Like `prove_state_transition`, with the proof randomness drawn from `rng`
+#[allow(clippy::too_many_arguments)]
+pub fn prove_state_transition_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    old_state: &InventoryState,
+    new_blinding: Fr,
+    item_id: u64,
+    amount: u64,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+    op_type: OpType,
+    rng: &mut R,
+) -> Result<StateTransitionResult, ProveError> {
+    prove_transition(
+        pk,
+        old_state,
+        new_blinding,
+        item_id,
+        amount,
+        registry,
+        max_capacity,
+        nonce,
+        inventory_id,
+        op_type,
+        None,
+        rng,
+    )
+}
+
+/// Generate proof for StateTransitionCircuit from a flat item list
+///
+/// Builds the old inventory tree from `old_items` (item_id, quantity) pairs
+/// and proves the operation against it. Both commitments are returned in the
+/// result. Other arguments are as for `prove_state_transition`.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_state_transition_from_items(
+    pk: &ProvingKey<Bn254>,
+    old_items: &[(u64, u64)],
+    old_volume: u64,
+    old_blinding: Fr,
+    new_blinding: Fr,
+    item_id: u64,
+    amount: u64,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+    op_type: OpType,
+) -> Result<StateTransitionResult, ProveError> {
+    prove_state_transition_from_items_with_rng(
+        pk,
+        old_items,
+        old_volume,
+        old_blinding,
+        new_blinding,
+        item_id,
+        amount,
+        registry,
+        max_capacity,
+        nonce,
+        inventory_id,
+        op_type,
+        &mut StdRng::from_entropy(),
+    )
+}
+
+/// Like `prove_state_transition_from_items`, with the proof randomness drawn from `rng`
+#[allow(clippy::too_many_arguments)]
+pub fn prove_state_transition_from_items_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    old_items: &[(u64, u64)],
+    old_volume: u64,
+    old_blinding: Fr,
+    new_blinding: Fr,
+    item_id: u64,
+    amount: u64,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+    op_type: OpType,
+    rng: &mut R,
+) -> Result<StateTransitionResult, ProveError> {
+    let mut old_state = InventoryState::from_items(old_items, old_blinding);
+    old_state.current_volume = old_volume;
+
+    prove_state_transition_with_rng(
+        pk,
+        &old_state,
+        new_blinding,
+        item_id,
+        amount,
+        registry,
+        max_capacity,
+        nonce,
+        inventory_id,
+        op_type,
+        rng,
+    )
+}
+
+/// Generate proof for a deposit whose volume growth is capped at `max_delta`
+///
+/// Requires keys from `setup_delta_capped_transition`. The signal hash (first
+/// public input) additionally binds `max_delta`, see
+/// `compute_delta_capped_signal_hash`. Other arguments are as for
+/// `prove_state_transition`.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_deposit_with_delta_cap(
+    pk: &ProvingKey<Bn254>,
+    old_state: &InventoryState,
+    new_blinding: Fr,
+    item_id: u64,
+    amount: u64,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+    max_delta: u64,
+) -> Result<StateTransitionResult, ProveError> {
+    prove_deposit_with_delta_cap_with_rng(
+        pk,
+        old_state,
+        new_blinding,
+        item_id,
+        amount,
+        registry,
+        max_capacity,
+        nonce,
+        inventory_id,
+        max_delta,
+        &mut StdRng::from_entropy(),
+    )
+}
+
+/// Like `prove_deposit_with_delta_cap`, with the proof randomness drawn from `rng`
+#[allow(clippy::too_many_arguments)]
+pub fn prove_deposit_with_delta_cap_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    old_state: &InventoryState,
+    new_blinding: Fr,
+    item_id: u64,
+    amount: u64,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+    max_delta: u64,
+    rng: &mut R,
+) -> Result<StateTransitionResult, ProveError> {
+    prove_transition(
+        pk,
+        old_state,
+        new_blinding,
+        item_id,
+        amount,
+        registry,
+        max_capacity,
+        nonce,
+        inventory_id,
+        OpType::Deposit,
+        Some(max_delta),
+        rng,
+    )
+}
+
+/// Generate proof for a withdrawal that leaves the inventory within `max_capacity`
+///
+/// Uses the StateTransitionCircuit keys. Withdrawals never grow the volume,
+/// but the post-state bound still applies, which proves an inventory is under
+/// a new lower cap. Fails with `CapacityExceeded` if the withdrawal leaves the
+/// volume above `max_capacity`. Other arguments are as for
+/// `prove_state_transition`.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_withdraw_with_capacity(
+    pk: &ProvingKey<Bn254>,
+    old_state: &InventoryState,
+    new_blinding: Fr,
+    item_id: u64,
+    amount: u64,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+) -> Result<StateTransitionResult, ProveError> {
+    prove_withdraw_with_capacity_with_rng(
+        pk,
+        old_state,
+        new_blinding,
+        item_id,
+        amount,
+        registry,
+        max_capacity,
+        nonce,
+        inventory_id,
+        &mut StdRng::from_entropy(),
+    )
+}
+
+/// Like `prove_withdraw_with_capacity`, with the proof randomness drawn from `rng`
+#[allow(clippy::too_many_arguments)]
+pub fn prove_withdraw_with_capacity_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    old_state: &InventoryState,
+    new_blinding: Fr,
+    item_id: u64,
+    amount: u64,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+    rng: &mut R,
+) -> Result<StateTransitionResult, ProveError> {
+    prove_transition(
+        pk,
+        old_state,
+        new_blinding,
+        item_id,
+        amount,
+        registry,
+        max_capacity,
+        nonce,
+        inventory_id,
+        OpType::Withdraw,
+        None,
+        rng,
+    )
+}
+
+/// Shared body of the state transition provers; `max_delta` selects the capped circuit.
+#[allow(clippy::too_many_arguments)]
+fn prove_transition<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    old_state: &InventoryState,
+    new_blinding: Fr,
+    item_id: u64,
+    amount: u64,
+    registry: &VolumeRegistry,
     max_capacity: u64,
     nonce: u64,
     inventory_id: Fr,
     op_type: OpType,
+    max_delta: Option<u64>,
+    rng: &mut R,
 ) -> Result<StateTransitionResult, ProveError> {
+    let item_volume = registry.get_volume(item_id);
+    let registry_root = compute_registry_hash(registry);
+    if amount > 0 && item_volume == 0 {
+        return Err(ProveError::InvalidState(
+            "item_volume must be non-zero for a non-zero amount".into(),
+        ));
+    }
+
     // Get old quantities and proof
     let old_quantity = old_state.get_quantity(item_id);
     let inventory_proof = old_state.get_proof(item_id);
 
     // Compute new state
+    let volume_delta = amount.checked_mul(item_volume)
+        .ok_or_else(|| ProveError::InvalidState("Volume overflow".into()))?;
     let (new_quantity, new_volume) = match op_type {
         OpType::Deposit => {
             let new_qty = old_quantity.checked_add(amount)
                 .ok_or_else(|| ProveError::InvalidState("Quantity overflow".into()))?;
-            let volume_delta = amount * item_volume;
             let new_vol = old_state.current_volume.checked_add(volume_delta)
                 .ok_or_else(|| ProveError::InvalidState("Volume overflow".into()))?;
-            // max_capacity of 0 means unlimited
-            if max_capacity > 0 && new_vol > max_capacity {
-                return Err(ProveError::InvalidState(format!(
-                    "Capacity exceeded: {} > {}",
-                    new_vol, max_capacity
-                )));
+            if new_vol > max_capacity {
+                return Err(ProveError::CapacityExceeded {
+                    volume: new_vol,
+                    capacity: max_capacity,
+                });
+            }
+            if let Some(max_delta) = max_delta {
+                if volume_delta > max_delta {
+                    return Err(ProveError::InvalidState(format!(
+                        "Volume growth exceeds delta cap: {} > {}",
+                        volume_delta, max_delta
+                    )));
+                }
             }
             (new_qty, new_vol)
         }
         OpType::Withdraw => {
             if old_quantity < amount {
-                return Err(ProveError::InvalidState(format!(
-                    "Insufficient quantity: have {}, need {}",
-                    old_quantity, amount
-                )));
+                return Err(ProveError::InsufficientQuantity {
+                    have: old_quantity,
+                    need: amount,
+                });
             }
             let new_qty = old_quantity - amount;
-            let volume_delta = amount * item_volume;
-            let new_vol = old_state.current_volume.saturating_sub(volume_delta);
+            let new_vol = old_state.current_volume.checked_sub(volume_delta)
+                .ok_or_else(|| ProveError::InvalidState("Volume underflow".into()))?;
+            // The circuit re-asserts capacity after a withdrawal, so a state
+            // above a lowered cap must withdraw enough to get back under it
+            if new_vol > max_capacity {
+                return Err(ProveError::CapacityExceeded {
+                    volume: new_vol,
+                    capacity: max_capacity,
+                });
+            }
             (new_qty, new_vol)
         }
     };
+    check_circuit_range(new_quantity, volume_delta, new_volume)?;
 
     // Create new tree state
     let mut new_tree = old_state.tree.clone();
@@ -289,18 +589,21 @@ pub fn prove_state_transition(
         amount,
         op_type,
         inventory_proof,
-        item_volume,
-        registry_root,
+        registry,
         max_capacity,
         nonce,
         inventory_id,
-    );
+    )
+    .with_depth(old_state.tree.depth());
+    let circuit = match max_delta {
+        Some(max_delta) => circuit.with_max_delta(max_delta),
+        None => circuit,
+    };
 
     let signal_hash = circuit.signal_hash.unwrap();
 
     // Generate proof
-    let mut rng = StdRng::from_entropy();
-    let proof = Groth16::<Bn254>::prove(pk, circuit, &mut rng)
+    let proof = Groth16::<Bn254>::prove(pk, circuit, rng)
         .map_err(|e| ProveError::ProofGeneration(e.to_string()))?;
 
     // Return all 4 public inputs for on-chain verification
@@ -310,6 +613,7 @@ pub fn prove_state_transition(
             proof,
             public_inputs: vec![signal_hash, Fr::from(nonce), inventory_id, registry_root],
         },
+        old_commitment: old_state.commitment(),
         new_state,
         new_commitment,
         nonce,
@@ -318,88 +622,1065 @@ pub fn prove_state_transition(
     })
 }
 
-/// Generate proof for ItemExistsSMTCircuit
-pub fn prove_item_exists(
-    pk: &ProvingKey<Bn254>,
-    state: &InventoryState,
-    item_id: u64,
-    min_quantity: u64,
-) -> Result<ProofWithInputs, ProveError> {
-    // Get actual quantity and proof
-    let actual_quantity = state.get_quantity(item_id);
-    if actual_quantity < min_quantity {
-        return Err(ProveError::InvalidState(format!(
-            "Insufficient quantity: have {}, need >= {}",
-            actual_quantity, min_quantity
-        )));
-    }
-
-    let proof = state.get_proof(item_id);
-
-    // Create circuit
-    let circuit = ItemExistsSMTCircuit::new(
-        state.tree.root(),
-        state.current_volume,
-        state.blinding,
-        item_id,
-        actual_quantity,
-        min_quantity,
-        proof,
-    );
-
-    let public_hash = circuit.public_hash.unwrap();
+/// A single deposit or withdrawal within a `prove_double_transition` call
+#[derive(Clone, Copy, Debug)]
+pub struct TransitionOp {
+    pub item_id: u64,
+    pub amount: u64,
+    pub op_type: OpType,
+}
 
-    // Generate proof
-    let mut rng = StdRng::from_entropy();
-    let zk_proof = Groth16::<Bn254>::prove(pk, circuit, &mut rng)
-        .map_err(|e| ProveError::ProofGeneration(e.to_string()))?;
+/// Result of a double transition proof
+pub struct DoubleTransitionResult {
+    pub proof: ProofWithInputs,
+    pub new_state: InventoryState,
+    pub new_commitment: Fr,
+}
 
-    Ok(ProofWithInputs {
-        proof: zk_proof,
-        public_inputs: vec![public_hash],
-    })
+/// Check a transition's native values against the 32-bit ranges the circuit enforces.
+///
+/// The new quantity, volume delta and new volume are range checked in-circuit, so
+/// values that fit in u64 but not u32 would only surface as a proof that fails to verify.
+fn check_circuit_range(
+    new_quantity: u64,
+    volume_delta: u64,
+    new_volume: u64,
+) -> Result<(), ProveError> {
+    let max = u64::from(u32::MAX);
+    if new_quantity > max {
+        return Err(ProveError::InvalidState("Quantity overflow".into()));
+    }
+    if volume_delta > max || new_volume > max {
+        return Err(ProveError::InvalidState("Volume overflow".into()));
+    }
+    Ok(())
 }
 
-/// Generate proof for CapacitySMTCircuit
-pub fn prove_capacity(
-    pk: &ProvingKey<Bn254>,
+/// Apply `op` to `state`, checking capacity, and return the new state with its step witnesses.
+///
+/// The item's volume is its entry in `registry`.
+fn apply_transition_op(
     state: &InventoryState,
+    op: TransitionOp,
+    registry: &VolumeRegistry,
+    new_blinding: Fr,
     max_capacity: u64,
-) -> Result<ProofWithInputs, ProveError> {
-    // Verify capacity compliance (max_capacity of 0 means unlimited)
-    if max_capacity > 0 && state.current_volume > max_capacity {
-        return Err(ProveError::InvalidState(format!(
-            "Volume exceeds capacity: {} > {}",
-            state.current_volume, max_capacity
-        )));
+) -> Result<(InventoryState, TransitionStep), ProveError> {
+    let item_volume = registry.get_volume(op.item_id);
+    if op.amount > 0 && item_volume == 0 {
+        return Err(ProveError::InvalidState(
+            "item_volume must be non-zero for a non-zero amount".into(),
+        ));
     }
 
-    // Create circuit
-    let circuit = CapacitySMTCircuit::new(
-        state.tree.root(),
-        state.current_volume,
-        state.blinding,
-        max_capacity,
-    );
+    let old_quantity = state.get_quantity(op.item_id);
+    let (new_state, inventory_proof) = match op.op_type {
+        OpType::Deposit => state.deposit(op.item_id, op.amount, item_volume, new_blinding)?,
+        OpType::Withdraw => state.withdraw(op.item_id, op.amount, item_volume, new_blinding)?,
+    };
 
-    let public_hash = circuit.public_hash.unwrap();
+    if new_state.current_volume > max_capacity {
+        return Err(ProveError::CapacityExceeded {
+            volume: new_state.current_volume,
+            capacity: max_capacity,
+        });
+    }
+    let new_quantity = new_state.get_quantity(op.item_id);
+    // deposit/withdraw already rejected an overflowing product
+    check_circuit_range(new_quantity, op.amount * item_volume, new_state.current_volume)?;
 
-    // Generate proof
-    let mut rng = StdRng::from_entropy();
-    let proof = Groth16::<Bn254>::prove(pk, circuit, &mut rng)
-        .map_err(|e| ProveError::ProofGeneration(e.to_string()))?;
+    let step = TransitionStep {
+        item_id: op.item_id,
+        old_quantity,
+        new_quantity,
+        amount: op.amount,
+        op_type: op.op_type,
+        inventory_proof,
+        item_volume,
+    };
 
-    Ok(ProofWithInputs {
-        proof,
-        public_inputs: vec![public_hash],
-    })
+    Ok((new_state, step))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::setup::{setup_capacity, setup_item_exists, setup_state_transition};
-    use ark_std::rand::SeedableRng;
+/// Generate proof for DoubleTransitionCircuit (two sequential operations)
+///
+/// Only the commitments to `old_state` and the final state are public; the
+/// intermediate state after `first` is never committed. Both the intermediate
+/// and the final volume must stay within `max_capacity`. Item volumes come
+/// from `registry`, whose hash is the public registry root.
+pub fn prove_double_transition(
+    pk: &ProvingKey<Bn254>,
+    old_state: &InventoryState,
+    first: TransitionOp,
+    second: TransitionOp,
+    new_blinding: Fr,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+) -> Result<DoubleTransitionResult, ProveError> {
+    prove_double_transition_with_rng(
+        pk,
+        old_state,
+        first,
+        second,
+        new_blinding,
+        registry,
+        max_capacity,
+        &mut StdRng::from_entropy(),
+    )
+}
+
+/// Like `prove_double_transition`, with the proof randomness drawn from `rng`
+#[allow(clippy::too_many_arguments)]
+pub fn prove_double_transition_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    old_state: &InventoryState,
+    first: TransitionOp,
+    second: TransitionOp,
+    new_blinding: Fr,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+    rng: &mut R,
+) -> Result<DoubleTransitionResult, ProveError> {
+    let registry_root = compute_registry_hash(registry);
+    let (mid_state, first_step) =
+        apply_transition_op(old_state, first, registry, old_state.blinding, max_capacity)?;
+    let (new_state, second_step) =
+        apply_transition_op(&mid_state, second, registry, new_blinding, max_capacity)?;
+
+    let circuit = DoubleTransitionCircuit::new(
+        old_state.root(),
+        old_state.current_volume,
+        old_state.blinding,
+        mid_state.root(),
+        mid_state.current_volume,
+        new_state.root(),
+        new_state.current_volume,
+        new_blinding,
+        first_step,
+        second_step,
+        registry,
+        max_capacity,
+    );
+
+    let old_commitment = circuit.old_commitment.unwrap();
+    let new_commitment = circuit.new_commitment.unwrap();
+
+    let proof = Groth16::<Bn254>::prove(pk, circuit, rng)
+        .map_err(|e| ProveError::ProofGeneration(e.to_string()))?;
+
+    // Order: old_commitment, new_commitment, registry_root, max_capacity
+    Ok(DoubleTransitionResult {
+        proof: ProofWithInputs {
+            proof,
+            public_inputs: vec![
+                old_commitment,
+                new_commitment,
+                registry_root,
+                Fr::from(max_capacity),
+            ],
+        },
+        new_state,
+        new_commitment,
+    })
+}
+
+/// Generate proof for BatchStateTransitionCircuit (up to `MAX_BATCH_OPS` operations)
+///
+/// Applies `ops` in order, pricing items with `registry`; each intermediate
+/// volume must stay within `max_capacity`. Public inputs are `[signal_hash, nonce, inventory_id, registry_root]`,
+/// as for `prove_state_transition`.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_batch_transition(
+    pk: &ProvingKey<Bn254>,
+    old_state: &InventoryState,
+    ops: &[TransitionOp],
+    new_blinding: Fr,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+) -> Result<StateTransitionResult, ProveError> {
+    prove_batch_transition_with_rng(
+        pk,
+        old_state,
+        ops,
+        new_blinding,
+        registry,
+        max_capacity,
+        nonce,
+        inventory_id,
+        &mut StdRng::from_entropy(),
+    )
+}
+
+/// Like `prove_batch_transition`, with the proof randomness drawn from `rng`
+#[allow(clippy::too_many_arguments)]
+pub fn prove_batch_transition_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    old_state: &InventoryState,
+    ops: &[TransitionOp],
+    new_blinding: Fr,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+    rng: &mut R,
+) -> Result<StateTransitionResult, ProveError> {
+    if ops.is_empty() || ops.len() > MAX_BATCH_OPS {
+        return Err(ProveError::InvalidState(format!(
+            "A batch holds 1 to {} operations, got {}",
+            MAX_BATCH_OPS,
+            ops.len()
+        )));
+    }
+
+    let registry_root = compute_registry_hash(registry);
+    let mut new_state = old_state.clone();
+    let mut steps = Vec::with_capacity(ops.len());
+    for &op in ops {
+        let (next_state, step) =
+            apply_transition_op(&new_state, op, registry, new_blinding, max_capacity)?;
+        new_state = next_state;
+        steps.push(step);
+    }
+
+    let circuit = BatchStateTransitionCircuit::new(
+        old_state.root(),
+        old_state.current_volume,
+        old_state.blinding,
+        new_blinding,
+        steps,
+        registry,
+        max_capacity,
+        nonce,
+        inventory_id,
+    );
+
+    let signal_hash = circuit.signal_hash.unwrap();
+    let new_commitment = new_state.commitment();
+
+    let proof = Groth16::<Bn254>::prove(pk, circuit, rng)
+        .map_err(|e| ProveError::ProofGeneration(e.to_string()))?;
+
+    // Order: signal_hash, nonce, inventory_id, registry_root
+    Ok(StateTransitionResult {
+        proof: ProofWithInputs {
+            proof,
+            public_inputs: vec![signal_hash, Fr::from(nonce), inventory_id, registry_root],
+        },
+        old_commitment: old_state.commitment(),
+        new_state,
+        new_commitment,
+        nonce,
+        inventory_id,
+        registry_root,
+    })
+}
+
+/// Generate proof for SwapCircuit (withdraw `give`, then deposit `take`)
+///
+/// `give` must be a withdrawal and `take` a deposit of a different item, both
+/// priced with `registry`.
+/// Public inputs are `[signal_hash, nonce, inventory_id, registry_root]`,
+/// as for `prove_state_transition`.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_swap(
+    pk: &ProvingKey<Bn254>,
+    old_state: &InventoryState,
+    give: TransitionOp,
+    take: TransitionOp,
+    new_blinding: Fr,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+) -> Result<StateTransitionResult, ProveError> {
+    prove_swap_with_rng(
+        pk,
+        old_state,
+        give,
+        take,
+        new_blinding,
+        registry,
+        max_capacity,
+        nonce,
+        inventory_id,
+        &mut StdRng::from_entropy(),
+    )
+}
+
+/// Like `prove_swap`, with the proof randomness drawn from `rng`
+#[allow(clippy::too_many_arguments)]
+pub fn prove_swap_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    old_state: &InventoryState,
+    give: TransitionOp,
+    take: TransitionOp,
+    new_blinding: Fr,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+    rng: &mut R,
+) -> Result<StateTransitionResult, ProveError> {
+    if give.op_type != OpType::Withdraw || take.op_type != OpType::Deposit {
+        return Err(ProveError::InvalidState(
+            "A swap withdraws the given item and deposits the taken one".into(),
+        ));
+    }
+    if give.item_id == take.item_id {
+        return Err(ProveError::InvalidState(format!(
+            "Cannot swap item {} for itself",
+            give.item_id
+        )));
+    }
+
+    let registry_root = compute_registry_hash(registry);
+    let (mid_state, give_step) =
+        apply_transition_op(old_state, give, registry, old_state.blinding, max_capacity)?;
+    let (new_state, take_step) =
+        apply_transition_op(&mid_state, take, registry, new_blinding, max_capacity)?;
+
+    let circuit = SwapCircuit::new(
+        old_state.root(),
+        old_state.current_volume,
+        old_state.blinding,
+        new_blinding,
+        give_step,
+        take_step,
+        registry,
+        max_capacity,
+        nonce,
+        inventory_id,
+    );
+
+    let signal_hash = circuit.signal_hash.unwrap();
+    let new_commitment = new_state.commitment();
+
+    let proof = Groth16::<Bn254>::prove(pk, circuit, rng)
+        .map_err(|e| ProveError::ProofGeneration(e.to_string()))?;
+
+    // Order: signal_hash, nonce, inventory_id, registry_root
+    Ok(StateTransitionResult {
+        proof: ProofWithInputs {
+            proof,
+            public_inputs: vec![signal_hash, Fr::from(nonce), inventory_id, registry_root],
+        },
+        old_commitment: old_state.commitment(),
+        new_state,
+        new_commitment,
+        nonce,
+        inventory_id,
+        registry_root,
+    })
+}
+
+/// Generate proof for ItemExistsSMTCircuit
+pub fn prove_item_exists(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    item_id: u64,
+    min_quantity: u64,
+) -> Result<ProofWithInputs, ProveError> {
+    prove_item_exists_with_rng(pk, state, item_id, min_quantity, &mut StdRng::from_entropy())
+}
+
+/// Like `prove_item_exists`, with the proof randomness drawn from `rng`
+pub fn prove_item_exists_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    item_id: u64,
+    min_quantity: u64,
+    rng: &mut R,
+) -> Result<ProofWithInputs, ProveError> {
+    // Get actual quantity and proof
+    let actual_quantity = state.get_quantity(item_id);
+    if actual_quantity < min_quantity {
+        return Err(ProveError::InsufficientQuantity {
+            have: actual_quantity,
+            need: min_quantity,
+        });
+    }
+
+    let proof = state.get_proof(item_id);
+
+    // Create circuit
+    let circuit = ItemExistsSMTCircuit::new(
+        state.tree.root(),
+        state.current_volume,
+        state.blinding,
+        item_id,
+        actual_quantity,
+        min_quantity,
+        proof,
+    );
+
+    let public_hash = circuit.public_hash.unwrap();
+
+    // Generate proof
+    let zk_proof = Groth16::<Bn254>::prove(pk, circuit, rng)
+        .map_err(|e| ProveError::ProofGeneration(e.to_string()))?;
+
+    Ok(ProofWithInputs {
+        proof: zk_proof,
+        public_inputs: vec![public_hash],
+    })
+}
+
+/// Generate proof for ItemExistsWithCapacityCircuit
+///
+/// Proves >= `min_quantity` of `item_id` and a volume within `max_capacity`.
+pub fn prove_item_exists_with_capacity(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    item_id: u64,
+    min_quantity: u64,
+    max_capacity: u64,
+) -> Result<ProofWithInputs, ProveError> {
+    prove_item_exists_with_capacity_with_rng(
+        pk,
+        state,
+        item_id,
+        min_quantity,
+        max_capacity,
+        &mut StdRng::from_entropy(),
+    )
+}
+
+/// Like `prove_item_exists_with_capacity`, with the proof randomness drawn from `rng`
+pub fn prove_item_exists_with_capacity_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    item_id: u64,
+    min_quantity: u64,
+    max_capacity: u64,
+    rng: &mut R,
+) -> Result<ProofWithInputs, ProveError> {
+    let actual_quantity = state.get_quantity(item_id);
+    if actual_quantity < min_quantity {
+        return Err(ProveError::InsufficientQuantity {
+            have: actual_quantity,
+            need: min_quantity,
+        });
+    }
+    if state.current_volume > max_capacity {
+        return Err(ProveError::CapacityExceeded {
+            volume: state.current_volume,
+            capacity: max_capacity,
+        });
+    }
+
+    let circuit = ItemExistsWithCapacityCircuit::new(
+        state.tree.root(),
+        state.current_volume,
+        state.blinding,
+        item_id,
+        actual_quantity,
+        min_quantity,
+        max_capacity,
+        state.get_proof(item_id),
+    );
+
+    let public_hash = circuit.public_hash.unwrap();
+
+    let proof = Groth16::<Bn254>::prove(pk, circuit, rng)
+        .map_err(|e| ProveError::ProofGeneration(e.to_string()))?;
+
+    Ok(ProofWithInputs {
+        proof,
+        public_inputs: vec![public_hash],
+    })
+}
+
+/// Generate proof for NonMembershipSMTCircuit
+pub fn prove_non_membership(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    item_id: u64,
+) -> Result<ProofWithInputs, ProveError> {
+    prove_non_membership_with_rng(pk, state, item_id, &mut StdRng::from_entropy())
+}
+
+/// Like `prove_non_membership`, with the proof randomness drawn from `rng`
+pub fn prove_non_membership_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    item_id: u64,
+    rng: &mut R,
+) -> Result<ProofWithInputs, ProveError> {
+    let proof = state.tree.prove_non_membership(item_id).ok_or_else(|| {
+        ProveError::InvalidState(format!(
+            "Item {} is present: have {}",
+            item_id,
+            state.get_quantity(item_id)
+        ))
+    })?;
+
+    let circuit = NonMembershipSMTCircuit::new(
+        state.tree.root(),
+        state.current_volume,
+        state.blinding,
+        item_id,
+        proof,
+    );
+
+    let public_hash = circuit.public_hash.unwrap();
+
+    let proof = Groth16::<Bn254>::prove(pk, circuit, rng)
+        .map_err(|e| ProveError::ProofGeneration(e.to_string()))?;
+
+    Ok(ProofWithInputs {
+        proof,
+        public_inputs: vec![public_hash],
+    })
+}
+
+/// Generate proof for CapacitySMTCircuit
+pub fn prove_capacity(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    max_capacity: u64,
+) -> Result<ProofWithInputs, ProveError> {
+    prove_capacity_with_rng(pk, state, max_capacity, &mut StdRng::from_entropy())
+}
+
+/// Like `prove_capacity`, with the proof randomness drawn from `rng`
+pub fn prove_capacity_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    max_capacity: u64,
+    rng: &mut R,
+) -> Result<ProofWithInputs, ProveError> {
+    // Verify capacity compliance (the circuit enforces the same bound)
+    if state.current_volume > max_capacity {
+        return Err(ProveError::CapacityExceeded {
+            volume: state.current_volume,
+            capacity: max_capacity,
+        });
+    }
+
+    // Create circuit
+    let circuit = CapacitySMTCircuit::new(
+        state.tree.root(),
+        state.current_volume,
+        state.blinding,
+        max_capacity,
+    );
+
+    let public_hash = circuit.public_hash.unwrap();
+
+    // Generate proof
+    let proof = Groth16::<Bn254>::prove(pk, circuit, rng)
+        .map_err(|e| ProveError::ProofGeneration(e.to_string()))?;
+
+    Ok(ProofWithInputs {
+        proof,
+        public_inputs: vec![public_hash],
+    })
+}
+
+/// Generate proof for CapacitySMTCircuit, deriving the volume from `registry`
+///
+/// Rejects inventories holding items the registry has no volume for, and a
+/// `current_volume` that differs from the registry's total.
+pub fn prove_capacity_with_registry(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+) -> Result<ProofWithInputs, ProveError> {
+    prove_capacity_with_registry_with_rng(
+        pk,
+        state,
+        registry,
+        max_capacity,
+        &mut StdRng::from_entropy(),
+    )
+}
+
+/// Like `prove_capacity_with_registry`, with the proof randomness drawn from `rng`
+pub fn prove_capacity_with_registry_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    registry: &VolumeRegistry,
+    max_capacity: u64,
+    rng: &mut R,
+) -> Result<ProofWithInputs, ProveError> {
+    registry.validate_covers(&state.tree)?;
+
+    let used_volume = registry.calculate_used_volume(&state.tree)?;
+    if used_volume != state.current_volume {
+        return Err(ProveError::InvalidState(format!(
+            "Volume mismatch: registry gives {}, state has {}",
+            used_volume, state.current_volume
+        )));
+    }
+
+    prove_capacity_with_rng(pk, state, max_capacity, rng)
+}
+
+/// Generate proof for SelectiveDisclosureCircuit
+///
+/// Reveals the exact quantity of `item_id`; public inputs are
+/// `[commitment, item_id, quantity]`.
+pub fn prove_disclose_item(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    item_id: u64,
+) -> Result<ProofWithInputs, ProveError> {
+    prove_disclose_item_with_rng(pk, state, item_id, &mut StdRng::from_entropy())
+}
+
+/// Like `prove_disclose_item`, with the proof randomness drawn from `rng`
+pub fn prove_disclose_item_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    item_id: u64,
+    rng: &mut R,
+) -> Result<ProofWithInputs, ProveError> {
+    let quantity = state.get_quantity(item_id);
+    if quantity == 0 {
+        return Err(ProveError::InvalidState(format!(
+            "Item {} is not in the inventory",
+            item_id
+        )));
+    }
+
+    let circuit = SelectiveDisclosureCircuit::new(
+        state.tree.root(),
+        state.current_volume,
+        state.blinding,
+        item_id,
+        quantity,
+        state.get_proof(item_id),
+    );
+
+    let commitment = circuit.commitment.unwrap();
+
+    let proof = Groth16::<Bn254>::prove(pk, circuit, rng)
+        .map_err(|e| ProveError::ProofGeneration(e.to_string()))?;
+
+    Ok(ProofWithInputs {
+        proof,
+        public_inputs: vec![commitment, Fr::from(item_id), Fr::from(quantity)],
+    })
+}
+
+/// Generate proof for SubsetCapacityCircuit
+///
+/// Only the item types selected by `mask` count towards `subset_capacity`.
+pub fn prove_subset_capacity(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    registry: &VolumeRegistry,
+    mask: u16,
+    subset_capacity: u64,
+) -> Result<ProofWithInputs, ProveError> {
+    prove_subset_capacity_with_rng(
+        pk,
+        state,
+        registry,
+        mask,
+        subset_capacity,
+        &mut StdRng::from_entropy(),
+    )
+}
+
+/// Like `prove_subset_capacity`, with the proof randomness drawn from `rng`
+pub fn prove_subset_capacity_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    registry: &VolumeRegistry,
+    mask: u16,
+    subset_capacity: u64,
+    rng: &mut R,
+) -> Result<ProofWithInputs, ProveError> {
+    registry.validate_covers(&state.tree)?;
+
+    let subset_volume = registry.calculate_masked_volume(&state.tree, mask)?;
+    if subset_volume > subset_capacity {
+        return Err(ProveError::InvalidState(format!(
+            "Subset volume {} exceeds subset capacity {}",
+            subset_volume, subset_capacity
+        )));
+    }
+
+    let circuit = SubsetCapacityCircuit::from_tree(
+        &state.tree,
+        state.current_volume,
+        state.blinding,
+        registry,
+        mask,
+        subset_capacity,
+    );
+
+    let public_hash = circuit.public_hash.unwrap();
+
+    let proof = Groth16::<Bn254>::prove(pk, circuit, rng)
+        .map_err(|e| ProveError::ProofGeneration(e.to_string()))?;
+
+    Ok(ProofWithInputs {
+        proof,
+        public_inputs: vec![public_hash],
+    })
+}
+
+/// Generate proof for CapacityHiddenSMTCircuit
+///
+/// The public input binds only a commitment to `max_capacity` (under `cap_blinding`),
+/// so the capacity limit is not revealed by the proof.
+pub fn prove_capacity_hidden_cap(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    max_capacity: u64,
+    cap_blinding: Fr,
+) -> Result<ProofWithInputs, ProveError> {
+    prove_capacity_hidden_cap_with_rng(
+        pk,
+        state,
+        max_capacity,
+        cap_blinding,
+        &mut StdRng::from_entropy(),
+    )
+}
+
+/// Like `prove_capacity_hidden_cap`, with the proof randomness drawn from `rng`
+pub fn prove_capacity_hidden_cap_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    state: &InventoryState,
+    max_capacity: u64,
+    cap_blinding: Fr,
+    rng: &mut R,
+) -> Result<ProofWithInputs, ProveError> {
+    if state.current_volume > max_capacity {
+        return Err(ProveError::CapacityExceeded {
+            volume: state.current_volume,
+            capacity: max_capacity,
+        });
+    }
+
+    let circuit = CapacityHiddenSMTCircuit::new(
+        state.tree.root(),
+        state.current_volume,
+        state.blinding,
+        max_capacity,
+        cap_blinding,
+    );
+
+    let public_hash = circuit.public_hash.unwrap();
+
+    let proof = Groth16::<Bn254>::prove(pk, circuit, rng)
+        .map_err(|e| ProveError::ProofGeneration(e.to_string()))?;
+
+    Ok(ProofWithInputs {
+        proof,
+        public_inputs: vec![public_hash],
+    })
+}
+
+/// One item-existence proof in a batch.
+#[derive(Clone)]
+pub struct ItemExistsRequest {
+    /// Inventory to prove against
+    pub state: InventoryState,
+    /// Item that must be present
+    pub item_id: u64,
+    /// Minimum quantity to prove
+    pub min_quantity: u64,
+}
+
+/// One capacity proof in a batch.
+#[derive(Clone)]
+pub struct CapacityRequest {
+    /// Inventory to prove against
+    pub state: InventoryState,
+    /// Capacity the volume must not exceed
+    pub max_capacity: u64,
+}
+
+/// Generate item-existence proofs for independent requests in parallel.
+///
+/// Runs on the current rayon pool; call from `ThreadPool::install` to bound
+/// the number of proving threads. Results are in request order and one
+/// failing request does not affect the others.
+pub fn prove_item_exists_batch(
+    pk: &ProvingKey<Bn254>,
+    requests: &[ItemExistsRequest],
+) -> Vec<Result<ProofWithInputs, ProveError>> {
+    prove_item_exists_batch_with_rng(pk, requests, &mut StdRng::from_entropy())
+}
+
+/// Like `prove_item_exists_batch`, with each proof's RNG seeded from `rng`
+pub fn prove_item_exists_batch_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    requests: &[ItemExistsRequest],
+    rng: &mut R,
+) -> Vec<Result<ProofWithInputs, ProveError>> {
+    prove_batch(requests, rng, |request, rng| {
+        prove_item_exists_with_rng(pk, &request.state, request.item_id, request.min_quantity, rng)
+    })
+}
+
+/// Generate capacity proofs for independent requests in parallel.
+///
+/// Same pool and ordering behaviour as `prove_item_exists_batch`.
+pub fn prove_capacity_batch(
+    pk: &ProvingKey<Bn254>,
+    requests: &[CapacityRequest],
+) -> Vec<Result<ProofWithInputs, ProveError>> {
+    prove_capacity_batch_with_rng(pk, requests, &mut StdRng::from_entropy())
+}
+
+/// Like `prove_capacity_batch`, with each proof's RNG seeded from `rng`
+pub fn prove_capacity_batch_with_rng<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    requests: &[CapacityRequest],
+    rng: &mut R,
+) -> Vec<Result<ProofWithInputs, ProveError>> {
+    prove_batch(requests, rng, |request, rng| {
+        prove_capacity_with_rng(pk, &request.state, request.max_capacity, rng)
+    })
+}
+
+/// Prove each request on the rayon pool (or in turn, without `parallel`) with its own `StdRng`.
+///
+/// Seeds are drawn from `rng` up front, so no two proofs share randomness and
+/// a seeded `rng` gives reproducible proofs regardless of scheduling.
+fn prove_batch<T, R, F>(
+    requests: &[T],
+    rng: &mut R,
+    prove: F,
+) -> Vec<Result<ProofWithInputs, ProveError>>
+where
+    T: Sync,
+    R: RngCore + CryptoRng,
+    F: Fn(&T, &mut StdRng) -> Result<ProofWithInputs, ProveError> + Sync,
+{
+    let seeds: Vec<[u8; 32]> = requests
+        .iter()
+        .map(|_| {
+            let mut seed = [0u8; 32];
+            rng.fill_bytes(&mut seed);
+            seed
+        })
+        .collect();
+
+    #[cfg(feature = "parallel")]
+    let requests = requests.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let requests = requests.iter();
+
+    requests
+        .zip(seeds)
+        .map(|(request, seed)| prove(request, &mut StdRng::from_seed(seed)))
+        .collect()
+}
+
+/// Generate a proof with a full key pair and return it with the matching verifying key.
+///
+/// `prove` is one of the `prove_*` functions applied to the proving key, e.g.
+/// `prove_and_bundle(&keys.capacity, |pk| prove_capacity(pk, &state, 1000))`.
+pub fn prove_and_bundle<F>(
+    keys: &CircuitKeyPair,
+    prove: F,
+) -> Result<(ProofWithInputs, VerifyingKey<Bn254>), ProveError>
+where
+    F: FnOnce(&ProvingKey<Bn254>) -> Result<ProofWithInputs, ProveError>,
+{
+    let proof = prove(&keys.proving_key)?;
+    Ok((proof, keys.verifying_key.clone()))
+}
+
+/// Check a freshly generated proof against the verifying key.
+///
+/// Catches setup/witness mismatches (stale keys, witnesses that break a
+/// constraint the native checks missed) before a proof leaves the prover.
+pub fn check_proof(vk: &VerifyingKey<Bn254>, proof: &ProofWithInputs) -> Result<(), ProveError> {
+    let valid = Groth16::<Bn254>::verify(vk, &proof.public_inputs, &proof.proof)
+        .map_err(|e| ProveError::ProofGeneration(e.to_string()))?;
+    if !valid {
+        return Err(ProveError::SelfCheckFailed);
+    }
+    Ok(())
+}
+
+/// Generate a proof with a full key pair and verify it before returning it.
+///
+/// Like `prove_and_bundle`, but fails with `ProveError::SelfCheckFailed`
+/// instead of returning a proof that its own verifying key rejects.
+pub fn prove_and_verify<F>(keys: &CircuitKeyPair, prove: F) -> Result<ProofWithInputs, ProveError>
+where
+    F: FnOnce(&ProvingKey<Bn254>) -> Result<ProofWithInputs, ProveError>,
+{
+    let proof = prove(&keys.proving_key)?;
+    check_proof(&keys.verifying_key, &proof)?;
+    Ok(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::setup::{
+        setup_batch_transition, setup_capacity, setup_capacity_hidden_cap,
+        setup_delta_capped_transition, setup_double_transition, setup_item_exists,
+        setup_item_exists_with_capacity, setup_non_membership, setup_selective_disclosure, setup_state_transition, setup_subset_capacity, setup_swap,
+    };
+    use inventory_circuits::{
+        compute_capacity_commitment, compute_delta_capped_signal_hash,
+        compute_hidden_capacity_hash, compute_item_exists_capacity_hash,
+        compute_non_membership_hash, compute_registry_hash, compute_signal_hash,
+        compute_subset_capacity_hash, compute_swap_signal_hash, RegistryError,
+    };
+    use ark_std::rand::SeedableRng;
+
+    #[test]
+    fn test_apply_with_volume_matches_recompute() {
+        let registry = VolumeRegistry::from_slice(&[0, 5, 10, 3]);
+        let mut state = InventoryState::new(Fr::from(12345u64));
+
+        let ops = [
+            (1, 10, OpType::Deposit),
+            (2, 4, OpType::Deposit),
+            (1, 3, OpType::Withdraw),
+            (3, 7, OpType::Deposit),
+            (2, 4, OpType::Withdraw),
+            (3, 1, OpType::Withdraw),
+        ];
+        for (item_id, amount, op_type) in ops {
+            let volume = state.apply_with_volume(item_id, amount, op_type, &registry).unwrap();
+            assert_eq!(volume, state.current_volume);
+            assert_eq!(Ok(volume), registry.calculate_used_volume(&state.tree));
+        }
+        assert_eq!(state.current_volume, 7 * 5 + 6 * 3);
+
+        // A failed withdrawal leaves the state untouched
+        let before = state.commitment();
+        assert!(state.apply_with_volume(2, 1, OpType::Withdraw, &registry).is_err());
+        assert_eq!(state.commitment(), before);
+    }
+
+    #[test]
+    fn test_withdraw_reports_available_quantity() {
+        let mut state = InventoryState::from_items(&[(1, 30)], Fr::from(12345u64));
+        state.current_volume = 300;
+
+        let result = state.withdraw(1, 31, 10, Fr::from(67890u64));
+        assert!(matches!(
+            result,
+            Err(ProveError::InsufficientQuantity { have: 30, need: 31 })
+        ));
+        // Items never held report zero available
+        let result = state.withdraw(2, 1, 10, Fr::from(67890u64));
+        assert!(matches!(
+            result,
+            Err(ProveError::InsufficientQuantity { have: 0, need: 1 })
+        ));
+        assert!(state.withdraw(1, 30, 10, Fr::from(67890u64)).is_ok());
+    }
+
+    #[test]
+    fn test_volume_overflow_rejected() {
+        let state = InventoryState::from_items(&[(1, 30)], Fr::from(12345u64));
+        let overflow = |result: Result<(InventoryState, MerkleProof<Fr>), ProveError>| {
+            matches!(result, Err(ProveError::InvalidState(e)) if e == "Volume overflow")
+        };
+
+        // amount * item_volume overflows before the volume is touched
+        assert!(overflow(state.deposit(2, u64::MAX, 2, Fr::from(67890u64))));
+        assert!(overflow(state.withdraw(1, 30, u64::MAX, Fr::from(67890u64))));
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition(&mut rng).unwrap();
+        let registry = VolumeRegistry::from_entries(&[(1, u64::MAX)]);
+        for op_type in [OpType::Deposit, OpType::Withdraw] {
+            let result = prove_state_transition(
+                &keys.proving_key,
+                &state,
+                Fr::from(67890u64),
+                1,
+                2,
+                &registry,
+                u64::MAX,
+                0,
+                Fr::from(12345678u64),
+                op_type,
+            );
+            assert!(matches!(result, Err(ProveError::InvalidState(e)) if e == "Volume overflow"));
+        }
+    }
+
+    #[test]
+    fn test_volume_underflow_rejected() {
+        // 30 units at volume 10 recorded as 200 instead of 300
+        let mut state = InventoryState::from_items(&[(1, 30)], Fr::from(12345u64));
+        state.current_volume = 200;
+
+        let result = state.withdraw(1, 30, 10, Fr::from(67890u64));
+        assert!(matches!(result, Err(ProveError::InvalidState(e)) if e == "Volume underflow"));
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition(&mut rng).unwrap();
+        let result = prove_state_transition(
+            &keys.proving_key,
+            &state,
+            Fr::from(67890u64),
+            1,
+            30,
+            &VolumeRegistry::from_entries(&[(1, 10)]),
+            1000,
+            0,
+            Fr::from(12345678u64),
+            OpType::Withdraw,
+        );
+        assert!(matches!(result, Err(ProveError::InvalidState(e)) if e == "Volume underflow"));
+    }
+
+    #[test]
+    fn test_values_above_u32_rejected() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition(&mut rng).unwrap();
+        let above_u32 = u64::from(u32::MAX) + 1;
+        let deposit = |state: &InventoryState, item_id: u64, amount: u64| {
+            prove_state_transition(
+                &keys.proving_key,
+                state,
+                Fr::from(67890u64),
+                item_id,
+                amount,
+                &VolumeRegistry::from_entries(&[(1, 1), (2, 2)]),
+                u64::MAX,
+                0,
+                Fr::from(12345678u64),
+                OpType::Deposit,
+            )
+        };
+
+        // New volume of u32::MAX + 1
+        let mut state = InventoryState::new(Fr::from(12345u64));
+        state.current_volume = u64::from(u32::MAX);
+        let result = deposit(&state, 1, 1);
+        assert!(matches!(result, Err(ProveError::InvalidState(e)) if e == "Volume overflow"));
+
+        // Volume delta of u32::MAX + 1
+        let state = InventoryState::new(Fr::from(12345u64));
+        let result = deposit(&state, 2, above_u32 / 2);
+        assert!(matches!(result, Err(ProveError::InvalidState(e)) if e == "Volume overflow"));
+
+        // New quantity of u32::MAX + 1
+        let state = InventoryState::from_items(&[(1, u64::from(u32::MAX))], Fr::from(12345u64));
+        let result = deposit(&state, 1, 1);
+        assert!(matches!(result, Err(ProveError::InvalidState(e)) if e == "Quantity overflow"));
+    }
+
+    #[test]
+    fn test_inventory_state_remaining_capacity() {
+        let mut state = InventoryState::from_items(&[(1, 10)], Fr::from(12345u64));
+        state.current_volume = 80;
+
+        assert_eq!(state.remaining_capacity(100), Some(20));
+        assert_eq!(state.remaining_capacity(80), Some(0));
+        assert_eq!(state.remaining_capacity(79), None);
+    }
 
     #[test]
     fn test_prove_item_exists() {
@@ -435,6 +1716,145 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_prove_item_exists_with_capacity() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_item_exists_with_capacity(&mut rng).unwrap();
+
+        let mut state = InventoryState::from_items(&[(42, 100)], Fr::from(12345u64));
+        state.current_volume = 500;
+
+        let proof =
+            prove_item_exists_with_capacity(&keys.proving_key, &state, 42, 50, 1000).unwrap();
+        check_proof(&keys.verifying_key, &proof).unwrap();
+        assert_eq!(
+            proof.public_inputs,
+            vec![compute_item_exists_capacity_hash(state.commitment(), 42, 50, 1000)]
+        );
+
+        // Each sub-condition fails on its own
+        assert!(matches!(
+            prove_item_exists_with_capacity(&keys.proving_key, &state, 42, 101, 1000),
+            Err(ProveError::InsufficientQuantity { have: 100, need: 101 })
+        ));
+        assert!(matches!(
+            prove_item_exists_with_capacity(&keys.proving_key, &state, 42, 50, 499),
+            Err(ProveError::CapacityExceeded { volume: 500, capacity: 499 })
+        ));
+    }
+
+    #[test]
+    fn test_prove_non_membership() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_non_membership(&mut rng).unwrap();
+
+        let mut state = InventoryState::from_items(&[(1, 100), (42, 5)], Fr::from(12345u64));
+        state.current_volume = 500;
+
+        let proof = prove_non_membership(&keys.proving_key, &state, 7).unwrap();
+        check_proof(&keys.verifying_key, &proof).unwrap();
+        assert_eq!(
+            proof.public_inputs,
+            vec![compute_non_membership_hash(state.commitment(), 7)]
+        );
+
+        // Item 42 is held, so there is nothing to prove
+        assert!(matches!(
+            prove_non_membership(&keys.proving_key, &state, 42),
+            Err(ProveError::InvalidState(_))
+        ));
+    }
+
+    #[test]
+    fn test_prove_item_exists_batch() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_item_exists(&mut rng).unwrap();
+
+        // Ten inventories; the last one cannot cover its minimum
+        let requests: Vec<ItemExistsRequest> = (0..10u64)
+            .map(|i| {
+                let mut state = InventoryState::new(Fr::from(1000 + i));
+                state.tree.update(i + 1, 10 * (i + 1));
+                state.current_volume = 100;
+                ItemExistsRequest {
+                    state,
+                    item_id: i + 1,
+                    min_quantity: if i == 9 { 1000 } else { 5 },
+                }
+            })
+            .collect();
+
+        let results = prove_item_exists_batch(&keys.proving_key, &requests);
+        assert_eq!(results.len(), 10);
+        for result in &results[..9] {
+            check_proof(&keys.verifying_key, result.as_ref().unwrap()).unwrap();
+        }
+        assert!(matches!(results[9], Err(ProveError::InsufficientQuantity { .. })));
+    }
+
+    #[test]
+    fn test_prove_capacity_batch_seeded() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_capacity(&mut rng).unwrap();
+
+        let state = InventoryState::from_items(&[(1, 100)], Fr::from(12345u64));
+        let requests = vec![
+            CapacityRequest {
+                state: state.clone(),
+                max_capacity: 1000,
+            },
+            CapacityRequest {
+                state,
+                max_capacity: 1000,
+            },
+        ];
+
+        let prove = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            prove_capacity_batch_with_rng(&keys.proving_key, &requests, &mut rng)
+        };
+        let (a, b) = (prove(7), prove(7));
+        let (a0, a1) = (a[0].as_ref().unwrap(), a[1].as_ref().unwrap());
+        check_proof(&keys.verifying_key, a0).unwrap();
+        check_proof(&keys.verifying_key, a1).unwrap();
+
+        // Identical requests get independent randomness, but a seed reproduces the batch
+        assert_ne!(a0.proof, a1.proof);
+        assert_eq!(a0.proof, b[0].as_ref().unwrap().proof);
+    }
+
+    #[cfg(all(feature = "single_threaded", not(feature = "parallel")))]
+    #[test]
+    fn test_single_threaded_proof_verifies() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition(&mut rng).unwrap();
+
+        let mut state = InventoryState::from_items(&[(1, 100)], Fr::from(12345u64));
+        state.current_volume = 1000;
+        let result = prove_state_transition_with_rng(
+            &keys.proving_key,
+            &state,
+            Fr::from(67890u64),
+            1,
+            30,
+            &VolumeRegistry::from_entries(&[(1, 10)]),
+            2000,
+            0,
+            Fr::from(42u64),
+            OpType::Withdraw,
+            &mut rng,
+        )
+        .unwrap();
+        check_proof(&keys.verifying_key, &result.proof).unwrap();
+
+        // Batches prove in turn on the calling thread
+        let requests = vec![CapacityRequest { state, max_capacity: 1000 }; 2];
+        let keys = setup_capacity(&mut rng).unwrap();
+        for proof in prove_capacity_batch_with_rng(&keys.proving_key, &requests, &mut rng) {
+            check_proof(&keys.verifying_key, &proof.unwrap()).unwrap();
+        }
+    }
+
     #[test]
     fn test_prove_capacity() {
         let mut rng = StdRng::seed_from_u64(42);
@@ -466,6 +1886,122 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_prove_capacity_hidden_cap() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_capacity_hidden_cap(&mut rng).unwrap();
+
+        let blinding = Fr::from(12345u64);
+        let mut state = InventoryState::new(blinding);
+        state.tree.update(1, 100);
+        state.current_volume = 500;
+
+        // Two different hidden capacities, both sufficient
+        let cap_blinding_a = Fr::from(111u64);
+        let cap_blinding_b = Fr::from(222u64);
+        let proof_a =
+            prove_capacity_hidden_cap(&keys.proving_key, &state, 1000, cap_blinding_a).unwrap();
+        let proof_b =
+            prove_capacity_hidden_cap(&keys.proving_key, &state, 2000, cap_blinding_b).unwrap();
+
+        // Both expose a single field element and verify under the same key
+        for proof in [&proof_a, &proof_b] {
+            assert_eq!(proof.public_inputs.len(), 1);
+            let valid = crate::verify::verify_capacity(
+                &keys.verifying_key,
+                &proof.proof,
+                proof.public_inputs[0],
+            )
+            .unwrap();
+            assert!(valid);
+        }
+
+        // The public input only binds the capacity commitment the verifier holds
+        let commitment = state.commitment();
+        let cap_commitment_a = compute_capacity_commitment(1000, cap_blinding_a);
+        let wrong_cap_commitment = compute_capacity_commitment(2000, cap_blinding_a);
+        assert_eq!(
+            proof_a.public_inputs[0],
+            compute_hidden_capacity_hash(commitment, cap_commitment_a)
+        );
+        assert_ne!(
+            proof_a.public_inputs[0],
+            compute_hidden_capacity_hash(commitment, wrong_cap_commitment)
+        );
+
+        // Exceeding the hidden capacity is rejected
+        let result = prove_capacity_hidden_cap(&keys.proving_key, &state, 400, cap_blinding_a);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_seeded_proofs_are_reproducible() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_item_exists(&mut rng).unwrap();
+
+        let mut state = InventoryState::new(Fr::from(12345u64));
+        state.tree.update(1, 100);
+        state.current_volume = 500;
+
+        let prove_seeded = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            prove_item_exists_with_rng(&keys.proving_key, &state, 1, 50, &mut rng)
+                .unwrap()
+                .serialize_proof()
+                .unwrap()
+        };
+
+        assert_eq!(prove_seeded(7), prove_seeded(7));
+        assert_ne!(prove_seeded(7), prove_seeded(8));
+    }
+
+    #[test]
+    fn test_prove_and_bundle_verifies() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_capacity(&mut rng).unwrap();
+
+        let blinding = Fr::from(12345u64);
+        let mut state = InventoryState::new(blinding);
+        state.tree.update(1, 100);
+        state.current_volume = 500;
+
+        let (proof, vk) =
+            prove_and_bundle(&keys, |pk| prove_capacity(pk, &state, 1000)).unwrap();
+
+        let valid =
+            crate::verify::verify_capacity(&vk, &proof.proof, proof.public_inputs[0]).unwrap();
+        assert!(valid);
+    }
+
+    #[test]
+    fn test_prove_and_verify_self_checks() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_capacity(&mut rng).unwrap();
+
+        let blinding = Fr::from(12345u64);
+        let mut state = InventoryState::new(blinding);
+        state.tree.update(1, 100);
+        state.current_volume = 500;
+
+        let proof = prove_and_verify(&keys, |pk| prove_capacity(pk, &state, 1000)).unwrap();
+
+        // A tampered public input no longer verifies
+        let mut tampered = proof.clone();
+        tampered.public_inputs[0] += Fr::from(1u64);
+        assert!(matches!(
+            check_proof(&keys.verifying_key, &tampered),
+            Err(ProveError::SelfCheckFailed)
+        ));
+
+        // Keys from a different setup are caught before the proof is returned
+        let mismatched = CircuitKeyPair {
+            proving_key: keys.proving_key.clone(),
+            verifying_key: setup_capacity(&mut rng).unwrap().verifying_key,
+        };
+        let result = prove_and_verify(&mismatched, |pk| prove_capacity(pk, &state, 1000));
+        assert!(matches!(result, Err(ProveError::SelfCheckFailed)));
+    }
+
     #[test]
     fn test_prove_state_transition_deposit() {
         let mut rng = StdRng::seed_from_u64(42);
@@ -475,8 +2011,9 @@ mod tests {
         let new_blinding = Fr::from(67890u64);
         let state = InventoryState::new(blinding);
 
-        // Simple registry root (would normally come from on-chain registry)
-        let registry_root = Fr::from(99999u64);
+        // Item 1 at volume 10 (the registry would normally come from on-chain)
+        let registry = VolumeRegistry::from_entries(&[(1, 10)]);
+        let registry_root = compute_registry_hash(&registry);
         let nonce = 0u64;
         let inventory_id = Fr::from(12345678u64);
 
@@ -486,8 +2023,7 @@ mod tests {
             new_blinding,
             1,    // item_id
             5,    // amount
-            10,   // item_volume
-            registry_root,
+            &registry,
             1000, // max_capacity
             nonce,
             inventory_id,
@@ -518,7 +2054,7 @@ mod tests {
         state.current_volume = 1000; // 100 items * 10 volume each
 
         // Registry root and security parameters
-        let registry_root = Fr::from(99999u64);
+        let registry = VolumeRegistry::from_entries(&[(1, 10)]);
         let nonce = 5u64;
         let inventory_id = Fr::from(12345678u64);
 
@@ -528,8 +2064,7 @@ mod tests {
             new_blinding,
             1,    // item_id
             30,   // amount to withdraw
-            10,   // item_volume
-            registry_root,
+            &registry,
             1000, // max_capacity
             nonce,
             inventory_id,
@@ -542,4 +2077,488 @@ mod tests {
         assert_eq!(res.new_state.current_volume, 700); // 1000 - 30*10
         assert_eq!(res.new_state.get_quantity(1), 70); // 100 - 30
     }
+
+    #[test]
+    fn test_zero_capacity_is_not_unlimited() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition(&mut rng).unwrap();
+
+        let mut state = InventoryState::from_items(&[(1, 100)], Fr::from(12345u64));
+        state.current_volume = 1000;
+        let registry = VolumeRegistry::from_entries(&[(1, 10)]);
+        let transition = |amount, op_type| {
+            prove_state_transition(
+                &keys.proving_key,
+                &state,
+                Fr::from(67890u64),
+                1,
+                amount,
+                &registry,
+                0,
+                0,
+                Fr::from(12345678u64),
+                op_type,
+            )
+        };
+
+        // A cap of 0 only admits an empty inventory, like the circuit
+        assert!(matches!(
+            transition(5, OpType::Deposit),
+            Err(ProveError::CapacityExceeded { volume: 1050, capacity: 0 })
+        ));
+        assert!(matches!(
+            transition(30, OpType::Withdraw),
+            Err(ProveError::CapacityExceeded { volume: 700, capacity: 0 })
+        ));
+        assert!(transition(100, OpType::Withdraw).is_ok());
+    }
+
+    #[test]
+    fn test_prove_withdraw_with_capacity() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition(&mut rng).unwrap();
+
+        // 100 units at volume 10, above a lowered cap of 800
+        let mut state = InventoryState::from_items(&[(1, 100)], Fr::from(12345u64));
+        state.current_volume = 1000;
+        let withdraw = |amount| {
+            prove_withdraw_with_capacity(
+                &keys.proving_key,
+                &state,
+                Fr::from(67890u64),
+                1,
+                amount,
+                &VolumeRegistry::from_entries(&[(1, 10)]),
+                800,
+                5,
+                Fr::from(12345678u64),
+            )
+        };
+
+        let result = withdraw(30).unwrap();
+        assert_eq!(result.new_state.current_volume, 700);
+        check_proof(&keys.verifying_key, &result.proof).unwrap();
+
+        assert!(matches!(
+            withdraw(10),
+            Err(ProveError::CapacityExceeded { volume: 900, capacity: 800 })
+        ));
+    }
+
+    #[test]
+    fn test_prove_state_transition_from_items() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition(&mut rng).unwrap();
+
+        let items = [(1, 100), (3, 20)];
+        let blinding = Fr::from(12345u64);
+        let new_blinding = Fr::from(67890u64);
+        let registry = VolumeRegistry::from_entries(&[(1, 10), (3, 10)]);
+        let inventory_id = Fr::from(12345678u64);
+
+        let mut state = InventoryState::new(blinding);
+        for &(item_id, quantity) in &items {
+            state.tree.update(item_id, quantity);
+        }
+        state.current_volume = 1200;
+
+        // Same RNG seed on both paths, so the proofs themselves must match
+        let from_items = prove_state_transition_from_items_with_rng(
+            &keys.proving_key,
+            &items,
+            1200,
+            blinding,
+            new_blinding,
+            3,
+            5,
+            &registry,
+            2000,
+            7,
+            inventory_id,
+            OpType::Withdraw,
+            &mut StdRng::seed_from_u64(1),
+        )
+        .unwrap();
+        let manual = prove_state_transition_with_rng(
+            &keys.proving_key,
+            &state,
+            new_blinding,
+            3,
+            5,
+            &registry,
+            2000,
+            7,
+            inventory_id,
+            OpType::Withdraw,
+            &mut StdRng::seed_from_u64(1),
+        )
+        .unwrap();
+
+        assert_eq!(from_items.old_commitment, state.commitment());
+        assert_eq!(from_items.old_commitment, manual.old_commitment);
+        assert_eq!(from_items.new_commitment, manual.new_commitment);
+        assert_eq!(from_items.new_state.get_quantity(3), 15);
+        assert_eq!(from_items.proof.public_inputs, manual.proof.public_inputs);
+        assert_eq!(from_items.proof.proof, manual.proof.proof);
+    }
+
+    #[test]
+    fn test_prove_double_transition() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_double_transition(&mut rng).unwrap();
+
+        let mut state = InventoryState::from_items(&[(1, 100)], Fr::from(12345u64));
+        state.current_volume = 1000;
+        let registry = VolumeRegistry::from_entries(&[(1, 10), (2, 5), (3, 2)]);
+        let registry_root = compute_registry_hash(&registry);
+
+        let deposit = TransitionOp { item_id: 2, amount: 20, op_type: OpType::Deposit };
+        let withdraw = TransitionOp { item_id: 1, amount: 30, op_type: OpType::Withdraw };
+
+        let result = prove_double_transition(
+            &keys.proving_key,
+            &state,
+            deposit,
+            withdraw,
+            Fr::from(67890u64),
+            &registry,
+            1200,
+        )
+        .unwrap();
+
+        assert_eq!(result.new_state.current_volume, 800);
+        assert_eq!(result.new_state.get_quantity(1), 70);
+        assert_eq!(result.new_state.get_quantity(2), 20);
+        assert_eq!(result.new_commitment, result.new_state.commitment());
+        assert_eq!(
+            result.proof.public_inputs,
+            vec![state.commitment(), result.new_commitment, registry_root, Fr::from(1200u64)]
+        );
+
+        let valid = Groth16::<Bn254>::verify(
+            &keys.verifying_key,
+            &result.proof.public_inputs,
+            &result.proof.proof,
+        )
+        .unwrap();
+        assert!(valid);
+    }
+
+    #[test]
+    fn test_prove_double_transition_intermediate_over_capacity() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_double_transition(&mut rng).unwrap();
+
+        let mut state = InventoryState::from_items(&[(1, 100)], Fr::from(12345u64));
+        state.current_volume = 1000;
+
+        // The final volume (1000) fits, but the intermediate (1500) does not
+        let deposit = TransitionOp { item_id: 2, amount: 100, op_type: OpType::Deposit };
+        let withdraw = TransitionOp { item_id: 2, amount: 100, op_type: OpType::Withdraw };
+
+        let result = prove_double_transition(
+            &keys.proving_key,
+            &state,
+            deposit,
+            withdraw,
+            Fr::from(67890u64),
+            &VolumeRegistry::from_entries(&[(2, 5)]),
+            1200,
+        );
+        assert!(matches!(
+            result,
+            Err(ProveError::CapacityExceeded { volume: 1500, capacity: 1200 })
+        ));
+    }
+
+    #[test]
+    fn test_prove_batch_transition() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_batch_transition(&mut rng).unwrap();
+
+        let mut state = InventoryState::from_items(&[(1, 100)], Fr::from(12345u64));
+        state.current_volume = 1000;
+        let registry = VolumeRegistry::from_entries(&[(1, 10), (2, 5), (3, 2)]);
+        let registry_root = compute_registry_hash(&registry);
+        let inventory_id = Fr::from(777u64);
+
+        let ops = [
+            TransitionOp { item_id: 2, amount: 20, op_type: OpType::Deposit },
+            TransitionOp { item_id: 3, amount: 10, op_type: OpType::Deposit },
+            TransitionOp { item_id: 1, amount: 30, op_type: OpType::Withdraw },
+        ];
+
+        let result = prove_batch_transition(
+            &keys.proving_key,
+            &state,
+            &ops,
+            Fr::from(67890u64),
+            &registry,
+            1200,
+            4,
+            inventory_id,
+        )
+        .unwrap();
+
+        assert_eq!(result.new_state.current_volume, 820);
+        assert_eq!(result.new_state.get_quantity(1), 70);
+        assert_eq!(result.new_commitment, result.new_state.commitment());
+        assert_eq!(
+            &result.proof.public_inputs[1..],
+            &[Fr::from(4u64), inventory_id, registry_root]
+        );
+
+        let valid = Groth16::<Bn254>::verify(
+            &keys.verifying_key,
+            &result.proof.public_inputs,
+            &result.proof.proof,
+        )
+        .unwrap();
+        assert!(valid);
+
+        // Too many operations for one batch
+        let too_many = [ops[0]; MAX_BATCH_OPS + 1];
+        let result = prove_batch_transition(
+            &keys.proving_key,
+            &state,
+            &too_many,
+            Fr::from(67890u64),
+            &registry,
+            1200,
+            4,
+            inventory_id,
+        );
+        assert!(matches!(result, Err(ProveError::InvalidState(_))));
+    }
+
+    #[test]
+    fn test_prove_swap() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_swap(&mut rng).unwrap();
+
+        let mut state = InventoryState::from_items(&[(1, 100)], Fr::from(12345u64));
+        state.current_volume = 1000;
+        let registry = VolumeRegistry::from_entries(&[(1, 10), (2, 5), (3, 2)]);
+        let registry_root = compute_registry_hash(&registry);
+        let inventory_id = Fr::from(777u64);
+
+        let give = TransitionOp { item_id: 1, amount: 30, op_type: OpType::Withdraw };
+        let take = TransitionOp { item_id: 2, amount: 40, op_type: OpType::Deposit };
+
+        let result = prove_swap(
+            &keys.proving_key,
+            &state,
+            give,
+            take,
+            Fr::from(67890u64),
+            &registry,
+            1200,
+            4,
+            inventory_id,
+        )
+        .unwrap();
+
+        assert_eq!(result.new_state.current_volume, 900);
+        assert_eq!(result.new_state.get_quantity(1), 70);
+        assert_eq!(result.new_state.get_quantity(2), 40);
+        let signal_hash = compute_swap_signal_hash(
+            state.commitment(),
+            result.new_commitment,
+            registry_root,
+            1200,
+            1,
+            30,
+            2,
+            40,
+            4,
+            inventory_id,
+        );
+        assert_eq!(
+            result.proof.public_inputs,
+            vec![signal_hash, Fr::from(4u64), inventory_id, registry_root]
+        );
+
+        let valid = Groth16::<Bn254>::verify(
+            &keys.verifying_key,
+            &result.proof.public_inputs,
+            &result.proof.proof,
+        )
+        .unwrap();
+        assert!(valid);
+
+        // Not enough of the given item
+        let give = TransitionOp { amount: 101, ..give };
+        let result = prove_swap(
+            &keys.proving_key,
+            &state,
+            give,
+            take,
+            Fr::from(67890u64),
+            &registry,
+            1200,
+            4,
+            inventory_id,
+        );
+        assert!(matches!(
+            result,
+            Err(ProveError::InsufficientQuantity { have: 100, need: 101 })
+        ));
+    }
+
+    #[test]
+    fn test_prove_deposit_with_delta_cap() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_delta_capped_transition(&mut rng).unwrap();
+
+        let mut state = InventoryState::from_items(&[(1, 50)], Fr::from(12345u64));
+        state.current_volume = 500;
+        let inventory_id = Fr::from(42u64);
+        let registry = VolumeRegistry::from_entries(&[(1, 10)]);
+        let registry_root = compute_registry_hash(&registry);
+
+        // 10 units at 10 each add 100, exactly the cap
+        let result = prove_deposit_with_delta_cap(
+            &keys.proving_key,
+            &state,
+            Fr::from(67890u64),
+            1,
+            10,
+            &registry,
+            1000,
+            0,
+            inventory_id,
+            100,
+        )
+        .unwrap();
+        assert_eq!(result.new_state.current_volume, 600);
+
+        let signal_hash = compute_delta_capped_signal_hash(
+            compute_signal_hash(
+                state.commitment(),
+                result.new_commitment,
+                registry_root,
+                1000,
+                1,
+                10,
+                OpType::Deposit,
+                0,
+                inventory_id,
+            ),
+            100,
+        );
+        assert_eq!(result.proof.public_inputs[0], signal_hash);
+
+        let valid = crate::verify::verify_state_transition(
+            &keys.verifying_key,
+            &result.proof.proof,
+            signal_hash,
+            0,
+            inventory_id,
+            registry_root,
+        )
+        .unwrap();
+        assert!(valid);
+
+        // 11 units add 110 > 100
+        let result = prove_deposit_with_delta_cap(
+            &keys.proving_key,
+            &state,
+            Fr::from(67890u64),
+            1,
+            11,
+            &registry,
+            1000,
+            0,
+            inventory_id,
+            100,
+        );
+        assert!(matches!(result, Err(ProveError::InvalidState(_))));
+    }
+
+    #[test]
+    fn test_prove_disclose_item() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_selective_disclosure(&mut rng).unwrap();
+
+        let mut state = InventoryState::from_items(&[(1, 100), (5, 50)], Fr::from(12345u64));
+        state.current_volume = 1000;
+
+        let proof = prove_disclose_item(&keys.proving_key, &state, 5).unwrap();
+        assert_eq!(
+            proof.public_inputs,
+            vec![state.commitment(), Fr::from(5u64), Fr::from(50u64)]
+        );
+        assert!(Groth16::<Bn254>::verify(&keys.verifying_key, &proof.public_inputs, &proof.proof)
+            .unwrap());
+
+        // Claiming a different quantity for the revealed item fails
+        let false_inputs = vec![state.commitment(), Fr::from(5u64), Fr::from(49u64)];
+        assert!(!Groth16::<Bn254>::verify(&keys.verifying_key, &false_inputs, &proof.proof)
+            .unwrap());
+
+        assert!(prove_disclose_item(&keys.proving_key, &state, 7).is_err());
+    }
+
+    #[test]
+    fn test_prove_subset_capacity() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_subset_capacity(&mut rng).unwrap();
+
+        // Item 1 is heavy (50 per unit), item 2 is light (1 per unit)
+        let registry = VolumeRegistry::from_slice(&[0, 50, 1]);
+        let mut state = InventoryState::from_items(&[(1, 10), (2, 100)], Fr::from(12345u64));
+        state.current_volume = registry.calculate_used_volume(&state.tree).unwrap();
+
+        let heavy = 1 << 1;
+        let proof =
+            prove_subset_capacity(&keys.proving_key, &state, &registry, heavy, 500).unwrap();
+
+        let expected = compute_subset_capacity_hash(
+            state.commitment(),
+            compute_registry_hash(&registry),
+            heavy,
+            500,
+        );
+        assert_eq!(proof.public_inputs, vec![expected]);
+        assert!(Groth16::<Bn254>::verify(&keys.verifying_key, &proof.public_inputs, &proof.proof)
+            .unwrap());
+
+        // The heavy compartment does not fit in 400, although the total (600) might elsewhere
+        assert!(prove_subset_capacity(&keys.proving_key, &state, &registry, heavy, 400).is_err());
+    }
+
+    #[test]
+    fn test_capacity_rejects_item_missing_from_registry() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_capacity(&mut rng).unwrap();
+        let subset_keys = setup_subset_capacity(&mut rng).unwrap();
+
+        // Item 3 has no registered volume and would weigh nothing
+        let registry = VolumeRegistry::from_slice(&[0, 5, 10]);
+        let mut state = InventoryState::from_items(&[(1, 10), (3, 1000)], Fr::from(12345u64));
+        state.current_volume = 50;
+
+        let result = prove_capacity_with_registry(&keys.proving_key, &state, &registry, 100);
+        assert!(matches!(
+            result,
+            Err(ProveError::Registry(RegistryError::MissingVolume { item_id: 3 }))
+        ));
+
+        let result =
+            prove_subset_capacity(&subset_keys.proving_key, &state, &registry, 1 << 1, 100);
+        assert!(matches!(
+            result,
+            Err(ProveError::Registry(RegistryError::MissingVolume { item_id: 3 }))
+        ));
+
+        // With item 3 covered, the registry total must match the state
+        let registry = VolumeRegistry::from_slice(&[0, 5, 10, 1]);
+        assert!(prove_capacity_with_registry(&keys.proving_key, &state, &registry, 2000).is_err());
+
+        state.current_volume = 1050;
+        let proof =
+            prove_capacity_with_registry(&keys.proving_key, &state, &registry, 2000).unwrap();
+        assert!(Groth16::<Bn254>::verify(&keys.verifying_key, &proof.public_inputs, &proof.proof)
+            .unwrap());
+    }
 }
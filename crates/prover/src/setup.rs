@@ -1,197 +1,209 @@
 //! Trusted setup utilities for generating proving and verifying keys.
 
-use ark_bn254::Bn254;
+use ark_bn254::{Bn254, Fr};
 use ark_groth16::{Groth16, ProvingKey, VerifyingKey};
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, SynthesisMode,
+};
 use ark_snark::SNARK;
 use ark_std::rand::rngs::StdRng;
-use thiserror::Error;
 
 use inventory_circuits::{
-    CapacitySMTCircuit, ItemExistsSMTCircuit, StateTransitionCircuit,
+    smt::DEFAULT_DEPTH, BatchStateTransitionCircuit, CapacityHiddenSMTCircuit, CapacitySMTCircuit,
+    DoubleTransitionCircuit, ItemExistsSMTCircuit, ItemExistsWithCapacityCircuit,
+    NonMembershipSMTCircuit,
+    SelectiveDisclosureCircuit, StateTransitionCircuit, SubsetCapacityCircuit, SwapCircuit,
 };
 
-/// Errors that can occur during setup
-#[derive(Error, Debug)]
-pub enum SetupError {
-    #[error("Circuit setup failed: {0}")]
-    CircuitSetup(String),
-    #[error("Serialization failed: {0}")]
-    Serialization(String),
-    #[error("Deserialization failed: {0}")]
-    Deserialization(String),
-    #[error("IO error: {0}")]
-    Io(#[from] std::io::Error),
-}
-
-/// Keys for a single circuit
-#[derive(Clone)]
-pub struct CircuitKeyPair {
-    pub proving_key: ProvingKey<Bn254>,
-    pub verifying_key: VerifyingKey<Bn254>,
-}
-
-impl CircuitKeyPair {
-    /// Serialize proving key to bytes
-    pub fn serialize_pk(&self) -> Result<Vec<u8>, SetupError> {
-        let mut bytes = Vec::new();
-        self.proving_key
-            .serialize_compressed(&mut bytes)
-            .map_err(|e| SetupError::Serialization(e.to_string()))?;
-        Ok(bytes)
-    }
+use crate::par;
+pub use crate::keys::{CircuitKeyPair, CircuitKeys, SetupError};
 
-    /// Serialize verifying key to bytes
-    pub fn serialize_vk(&self) -> Result<Vec<u8>, SetupError> {
-        let mut bytes = Vec::new();
-        self.verifying_key
-            .serialize_compressed(&mut bytes)
-            .map_err(|e| SetupError::Serialization(e.to_string()))?;
-        Ok(bytes)
-    }
+/// Largest tree depth considered when diagnosing mismatched keys
+const MAX_KEY_DEPTH: usize = 32;
 
-    /// Deserialize proving key from bytes
-    pub fn deserialize_pk(bytes: &[u8]) -> Result<ProvingKey<Bn254>, SetupError> {
-        ProvingKey::deserialize_compressed(bytes)
-            .map_err(|e| SetupError::Deserialization(e.to_string()))
+/// Count the variables a circuit allocates, as seen by Groth16 setup.
+fn circuit_num_variables<C: ConstraintSynthesizer<Fr>>(circuit: C) -> Result<usize, SetupError> {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    cs.set_optimization_goal(OptimizationGoal::Constraints);
+    cs.set_mode(SynthesisMode::Setup);
+    circuit
+        .generate_constraints(cs.clone())
+        .map_err(|e| SetupError::CircuitSetup(e.to_string()))?;
+    cs.finalize();
+
+    Ok(cs.num_instance_variables() + cs.num_witness_variables())
+}
+
+/// Check that a proving key was generated for a circuit of `DEFAULT_DEPTH`.
+///
+/// The key's A query has one entry per circuit variable, so it is compared with
+/// the circuit built by `empty_with_depth(DEFAULT_DEPTH)`. On a mismatch, other
+/// depths are tried to report which depth the key was generated for.
+pub fn check_key_depth<C, F>(
+    name: &str,
+    pk: &ProvingKey<Bn254>,
+    empty_with_depth: F,
+) -> Result<(), SetupError>
+where
+    C: ConstraintSynthesizer<Fr>,
+    F: Fn(usize) -> C,
+{
+    let found = pk.a_query.len();
+    if circuit_num_variables(empty_with_depth(DEFAULT_DEPTH))? == found {
+        return Ok(());
     }
 
-    /// Deserialize verifying key from bytes
-    pub fn deserialize_vk(bytes: &[u8]) -> Result<VerifyingKey<Bn254>, SetupError> {
-        VerifyingKey::deserialize_compressed(bytes)
-            .map_err(|e| SetupError::Deserialization(e.to_string()))
+    for depth in 1..=MAX_KEY_DEPTH {
+        if depth != DEFAULT_DEPTH && circuit_num_variables(empty_with_depth(depth))? == found {
+            return Err(SetupError::DepthMismatch {
+                expected: DEFAULT_DEPTH,
+                found: depth,
+            });
+        }
     }
-}
 
-/// All circuit keys for SMT-based circuits
-pub struct CircuitKeys {
-    /// StateTransition circuit (deposit/withdraw with capacity)
-    pub state_transition: CircuitKeyPair,
-    /// ItemExists circuit (prove ownership of items)
-    pub item_exists: CircuitKeyPair,
-    /// Capacity circuit (prove volume compliance)
-    pub capacity: CircuitKeyPair,
+    Err(SetupError::KeyMismatch(name.to_string()))
 }
 
-impl CircuitKeys {
-    /// Save all keys to a directory
-    pub fn save_to_directory(&self, dir: &std::path::Path) -> Result<(), SetupError> {
-        std::fs::create_dir_all(dir)?;
-
-        std::fs::write(
-            dir.join("state_transition.pk"),
-            self.state_transition.serialize_pk()?,
-        )?;
-        std::fs::write(
-            dir.join("state_transition.vk"),
-            self.state_transition.serialize_vk()?,
-        )?;
+/// Base seed for setup randomness; circuit `i` uses `SETUP_SEED + i`
+const SETUP_SEED: u64 = 42;
 
-        std::fs::write(dir.join("item_exists.pk"), self.item_exists.serialize_pk()?)?;
-        std::fs::write(dir.join("item_exists.vk"), self.item_exists.serialize_vk()?)?;
+/// Deterministic RNG for the `index`-th circuit in `CircuitKeys`.
+///
+/// Each circuit gets its own stream so the keys do not depend on the order
+/// (or thread) in which the setups run.
+fn circuit_rng(index: u64) -> StdRng {
+    // Use a fixed seed for reproducible setup (in production, use secure randomness)
+    ark_std::rand::SeedableRng::seed_from_u64(SETUP_SEED + index)
+}
 
-        std::fs::write(dir.join("capacity.pk"), self.capacity.serialize_pk()?)?;
-        std::fs::write(dir.join("capacity.vk"), self.capacity.serialize_vk()?)?;
+/// Run trusted setup for all SMT circuits
+///
+/// The setups are independent and run concurrently on the rayon pool.
+pub fn setup_all_circuits() -> Result<CircuitKeys, SetupError> {
+    println!("Setting up StateTransitionCircuit, ItemExistsSMTCircuit and CapacitySMTCircuit...");
+    let (state_transition, (item_exists, capacity)) = par::join(
+        || setup_state_transition(&mut circuit_rng(0)),
+        || {
+            par::join(
+                || setup_item_exists(&mut circuit_rng(1)),
+                || setup_capacity(&mut circuit_rng(2)),
+            )
+        },
+    );
 
-        Ok(())
-    }
+    Ok(CircuitKeys {
+        state_transition: state_transition?,
+        item_exists: item_exists?,
+        capacity: capacity?,
+    })
+}
 
-    /// Load all keys from a directory
-    pub fn load_from_directory(dir: &std::path::Path) -> Result<Self, SetupError> {
-        let state_transition = CircuitKeyPair {
-            proving_key: CircuitKeyPair::deserialize_pk(&std::fs::read(
-                dir.join("state_transition.pk"),
-            )?)?,
-            verifying_key: CircuitKeyPair::deserialize_vk(&std::fs::read(
-                dir.join("state_transition.vk"),
-            )?)?,
-        };
+/// Check that a circuit's own witnesses satisfy its constraints.
+///
+/// Setup only looks at the constraint structure, so an empty circuit with
+/// inconsistent dummy witnesses still produces keys. Those keys then fail in
+/// confusing ways at proving time, so we reject such circuits up front.
+pub fn validate_empty_circuit<C: ConstraintSynthesizer<Fr>>(circuit: C) -> Result<(), SetupError> {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    circuit
+        .generate_constraints(cs.clone())
+        .map_err(|e| SetupError::CircuitSetup(e.to_string()))?;
 
-        let item_exists = CircuitKeyPair {
-            proving_key: CircuitKeyPair::deserialize_pk(&std::fs::read(
-                dir.join("item_exists.pk"),
-            )?)?,
-            verifying_key: CircuitKeyPair::deserialize_vk(&std::fs::read(
-                dir.join("item_exists.vk"),
-            )?)?,
-        };
+    match cs
+        .which_is_unsatisfied()
+        .map_err(|e| SetupError::CircuitSetup(e.to_string()))?
+    {
+        None => Ok(()),
+        Some(constraint) => Err(SetupError::UnsatisfiableCircuit(constraint)),
+    }
+}
 
-        let capacity = CircuitKeyPair {
-            proving_key: CircuitKeyPair::deserialize_pk(&std::fs::read(dir.join("capacity.pk"))?)?,
-            verifying_key: CircuitKeyPair::deserialize_vk(&std::fs::read(
-                dir.join("capacity.vk"),
-            )?)?,
-        };
+/// Run Groth16 setup for `circuit` after checking its witnesses satisfy it.
+///
+/// `circuit` is the empty circuit fixing the constraint structure; the
+/// `setup_*` functions below only choose it, and the caller chooses `rng`.
+pub fn setup_circuit<C: ConstraintSynthesizer<Fr> + Clone>(
+    circuit: C,
+    rng: &mut StdRng,
+) -> Result<(ProvingKey<Bn254>, VerifyingKey<Bn254>), SetupError> {
+    validate_empty_circuit(circuit.clone())?;
+    Groth16::<Bn254>::circuit_specific_setup(circuit, rng)
+        .map_err(|e| SetupError::CircuitSetup(e.to_string()))
+}
 
-        Ok(Self {
-            state_transition,
-            item_exists,
-            capacity,
-        })
-    }
+/// Setup StateTransitionCircuit
+pub fn setup_state_transition(rng: &mut StdRng) -> Result<CircuitKeyPair, SetupError> {
+    setup_circuit(StateTransitionCircuit::empty(), rng).map(CircuitKeyPair::from)
 }
 
-/// Run trusted setup for all SMT circuits
-pub fn setup_all_circuits() -> Result<CircuitKeys, SetupError> {
-    // Use a fixed seed for reproducible setup (in production, use secure randomness)
-    let mut rng = ark_std::rand::SeedableRng::seed_from_u64(42);
+/// Setup ItemExistsSMTCircuit
+pub fn setup_item_exists(rng: &mut StdRng) -> Result<CircuitKeyPair, SetupError> {
+    setup_circuit(ItemExistsSMTCircuit::empty(), rng).map(CircuitKeyPair::from)
+}
 
-    println!("Setting up StateTransitionCircuit...");
-    let state_transition = setup_state_transition(&mut rng)?;
+/// Setup ItemExistsWithCapacityCircuit
+pub fn setup_item_exists_with_capacity(rng: &mut StdRng) -> Result<CircuitKeyPair, SetupError> {
+    setup_circuit(ItemExistsWithCapacityCircuit::empty(), rng).map(CircuitKeyPair::from)
+}
 
-    println!("Setting up ItemExistsSMTCircuit...");
-    let item_exists = setup_item_exists(&mut rng)?;
+/// Setup NonMembershipSMTCircuit
+pub fn setup_non_membership(rng: &mut StdRng) -> Result<CircuitKeyPair, SetupError> {
+    setup_circuit(NonMembershipSMTCircuit::empty(), rng).map(CircuitKeyPair::from)
+}
 
-    println!("Setting up CapacitySMTCircuit...");
-    let capacity = setup_capacity(&mut rng)?;
+/// Setup CapacitySMTCircuit
+pub fn setup_capacity(rng: &mut StdRng) -> Result<CircuitKeyPair, SetupError> {
+    setup_circuit(CapacitySMTCircuit::empty(), rng).map(CircuitKeyPair::from)
+}
 
-    Ok(CircuitKeys {
-        state_transition,
-        item_exists,
-        capacity,
-    })
+/// Setup CapacityHiddenSMTCircuit
+///
+/// Not part of `CircuitKeys`; callers that use hidden capacities manage these keys.
+pub fn setup_capacity_hidden_cap(rng: &mut StdRng) -> Result<CircuitKeyPair, SetupError> {
+    setup_circuit(CapacityHiddenSMTCircuit::empty(), rng).map(CircuitKeyPair::from)
 }
 
-/// Setup StateTransitionCircuit
-pub fn setup_state_transition(
-    rng: &mut StdRng,
-) -> Result<CircuitKeyPair, SetupError> {
-    let circuit = StateTransitionCircuit::empty();
-    let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(circuit, rng)
-        .map_err(|e| SetupError::CircuitSetup(e.to_string()))?;
+/// Setup StateTransitionCircuit with a deposit growth cap
+///
+/// Not part of `CircuitKeys`; deployments enforcing `max_delta` manage these keys.
+pub fn setup_delta_capped_transition(rng: &mut StdRng) -> Result<CircuitKeyPair, SetupError> {
+    setup_circuit(StateTransitionCircuit::empty().with_max_delta(0), rng).map(CircuitKeyPair::from)
+}
 
-    Ok(CircuitKeyPair {
-        proving_key: pk,
-        verifying_key: vk,
-    })
+/// Setup SelectiveDisclosureCircuit
+///
+/// Not part of `CircuitKeys`; callers that disclose single items manage these keys.
+pub fn setup_selective_disclosure(rng: &mut StdRng) -> Result<CircuitKeyPair, SetupError> {
+    setup_circuit(SelectiveDisclosureCircuit::empty(), rng).map(CircuitKeyPair::from)
 }
 
-/// Setup ItemExistsSMTCircuit
-pub fn setup_item_exists(
-    rng: &mut StdRng,
-) -> Result<CircuitKeyPair, SetupError> {
-    let circuit = ItemExistsSMTCircuit::empty();
-    let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(circuit, rng)
-        .map_err(|e| SetupError::CircuitSetup(e.to_string()))?;
+/// Setup SubsetCapacityCircuit
+///
+/// Not part of `CircuitKeys`; callers with per-compartment limits manage these keys.
+pub fn setup_subset_capacity(rng: &mut StdRng) -> Result<CircuitKeyPair, SetupError> {
+    setup_circuit(SubsetCapacityCircuit::empty(), rng).map(CircuitKeyPair::from)
+}
 
-    Ok(CircuitKeyPair {
-        proving_key: pk,
-        verifying_key: vk,
-    })
+/// Setup DoubleTransitionCircuit
+///
+/// Not part of `CircuitKeys`; callers that batch two transitions manage these keys.
+pub fn setup_double_transition(rng: &mut StdRng) -> Result<CircuitKeyPair, SetupError> {
+    setup_circuit(DoubleTransitionCircuit::empty(), rng).map(CircuitKeyPair::from)
 }
 
-/// Setup CapacitySMTCircuit
-pub fn setup_capacity(
-    rng: &mut StdRng,
-) -> Result<CircuitKeyPair, SetupError> {
-    let circuit = CapacitySMTCircuit::empty();
-    let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(circuit, rng)
-        .map_err(|e| SetupError::CircuitSetup(e.to_string()))?;
+/// Setup BatchStateTransitionCircuit
+///
+/// Not part of `CircuitKeys`; callers that batch several transitions manage these keys.
+pub fn setup_batch_transition(rng: &mut StdRng) -> Result<CircuitKeyPair, SetupError> {
+    setup_circuit(BatchStateTransitionCircuit::empty(), rng).map(CircuitKeyPair::from)
+}
 
-    Ok(CircuitKeyPair {
-        proving_key: pk,
-        verifying_key: vk,
-    })
+/// Setup SwapCircuit
+///
+/// Not part of `CircuitKeys`; callers that swap items atomically manage these keys.
+pub fn setup_swap(rng: &mut StdRng) -> Result<CircuitKeyPair, SetupError> {
+    setup_circuit(SwapCircuit::empty(), rng).map(CircuitKeyPair::from)
 }
 
 #[cfg(test)]
@@ -235,4 +247,147 @@ mod tests {
         let _pk = CircuitKeyPair::deserialize_pk(&pk_bytes).unwrap();
         let _vk = CircuitKeyPair::deserialize_vk(&vk_bytes).unwrap();
     }
+
+    #[test]
+    fn test_vk_fingerprint_stable_across_loads() {
+        let keys = setup_capacity(&mut StdRng::seed_from_u64(42)).unwrap();
+        let pk_bytes = keys.serialize_pk().unwrap();
+        let vk_bytes = keys.serialize_vk().unwrap();
+
+        let load = || CircuitKeyPair {
+            proving_key: CircuitKeyPair::deserialize_pk(&pk_bytes).unwrap(),
+            verifying_key: CircuitKeyPair::deserialize_vk(&vk_bytes).unwrap(),
+        };
+        let fingerprint = load().vk_fingerprint().unwrap();
+        assert_eq!(fingerprint.len(), 16);
+        assert_eq!(fingerprint, load().vk_fingerprint().unwrap());
+        assert_eq!(fingerprint, keys.vk_fingerprint().unwrap());
+
+        // A different setup yields a different fingerprint
+        let other = setup_capacity(&mut StdRng::seed_from_u64(43)).unwrap();
+        assert_ne!(fingerprint, other.vk_fingerprint().unwrap());
+    }
+
+    #[test]
+    fn test_empty_circuits_satisfiable() {
+        validate_empty_circuit(StateTransitionCircuit::empty()).unwrap();
+        validate_empty_circuit(ItemExistsSMTCircuit::empty()).unwrap();
+        validate_empty_circuit(CapacitySMTCircuit::empty()).unwrap();
+    }
+
+    #[test]
+    fn test_unsatisfiable_circuit_rejected() {
+        let mut circuit = CapacitySMTCircuit::empty();
+        circuit.public_hash = Some(Fr::from(1u64));
+
+        let result = validate_empty_circuit(circuit);
+        assert!(matches!(result, Err(SetupError::UnsatisfiableCircuit(_))));
+    }
+
+    #[test]
+    fn test_load_keys_with_wrong_depth() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = CircuitKeys {
+            state_transition: setup_state_transition(&mut rng).unwrap(),
+            item_exists: setup_item_exists(&mut rng).unwrap(),
+            capacity: setup_capacity(&mut rng).unwrap(),
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        keys.save_to_directory(dir.path()).unwrap();
+        CircuitKeys::load_from_directory(dir.path()).unwrap();
+
+        // Overwrite the state transition keys with keys from a depth-8 build
+        let depth = 8;
+        assert_ne!(depth, DEFAULT_DEPTH);
+        let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(
+            StateTransitionCircuit::empty_with_depth(depth),
+            &mut rng,
+        )
+        .unwrap();
+        let other = CircuitKeyPair {
+            proving_key: pk,
+            verifying_key: vk,
+        };
+        std::fs::write(dir.path().join("state_transition.pk"), other.serialize_pk().unwrap())
+            .unwrap();
+        std::fs::write(dir.path().join("state_transition.vk"), other.serialize_vk().unwrap())
+            .unwrap();
+
+        let result = CircuitKeys::load_from_directory(dir.path());
+        assert!(matches!(
+            result,
+            Err(SetupError::DepthMismatch { expected: DEFAULT_DEPTH, found: 8 })
+        ));
+    }
+
+    #[test]
+    fn test_streaming_and_parallel_loads_match_buffered_load() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = CircuitKeys {
+            state_transition: setup_state_transition(&mut rng).unwrap(),
+            item_exists: setup_item_exists(&mut rng).unwrap(),
+            capacity: setup_capacity(&mut rng).unwrap(),
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        keys.save_to_directory(dir.path()).unwrap();
+        let buffered = CircuitKeys::load_from_directory(dir.path()).unwrap();
+        let streamed = CircuitKeys::load_from_directory_streaming(dir.path()).unwrap();
+        let parallel = CircuitKeys::load_from_directory_parallel(dir.path()).unwrap();
+
+        for other in [&streamed, &parallel] {
+            for (name, a, b) in [
+                ("state_transition", &buffered.state_transition, &other.state_transition),
+                ("item_exists", &buffered.item_exists, &other.item_exists),
+                ("capacity", &buffered.capacity, &other.capacity),
+            ] {
+                assert!(a.proving_key == b.proving_key, "{} pk differs", name);
+                assert!(a.verifying_key == b.verifying_key, "{} vk differs", name);
+            }
+        }
+
+        std::fs::remove_file(dir.path().join("capacity.pk")).unwrap();
+        assert!(matches!(
+            CircuitKeys::load_from_directory_streaming(dir.path()),
+            Err(SetupError::Io(_))
+        ));
+        assert!(matches!(
+            CircuitKeys::load_from_directory_parallel(dir.path()),
+            Err(SetupError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_setup_is_reproducible() {
+        let first = setup_all_circuits().unwrap();
+        let second = setup_all_circuits().unwrap();
+
+        for (name, a, b) in [
+            ("state_transition", &first.state_transition, &second.state_transition),
+            ("item_exists", &first.item_exists, &second.item_exists),
+            ("capacity", &first.capacity, &second.capacity),
+        ] {
+            assert!(a.serialize_pk().unwrap() == b.serialize_pk().unwrap(), "{} pk differs", name);
+            assert!(a.serialize_vk().unwrap() == b.serialize_vk().unwrap(), "{} vk differs", name);
+        }
+    }
+
+    #[test]
+    fn test_parallel_setup_matches_sequential() {
+        let parallel = setup_all_circuits().unwrap();
+        let sequential = CircuitKeys {
+            state_transition: setup_state_transition(&mut circuit_rng(0)).unwrap(),
+            item_exists: setup_item_exists(&mut circuit_rng(1)).unwrap(),
+            capacity: setup_capacity(&mut circuit_rng(2)).unwrap(),
+        };
+
+        for (name, a, b) in [
+            ("state_transition", &parallel.state_transition, &sequential.state_transition),
+            ("item_exists", &parallel.item_exists, &sequential.item_exists),
+            ("capacity", &parallel.capacity, &sequential.capacity),
+        ] {
+            assert!(a.serialize_vk().unwrap() == b.serialize_vk().unwrap(), "{} vk differs", name);
+        }
+    }
 }
@@ -0,0 +1,127 @@
+//! Off-circuit signal hash computation for integrators.
+//!
+//! The state transition proof's first public input is a Poseidon hash of the
+//! operation parameters. Computing it here lets a caller check a proof against
+//! on-chain values without building the circuit.
+//!
+//! `registry_hash_matches` does the same for the registry_root input, which
+//! binds the volume registry the proof priced items with.
+
+use ark_bn254::Fr;
+use ark_serialize::CanonicalSerialize;
+
+use inventory_circuits::signal::{compute_signal_hash, OpType};
+use inventory_circuits::VolumeRegistry;
+
+/// Compute the signal hash of a state transition.
+///
+/// Matches the first public input of `prove_state_transition` for the same
+/// parameters.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_state_transition_signal(
+    old_commitment: Fr,
+    new_commitment: Fr,
+    registry_root: Fr,
+    max_capacity: u64,
+    item_id: u64,
+    amount: u64,
+    op_type: OpType,
+    nonce: u64,
+    inventory_id: Fr,
+) -> Fr {
+    compute_signal_hash(
+        old_commitment,
+        new_commitment,
+        registry_root,
+        max_capacity,
+        item_id,
+        amount,
+        op_type,
+        nonce,
+        inventory_id,
+    )
+}
+
+/// Whether `claimed` is the hash of `registry`.
+///
+/// A verifier only sees a proof's registry_root. Checking it against the
+/// canonical (e.g. on-chain) registry confirms the proof used real volumes.
+pub fn registry_hash_matches(registry: &VolumeRegistry, claimed: Fr) -> bool {
+    registry.verify_hash(claimed)
+}
+
+/// Encode a signal hash as 0x-prefixed little-endian hex.
+///
+/// Same encoding as the proof server's public inputs; decode with
+/// `public_input_from_hex`.
+pub fn signal_hash_to_hex(signal_hash: &Fr) -> String {
+    let mut bytes = Vec::new();
+    signal_hash
+        .serialize_compressed(&mut bytes)
+        .expect("serializing into a Vec cannot fail");
+    format!("0x{}", hex::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prove::{prove_state_transition, InventoryState};
+    use crate::setup::setup_state_transition;
+    use crate::verify::public_input_from_hex;
+    use inventory_circuits::compute_registry_hash;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_signal_matches_proof_input() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition(&mut rng).unwrap();
+
+        let mut state = InventoryState::from_items(&[(1, 100)], Fr::from(12345u64));
+        state.current_volume = 1000;
+        let registry = VolumeRegistry::from_entries(&[(1, 10)]);
+        let registry_root = compute_registry_hash(&registry);
+        let inventory_id = Fr::from(42u64);
+
+        let result = prove_state_transition(
+            &keys.proving_key,
+            &state,
+            Fr::from(67890u64),
+            1,
+            50,
+            &registry,
+            2000,
+            3,
+            inventory_id,
+            OpType::Deposit,
+        )
+        .unwrap();
+
+        let signal = compute_state_transition_signal(
+            state.commitment(),
+            result.new_commitment,
+            registry_root,
+            2000,
+            1,
+            50,
+            OpType::Deposit,
+            3,
+            inventory_id,
+        );
+        assert_eq!(signal, result.proof.public_inputs[0]);
+
+        let hex = signal_hash_to_hex(&signal);
+        assert_eq!(hex.len(), 2 + 64);
+        assert_eq!(public_input_from_hex(&hex).unwrap(), signal);
+    }
+
+    #[test]
+    fn test_registry_hash_matches() {
+        let registry = VolumeRegistry::from_entries(&[(1, 10), (2, 5)]);
+        let claimed = compute_registry_hash(&registry);
+        assert!(registry_hash_matches(&registry, claimed));
+
+        let other = VolumeRegistry::from_entries(&[(1, 10), (2, 6)]);
+        assert!(!registry_hash_matches(&other, claimed));
+        assert!(!registry_hash_matches(&registry, claimed + Fr::from(1u64)));
+    }
+}
@@ -1,10 +1,15 @@
 //! Local proof verification for testing SMT-based circuits.
 
+use std::time::{Duration, Instant};
+
 use ark_bn254::{Bn254, Fr};
-use ark_groth16::{Groth16, Proof, VerifyingKey};
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, VerifyingKey};
+use ark_serialize::CanonicalDeserialize;
 use ark_snark::SNARK;
 use thiserror::Error;
 
+use crate::keys::{CircuitKeyPair, CircuitKeys};
+
 /// Errors during verification
 #[derive(Error, Debug)]
 pub enum VerifyError {
@@ -12,15 +17,178 @@ pub enum VerifyError {
     Verification(String),
     #[error("Invalid public inputs")]
     InvalidInputs,
+    #[error("Non-canonical public input encoding")]
+    NonCanonicalInput,
+    #[error("{kind:?} expects {expected} public inputs, got {found}")]
+    InputCountMismatch {
+        kind: CircuitKind,
+        expected: usize,
+        found: usize,
+    },
+}
+
+/// The circuits with keys in `CircuitKeys`, for generic verification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitKind {
+    /// Deposit/withdraw with capacity checking
+    StateTransition,
+    /// Prove ownership of items
+    ItemExists,
+    /// Prove volume compliance
+    Capacity,
+}
+
+impl CircuitKind {
+    /// Number of public inputs the circuit exposes.
+    pub fn public_input_count(self) -> usize {
+        match self {
+            // signal_hash, nonce, inventory_id, registry_root
+            CircuitKind::StateTransition => 4,
+            CircuitKind::ItemExists | CircuitKind::Capacity => 1,
+        }
+    }
+
+    /// Byte identifying the circuit in a serialized `ProofEnvelope`.
+    pub fn tag(self) -> u8 {
+        match self {
+            CircuitKind::StateTransition => 0,
+            CircuitKind::ItemExists => 1,
+            CircuitKind::Capacity => 2,
+        }
+    }
+
+    /// Inverse of `tag`.
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(CircuitKind::StateTransition),
+            1 => Some(CircuitKind::ItemExists),
+            2 => Some(CircuitKind::Capacity),
+            _ => None,
+        }
+    }
+
+    /// Get this circuit's key pair.
+    pub fn keys(self, keys: &CircuitKeys) -> &CircuitKeyPair {
+        match self {
+            CircuitKind::StateTransition => &keys.state_transition,
+            CircuitKind::ItemExists => &keys.item_exists,
+            CircuitKind::Capacity => &keys.capacity,
+        }
+    }
+}
+
+/// Processed verifying keys for every circuit in `CircuitKeys`.
+///
+/// `Groth16::verify` processes its verifying key (including a pairing) on
+/// every call. Build this once and verify with `verify_prepared` when many
+/// proofs are checked against the same keys.
+#[derive(Clone)]
+pub struct PreparedCircuitKeys {
+    pub state_transition: PreparedVerifyingKey<Bn254>,
+    pub item_exists: PreparedVerifyingKey<Bn254>,
+    pub capacity: PreparedVerifyingKey<Bn254>,
+}
+
+impl PreparedCircuitKeys {
+    /// Process each circuit's verifying key.
+    pub fn new(keys: &CircuitKeys) -> Self {
+        let prepare = |kind: CircuitKind| {
+            Groth16::<Bn254>::process_vk(&kind.keys(keys).verifying_key)
+                .expect("processing a verifying key cannot fail")
+        };
+        Self {
+            state_transition: prepare(CircuitKind::StateTransition),
+            item_exists: prepare(CircuitKind::ItemExists),
+            capacity: prepare(CircuitKind::Capacity),
+        }
+    }
+
+    /// Get this circuit's processed verifying key.
+    pub fn key(&self, kind: CircuitKind) -> &PreparedVerifyingKey<Bn254> {
+        match kind {
+            CircuitKind::StateTransition => &self.state_transition,
+            CircuitKind::ItemExists => &self.item_exists,
+            CircuitKind::Capacity => &self.capacity,
+        }
+    }
+}
+
+/// Verify a proof for any circuit kind, checking the public input count first.
+pub fn verify(
+    kind: CircuitKind,
+    keys: &CircuitKeys,
+    proof: &Proof<Bn254>,
+    public_inputs: &[Fr],
+) -> Result<bool, VerifyError> {
+    check_input_count(kind, public_inputs)?;
+
+    Groth16::<Bn254>::verify(&kind.keys(keys).verifying_key, public_inputs, proof)
+        .map_err(|e| VerifyError::Verification(e.to_string()))
+}
+
+/// Like `verify`, against keys processed once with `PreparedCircuitKeys::new`.
+pub fn verify_prepared(
+    kind: CircuitKind,
+    keys: &PreparedCircuitKeys,
+    proof: &Proof<Bn254>,
+    public_inputs: &[Fr],
+) -> Result<bool, VerifyError> {
+    check_input_count(kind, public_inputs)?;
+
+    Groth16::<Bn254>::verify_with_processed_vk(keys.key(kind), public_inputs, proof)
+        .map_err(|e| VerifyError::Verification(e.to_string()))
 }
 
-/// Verify a StateTransition proof (uses signal hash as single public input)
+fn check_input_count(kind: CircuitKind, public_inputs: &[Fr]) -> Result<(), VerifyError> {
+    let expected = kind.public_input_count();
+    if public_inputs.len() != expected {
+        return Err(VerifyError::InputCountMismatch {
+            kind,
+            expected,
+            found: public_inputs.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Decode a public input from its 32-byte compressed encoding.
+///
+/// Rejects encodings >= the field modulus instead of reducing them, so a
+/// verifier never accepts an input the prover could not have produced.
+pub fn public_input_from_bytes(bytes: &[u8]) -> Result<Fr, VerifyError> {
+    if bytes.len() != 32 {
+        return Err(VerifyError::InvalidInputs);
+    }
+    Fr::deserialize_compressed(bytes).map_err(|_| VerifyError::NonCanonicalInput)
+}
+
+/// Decode a public input from a hex string (optionally 0x-prefixed).
+pub fn public_input_from_hex(hex: &str) -> Result<Fr, VerifyError> {
+    let bytes = hex::decode(hex.trim_start_matches("0x"))
+        .map_err(|_| VerifyError::InvalidInputs)?;
+    public_input_from_bytes(&bytes)
+}
+
+/// Decode concatenated public inputs (as produced by `ProofWithInputs::serialize_public_inputs`).
+pub fn public_inputs_from_bytes(bytes: &[u8]) -> Result<Vec<Fr>, VerifyError> {
+    if !bytes.len().is_multiple_of(32) {
+        return Err(VerifyError::InvalidInputs);
+    }
+    bytes.chunks(32).map(public_input_from_bytes).collect()
+}
+
+/// Verify a StateTransition proof
+///
+/// Public inputs, in order: signal_hash, nonce, inventory_id, registry_root
 pub fn verify_state_transition(
     vk: &VerifyingKey<Bn254>,
     proof: &Proof<Bn254>,
     signal_hash: Fr,
+    nonce: u64,
+    inventory_id: Fr,
+    registry_root: Fr,
 ) -> Result<bool, VerifyError> {
-    let public_inputs = vec![signal_hash];
+    let public_inputs = vec![signal_hash, Fr::from(nonce), inventory_id, registry_root];
 
     Groth16::<Bn254>::verify(vk, &public_inputs, proof)
         .map_err(|e| VerifyError::Verification(e.to_string()))
@@ -50,13 +218,150 @@ pub fn verify_capacity(
         .map_err(|e| VerifyError::Verification(e.to_string()))
 }
 
-#[cfg(test)]
+/// Verify many proofs against one verifying key, returning one result per proof.
+///
+/// The key is processed once for the whole batch rather than once per proof.
+/// A proof whose input count does not match the key is reported as invalid.
+pub fn verify_batch(vk: &VerifyingKey<Bn254>, items: &[(Proof<Bn254>, Vec<Fr>)]) -> Vec<bool> {
+    let pvk = Groth16::<Bn254>::process_vk(vk).expect("processing a verifying key cannot fail");
+    items
+        .iter()
+        .map(|(proof, public_inputs)| {
+            Groth16::<Bn254>::verify_with_processed_vk(&pvk, public_inputs, proof)
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+/// Outcome of `verify_detailed`, classifying why a proof was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyReport {
+    /// The proof verifies against the given inputs
+    Valid,
+    /// Inputs are well-formed but the proof does not verify against them
+    InvalidProof,
+    /// The number of public inputs does not match the verifying key
+    WrongInputCount { expected: usize, found: usize },
+    /// The input at `index` is not a canonical field element encoding
+    InputOutOfDomain { index: usize },
+}
+
+impl VerifyReport {
+    /// Whether the proof was accepted.
+    pub fn is_valid(&self) -> bool {
+        matches!(self, VerifyReport::Valid)
+    }
+}
+
+/// Verify a proof and report the reason for failure.
+///
+/// Public inputs are given as their 32-byte compressed encodings (as produced by
+/// `ProofWithInputs::serialize_public_inputs`), so out-of-domain values sent by a
+/// client can be reported instead of being silently reduced.
+pub fn verify_detailed(
+    vk: &VerifyingKey<Bn254>,
+    proof: &Proof<Bn254>,
+    public_inputs: &[[u8; 32]],
+) -> VerifyReport {
+    // gamma_abc_g1 has one entry for the constant term plus one per public input
+    let expected = vk.gamma_abc_g1.len().saturating_sub(1);
+    if public_inputs.len() != expected {
+        return VerifyReport::WrongInputCount {
+            expected,
+            found: public_inputs.len(),
+        };
+    }
+
+    let mut inputs = Vec::with_capacity(public_inputs.len());
+    for (index, bytes) in public_inputs.iter().enumerate() {
+        match public_input_from_bytes(bytes) {
+            Ok(input) => inputs.push(input),
+            Err(_) => return VerifyReport::InputOutOfDomain { index },
+        }
+    }
+
+    let pvk = Groth16::<Bn254>::process_vk(vk).expect("processing a verifying key cannot fail");
+    match Groth16::<Bn254>::verify_with_processed_vk(&pvk, &inputs, proof) {
+        Ok(true) => VerifyReport::Valid,
+        _ => VerifyReport::InvalidProof,
+    }
+}
+
+/// Measured verification cost of a proof, for latency and gas budgeting.
+///
+/// Groth16 verification is three pairings plus one scalar multiplication per
+/// public input, so the cost depends on the input count, not on the circuit size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofInfo {
+    /// Number of public inputs
+    pub public_inputs: usize,
+    /// Fastest time to fold full-width public inputs into the input commitment
+    pub prepare_inputs_time: Duration,
+    /// Fastest time for a full verification of the proof against a processed key
+    pub verify_time: Duration,
+}
+
+/// Measure the verification cost of a proof, keeping the fastest of `runs`.
+///
+/// Small inputs (a zero nonce, say) make cheap scalar multiplications, so input
+/// preparation is timed on full-width inputs of the same count: the figure is
+/// the worst case for this verifying key. Fails unless the proof verifies.
+pub fn proof_info(
+    vk: &VerifyingKey<Bn254>,
+    proof: &Proof<Bn254>,
+    public_inputs: &[Fr],
+    runs: usize,
+) -> Result<ProofInfo, VerifyError> {
+    let pvk = Groth16::<Bn254>::process_vk(vk)
+        .map_err(|e| VerifyError::Verification(e.to_string()))?;
+    let verify = || {
+        Groth16::<Bn254>::verify_with_processed_vk(&pvk, public_inputs, proof)
+            .map_err(|e| VerifyError::Verification(e.to_string()))
+    };
+    if !verify()? {
+        return Err(VerifyError::Verification("proof does not verify".to_string()));
+    }
+
+    // -1 is the largest field element
+    let full_width = vec![-Fr::from(1u64); public_inputs.len()];
+
+    let mut prepare_inputs_time = Duration::MAX;
+    let mut verify_time = Duration::MAX;
+    for _ in 0..runs.max(1) {
+        let start = Instant::now();
+        let _ = Groth16::<Bn254>::prepare_inputs(&pvk, &full_width)
+            .map_err(|e| VerifyError::Verification(e.to_string()))?;
+        prepare_inputs_time = prepare_inputs_time.min(start.elapsed());
+
+        let start = Instant::now();
+        verify()?;
+        verify_time = verify_time.min(start.elapsed());
+    }
+
+    Ok(ProofInfo {
+        public_inputs: public_inputs.len(),
+        prepare_inputs_time,
+        verify_time,
+    })
+}
+
+#[cfg(all(test, feature = "prove"))]
 mod tests {
     use super::*;
-    use crate::prove::{prove_capacity, prove_item_exists, InventoryState};
-    use crate::setup::{setup_capacity, setup_item_exists};
+    use crate::prove::{prove_capacity, prove_item_exists, prove_state_transition, InventoryState};
+    use crate::setup::{setup_capacity, setup_item_exists, setup_state_transition};
+    use inventory_circuits::signal::OpType;
+    use inventory_circuits::VolumeRegistry;
+    use ark_ff::{BigInteger, PrimeField};
+    use ark_serialize::CanonicalSerialize;
     use ark_std::rand::{rngs::StdRng, SeedableRng};
 
+    fn encode(input: Fr) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        input.serialize_compressed(&mut bytes[..]).unwrap();
+        bytes
+    }
+
     #[test]
     fn test_verify_item_exists() {
         let mut rng = StdRng::seed_from_u64(42);
@@ -124,4 +429,256 @@ mod tests {
 
         assert!(valid);
     }
+
+    #[test]
+    fn test_verify_batch_reports_each_proof() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_capacity(&mut rng).unwrap();
+
+        let mut state = InventoryState::new(Fr::from(12345u64));
+        state.tree.update(1, 100);
+        state.current_volume = 500;
+
+        let prove = |max_capacity| {
+            let result = prove_capacity(&keys.proving_key, &state, max_capacity).unwrap();
+            (result.proof, result.public_inputs)
+        };
+        let (proof_a, inputs_a) = prove(1000);
+        let (proof_b, inputs_b) = prove(2000);
+
+        let items = vec![
+            (proof_a.clone(), inputs_a.clone()),
+            // Proof for one statement against another's inputs
+            (proof_a.clone(), inputs_b.clone()),
+            (proof_b.clone(), inputs_b),
+            (proof_b, vec![Fr::from(99999u64)]),
+            // Wrong input count
+            (proof_a, vec![inputs_a[0], inputs_a[0]]),
+        ];
+        assert_eq!(
+            verify_batch(&keys.verifying_key, &items),
+            vec![true, false, true, false, false]
+        );
+        assert!(verify_batch(&keys.verifying_key, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_verify_detailed_classifications() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_capacity(&mut rng).unwrap();
+
+        let blinding = Fr::from(12345u64);
+        let mut state = InventoryState::new(blinding);
+        state.tree.update(1, 100);
+        state.current_volume = 500;
+
+        let proof_result = prove_capacity(&keys.proving_key, &state, 1000).unwrap();
+        let public_hash = encode(proof_result.public_inputs[0]);
+
+        // Valid
+        let report = verify_detailed(&keys.verifying_key, &proof_result.proof, &[public_hash]);
+        assert_eq!(report, VerifyReport::Valid);
+        assert!(report.is_valid());
+
+        // Wrong input value
+        let report = verify_detailed(
+            &keys.verifying_key,
+            &proof_result.proof,
+            &[encode(Fr::from(99999u64))],
+        );
+        assert_eq!(report, VerifyReport::InvalidProof);
+
+        // Wrong input count
+        let report = verify_detailed(
+            &keys.verifying_key,
+            &proof_result.proof,
+            &[public_hash, public_hash],
+        );
+        assert_eq!(report, VerifyReport::WrongInputCount { expected: 1, found: 2 });
+
+        // Input >= field modulus
+        let report = verify_detailed(&keys.verifying_key, &proof_result.proof, &[[0xff; 32]]);
+        assert_eq!(report, VerifyReport::InputOutOfDomain { index: 0 });
+    }
+
+    #[test]
+    fn test_public_input_decoding_rejects_non_canonical() {
+        let input = Fr::from(12345u64);
+        let bytes = encode(input);
+        assert_eq!(public_input_from_bytes(&bytes).unwrap(), input);
+        assert_eq!(
+            public_input_from_hex(&format!("0x{}", hex::encode(bytes))).unwrap(),
+            input
+        );
+
+        // input + modulus still fits in 32 bytes and would reduce to `input`
+        let mut non_canonical = Fr::MODULUS;
+        non_canonical.add_with_carry(&input.into_bigint());
+        let mut non_canonical_bytes = [0u8; 32];
+        non_canonical.serialize_compressed(&mut non_canonical_bytes[..]).unwrap();
+        assert_eq!(Fr::from_le_bytes_mod_order(&non_canonical_bytes), input);
+
+        assert!(matches!(
+            public_input_from_bytes(&non_canonical_bytes),
+            Err(VerifyError::NonCanonicalInput)
+        ));
+        assert!(matches!(
+            public_input_from_hex(&hex::encode(non_canonical_bytes)),
+            Err(VerifyError::NonCanonicalInput)
+        ));
+
+        let mut concatenated = bytes.to_vec();
+        concatenated.extend_from_slice(&non_canonical_bytes);
+        assert!(matches!(
+            public_inputs_from_bytes(&concatenated),
+            Err(VerifyError::NonCanonicalInput)
+        ));
+        assert!(matches!(
+            public_inputs_from_bytes(&bytes[..31]),
+            Err(VerifyError::InvalidInputs)
+        ));
+    }
+
+    #[test]
+    fn test_verify_dispatch() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = CircuitKeys {
+            state_transition: setup_state_transition(&mut rng).unwrap(),
+            item_exists: setup_item_exists(&mut rng).unwrap(),
+            capacity: setup_capacity(&mut rng).unwrap(),
+        };
+
+        let blinding = Fr::from(12345u64);
+        let mut state = InventoryState::new(blinding);
+        state.tree.update(1, 100);
+        state.current_volume = 500;
+
+        let transition = prove_state_transition(
+            &keys.state_transition.proving_key,
+            &state,
+            Fr::from(67890u64),
+            1,
+            5,
+            &VolumeRegistry::from_entries(&[(1, 5)]),
+            1000,
+            0,
+            Fr::from(12345678u64),
+            OpType::Deposit,
+        )
+        .unwrap()
+        .proof;
+        let item_exists = prove_item_exists(&keys.item_exists.proving_key, &state, 1, 50).unwrap();
+        let capacity = prove_capacity(&keys.capacity.proving_key, &state, 1000).unwrap();
+
+        let cases = [
+            (CircuitKind::StateTransition, &transition),
+            (CircuitKind::ItemExists, &item_exists),
+            (CircuitKind::Capacity, &capacity),
+        ];
+
+        for (kind, proof) in cases {
+            // Accepts its own proof
+            assert!(verify(kind, &keys, &proof.proof, &proof.public_inputs).unwrap());
+
+            // Rejects a tampered first input
+            let mut tampered = proof.public_inputs.clone();
+            tampered[0] = Fr::from(99999u64);
+            assert!(!verify(kind, &keys, &proof.proof, &tampered).unwrap());
+        }
+
+        // Processed keys give the same answers, including for the wrong kind
+        let prepared = PreparedCircuitKeys::new(&keys);
+        for (kind, proof) in cases {
+            let mut tampered = proof.public_inputs.clone();
+            tampered[0] = Fr::from(99999u64);
+            for claimed in [CircuitKind::ItemExists, CircuitKind::Capacity, kind] {
+                for inputs in [&proof.public_inputs, &tampered] {
+                    assert_eq!(
+                        verify_prepared(claimed, &prepared, &proof.proof, inputs).ok(),
+                        verify(claimed, &keys, &proof.proof, inputs).ok()
+                    );
+                }
+            }
+        }
+        assert!(matches!(
+            verify_prepared(
+                CircuitKind::StateTransition,
+                &prepared,
+                &item_exists.proof,
+                &item_exists.public_inputs,
+            ),
+            Err(VerifyError::InputCountMismatch { expected: 4, found: 1, .. })
+        ));
+
+        // ItemExists proof dispatched as StateTransition: count mismatch
+        let result = verify(
+            CircuitKind::StateTransition,
+            &keys,
+            &item_exists.proof,
+            &item_exists.public_inputs,
+        );
+        assert!(matches!(
+            result,
+            Err(VerifyError::InputCountMismatch { expected: 4, found: 1, .. })
+        ));
+
+        // Same input count, wrong kind: rejected by the verifying key
+        assert!(!verify(
+            CircuitKind::Capacity,
+            &keys,
+            &item_exists.proof,
+            &item_exists.public_inputs
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn test_proof_info_reports_inputs_and_timings() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let item_keys = setup_item_exists(&mut rng).unwrap();
+        let transition_keys = setup_state_transition(&mut rng).unwrap();
+
+        let mut state = InventoryState::new(Fr::from(12345u64));
+        state.tree.update(1, 100);
+        state.current_volume = 500;
+
+        let item = prove_item_exists(&item_keys.proving_key, &state, 1, 50).unwrap();
+        let transition = prove_state_transition(
+            &transition_keys.proving_key,
+            &state,
+            Fr::from(67890u64),
+            1,
+            5,
+            &VolumeRegistry::from_entries(&[(1, 5)]),
+            1000,
+            0,
+            Fr::from(12345678u64),
+            OpType::Deposit,
+        )
+        .unwrap()
+        .proof;
+
+        let item_info =
+            proof_info(&item_keys.verifying_key, &item.proof, &item.public_inputs, 20).unwrap();
+        let transition_info = proof_info(
+            &transition_keys.verifying_key,
+            &transition.proof,
+            &transition.public_inputs,
+            20,
+        )
+        .unwrap();
+
+        assert_eq!(item_info.public_inputs, 1);
+        assert_eq!(transition_info.public_inputs, 4);
+        // Comparing the timings is left to the verify-bench binary
+        for info in [&item_info, &transition_info] {
+            assert!(info.prepare_inputs_time > Duration::ZERO);
+            assert!(info.verify_time > Duration::ZERO);
+        }
+
+        // No figures for a rejected proof
+        let mut tampered = item.public_inputs.clone();
+        tampered[0] = Fr::from(99999u64);
+        assert!(proof_info(&item_keys.verifying_key, &item.proof, &tampered, 1).is_err());
+    }
 }
@@ -0,0 +1,56 @@
+//! Verification without the proving dependencies.
+//!
+//! Run as a verifier-only build with:
+//! `cargo test -p inventory-prover --no-default-features --features verify-only --test verify_only`
+//!
+//! The fixture is a capacity proof generated once with the full build, so this
+//! test needs no circuits or proving keys.
+
+use ark_bn254::Fr;
+use inventory_prover::{
+    public_input_from_hex, verify_capacity, verify_detailed, CircuitKeyPair, ProofWithInputs,
+    VerifyReport,
+};
+
+struct Fixture {
+    vk_bytes: Vec<u8>,
+    proof_bytes: Vec<u8>,
+    public_input: Fr,
+}
+
+fn load_fixture() -> Fixture {
+    let json: serde_json::Value =
+        serde_json::from_str(include_str!("fixtures/capacity_proof.json")).unwrap();
+    let decode = |v: &serde_json::Value| {
+        hex::decode(v.as_str().unwrap().trim_start_matches("0x")).unwrap()
+    };
+
+    Fixture {
+        vk_bytes: decode(&json["verifying_key"]),
+        proof_bytes: decode(&json["proof"]),
+        public_input: public_input_from_hex(json["public_inputs"][0].as_str().unwrap()).unwrap(),
+    }
+}
+
+#[test]
+fn test_verify_capacity_fixture() {
+    let fixture = load_fixture();
+    let vk = CircuitKeyPair::deserialize_vk(&fixture.vk_bytes).unwrap();
+    let proof = ProofWithInputs::deserialize_proof(&fixture.proof_bytes).unwrap();
+
+    assert!(verify_capacity(&vk, &proof, fixture.public_input).unwrap());
+    assert!(!verify_capacity(&vk, &proof, fixture.public_input + Fr::from(1u64)).unwrap());
+}
+
+#[test]
+fn test_verify_detailed_fixture() {
+    let fixture = load_fixture();
+    let vk = CircuitKeyPair::deserialize_vk(&fixture.vk_bytes).unwrap();
+    let proof = ProofWithInputs::deserialize_proof(&fixture.proof_bytes).unwrap();
+
+    let mut input = [0u8; 32];
+    ark_serialize::CanonicalSerialize::serialize_compressed(&fixture.public_input, &mut input[..])
+        .unwrap();
+
+    assert_eq!(verify_detailed(&vk, &proof, &[input]), VerifyReport::Valid);
+}
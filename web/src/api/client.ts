@@ -54,8 +54,8 @@ export interface StateTransitionRequest {
   new_blinding: string;
   item_id: number;
   amount: number;
-  item_volume: number;
-  registry_root: string;
+  /** Volume per unit for item IDs 0-15 (its hash must match on-chain) */
+  volume_registry: number[];
   max_capacity: number;
   /** Current nonce from on-chain inventory (for replay protection) */
   nonce: number;
@@ -83,8 +83,7 @@ export async function proveDeposit(
   newBlinding: string,
   itemId: number,
   amount: number,
-  itemVolume: number,
-  registryRoot: string,
+  volumeRegistry: number[],
   maxCapacity: number,
   nonce: number,
   inventoryId: string
@@ -96,8 +95,7 @@ export async function proveDeposit(
     new_blinding: newBlinding,
     item_id: itemId,
     amount,
-    item_volume: itemVolume,
-    registry_root: registryRoot,
+    volume_registry: volumeRegistry,
     max_capacity: maxCapacity,
     nonce,
     inventory_id: inventoryId,
@@ -113,8 +111,7 @@ export async function proveWithdraw(
   newBlinding: string,
   itemId: number,
   amount: number,
-  itemVolume: number,
-  registryRoot: string,
+  volumeRegistry: number[],
   maxCapacity: number,
   nonce: number,
   inventoryId: string
@@ -126,8 +123,7 @@ export async function proveWithdraw(
     new_blinding: newBlinding,
     item_id: itemId,
     amount,
-    item_volume: itemVolume,
-    registry_root: registryRoot,
+    volume_registry: volumeRegistry,
     max_capacity: maxCapacity,
     nonce,
     inventory_id: inventoryId,
@@ -229,7 +225,7 @@ export interface BatchOperationsResult {
  * @param operations - Array of operations to perform
  * @param inventoryId - On-chain inventory object ID
  * @param startNonce - Starting nonce (will increment for each operation)
- * @param registryRoot - Registry root for volume validation
+ * @param volumeRegistry - Volume per unit for item IDs 0-15
  * @param maxCapacity - Maximum inventory capacity
  */
 export async function proveBatchOperations(
@@ -239,7 +235,7 @@ export async function proveBatchOperations(
   operations: BatchOperation[],
   inventoryId: string,
   startNonce: number,
-  registryRoot: string,
+  volumeRegistry: number[],
   maxCapacity: number
 ): Promise<BatchOperationsResult> {
   if (operations.length === 0) {
@@ -330,8 +326,7 @@ export async function proveBatchOperations(
       new_blinding: newBlinding,
       item_id: op.item_id,
       amount: op.amount,
-      item_volume: op.item_volume,
-      registry_root: registryRoot,
+      volume_registry: volumeRegistry,
       max_capacity: maxCapacity,
       nonce: state.nonce,
       inventory_id: inventoryId,
@@ -435,8 +430,7 @@ export async function proveTransfer(
   dstInventoryId: string,
   itemId: number,
   amount: number,
-  itemVolume: number,
-  registryRoot: string,
+  volumeRegistry: number[],
   srcMaxCapacity: number,
   dstMaxCapacity: number
 ): Promise<TransferProofs> {
@@ -448,8 +442,7 @@ export async function proveTransfer(
     srcNewBlinding,
     itemId,
     amount,
-    itemVolume,
-    registryRoot,
+    volumeRegistry,
     srcMaxCapacity,
     srcNonce,
     srcInventoryId
@@ -463,8 +456,7 @@ export async function proveTransfer(
     dstNewBlinding,
     itemId,
     amount,
-    itemVolume,
-    registryRoot,
+    volumeRegistry,
     dstMaxCapacity,
     dstNonce,
     dstInventoryId
@@ -12,7 +12,7 @@ import {
 } from '../components/OnChainInventorySelector';
 import { useContractAddresses } from '../sui/ContractConfig';
 import { buildBatchOperationsTx, hexToBytes, type BatchTxOperation } from '../sui/transactions';
-import { ITEM_NAMES, ITEM_VOLUMES, canDeposit, calculateUsedVolume, getRegistryRoot } from '../types';
+import { ITEM_NAMES, ITEM_VOLUMES, canDeposit, calculateUsedVolume, getVolumeRegistryArray } from '../types';
 import * as api from '../api/client';
 import type { BatchOperation, BatchOperationsResult } from '../api/client';
 import type { OnChainInventory } from '../sui/hooks';
@@ -183,7 +183,7 @@ export function DepositWithdraw() {
       const startNonce = freshInventory?.nonce ?? selectedInventory.nonce;
 
       const currentVolume = calculateUsedVolume(currentSlots);
-      const registryRoot = getRegistryRoot();
+      const volumeRegistry = getVolumeRegistryArray();
 
       // Convert pending ops to batch operations
       const operations: BatchOperation[] = pendingOps.map(op => ({
@@ -201,7 +201,7 @@ export function DepositWithdraw() {
         operations,
         selectedInventory.id,
         startNonce,
-        registryRoot,
+        volumeRegistry,
         maxCapacity
       );
 
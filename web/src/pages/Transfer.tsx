@@ -12,7 +12,7 @@ import {
 } from '../components/OnChainInventorySelector';
 import { useContractAddresses } from '../sui/ContractConfig';
 import { buildBatchTransfersTx, hexToBytes, type BatchTransferTxOperation } from '../sui/transactions';
-import { ITEM_NAMES, ITEM_VOLUMES, canDeposit, calculateUsedVolume, getRegistryRoot } from '../types';
+import { ITEM_NAMES, ITEM_VOLUMES, canDeposit, calculateUsedVolume, getVolumeRegistryArray } from '../types';
 import * as api from '../api/client';
 import type { TransferProofs } from '../api/client';
 import type { OnChainInventory } from '../sui/hooks';
@@ -179,7 +179,7 @@ export function Transfer() {
       const freshSrcOnChain = fetchedSrc || srcOnChain;
       const freshDstOnChain = fetchedDst || dstOnChain;
 
-      const registryRoot = getRegistryRoot();
+      const volumeRegistry = getVolumeRegistryArray();
       const srcMaxCapacity = srcOnChain.maxCapacity;
 
       // Generate proofs sequentially (each depends on previous state)
@@ -201,12 +201,11 @@ export function Transfer() {
 
         const srcVolume = calculateUsedVolume(srcSlots);
         const dstVolume = calculateUsedVolume(dstSlots);
-        const itemVolume = ITEM_VOLUMES[t.item_id] ?? 0;
 
         const result = await api.proveTransfer(
           srcSlots, srcVolume, srcBlinding, srcNewBlinding, srcNonce, srcOnChain.id,
           dstSlots, dstVolume, dstBlinding, dstNewBlinding, dstNonce, dstOnChain.id,
-          t.item_id, t.amount, itemVolume, registryRoot, srcMaxCapacity, dstMaxCapacity
+          t.item_id, t.amount, volumeRegistry, srcMaxCapacity, dstMaxCapacity
         );
 
         transfers.push(result);